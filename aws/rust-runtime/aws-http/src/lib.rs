@@ -27,7 +27,7 @@ pub mod retry;
 pub mod user_agent;
 
 /// AWS-specific content-encoding tools
-pub mod content_encoding;
+pub use aws_smithy_http::content_encoding;
 
 /// AWS-specific request ID support
 pub mod request_id;
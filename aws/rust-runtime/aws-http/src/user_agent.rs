@@ -30,6 +30,7 @@ pub struct AwsUserAgent {
     feature_metadata: Vec<FeatureMetadata>,
     config_metadata: Vec<ConfigMetadata>,
     framework_metadata: Vec<FrameworkMetadata>,
+    custom_metadata: Vec<CustomMetadata>,
     app_name: Option<AppName>,
 }
 
@@ -66,6 +67,7 @@ impl AwsUserAgent {
             feature_metadata: Default::default(),
             config_metadata: Default::default(),
             framework_metadata: Default::default(),
+            custom_metadata: Default::default(),
             app_name: Default::default(),
         }
     }
@@ -96,6 +98,7 @@ impl AwsUserAgent {
             feature_metadata: Vec::new(),
             config_metadata: Vec::new(),
             framework_metadata: Vec::new(),
+            custom_metadata: Vec::new(),
             app_name: None,
         }
     }
@@ -142,6 +145,27 @@ impl AwsUserAgent {
         self
     }
 
+    /// Adds a custom `md/{key}/{value}` fragment to the user agent.
+    ///
+    /// Characters that aren't allowed in user agent metadata are replaced with `_`. See
+    /// [`InvalidMetadataValue`] for the allowed characters.
+    pub fn with_custom_metadata(mut self, key: &str, value: &str) -> Self {
+        self.add_custom_metadata(key, value);
+        self
+    }
+
+    /// Adds a custom `md/{key}/{value}` fragment to the user agent.
+    ///
+    /// Characters that aren't allowed in user agent metadata are replaced with `_`. See
+    /// [`InvalidMetadataValue`] for the allowed characters.
+    pub fn add_custom_metadata(&mut self, key: &str, value: &str) -> &mut Self {
+        self.custom_metadata.push(CustomMetadata {
+            key: sanitize_metadata(key),
+            value: sanitize_metadata(value),
+        });
+        self
+    }
+
     /// Sets the app name for the user agent.
     pub fn with_app_name(mut self, app_name: AppName) -> Self {
         self.app_name = Some(app_name);
@@ -168,6 +192,7 @@ impl AwsUserAgent {
                     *(feat-metadata RWS)
                     *(config-metadata RWS)
                     *(framework-metadata RWS)
+                    *(custom-metadata RWS)
                     [appId]
         */
         let mut ua_value = String::new();
@@ -189,6 +214,9 @@ impl AwsUserAgent {
         for framework in &self.framework_metadata {
             write!(ua_value, "{} ", framework).unwrap();
         }
+        for custom in &self.custom_metadata {
+            write!(ua_value, "{} ", custom).unwrap();
+        }
         if let Some(app_name) = &self.app_name {
             write!(ua_value, "app/{}", app_name).unwrap();
         }
@@ -270,21 +298,48 @@ impl fmt::Display for InvalidMetadataValue {
     }
 }
 
-fn validate_metadata(value: Cow<'static, str>) -> Result<Cow<'static, str>, InvalidMetadataValue> {
-    fn valid_character(c: char) -> bool {
-        match c {
-            _ if c.is_ascii_alphanumeric() => true,
-            '!' | '#' | '$' | '%' | '&' | '\'' | '*' | '+' | '-' | '.' | '^' | '_' | '`' | '|'
-            | '~' => true,
-            _ => false,
-        }
+fn valid_character(c: char) -> bool {
+    match c {
+        _ if c.is_ascii_alphanumeric() => true,
+        '!' | '#' | '$' | '%' | '&' | '\'' | '*' | '+' | '-' | '.' | '^' | '_' | '`' | '|'
+        | '~' => true,
+        _ => false,
     }
+}
+
+fn validate_metadata(value: Cow<'static, str>) -> Result<Cow<'static, str>, InvalidMetadataValue> {
     if !value.chars().all(valid_character) {
         return Err(InvalidMetadataValue);
     }
     Ok(value)
 }
 
+/// Replaces the characters of `value` that aren't allowed in metadata with `_`.
+fn sanitize_metadata(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if valid_character(c) { c } else { '_' })
+        .collect()
+}
+
+/// A custom `md/{key}/{value}` fragment, sanitized to the allowed characters.
+#[derive(Clone, Debug)]
+struct CustomMetadata {
+    key: String,
+    value: String,
+}
+
+impl fmt::Display for CustomMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // custom-metadata = "md/" ua-pair
+        if self.value.is_empty() {
+            write!(f, "md/{}", self.key)
+        } else {
+            write!(f, "md/{}/{}", self.key, self.value)
+        }
+    }
+}
+
 #[doc(hidden)]
 /// Additional metadata that can be bundled with framework or feature metadata.
 #[derive(Clone, Debug)]
@@ -756,6 +811,24 @@ mod test {
         );
     }
 
+    #[test]
+    fn generate_a_valid_ua_with_custom_metadata() {
+        let api_metadata = ApiMetadata {
+            service_id: "dynamodb".into(),
+            version: "123",
+        };
+        let mut ua = AwsUserAgent::new_from_environment(Env::from_slice(&[]), api_metadata)
+            .with_app_name(AppName::new("my_app").unwrap())
+            .with_custom_metadata("team", "payments")
+            .with_custom_metadata("build id", "a/b(c)")
+            .with_custom_metadata("flag", "");
+        make_deterministic(&mut ua);
+        assert_eq!(
+            ua.aws_ua_header(),
+            "aws-sdk-rust/0.1 api/dynamodb/123 os/macos/1.15 lang/rust/1.50.0 md/team/payments md/build_id/a_b_c_ md/flag app/my_app"
+        );
+    }
+
     #[test]
     fn ua_stage_adds_headers() {
         let stage = UserAgentStage::new();
@@ -804,6 +877,7 @@ env-metadata         = "exec-env/" name
 feat-metadata        = "ft/" name ["/" version] *(RWS additional-metadata)
 config-metadata      = "cfg/" config ["/" value]
 framework-metadata   = "lib/" name ["/" version] *(RWS additional-metadata)
+custom-metadata      = "md/" ua-pair
 appId                = "app/" name
 ua-string            = sdk-metadata RWS
                        [api-metadata RWS]
@@ -813,6 +887,7 @@ ua-string            = sdk-metadata RWS
                        *(feat-metadata RWS)
                        *(config-metadata RWS)
                        *(framework-metadata RWS)
+                       *(custom-metadata RWS)
                        [appId]
 
 # New metadata field might be added in the future and they must follow this format
@@ -13,7 +13,9 @@ pub mod sigv4 {
     };
     use aws_smithy_runtime_api::client::auth::{AuthSchemeId, HttpAuthScheme, HttpRequestSigner};
     use aws_smithy_runtime_api::client::identity::{Identity, IdentityResolver, IdentityResolvers};
-    use aws_smithy_runtime_api::client::orchestrator::{BoxError, ConfigBagAccessors, HttpRequest};
+    use aws_smithy_runtime_api::client::orchestrator::{
+        BoxError, ConfigBagAccessors, HttpRequest, SigningTime,
+    };
     use aws_smithy_runtime_api::config_bag::ConfigBag;
     use aws_types::region::SigningRegion;
     use aws_types::SigningService;
@@ -197,7 +199,11 @@ pub mod sigv4 {
             let operation_config = config_bag
                 .get::<SigV4OperationSigningConfig>()
                 .ok_or("missing operation signing config for SigV4")?;
-            let request_time = config_bag.request_time().unwrap_or_default().system_time();
+            // The orchestrator records the signing time before auth, corrected for clock skew
+            let request_time = match config_bag.load::<SigningTime>() {
+                Some(signing_time) => signing_time.time(),
+                None => config_bag.request_time().unwrap_or_default().system_time(),
+            };
 
             let credentials = if let Some(creds) = identity.data::<Credentials>() {
                 creds
@@ -30,3 +30,6 @@ pub mod retries;
 
 /// Supporting code for invocation ID headers in the AWS SDK.
 pub mod invocation_id;
+
+/// Supporting code for propagating W3C trace context headers in the AWS SDK.
+pub mod trace_context;
@@ -30,6 +30,13 @@ pub const THROTTLING_ERRORS: &[&str] = &[
 /// AWS error codes that represent transient errors.
 pub const TRANSIENT_ERRORS: &[&str] = &["RequestTimeout", "RequestTimeoutException"];
 
+/// AWS error codes that represent requests rejected because of clock skew.
+pub const CLOCK_SKEW_ERRORS: &[&str] = &[
+    "RequestTimeTooSkewed",
+    "RequestExpired",
+    "RequestInTheFuture",
+];
+
 /// A retry classifier for determining if the response sent by an AWS service requires a retry.
 #[derive(Debug)]
 pub struct AwsErrorCodeClassifier;
@@ -53,6 +60,19 @@ impl AwsErrorCodeClassifier {
     }
 }
 
+/// A classifier for determining if an AWS service rejected a request because of clock skew.
+#[derive(Debug)]
+pub struct AwsClockSkewClassifier;
+
+impl AwsClockSkewClassifier {
+    /// Returns `true` if the error code is one of the [clock skew error codes](CLOCK_SKEW_ERRORS).
+    pub fn is_clock_skew_error<E: ProvideErrorMetadata>(&self, error: &E) -> bool {
+        error
+            .code()
+            .map_or(false, |error_code| CLOCK_SKEW_ERRORS.contains(&error_code))
+    }
+}
+
 /// A retry classifier that checks for `x-amz-retry-after` headers. If one is found, a
 /// [`RetryReason::Explicit`] is returned containing the duration to wait before retrying.
 #[derive(Debug)]
@@ -74,7 +94,7 @@ impl AmzRetryAfterHeaderClassifier {
 
 #[cfg(test)]
 mod test {
-    use super::{AmzRetryAfterHeaderClassifier, AwsErrorCodeClassifier};
+    use super::{AmzRetryAfterHeaderClassifier, AwsClockSkewClassifier, AwsErrorCodeClassifier};
     use aws_smithy_http::body::SdkBody;
     use aws_smithy_http::operation;
     use aws_smithy_http::result::SdkError;
@@ -155,6 +175,14 @@ mod test {
         );
     }
 
+    #[test]
+    fn classify_clock_skew_by_error_code() {
+        let classifier = AwsClockSkewClassifier;
+        assert!(classifier.is_clock_skew_error(&CodedError::new("RequestTimeTooSkewed")));
+        assert!(classifier.is_clock_skew_error(&CodedError::new("RequestExpired")));
+        assert!(!classifier.is_clock_skew_error(&CodedError::new("AccessDenied")));
+    }
+
     #[test]
     fn test_retry_after_header() {
         let policy = AmzRetryAfterHeaderClassifier;
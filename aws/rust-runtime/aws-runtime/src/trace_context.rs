@@ -0,0 +1,247 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use aws_smithy_runtime_api::client::interceptors::{BoxError, Interceptor, InterceptorContext};
+use aws_smithy_runtime_api::config_bag::ConfigBag;
+use http::HeaderValue;
+use std::fmt::{self, Write};
+use std::sync::Arc;
+
+const TRACEPARENT_HEADER: &str = "traceparent";
+const TRACESTATE_HEADER: &str = "tracestate";
+
+/// The only version of the `traceparent` header defined by the W3C Trace Context specification.
+const TRACEPARENT_VERSION: u8 = 0;
+const SAMPLED_FLAG: u8 = 0x01;
+
+/// A [W3C trace context](https://www.w3.org/TR/trace-context/), identifying the span that a
+/// request is made from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceContext {
+    trace_id: [u8; 16],
+    parent_id: [u8; 8],
+    flags: u8,
+    trace_state: Option<String>,
+}
+
+impl TraceContext {
+    /// Creates a [`TraceContext`] for the trace `trace_id`, whose current span is `parent_id`.
+    ///
+    /// The trace isn't sampled, and has no vendor-specific trace state.
+    pub fn new(trace_id: [u8; 16], parent_id: [u8; 8]) -> Self {
+        Self {
+            trace_id,
+            parent_id,
+            flags: 0,
+            trace_state: None,
+        }
+    }
+
+    /// Sets whether the caller may have recorded the trace.
+    pub fn sampled(mut self, sampled: bool) -> Self {
+        if sampled {
+            self.flags |= SAMPLED_FLAG;
+        } else {
+            self.flags &= !SAMPLED_FLAG;
+        }
+        self
+    }
+
+    /// Sets the vendor-specific trace state, which is sent in the `tracestate` header as is.
+    pub fn trace_state(mut self, trace_state: impl Into<String>) -> Self {
+        self.trace_state = Some(trace_state.into());
+        self
+    }
+
+    /// Returns true if the trace and parent IDs are valid, meaning they aren't all zeros.
+    pub fn is_valid(&self) -> bool {
+        self.trace_id != [0; 16] && self.parent_id != [0; 8]
+    }
+
+    /// Returns the value of the `traceparent` header for this trace context.
+    pub fn traceparent(&self) -> String {
+        let mut traceparent = String::with_capacity(55);
+        write_hex(&mut traceparent, &[TRACEPARENT_VERSION]);
+        traceparent.push('-');
+        write_hex(&mut traceparent, &self.trace_id);
+        traceparent.push('-');
+        write_hex(&mut traceparent, &self.parent_id);
+        traceparent.push('-');
+        write_hex(&mut traceparent, &[self.flags]);
+        traceparent
+    }
+}
+
+fn write_hex(out: &mut String, bytes: &[u8]) {
+    for byte in bytes {
+        write!(out, "{:02x}", byte).expect("writing to a string can't fail");
+    }
+}
+
+/// Extracts the ambient trace context, for example from the current span of a tracing library.
+pub trait ExtractTraceContext: fmt::Debug + Send + Sync {
+    /// Returns the current trace context, or `None` if the request isn't made as part of a trace.
+    fn extract(&self) -> Option<TraceContext>;
+}
+
+/// A fixed trace context is propagated with every request.
+impl ExtractTraceContext for TraceContext {
+    fn extract(&self) -> Option<TraceContext> {
+        Some(self.clone())
+    }
+}
+
+/// Trace Context Interceptor
+///
+/// This interceptor propagates the trace context returned by an [`ExtractTraceContext`] in the
+/// `traceparent` and `tracestate` headers defined by the
+/// [W3C Trace Context](https://www.w3.org/TR/trace-context/) specification, so that the service's
+/// spans can be attributed to the caller's trace. The headers are set before signing, so they're
+/// signed along with the rest of the request.
+///
+/// Requests that already have a `traceparent` header are left as they are, and invalid trace
+/// contexts aren't propagated.
+#[derive(Clone, Debug)]
+pub struct TraceContextInterceptor {
+    extractor: Arc<dyn ExtractTraceContext>,
+}
+
+impl TraceContextInterceptor {
+    /// Creates a new `TraceContextInterceptor` that propagates the trace context returned by
+    /// `extractor`.
+    pub fn new(extractor: impl ExtractTraceContext + 'static) -> Self {
+        Self {
+            extractor: Arc::new(extractor),
+        }
+    }
+}
+
+impl Interceptor for TraceContextInterceptor {
+    fn modify_before_signing(
+        &self,
+        context: &mut InterceptorContext,
+        _cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let request = context.request_mut()?;
+        if request.headers().contains_key(TRACEPARENT_HEADER) {
+            return Ok(());
+        }
+        let trace_context = match self.extractor.extract() {
+            Some(trace_context) if trace_context.is_valid() => trace_context,
+            _ => return Ok(()),
+        };
+
+        let traceparent = HeaderValue::try_from(trace_context.traceparent())
+            .expect("the traceparent is hex digits and dashes, so it's a valid header");
+        let headers = request.headers_mut();
+        headers.insert(TRACEPARENT_HEADER, traceparent);
+        // The trace state is optional, so one that isn't a valid header is dropped instead of
+        // failing the request
+        if let Some(trace_state) = trace_context
+            .trace_state
+            .filter(|trace_state| !trace_state.is_empty())
+        {
+            match HeaderValue::try_from(trace_state) {
+                Ok(trace_state) => {
+                    headers.insert(TRACESTATE_HEADER, trace_state);
+                }
+                Err(_) => tracing::debug!("dropping a tracestate that isn't a valid header value"),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_smithy_http::body::SdkBody;
+    use aws_smithy_runtime_api::type_erasure::TypedBox;
+
+    const TRACE_ID: [u8; 16] = [
+        0x4b, 0xf9, 0x2f, 0x35, 0x77, 0xb3, 0x4d, 0xa6, 0xa3, 0xce, 0x92, 0x9d, 0x0e, 0x0e, 0x47,
+        0x36,
+    ];
+    const PARENT_ID: [u8; 8] = [0x00, 0xf0, 0x67, 0xaa, 0x0b, 0xa9, 0x02, 0xb7];
+
+    fn context_with_request(request: http::request::Builder) -> InterceptorContext {
+        let mut context = InterceptorContext::new(TypedBox::new("doesntmatter").erase());
+        context.set_request(request.body(SdkBody::empty()).unwrap());
+        context
+    }
+
+    fn header<'a>(context: &'a InterceptorContext, name: &str) -> Option<&'a str> {
+        let value = context.request().unwrap().headers().get(name)?;
+        Some(value.to_str().unwrap())
+    }
+
+    #[test]
+    fn traceparent_is_set_and_well_formed() {
+        let mut context = context_with_request(http::Request::builder());
+        let interceptor = TraceContextInterceptor::new(
+            TraceContext::new(TRACE_ID, PARENT_ID)
+                .sampled(true)
+                .trace_state("congo=t61rcWkgMzE"),
+        );
+        interceptor
+            .modify_before_signing(&mut context, &mut ConfigBag::base())
+            .unwrap();
+
+        let traceparent = header(&context, "traceparent").expect("traceparent is set");
+        let fields: Vec<&str> = traceparent.split('-').collect();
+        assert_eq!(
+            vec![2, 32, 16, 2],
+            fields.iter().map(|field| field.len()).collect::<Vec<_>>(),
+            "unexpected traceparent format: {traceparent}"
+        );
+        assert!(fields
+            .iter()
+            .flat_map(|field| field.chars())
+            .all(|c| matches!(c, '0'..='9' | 'a'..='f')));
+        assert_eq!(
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            traceparent
+        );
+        assert_eq!(Some("congo=t61rcWkgMzE"), header(&context, "tracestate"));
+    }
+
+    #[test]
+    fn existing_traceparent_is_kept() {
+        let existing = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-00";
+        let mut context =
+            context_with_request(http::Request::builder().header("traceparent", existing));
+        TraceContextInterceptor::new(TraceContext::new(TRACE_ID, PARENT_ID))
+            .modify_before_signing(&mut context, &mut ConfigBag::base())
+            .unwrap();
+
+        assert_eq!(Some(existing), header(&context, "traceparent"));
+    }
+
+    #[derive(Debug)]
+    struct NoTraceContext;
+
+    impl ExtractTraceContext for NoTraceContext {
+        fn extract(&self) -> Option<TraceContext> {
+            None
+        }
+    }
+
+    #[test]
+    fn missing_or_invalid_trace_contexts_are_not_propagated() {
+        let interceptors = [
+            TraceContextInterceptor::new(NoTraceContext),
+            TraceContextInterceptor::new(TraceContext::new([0; 16], PARENT_ID)),
+            TraceContextInterceptor::new(TraceContext::new(TRACE_ID, [0; 8])),
+        ];
+        for interceptor in interceptors {
+            let mut context = context_with_request(http::Request::builder());
+            interceptor
+                .modify_before_signing(&mut context, &mut ConfigBag::base())
+                .unwrap();
+            assert_eq!(None, header(&context, "traceparent"), "{interceptor:?}");
+            assert_eq!(None, header(&context, "tracestate"), "{interceptor:?}");
+        }
+    }
+}
@@ -3,9 +3,12 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use aws_http::user_agent::{ApiMetadata, AwsUserAgent};
+use aws_http::user_agent::{ApiMetadata, AwsUserAgent, FrameworkMetadata};
 use aws_smithy_runtime_api::client::interceptors::error::BoxError;
-use aws_smithy_runtime_api::client::interceptors::{Interceptor, InterceptorContext};
+use aws_smithy_runtime_api::client::interceptors::{
+    Interceptor, InterceptorContext, Interceptors, SharedInterceptor,
+};
+use aws_smithy_runtime_api::client::runtime_plugin::RuntimePlugin;
 use aws_smithy_runtime_api::config_bag::ConfigBag;
 use aws_types::app_name::AppName;
 use aws_types::os_shim_internal::Env;
@@ -13,6 +16,7 @@ use http::header::{InvalidHeaderValue, USER_AGENT};
 use http::{HeaderName, HeaderValue};
 use std::borrow::Cow;
 use std::fmt;
+use std::sync::Arc;
 
 #[allow(clippy::declare_interior_mutable_const)] // we will never mutate this
 const X_AMZ_USER_AGENT: HeaderName = HeaderName::from_static("x-amz-user-agent");
@@ -48,14 +52,115 @@ impl From<InvalidHeaderValue> for UserAgentInterceptorError {
 }
 
 /// Generates and attaches the AWS SDK's user agent to a HTTP request
+///
+/// The fields that aren't set with a [`UserAgentInterceptorBuilder`] are taken from the
+/// [`ConfigBag`], or from the environment.
 #[non_exhaustive]
 #[derive(Debug, Default)]
-pub struct UserAgentInterceptor;
+pub struct UserAgentInterceptor {
+    api_metadata: Option<ApiMetadata>,
+    app_name: Option<AppName>,
+    framework_metadata: Vec<FrameworkMetadata>,
+    custom_metadata: Vec<(String, String)>,
+}
 
 impl UserAgentInterceptor {
     /// Creates a new `UserAgentInterceptor`
     pub fn new() -> Self {
-        UserAgentInterceptor
+        Self::default()
+    }
+
+    /// Creates a builder for a `UserAgentInterceptor`
+    pub fn builder() -> UserAgentInterceptorBuilder {
+        UserAgentInterceptorBuilder::default()
+    }
+}
+
+/// Builder for [`UserAgentInterceptor`]
+///
+/// # Examples
+///
+/// ```no_run
+/// use aws_http::user_agent::ApiMetadata;
+/// use aws_runtime::user_agent::UserAgentInterceptor;
+/// use aws_types::app_name::AppName;
+///
+/// let user_agent = UserAgentInterceptor::builder()
+///     .api_metadata(ApiMetadata::new("my-service", "1.0"))
+///     .app_name(AppName::new("my-app").unwrap())
+///     .custom_metadata("team", "payments")
+///     .build_runtime_plugin();
+/// ```
+#[derive(Debug, Default)]
+pub struct UserAgentInterceptorBuilder {
+    api_metadata: Option<ApiMetadata>,
+    app_name: Option<AppName>,
+    framework_metadata: Vec<FrameworkMetadata>,
+    custom_metadata: Vec<(String, String)>,
+}
+
+impl UserAgentInterceptorBuilder {
+    /// Sets the service and version reported in the user agent, instead of the [`ApiMetadata`]
+    /// in the [`ConfigBag`].
+    pub fn api_metadata(mut self, api_metadata: ApiMetadata) -> Self {
+        self.api_metadata = Some(api_metadata);
+        self
+    }
+
+    /// Sets the app name reported in the user agent, instead of the [`AppName`] in the
+    /// [`ConfigBag`].
+    pub fn app_name(mut self, app_name: AppName) -> Self {
+        self.app_name = Some(app_name);
+        self
+    }
+
+    /// Adds a framework that the client is used by to the user agent.
+    pub fn framework_metadata(mut self, framework_metadata: FrameworkMetadata) -> Self {
+        self.framework_metadata.push(framework_metadata);
+        self
+    }
+
+    /// Adds a custom `md/{key}/{value}` fragment to the user agent.
+    ///
+    /// Characters that aren't allowed in user agent metadata are replaced with `_`.
+    pub fn custom_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.custom_metadata.push((key.into(), value.into()));
+        self
+    }
+
+    /// Builds the [`UserAgentInterceptor`].
+    pub fn build(self) -> UserAgentInterceptor {
+        UserAgentInterceptor {
+            api_metadata: self.api_metadata,
+            app_name: self.app_name,
+            framework_metadata: self.framework_metadata,
+            custom_metadata: self.custom_metadata,
+        }
+    }
+
+    /// Builds a [`RuntimePlugin`] that registers the [`UserAgentInterceptor`] with the client.
+    pub fn build_runtime_plugin(self) -> UserAgentRuntimePlugin {
+        UserAgentRuntimePlugin {
+            interceptor: Arc::new(self.build()),
+        }
+    }
+}
+
+/// A [`RuntimePlugin`] that registers a [`UserAgentInterceptor`] as a client interceptor.
+#[derive(Debug)]
+pub struct UserAgentRuntimePlugin {
+    interceptor: Arc<UserAgentInterceptor>,
+}
+
+impl RuntimePlugin for UserAgentRuntimePlugin {
+    fn configure(
+        &self,
+        _cfg: &mut ConfigBag,
+        interceptors: &mut Interceptors,
+    ) -> Result<(), BoxError> {
+        let interceptor: SharedInterceptor = self.interceptor.clone();
+        interceptors.register_client_interceptor(interceptor);
+        Ok(())
     }
 }
 
@@ -75,8 +180,10 @@ impl Interceptor for UserAgentInterceptor {
         context: &mut InterceptorContext,
         cfg: &mut ConfigBag,
     ) -> Result<(), BoxError> {
-        let api_metadata = cfg
-            .get::<ApiMetadata>()
+        let api_metadata = self
+            .api_metadata
+            .as_ref()
+            .or_else(|| cfg.get::<ApiMetadata>())
             .ok_or(UserAgentInterceptorError::MissingApiMetadata)?;
 
         // Allow for overriding the user agent by an earlier interceptor (so, for example,
@@ -88,10 +195,16 @@ impl Interceptor for UserAgentInterceptor {
             .unwrap_or_else(|| {
                 let mut ua = AwsUserAgent::new_from_environment(Env::real(), api_metadata.clone());
 
-                let maybe_app_name = cfg.get::<AppName>();
+                let maybe_app_name = self.app_name.as_ref().or_else(|| cfg.get::<AppName>());
                 if let Some(app_name) = maybe_app_name {
                     ua.set_app_name(app_name.clone());
                 }
+                for framework_metadata in &self.framework_metadata {
+                    ua.add_framework_metadata(framework_metadata.clone());
+                }
+                for (key, value) in &self.custom_metadata {
+                    ua.add_custom_metadata(key, value);
+                }
                 Cow::Owned(ua)
             });
 
@@ -107,7 +220,9 @@ impl Interceptor for UserAgentInterceptor {
 mod tests {
     use super::*;
     use aws_smithy_http::body::SdkBody;
-    use aws_smithy_runtime_api::client::interceptors::{Interceptor, InterceptorContext};
+    use aws_smithy_runtime_api::client::interceptors::{
+        Interceptor, InterceptorContext, Interceptors,
+    };
     use aws_smithy_runtime_api::config_bag::ConfigBag;
     use aws_smithy_runtime_api::type_erasure::TypedBox;
     use aws_smithy_types::error::display::DisplayErrorContext;
@@ -205,6 +320,60 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_builder() {
+        let mut context = InterceptorContext::new(TypedBox::new("doesntmatter").erase());
+        context.set_request(http::Request::builder().body(SdkBody::empty()).unwrap());
+
+        // The builder's fields take precedence over the config bag
+        let mut config = ConfigBag::base();
+        config.put(ApiMetadata::new("unused", "unused"));
+        config.put(AppName::new("unused").unwrap());
+
+        let interceptor = UserAgentInterceptor::builder()
+            .api_metadata(ApiMetadata::new("some-service", "some-version"))
+            .app_name(AppName::new("my_awesome_app").unwrap())
+            .framework_metadata(FrameworkMetadata::new("some-framework", None).unwrap())
+            .custom_metadata("team", "payments")
+            .custom_metadata("build id", "a/b(c)")
+            .build();
+        interceptor
+            .modify_before_signing(&mut context, &mut config)
+            .unwrap();
+
+        let header = expect_header(&context, "x-amz-user-agent");
+        assert!(
+            header.contains(" api/some-service/some-version "),
+            "expected `{header}` to contain the API metadata"
+        );
+        assert!(
+            header.ends_with(
+                " lib/some-framework md/team/payments md/build_id/a_b_c_ app/my_awesome_app"
+            ),
+            "expected `{header}` to end with the sanitized custom metadata and app name"
+        );
+        assert!(!header.contains("unused"));
+    }
+
+    #[test]
+    fn test_runtime_plugin_registers_the_interceptor() {
+        let mut config = ConfigBag::base();
+        let mut interceptors = Interceptors::new();
+        UserAgentInterceptor::builder()
+            .build_runtime_plugin()
+            .configure(&mut config, &mut interceptors)
+            .unwrap();
+
+        let mut context = InterceptorContext::new(TypedBox::new("doesntmatter").erase());
+        context.set_request(http::Request::builder().body(SdkBody::empty()).unwrap());
+        config.put(ApiMetadata::new("some-service", "some-version"));
+        interceptors
+            .modify_before_signing(&mut context, &mut config)
+            .unwrap();
+
+        assert!(expect_header(&context, "x-amz-user-agent").contains("api/some-service"));
+    }
+
     #[test]
     fn test_api_metadata_missing() {
         let mut context = InterceptorContext::new(TypedBox::new("doesntmatter").erase());
@@ -0,0 +1,226 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! A [`Plugin`] which sends a percentage of requests to an experimental version of an operation.
+//!
+//! The experimental version is built by applying a [`Layer`] to the stable one, so it can wrap it, or replace it
+//! with a different handler altogether. For every request, a random number generator decides which of the two
+//! handles it, and the decision is logged with the name of the chosen arm.
+//!
+//! The plugin applies to every operation; use [`filter_by_operation_name`](super::filter_by_operation_name) to
+//! restrict it to some of them.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use aws_smithy_http_server::plugin::{filter_by_operation_name, PluginPipeline, ab_test::AbTestPlugin};
+//! # use tower::layer::util::Identity;
+//! # struct GetPokemonSpecies;
+//! # impl GetPokemonSpecies { const NAME: &'static str = ""; }
+//! # let experimental_layer = Identity::new();
+//! // Send 5% of `GetPokemonSpecies` requests to the experimental handler.
+//! let ab_test = AbTestPlugin::new(experimental_layer, 5);
+//! let plugins = PluginPipeline::new().push(filter_by_operation_name(ab_test, |name| name == GetPokemonSpecies::NAME));
+//! ```
+
+use std::{
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use futures_util::ready;
+use tower::{layer::util::Stack, Layer, Service};
+
+use crate::operation::{Operation, OperationShape};
+
+use super::{Either, Plugin};
+
+/// A [`Plugin`] which applies an [`AbTestLayer`] to every operation.
+///
+/// Clones of the plugin share their random number generator. See the [module](crate::plugin::ab_test)
+/// documentation for more information.
+#[derive(Clone, Debug)]
+pub struct AbTestPlugin<Exp> {
+    experimental: Exp,
+    percent: u8,
+    rng: Arc<Mutex<fastrand::Rng>>,
+}
+
+impl<Exp> AbTestPlugin<Exp> {
+    /// Sends `percent` percent of requests to the service built by the `experimental` layer, and the rest to the
+    /// stable service.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percent` is greater than 100.
+    pub fn new(experimental: Exp, percent: u8) -> Self {
+        Self::with_rng(experimental, percent, fastrand::Rng::new())
+    }
+
+    /// Like [`AbTestPlugin::new`], but seeds the random number generator with `seed`, so that the split is
+    /// reproducible.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percent` is greater than 100.
+    pub fn with_seed(experimental: Exp, percent: u8, seed: u64) -> Self {
+        Self::with_rng(experimental, percent, fastrand::Rng::with_seed(seed))
+    }
+
+    fn with_rng(experimental: Exp, percent: u8, rng: fastrand::Rng) -> Self {
+        assert!(percent <= 100, "`percent` must be at most 100, but was {percent}");
+        Self {
+            experimental,
+            percent,
+            rng: Arc::new(Mutex::new(rng)),
+        }
+    }
+}
+
+impl<P, Op, S, L, Exp> Plugin<P, Op, S, L> for AbTestPlugin<Exp>
+where
+    Op: OperationShape,
+    Exp: Clone,
+{
+    type Service = S;
+    type Layer = Stack<L, AbTestLayer<Exp>>;
+
+    fn map(&self, input: Operation<S, L>) -> Operation<Self::Service, Self::Layer> {
+        input.layer(AbTestLayer {
+            experimental: self.experimental.clone(),
+            percent: self.percent,
+            rng: self.rng.clone(),
+            operation_name: Op::NAME,
+        })
+    }
+}
+
+/// A [`Layer`] used to apply [`AbTestService`].
+#[derive(Clone, Debug)]
+pub struct AbTestLayer<Exp> {
+    experimental: Exp,
+    percent: u8,
+    rng: Arc<Mutex<fastrand::Rng>>,
+    operation_name: &'static str,
+}
+
+impl<S, Exp> Layer<S> for AbTestLayer<Exp>
+where
+    S: Clone,
+    Exp: Layer<S>,
+{
+    type Service = AbTestService<Exp::Service, S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AbTestService {
+            experimental: self.experimental.layer(inner.clone()),
+            stable: inner,
+            percent: self.percent,
+            rng: self.rng.clone(),
+            operation_name: self.operation_name,
+        }
+    }
+}
+
+/// A middleware [`Service`] which sends each request to either the experimental or the stable service.
+///
+/// Its future is an [`Either::Left`] when the experimental service was chosen, and an [`Either::Right`] when the
+/// stable one was.
+#[derive(Clone, Debug)]
+pub struct AbTestService<E, S> {
+    experimental: E,
+    stable: S,
+    percent: u8,
+    rng: Arc<Mutex<fastrand::Rng>>,
+    operation_name: &'static str,
+}
+
+impl<E, S, R> Service<R> for AbTestService<E, S>
+where
+    E: Service<R>,
+    S: Service<R, Response = E::Response, Error = E::Error>,
+{
+    type Response = E::Response;
+    type Error = E::Error;
+    type Future = Either<E::Future, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // Which service handles the request is only decided once it's called, so both must be ready
+        ready!(self.experimental.poll_ready(cx))?;
+        self.stable.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        let experimental = self.rng.lock().unwrap().u8(0..100) < self.percent;
+        if experimental {
+            tracing::debug!(
+                operation = self.operation_name,
+                arm = "experimental",
+                "A/B test chose an arm"
+            );
+            Either::Left {
+                value: self.experimental.call(req),
+            }
+        } else {
+            tracing::debug!(operation = self.operation_name, arm = "stable", "A/B test chose an arm");
+            Either::Right {
+                value: self.stable.call(req),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use http::{Request, Response};
+    use tower::layer::{layer_fn, util::Identity};
+    use tower::util::BoxCloneService;
+    use tower::{service_fn, ServiceExt};
+
+    use crate::body::{Body, BoxBody};
+    use crate::plugin::test_operations::{layer_operation, GetPokemonSpecies};
+
+    use super::*;
+
+    type StableService = BoxCloneService<Request<Body>, Response<BoxBody>, Infallible>;
+
+    fn respond(arm: &'static str) -> Result<Response<BoxBody>, Infallible> {
+        Ok(Response::builder()
+            .header("x-arm", arm)
+            .body(crate::body::empty())
+            .unwrap())
+    }
+
+    #[tokio::test]
+    async fn requests_are_split_by_percentage() {
+        // The experimental handler replaces the stable one entirely
+        let experimental =
+            layer_fn(|_stable: StableService| service_fn(|_req: Request<Body>| async { respond("experimental") }));
+        let plugin = AbTestPlugin::with_seed(experimental, 20, 42);
+        let svc = layer_operation::<GetPokemonSpecies, _, _>(
+            &plugin,
+            StableService::new(service_fn(|_req: Request<Body>| async { respond("stable") })),
+        );
+
+        let requests = 10_000;
+        let mut experimental_responses = 0;
+        for _ in 0..requests {
+            let response = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+            if response.headers()["x-arm"] == "experimental" {
+                experimental_responses += 1;
+            }
+        }
+        let ratio = experimental_responses as f64 / requests as f64;
+        assert!((0.18..=0.22).contains(&ratio), "{ratio}");
+    }
+
+    #[test]
+    #[should_panic(expected = "`percent` must be at most 100")]
+    fn percent_over_100_panics() {
+        AbTestPlugin::new(Identity::new(), 101);
+    }
+}
@@ -0,0 +1,327 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! A [`Plugin`] which bounds the size of request bodies.
+//!
+//! Requests whose `Content-Length` exceeds the limit are rejected with a `413 Payload Too Large` response, without
+//! calling the operation. Since `Content-Length` can't be trusted, the limit is also enforced while the body is
+//! streamed: once the body has produced more bytes than the limit, it fails with a [`BodyTooLarge`] error, which
+//! the operation rejects like any other error reading the body.
+//!
+//! Each operation may be given its own limit. Operations without a limit accept bodies of any size.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use aws_smithy_http_server::plugin::{PluginPipeline, body_limit::BodyLimitPlugin};
+//! # struct UploadPicture;
+//! # impl UploadPicture { const NAME: &'static str = ""; }
+//! let plugins = PluginPipeline::new().push(
+//!     // Request bodies may be up to 64 KiB...
+//!     BodyLimitPlugin::new(64 * 1024)
+//!         // ...except for `UploadPicture`, which accepts up to 10 MiB.
+//!         .operation_limit(UploadPicture::NAME, 10 * 1024 * 1024),
+//! );
+//! ```
+
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Buf;
+use futures_util::ready;
+use http::{header::CONTENT_LENGTH, HeaderMap, Request, Response, StatusCode};
+use http_body::{Body, SizeHint};
+use pin_project_lite::pin_project;
+use tower::{layer::util::Stack, Layer, Service};
+
+use crate::body::BoxBody;
+use crate::error::BoxError;
+use crate::operation::{Operation, OperationShape};
+
+use super::{empty_response, Plugin};
+
+/// A [`Plugin`] which applies a [`BodyLimitLayer`] to every operation that has a body limit.
+///
+/// See the [module](crate::plugin::body_limit) documentation for more information.
+#[derive(Clone, Debug, Default)]
+pub struct BodyLimitPlugin {
+    default_limit: Option<u64>,
+    operation_limits: HashMap<&'static str, u64>,
+}
+
+impl BodyLimitPlugin {
+    /// Limits the body of every request to `limit` bytes.
+    pub fn new(limit: u64) -> Self {
+        Self {
+            default_limit: Some(limit),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the body limit of the operation named `operation_name`, overriding the default limit.
+    ///
+    /// The name is compared against [`OperationShape::NAME`].
+    pub fn operation_limit(mut self, operation_name: &'static str, limit: u64) -> Self {
+        self.operation_limits.insert(operation_name, limit);
+        self
+    }
+}
+
+impl<P, Op, S, L> Plugin<P, Op, S, L> for BodyLimitPlugin
+where
+    Op: OperationShape,
+{
+    type Service = S;
+    type Layer = Stack<L, BodyLimitLayer>;
+
+    fn map(&self, input: Operation<S, L>) -> Operation<Self::Service, Self::Layer> {
+        let limit = self.operation_limits.get(Op::NAME).copied().or(self.default_limit);
+        input.layer(BodyLimitLayer { limit })
+    }
+}
+
+/// A [`Layer`] used to apply [`BodyLimitService`].
+#[derive(Clone, Debug)]
+pub struct BodyLimitLayer {
+    limit: Option<u64>,
+}
+
+impl<S> Layer<S> for BodyLimitLayer {
+    type Service = BodyLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BodyLimitService {
+            inner,
+            limit: self.limit,
+        }
+    }
+}
+
+/// A middleware [`Service`] which rejects requests whose body is larger than its limit.
+///
+/// The inner service receives the request body wrapped in a [`LimitedBody`], which is unbounded if the
+/// operation has no limit.
+#[derive(Clone, Debug)]
+pub struct BodyLimitService<S> {
+    inner: S,
+    limit: Option<u64>,
+}
+
+impl<S, B> Service<Request<B>> for BodyLimitService<S>
+where
+    S: Service<Request<LimitedBody<B>>, Response = Response<BoxBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BodyLimitFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let content_length = req
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        let inner = match (content_length, self.limit) {
+            (Some(content_length), Some(limit)) if content_length > limit => None,
+            _ => {
+                let limit = self.limit.unwrap_or(u64::MAX);
+                Some(self.inner.call(req.map(|body| LimitedBody::new(body, limit))))
+            }
+        };
+        BodyLimitFuture { inner }
+    }
+}
+
+pin_project! {
+    /// Future for [`BodyLimitService`].
+    pub struct BodyLimitFuture<F> {
+        // `None` if the request was rejected because of its `Content-Length`.
+        #[pin]
+        inner: Option<F>,
+    }
+}
+
+impl<F, E> Future for BodyLimitFuture<F>
+where
+    F: Future<Output = Result<Response<BoxBody>, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project().inner.as_pin_mut() {
+            Some(future) => future.poll(cx),
+            None => Poll::Ready(Ok(empty_response(StatusCode::PAYLOAD_TOO_LARGE))),
+        }
+    }
+}
+
+/// The error a [`LimitedBody`] fails with once it has produced more bytes than its limit.
+#[derive(Debug)]
+pub struct BodyTooLarge {
+    limit: u64,
+}
+
+impl BodyTooLarge {
+    /// Returns the limit that the body exceeded, in bytes.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+}
+
+impl fmt::Display for BodyTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the request body is larger than the limit of {} bytes", self.limit)
+    }
+}
+
+impl std::error::Error for BodyTooLarge {}
+
+pin_project! {
+    /// A request body which fails with [`BodyTooLarge`] once it has produced more than its limit.
+    pub struct LimitedBody<B> {
+        #[pin]
+        inner: B,
+        remaining: u64,
+        limit: u64,
+    }
+}
+
+impl<B> LimitedBody<B> {
+    fn new(inner: B, limit: u64) -> Self {
+        Self {
+            inner,
+            remaining: limit,
+            limit,
+        }
+    }
+}
+
+impl<B> Body for LimitedBody<B>
+where
+    B: Body,
+    B::Error: Into<BoxError>,
+{
+    type Data = B::Data;
+    type Error = BoxError;
+
+    fn poll_data(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.project();
+        match ready!(this.inner.poll_data(cx)) {
+            Some(Ok(data)) => {
+                let len = data.remaining() as u64;
+                if len > *this.remaining {
+                    *this.remaining = 0;
+                    return Poll::Ready(Some(Err(Box::new(BodyTooLarge { limit: *this.limit }))));
+                }
+                *this.remaining -= len;
+                Poll::Ready(Some(Ok(data)))
+            }
+            Some(Err(err)) => Poll::Ready(Some(Err(err.into()))),
+            None => Poll::Ready(None),
+        }
+    }
+
+    fn poll_trailers(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+        self.project().inner.poll_trailers(cx).map_err(Into::into)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use bytes::Bytes;
+    use tower::{service_fn, ServiceExt};
+
+    use crate::body::Body;
+    use crate::plugin::test_operations::{layer_operation, UploadPicture};
+
+    use super::*;
+
+    /// Applies `plugin` to an operation which reads the whole body, and responds with `500 Internal Server Error`
+    /// and the error message if that fails.
+    async fn send(plugin: &BodyLimitPlugin, request: Request<Body>) -> Response<BoxBody> {
+        let svc = layer_operation::<UploadPicture, _, _>(
+            plugin,
+            service_fn(|req: Request<LimitedBody<Body>>| async move {
+                let response = match hyper::body::to_bytes(req.into_body()).await {
+                    Ok(_) => Response::builder()
+                        .header("x-handled", "true")
+                        .body(crate::body::empty()),
+                    Err(err) => Response::builder()
+                        .status(StatusCode::INTERNAL_SERVER_ERROR)
+                        .body(crate::body::to_boxed(err.to_string())),
+                };
+                Ok::<_, Infallible>(response.unwrap())
+            }),
+        );
+        svc.oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn body_under_the_limit_passes_through() {
+        let plugin = BodyLimitPlugin::new(16);
+
+        let response = send(&plugin, Request::new(Body::from("hello"))).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!("true", response.headers()["x-handled"]);
+    }
+
+    #[tokio::test]
+    async fn content_length_over_the_limit_is_rejected() {
+        let plugin = BodyLimitPlugin::new(4);
+        let request = Request::builder()
+            .header(CONTENT_LENGTH, "5")
+            .body(Body::from("hello"))
+            .unwrap();
+
+        let response = send(&plugin, request).await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+        assert!(response.headers().get("x-handled").is_none());
+    }
+
+    #[tokio::test]
+    async fn body_streaming_past_the_limit_is_cut_off() {
+        let plugin = BodyLimitPlugin::new(8);
+        let chunks = ["hello ", "world"].map(|chunk| Ok::<_, Infallible>(Bytes::from(chunk)));
+        let request = Request::builder()
+            .header(CONTENT_LENGTH, "4")
+            .body(Body::wrap_stream(futures_util::stream::iter(chunks)))
+            .unwrap();
+
+        let response = send(&plugin, request).await;
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, response.status());
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(
+            "the request body is larger than the limit of 8 bytes",
+            std::str::from_utf8(&body).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn operation_limit_overrides_the_default() {
+        let plugin = BodyLimitPlugin::new(4).operation_limit(UploadPicture::NAME, 16);
+
+        let response = send(&plugin, Request::new(Body::from("hello"))).await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+}
@@ -0,0 +1,441 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! A [`Plugin`] which stops calling an operation that keeps failing.
+//!
+//! After a configurable number of consecutive failures, the circuit "opens" and requests to the operation are
+//! rejected with a `503 Service Unavailable` response, without calling the operation, until a cooldown has passed.
+//! The circuit then "half-opens": a single trial request is let through. If it succeeds, the circuit closes and
+//! requests flow normally again; if it fails, the circuit opens for another cooldown.
+//!
+//! A response is considered a failure if it is an error or has a `5xx` status code. Each operation has its own
+//! circuit, so a failing operation doesn't affect the others.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use aws_smithy_http_server::plugin::{PluginPipeline, circuit_breaker::CircuitBreakerPlugin};
+//! let plugins = PluginPipeline::new()
+//!     // After 5 consecutive failures, reject an operation's requests for 30 seconds.
+//!     .push(CircuitBreakerPlugin::new(5, Duration::from_secs(30)));
+//! ```
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures_util::ready;
+use http::{Request, Response, StatusCode};
+use pin_project_lite::pin_project;
+use tower::{layer::util::Stack, Layer, Service};
+
+use crate::body::BoxBody;
+use crate::operation::Operation;
+
+use super::{empty_response, Plugin};
+
+/// A source of the current time, used by [`CircuitBreakerPlugin`] to measure the cooldown.
+pub trait Clock: Clone {
+    /// Returns the current time.
+    fn now(&self) -> Instant;
+}
+
+/// A [`Clock`] backed by [`Instant::now`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A [`Plugin`] which applies a [`CircuitBreakerLayer`] to every operation.
+///
+/// See the [module](crate::plugin::circuit_breaker) documentation for more information.
+#[derive(Clone, Debug)]
+pub struct CircuitBreakerPlugin<C = SystemClock> {
+    failure_threshold: u32,
+    cooldown: Duration,
+    clock: C,
+}
+
+impl CircuitBreakerPlugin {
+    /// Opens an operation's circuit after `failure_threshold` consecutive failures, and keeps it open for `cooldown`.
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold,
+            cooldown,
+            clock: SystemClock,
+        }
+    }
+}
+
+impl<C> CircuitBreakerPlugin<C> {
+    /// Replaces the [`Clock`] used to measure the cooldown.
+    pub fn clock<NewClock>(self, clock: NewClock) -> CircuitBreakerPlugin<NewClock> {
+        CircuitBreakerPlugin {
+            failure_threshold: self.failure_threshold,
+            cooldown: self.cooldown,
+            clock,
+        }
+    }
+}
+
+impl<P, Op, S, L, C> Plugin<P, Op, S, L> for CircuitBreakerPlugin<C>
+where
+    C: Clock,
+{
+    type Service = S;
+    type Layer = Stack<L, CircuitBreakerLayer<C>>;
+
+    fn map(&self, input: Operation<S, L>) -> Operation<Self::Service, Self::Layer> {
+        // Every operation is mapped once, so this gives each operation its own circuit.
+        input.layer(CircuitBreakerLayer {
+            breaker: Breaker::new(self.failure_threshold, self.cooldown, self.clock.clone()),
+        })
+    }
+}
+
+/// A [`Layer`] used to apply [`CircuitBreakerService`].
+///
+/// All the services produced by a layer share the same circuit.
+#[derive(Clone, Debug)]
+pub struct CircuitBreakerLayer<C = SystemClock> {
+    breaker: Breaker<C>,
+}
+
+impl<S, C> Layer<S> for CircuitBreakerLayer<C>
+where
+    C: Clone,
+{
+    type Service = CircuitBreakerService<S, C>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CircuitBreakerService {
+            inner,
+            breaker: self.breaker.clone(),
+        }
+    }
+}
+
+/// A middleware [`Service`] which rejects requests while its circuit is open.
+#[derive(Clone, Debug)]
+pub struct CircuitBreakerService<S, C = SystemClock> {
+    inner: S,
+    breaker: Breaker<C>,
+}
+
+impl<S, B, C> Service<Request<B>> for CircuitBreakerService<S, C>
+where
+    S: Service<Request<B>, Response = Response<BoxBody>>,
+    C: Clock,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = CircuitBreakerFuture<S::Future, C>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let inner = if self.breaker.try_acquire() {
+            Some(self.inner.call(req))
+        } else {
+            None
+        };
+        CircuitBreakerFuture {
+            inner,
+            breaker: self.breaker.clone(),
+        }
+    }
+}
+
+pin_project! {
+    /// Future for [`CircuitBreakerService`].
+    pub struct CircuitBreakerFuture<F, C> {
+        // `None` if the request was rejected because the circuit is open.
+        #[pin]
+        inner: Option<F>,
+        breaker: Breaker<C>,
+    }
+}
+
+impl<F, E, C> Future for CircuitBreakerFuture<F, C>
+where
+    F: Future<Output = Result<Response<BoxBody>, E>>,
+    C: Clock,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        match this.inner.as_pin_mut() {
+            Some(future) => {
+                let result = ready!(future.poll(cx));
+                let succeeded = matches!(&result, Ok(response) if !response.status().is_server_error());
+                this.breaker.record(succeeded);
+                Poll::Ready(result)
+            }
+            None => Poll::Ready(Ok(empty_response(StatusCode::SERVICE_UNAVAILABLE))),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum CircuitState {
+    Closed { consecutive_failures: u32 },
+    Open { opened_at: Instant },
+    // A trial request is in flight. Should the trial be cancelled, another is let through after a further cooldown.
+    HalfOpen { trial_started_at: Instant },
+}
+
+#[derive(Clone, Debug)]
+struct Breaker<C> {
+    state: Arc<Mutex<CircuitState>>,
+    failure_threshold: u32,
+    cooldown: Duration,
+    clock: C,
+}
+
+impl<C> Breaker<C>
+where
+    C: Clock,
+{
+    fn new(failure_threshold: u32, cooldown: Duration, clock: C) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(CircuitState::Closed {
+                consecutive_failures: 0,
+            })),
+            failure_threshold,
+            cooldown,
+            clock,
+        }
+    }
+
+    /// Returns `true` if a request may be sent.
+    fn try_acquire(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            CircuitState::Closed { .. } => true,
+            CircuitState::Open { opened_at: since }
+            | CircuitState::HalfOpen {
+                trial_started_at: since,
+            } => {
+                let now = self.clock.now();
+                if now.saturating_duration_since(since) >= self.cooldown {
+                    *state = CircuitState::HalfOpen { trial_started_at: now };
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    /// Records the outcome of a request that was sent.
+    fn record(&self, succeeded: bool) {
+        let mut state = self.state.lock().unwrap();
+        *state = match *state {
+            _ if succeeded => CircuitState::Closed {
+                consecutive_failures: 0,
+            },
+            CircuitState::Closed { consecutive_failures } if consecutive_failures + 1 < self.failure_threshold => {
+                CircuitState::Closed {
+                    consecutive_failures: consecutive_failures + 1,
+                }
+            }
+            _ => CircuitState::Open {
+                opened_at: self.clock.now(),
+            },
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use tower::{service_fn, ServiceExt};
+
+    use crate::body::Body;
+    use crate::operation::OperationShape;
+    use crate::plugin::filter_by_operation_name;
+    use crate::plugin::test_operations::{layer_operation, GetPokemon};
+
+    use super::*;
+
+    #[derive(Clone, Debug)]
+    struct TestClock(Arc<Mutex<Instant>>);
+
+    impl TestClock {
+        fn new() -> Self {
+            Self(Arc::new(Mutex::new(Instant::now())))
+        }
+
+        fn advance(&self, duration: Duration) {
+            *self.0.lock().unwrap() += duration;
+        }
+    }
+
+    impl Clock for TestClock {
+        fn now(&self) -> Instant {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    /// An operation service which responds with `status` and counts how many times it was called.
+    #[derive(Clone, Default)]
+    struct Backend {
+        status: Arc<Mutex<StatusCode>>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl Backend {
+        fn respond_with(&self, status: StatusCode) {
+            *self.status.lock().unwrap() = status;
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    fn apply(
+        plugin: &CircuitBreakerPlugin<TestClock>,
+        backend: &Backend,
+    ) -> impl Service<Request<Body>, Response = Response<BoxBody>, Error = Infallible> {
+        let backend = backend.clone();
+        layer_operation::<GetPokemon, _, _>(
+            plugin,
+            service_fn(move |_req: Request<Body>| {
+                backend.calls.fetch_add(1, Ordering::SeqCst);
+                let status = *backend.status.lock().unwrap();
+                async move { Ok::<_, Infallible>(Response::builder().status(status).body(crate::body::empty()).unwrap()) }
+            }),
+        )
+    }
+
+    async fn send<S>(svc: &mut S) -> StatusCode
+    where
+        S: Service<Request<Body>, Response = Response<BoxBody>, Error = Infallible>,
+    {
+        let response = svc.ready().await.unwrap().call(Request::new(Body::empty())).await;
+        response.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn opens_after_threshold_consecutive_failures() {
+        let plugin = CircuitBreakerPlugin::new(3, Duration::from_secs(10)).clock(TestClock::new());
+        let backend = Backend::default();
+        backend.respond_with(StatusCode::INTERNAL_SERVER_ERROR);
+        let mut svc = apply(&plugin, &backend);
+
+        for _ in 0..3 {
+            assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, send(&mut svc).await);
+        }
+        assert_eq!(3, backend.calls());
+
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, send(&mut svc).await);
+        assert_eq!(3, backend.calls());
+    }
+
+    #[tokio::test]
+    async fn success_resets_consecutive_failures() {
+        let plugin = CircuitBreakerPlugin::new(2, Duration::from_secs(10)).clock(TestClock::new());
+        let backend = Backend::default();
+        let mut svc = apply(&plugin, &backend);
+
+        backend.respond_with(StatusCode::INTERNAL_SERVER_ERROR);
+        send(&mut svc).await;
+        backend.respond_with(StatusCode::OK);
+        send(&mut svc).await;
+        backend.respond_with(StatusCode::INTERNAL_SERVER_ERROR);
+        send(&mut svc).await;
+
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, send(&mut svc).await);
+        assert_eq!(4, backend.calls());
+    }
+
+    #[tokio::test]
+    async fn open_circuit_rejects_without_calling_the_operation() {
+        let clock = TestClock::new();
+        let plugin = CircuitBreakerPlugin::new(1, Duration::from_secs(10)).clock(clock.clone());
+        let backend = Backend::default();
+        backend.respond_with(StatusCode::INTERNAL_SERVER_ERROR);
+        let mut svc = apply(&plugin, &backend);
+        send(&mut svc).await;
+
+        // The operation has recovered, but the circuit stays open until the cooldown has passed.
+        backend.respond_with(StatusCode::OK);
+        clock.advance(Duration::from_secs(9));
+        for _ in 0..5 {
+            assert_eq!(StatusCode::SERVICE_UNAVAILABLE, send(&mut svc).await);
+        }
+        assert_eq!(1, backend.calls());
+    }
+
+    #[tokio::test]
+    async fn half_open_circuit_closes_after_a_successful_trial() {
+        let clock = TestClock::new();
+        let plugin = CircuitBreakerPlugin::new(1, Duration::from_secs(10)).clock(clock.clone());
+        let backend = Backend::default();
+        backend.respond_with(StatusCode::INTERNAL_SERVER_ERROR);
+        let mut svc = apply(&plugin, &backend);
+        send(&mut svc).await;
+
+        backend.respond_with(StatusCode::OK);
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(StatusCode::OK, send(&mut svc).await);
+        assert_eq!(StatusCode::OK, send(&mut svc).await);
+        assert_eq!(3, backend.calls());
+    }
+
+    #[tokio::test]
+    async fn half_open_circuit_reopens_after_a_failed_trial() {
+        let clock = TestClock::new();
+        let plugin = CircuitBreakerPlugin::new(1, Duration::from_secs(10)).clock(clock.clone());
+        let backend = Backend::default();
+        backend.respond_with(StatusCode::INTERNAL_SERVER_ERROR);
+        let mut svc = apply(&plugin, &backend);
+        send(&mut svc).await;
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, send(&mut svc).await);
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, send(&mut svc).await);
+        assert_eq!(2, backend.calls());
+    }
+
+    #[tokio::test]
+    async fn circuits_are_per_operation() {
+        let plugin = CircuitBreakerPlugin::new(1, Duration::from_secs(10)).clock(TestClock::new());
+        let (failing, healthy) = (Backend::default(), Backend::default());
+        failing.respond_with(StatusCode::INTERNAL_SERVER_ERROR);
+        let mut failing_svc = apply(&plugin, &failing);
+        let mut healthy_svc = apply(&plugin, &healthy);
+
+        send(&mut failing_svc).await;
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, send(&mut failing_svc).await);
+        assert_eq!(StatusCode::OK, send(&mut healthy_svc).await);
+    }
+
+    #[test]
+    fn composes_with_either() {
+        let plugin = filter_by_operation_name(CircuitBreakerPlugin::new(1, Duration::from_secs(10)), |name| {
+            name == GetPokemon::NAME
+        });
+        assert!(layer_operation::<GetPokemon, _, _>(&plugin, ()).left().is_some());
+
+        let plugin = filter_by_operation_name(CircuitBreakerPlugin::new(1, Duration::from_secs(10)), |_| false);
+        assert!(layer_operation::<GetPokemon, _, _>(&plugin, ()).right().is_some());
+    }
+}
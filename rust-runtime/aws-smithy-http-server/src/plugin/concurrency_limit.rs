@@ -0,0 +1,337 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! A [`Plugin`] which bounds how many requests an operation handles at the same time.
+//!
+//! Requests beyond an operation's limit are rejected right away with a `503 Service Unavailable` response, rather
+//! than queued until a request in flight completes, so that a burst of requests can't pile up unboundedly in front
+//! of a slow handler. The response can be replaced using [`ConcurrencyLimitPlugin::limit_response`], for example
+//! with a modeled error.
+//!
+//! Each operation may be given its own limit, and has its own permits, so a busy operation doesn't affect the
+//! others. Operations without a limit are left untouched.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use aws_smithy_http_server::plugin::{PluginPipeline, concurrency_limit::ConcurrencyLimitPlugin};
+//! # struct UploadPicture;
+//! # impl UploadPicture { const NAME: &'static str = ""; }
+//! let plugins = PluginPipeline::new().push(
+//!     // Operations may handle 100 requests at a time...
+//!     ConcurrencyLimitPlugin::new(100)
+//!         // ...except for `UploadPicture`, which may only handle 5.
+//!         .operation_limit(UploadPicture::NAME, 5),
+//! );
+//! ```
+
+use std::{
+    collections::HashMap,
+    future::{self, Future, Ready},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures_util::ready;
+use http::{Request, Response, StatusCode};
+use pin_project_lite::pin_project;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tower::{
+    layer::util::{Identity, Stack},
+    Layer, Service,
+};
+
+use crate::body::BoxBody;
+use crate::operation::{Operation, OperationShape};
+
+use super::{empty_response, Either, Plugin};
+
+/// A [`Plugin`] which applies a [`ConcurrencyLimitLayer`] to every operation that has a limit.
+///
+/// See the [module](crate::plugin::concurrency_limit) documentation for more information.
+#[derive(Clone, Debug)]
+pub struct ConcurrencyLimitPlugin {
+    default_limit: Option<usize>,
+    operation_limits: HashMap<&'static str, usize>,
+    limit_response: fn() -> Response<BoxBody>,
+}
+
+impl Default for ConcurrencyLimitPlugin {
+    /// Creates a [`ConcurrencyLimitPlugin`] that only applies the limits set with
+    /// [`ConcurrencyLimitPlugin::operation_limit`].
+    fn default() -> Self {
+        Self {
+            default_limit: None,
+            operation_limits: HashMap::new(),
+            limit_response: service_unavailable,
+        }
+    }
+}
+
+impl ConcurrencyLimitPlugin {
+    /// Limits every operation to handling `limit` requests at the same time.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            default_limit: Some(limit),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the limit of the operation named `operation_name`, overriding the default limit.
+    ///
+    /// The name is compared against [`OperationShape::NAME`].
+    pub fn operation_limit(mut self, operation_name: &'static str, limit: usize) -> Self {
+        self.operation_limits.insert(operation_name, limit);
+        self
+    }
+
+    /// Replaces the `503 Service Unavailable` response returned when an operation is at its limit.
+    pub fn limit_response(mut self, limit_response: fn() -> Response<BoxBody>) -> Self {
+        self.limit_response = limit_response;
+        self
+    }
+}
+
+impl<P, Op, S, L> Plugin<P, Op, S, L> for ConcurrencyLimitPlugin
+where
+    Op: OperationShape,
+{
+    type Service = S;
+    type Layer = Stack<L, Either<ConcurrencyLimitLayer, Identity>>;
+
+    fn map(&self, input: Operation<S, L>) -> Operation<Self::Service, Self::Layer> {
+        let limit = self.operation_limits.get(Op::NAME).copied().or(self.default_limit);
+        let layer = match limit {
+            Some(limit) => Either::Left {
+                value: ConcurrencyLimitLayer {
+                    operation_name: Op::NAME,
+                    semaphore: Arc::new(Semaphore::new(limit)),
+                    limit_response: self.limit_response,
+                },
+            },
+            None => Either::Right { value: Identity::new() },
+        };
+        input.layer(layer)
+    }
+}
+
+/// A [`Layer`] used to apply [`ConcurrencyLimitService`].
+///
+/// The services it creates share its permits.
+#[derive(Clone, Debug)]
+pub struct ConcurrencyLimitLayer {
+    operation_name: &'static str,
+    semaphore: Arc<Semaphore>,
+    limit_response: fn() -> Response<BoxBody>,
+}
+
+impl<S> Layer<S> for ConcurrencyLimitLayer {
+    type Service = ConcurrencyLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ConcurrencyLimitService {
+            inner,
+            operation_name: self.operation_name,
+            semaphore: self.semaphore.clone(),
+            limit_response: self.limit_response,
+        }
+    }
+}
+
+/// A middleware [`Service`] which responds with a limit response, without calling the inner service, if the
+/// operation is already handling as many requests as it's allowed to.
+#[derive(Clone, Debug)]
+pub struct ConcurrencyLimitService<S> {
+    inner: S,
+    operation_name: &'static str,
+    semaphore: Arc<Semaphore>,
+    limit_response: fn() -> Response<BoxBody>,
+}
+
+impl<S> ConcurrencyLimitService<S> {
+    /// Returns how many more requests the operation can handle right now.
+    pub fn available_permits(&self) -> usize {
+        self.semaphore.available_permits()
+    }
+}
+
+impl<S, B> Service<Request<B>> for ConcurrencyLimitService<S>
+where
+    S: Service<Request<B>, Response = Response<BoxBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Either<Ready<Result<S::Response, S::Error>>, ConcurrencyLimitFuture<S::Future>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        match self.semaphore.clone().try_acquire_owned() {
+            Ok(permit) => Either::Right {
+                value: ConcurrencyLimitFuture {
+                    inner: self.inner.call(req),
+                    permit: Some(permit),
+                },
+            },
+            Err(_) => {
+                tracing::debug!(
+                    operation = self.operation_name,
+                    "rejecting request because the operation is at its concurrency limit"
+                );
+                Either::Left {
+                    value: future::ready(Ok((self.limit_response)())),
+                }
+            }
+        }
+    }
+}
+
+pin_project! {
+    /// Future for [`ConcurrencyLimitService`], which holds its permit until it completes or is dropped.
+    pub struct ConcurrencyLimitFuture<F> {
+        #[pin]
+        inner: F,
+        permit: Option<OwnedSemaphorePermit>,
+    }
+}
+
+impl<F: Future> Future for ConcurrencyLimitFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let output = ready!(this.inner.poll(cx));
+        // Release the permit as soon as the request is handled, even if the future isn't dropped right away
+        this.permit.take();
+        Poll::Ready(output)
+    }
+}
+
+fn service_unavailable() -> Response<BoxBody> {
+    empty_response(StatusCode::SERVICE_UNAVAILABLE)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use tower::{service_fn, ServiceExt};
+
+    use crate::body::Body;
+    use crate::plugin::test_operations::{layer_operation, GetPokemon};
+
+    use super::*;
+
+    /// Applies `plugin` to an operation which responds once it acquires a permit from `gate`.
+    fn apply(
+        plugin: &ConcurrencyLimitPlugin,
+        gate: Arc<Semaphore>,
+    ) -> ConcurrencyLimitService<
+        impl Service<Request<Body>, Response = Response<BoxBody>, Error = Infallible, Future = impl Send> + Clone,
+    > {
+        let svc = layer_operation::<GetPokemon, _, _>(
+            plugin,
+            service_fn(move |_req: Request<Body>| {
+                let gate = gate.clone();
+                async move {
+                    gate.acquire().await.unwrap().forget();
+                    Ok::<_, Infallible>(Response::new(crate::body::empty()))
+                }
+            }),
+        );
+        match svc {
+            Either::Left { value } => value,
+            Either::Right { .. } => panic!("`GetPokemon` has a limit"),
+        }
+    }
+
+    #[tokio::test]
+    async fn requests_within_the_limit_succeed() {
+        let gate = Arc::new(Semaphore::new(2));
+        let svc = apply(&ConcurrencyLimitPlugin::new(2), gate);
+
+        let (first, second) = tokio::join!(
+            svc.clone().oneshot(Request::new(Body::empty())),
+            svc.clone().oneshot(Request::new(Body::empty()))
+        );
+        assert_eq!(StatusCode::OK, first.unwrap().status());
+        assert_eq!(StatusCode::OK, second.unwrap().status());
+    }
+
+    #[tokio::test]
+    async fn requests_over_the_limit_are_rejected_immediately() {
+        let gate = Arc::new(Semaphore::new(0));
+        let svc = apply(&ConcurrencyLimitPlugin::new(2), gate.clone());
+        let first = tokio::spawn(svc.clone().oneshot(Request::new(Body::empty())));
+        let second = tokio::spawn(svc.clone().oneshot(Request::new(Body::empty())));
+        while svc.available_permits() > 0 {
+            tokio::task::yield_now().await;
+        }
+
+        let response = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, response.status());
+
+        gate.add_permits(2);
+        assert_eq!(StatusCode::OK, first.await.unwrap().unwrap().status());
+        assert_eq!(StatusCode::OK, second.await.unwrap().unwrap().status());
+    }
+
+    #[tokio::test]
+    async fn permits_are_released_once_requests_complete() {
+        let gate = Arc::new(Semaphore::new(0));
+        let svc = apply(&ConcurrencyLimitPlugin::new(1), gate.clone());
+        let first = tokio::spawn(svc.clone().oneshot(Request::new(Body::empty())));
+        while svc.available_permits() > 0 {
+            tokio::task::yield_now().await;
+        }
+
+        gate.add_permits(1);
+        first.await.unwrap().unwrap();
+        assert_eq!(1, svc.available_permits());
+        gate.add_permits(1);
+        let response = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[tokio::test]
+    async fn operation_limit_overrides_the_default() {
+        let gate = Arc::new(Semaphore::new(0));
+        let plugin = ConcurrencyLimitPlugin::new(5).operation_limit(GetPokemon::NAME, 1);
+        let svc = apply(&plugin, gate.clone());
+        let _first = tokio::spawn(svc.clone().oneshot(Request::new(Body::empty())));
+        while svc.available_permits() > 0 {
+            tokio::task::yield_now().await;
+        }
+
+        let response = svc.clone().oneshot(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, response.status());
+    }
+
+    #[tokio::test]
+    async fn limit_response_is_configurable() {
+        let plugin = ConcurrencyLimitPlugin::new(0).limit_response(|| {
+            Response::builder()
+                .status(StatusCode::TOO_MANY_REQUESTS)
+                .body(crate::body::empty())
+                .unwrap()
+        });
+        let svc = apply(&plugin, Arc::new(Semaphore::new(0)));
+
+        let response = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+    }
+
+    #[test]
+    fn operations_without_a_limit_are_left_untouched() {
+        assert!(
+            layer_operation::<GetPokemon, _, _>(&ConcurrencyLimitPlugin::default(), ())
+                .right()
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,404 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! A [`Plugin`] which rejects operation inputs that violate their modeled [constraint traits] before the handler
+//! is called.
+//!
+//! Unlike the other plugins, this one wraps the operation's inner service rather than the HTTP service, since it
+//! needs the parsed input. Each operation describes its constraints by implementing [`ValidateConstraints`], using
+//! [`check_length`], [`check_range`] and [`check_pattern`] to check its members, and converting the resulting
+//! [`ConstraintViolation`] into its validation error. Operations without constrained members implement it by
+//! returning `Ok(())`.
+//!
+//! [constraint traits]: https://awslabs.github.io/smithy/2.0/spec/constraint-traits.html
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use aws_smithy_http_server::plugin::{PluginPipeline, constraint_validation::ConstraintValidationPlugin};
+//! let plugins = PluginPipeline::new().push(ConstraintValidationPlugin);
+//! ```
+
+use std::{
+    fmt,
+    future::{ready, Ready},
+    marker::PhantomData,
+    task::{Context, Poll},
+};
+
+use regex::Regex;
+use tower::Service;
+
+use crate::operation::{Operation, OperationError, OperationShape};
+
+use super::{Either, Plugin};
+
+/// Checks an operation's input against the constraints modeled on its members.
+pub trait ValidateConstraints: OperationShape {
+    /// Returns the operation's validation error if `input` violates any of its constraints.
+    fn validate(input: &Self::Input) -> Result<(), Self::Error>;
+}
+
+/// A violation of a single constraint trait, as found by [`check_length`], [`check_range`] or [`check_pattern`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConstraintViolation {
+    /// The member violated its `@length` constraint.
+    Length {
+        /// The name of the member.
+        member: &'static str,
+        /// The length of the member's value.
+        length: usize,
+        /// The minimum length, if there is one.
+        min: Option<usize>,
+        /// The maximum length, if there is one.
+        max: Option<usize>,
+    },
+    /// The member violated its `@range` constraint.
+    Range {
+        /// The name of the member.
+        member: &'static str,
+        /// The member's value.
+        value: i64,
+        /// The minimum value, if there is one.
+        min: Option<i64>,
+        /// The maximum value, if there is one.
+        max: Option<i64>,
+    },
+    /// The member violated its `@pattern` constraint.
+    Pattern {
+        /// The name of the member.
+        member: &'static str,
+        /// The pattern that the member's value didn't match.
+        pattern: String,
+    },
+}
+
+impl ConstraintViolation {
+    /// Returns the name of the member that violated its constraint.
+    pub fn member(&self) -> &'static str {
+        match self {
+            ConstraintViolation::Length { member, .. }
+            | ConstraintViolation::Range { member, .. }
+            | ConstraintViolation::Pattern { member, .. } => member,
+        }
+    }
+}
+
+/// Formats `min` and `max` as the bounds of a constraint.
+fn bounds<T: fmt::Display>(min: &Option<T>, max: &Option<T>) -> String {
+    match (min, max) {
+        (Some(min), Some(max)) => format!("between {min} and {max}, inclusive"),
+        (Some(min), None) => format!("greater than or equal to {min}"),
+        (None, Some(max)) => format!("less than or equal to {max}"),
+        (None, None) => "unbounded".to_string(),
+    }
+}
+
+impl fmt::Display for ConstraintViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstraintViolation::Length {
+                member,
+                length,
+                min,
+                max,
+            } => write!(
+                f,
+                "Value with length {length} at '/{member}' failed to satisfy constraint: Member must have length {}",
+                bounds(min, max)
+            ),
+            ConstraintViolation::Range {
+                member,
+                value,
+                min,
+                max,
+            } => write!(
+                f,
+                "Value {value} at '/{member}' failed to satisfy constraint: Member must be {}",
+                bounds(min, max)
+            ),
+            ConstraintViolation::Pattern { member, pattern } => write!(
+                f,
+                "Value at '/{member}' failed to satisfy constraint: Member must satisfy regular expression pattern: \
+                {pattern}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConstraintViolation {}
+
+/// Checks the `length` of `member` against its `@length` constraint.
+pub fn check_length(
+    member: &'static str,
+    length: usize,
+    min: Option<usize>,
+    max: Option<usize>,
+) -> Result<(), ConstraintViolation> {
+    let too_short = min.map_or(false, |min| length < min);
+    let too_long = max.map_or(false, |max| length > max);
+    if too_short || too_long {
+        return Err(ConstraintViolation::Length {
+            member,
+            length,
+            min,
+            max,
+        });
+    }
+    Ok(())
+}
+
+/// Checks the `value` of `member` against its `@range` constraint.
+pub fn check_range(
+    member: &'static str,
+    value: i64,
+    min: Option<i64>,
+    max: Option<i64>,
+) -> Result<(), ConstraintViolation> {
+    let too_small = min.map_or(false, |min| value < min);
+    let too_large = max.map_or(false, |max| value > max);
+    if too_small || too_large {
+        return Err(ConstraintViolation::Range {
+            member,
+            value,
+            min,
+            max,
+        });
+    }
+    Ok(())
+}
+
+/// Checks the `value` of `member` against its `@pattern` constraint.
+///
+/// As the constraint trait specifies, the pattern isn't implicitly anchored: it only has to match part of the value.
+pub fn check_pattern(member: &'static str, value: &str, pattern: &Regex) -> Result<(), ConstraintViolation> {
+    if !pattern.is_match(value) {
+        return Err(ConstraintViolation::Pattern {
+            member,
+            pattern: pattern.as_str().to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// A [`Plugin`] which wraps the inner service of every operation in a [`ConstraintValidationService`].
+///
+/// See the [module](crate::plugin::constraint_validation) documentation for more information.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConstraintValidationPlugin;
+
+impl<P, Op, S, L> Plugin<P, Op, S, L> for ConstraintValidationPlugin
+where
+    Op: ValidateConstraints,
+{
+    type Service = ConstraintValidationService<Op, S>;
+    type Layer = L;
+
+    fn map(&self, input: Operation<S, L>) -> Operation<Self::Service, Self::Layer> {
+        Operation {
+            inner: ConstraintValidationService {
+                inner: input.inner,
+                _operation: PhantomData,
+            },
+            layer: input.layer,
+        }
+    }
+}
+
+/// A middleware [`Service`] which validates the input of the operation `Op` before calling the inner service.
+///
+/// Its future is an [`Either::Left`] holding the validation error when the input is invalid, and an [`Either::Right`]
+/// holding the inner service's future otherwise.
+pub struct ConstraintValidationService<Op, S> {
+    inner: S,
+    _operation: PhantomData<Op>,
+}
+
+impl<Op, S> Clone for ConstraintValidationService<Op, S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            _operation: PhantomData,
+        }
+    }
+}
+
+impl<Op, S> fmt::Debug for ConstraintValidationService<Op, S>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConstraintValidationService")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<Op, S, Exts, PollError> Service<(Op::Input, Exts)> for ConstraintValidationService<Op, S>
+where
+    Op: ValidateConstraints,
+    S: Service<(Op::Input, Exts), Error = OperationError<Op::Error, PollError>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Either<Ready<Result<S::Response, S::Error>>, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, (input, exts): (Op::Input, Exts)) -> Self::Future {
+        match Op::validate(&input) {
+            Ok(()) => Either::Right {
+                value: self.inner.call((input, exts)),
+            },
+            Err(err) => {
+                tracing::debug!(operation = Op::NAME, "rejecting input which violates its constraints");
+                Either::Left {
+                    value: ready(Err(OperationError::Model(err))),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use once_cell::sync::Lazy;
+    use tower::{service_fn, ServiceExt};
+
+    use super::*;
+
+    struct CreatePokemon;
+
+    struct CreatePokemonInput {
+        name: String,
+        level: i64,
+        trainer_id: String,
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum CreatePokemonError {
+        Validation(ConstraintViolation),
+    }
+
+    impl From<ConstraintViolation> for CreatePokemonError {
+        fn from(violation: ConstraintViolation) -> Self {
+            CreatePokemonError::Validation(violation)
+        }
+    }
+
+    impl OperationShape for CreatePokemon {
+        const NAME: &'static str = "CreatePokemon";
+
+        type Input = CreatePokemonInput;
+        type Output = String;
+        type Error = CreatePokemonError;
+    }
+
+    static TRAINER_ID: Lazy<Regex> = Lazy::new(|| Regex::new("^[a-z]+-[0-9]+$").unwrap());
+
+    impl ValidateConstraints for CreatePokemon {
+        fn validate(input: &Self::Input) -> Result<(), Self::Error> {
+            check_length("name", input.name.chars().count(), Some(1), Some(12))?;
+            check_range("level", input.level, Some(1), Some(100))?;
+            check_pattern("trainer_id", &input.trainer_id, &TRAINER_ID)?;
+            Ok(())
+        }
+    }
+
+    fn input() -> CreatePokemonInput {
+        CreatePokemonInput {
+            name: "pikachu".to_string(),
+            level: 5,
+            trainer_id: "ash-1".to_string(),
+        }
+    }
+
+    /// Calls an operation whose handler responds with the name of the Pokémon.
+    async fn create_pokemon(input: CreatePokemonInput) -> Result<String, CreatePokemonError> {
+        let operation = Plugin::<(), CreatePokemon, _, ()>::map(
+            &ConstraintValidationPlugin,
+            Operation {
+                inner: service_fn(|(input, ()): (CreatePokemonInput, ())| async move {
+                    Ok::<_, OperationError<CreatePokemonError, Infallible>>(input.name)
+                }),
+                layer: (),
+            },
+        );
+        match operation.inner.oneshot((input, ())).await {
+            Ok(output) => Ok(output),
+            Err(OperationError::Model(err)) => Err(err),
+            Err(OperationError::PollReady(_)) => unreachable!("the handler is always ready"),
+        }
+    }
+
+    #[tokio::test]
+    async fn valid_input_reaches_the_handler() {
+        assert_eq!(Ok("pikachu".to_string()), create_pokemon(input()).await);
+    }
+
+    #[tokio::test]
+    async fn length_violation_is_rejected() {
+        let input = CreatePokemonInput {
+            name: "".to_string(),
+            ..input()
+        };
+
+        let err = create_pokemon(input).await.unwrap_err();
+        assert_eq!(
+            CreatePokemonError::Validation(ConstraintViolation::Length {
+                member: "name",
+                length: 0,
+                min: Some(1),
+                max: Some(12),
+            }),
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn range_violation_is_rejected() {
+        let input = CreatePokemonInput { level: 101, ..input() };
+
+        let CreatePokemonError::Validation(violation) = create_pokemon(input).await.unwrap_err();
+        assert_eq!(
+            ConstraintViolation::Range {
+                member: "level",
+                value: 101,
+                min: Some(1),
+                max: Some(100),
+            },
+            violation
+        );
+        assert_eq!(
+            "Value 101 at '/level' failed to satisfy constraint: Member must be between 1 and 100, inclusive",
+            violation.to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn pattern_violation_is_rejected() {
+        let input = CreatePokemonInput {
+            trainer_id: "Ash".to_string(),
+            ..input()
+        };
+
+        let CreatePokemonError::Validation(violation) = create_pokemon(input).await.unwrap_err();
+        assert_eq!("trainer_id", violation.member());
+        assert_eq!(
+            ConstraintViolation::Pattern {
+                member: "trainer_id",
+                pattern: "^[a-z]+-[0-9]+$".to_string(),
+            },
+            violation
+        );
+    }
+}
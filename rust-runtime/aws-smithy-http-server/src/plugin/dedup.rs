@@ -0,0 +1,629 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! A [`Plugin`] which coalesces identical requests that are in flight at the same time.
+//!
+//! Requests are identified by a key computed from their method, URI and headers by a user-supplied function. While
+//! a request is being handled, any other request to the same operation with the same key waits for it to complete
+//! instead of calling the handler, and receives a copy of its response. Requests with distinct keys, or for which
+//! the function returns `None`, are handled independently.
+//!
+//! This is only appropriate for expensive idempotent read operations, so operations have to be opted in with
+//! [`DedupPlugin::operation`]. The key is computed before the request body is read, so requests that only differ
+//! by their bodies are coalesced: the plugin is meant for operations whose requests don't have a body.
+//!
+//! Responses are buffered in memory so that they can be shared, and the copies don't include the
+//! [extensions](http::Extensions) of the original response. Only successful responses are shared: if the handler
+//! fails, responds with a status other than 2xx, or its response body can't be read, the requests that were waiting
+//! on it are handled independently. So are they if the response body is larger than
+//! [`DedupPlugin::max_buffered_size`], in which case the response is passed through without being buffered.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use aws_smithy_http_server::plugin::{PluginPipeline, dedup::DedupPlugin};
+//! # struct GetPokemonSpecies;
+//! # impl GetPokemonSpecies { const NAME: &'static str = ""; }
+//! let plugins = PluginPipeline::new().push(
+//!     // Requests for the same species share a response.
+//!     DedupPlugin::new(|parts| Some(parts.uri.to_string())).operation(GetPokemonSpecies::NAME),
+//! );
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    future::Future,
+    hash::Hash,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use bytes::{Bytes, BytesMut};
+use http::{request, HeaderMap, Request, Response, StatusCode, Version};
+use http_body::Body as _;
+use tokio::sync::broadcast;
+use tower::{
+    layer::util::{Identity, Stack},
+    Layer, Service, ServiceExt,
+};
+
+use crate::body::BoxBody;
+use crate::operation::{Operation, OperationShape};
+
+use super::{empty_response, Either, Plugin};
+
+type InFlightRequests<K> = Arc<Mutex<HashMap<K, broadcast::Sender<BufferedResponse>>>>;
+
+/// The largest response body that is buffered to be shared, unless configured otherwise: 1 MiB.
+const DEFAULT_MAX_BUFFERED_SIZE: usize = 1024 * 1024;
+
+/// A [`Plugin`] which applies a [`DedupLayer`] to every operation that has been opted in.
+///
+/// See the [module](crate::plugin::dedup) documentation for more information.
+pub struct DedupPlugin<K> {
+    key: fn(&request::Parts) -> Option<K>,
+    operations: HashSet<&'static str>,
+    max_buffered_size: usize,
+}
+
+impl<K> Clone for DedupPlugin<K> {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key,
+            operations: self.operations.clone(),
+            max_buffered_size: self.max_buffered_size,
+        }
+    }
+}
+
+impl<K> fmt::Debug for DedupPlugin<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DedupPlugin")
+            .field("operations", &self.operations)
+            .field("max_buffered_size", &self.max_buffered_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<K> DedupPlugin<K> {
+    /// Creates a [`DedupPlugin`] which identifies requests by the key returned by `key`.
+    ///
+    /// Requests for which `key` returns `None` are never coalesced. The request body isn't available to `key`, so
+    /// the operations that are opted in shouldn't have request bodies.
+    pub fn new(key: fn(&request::Parts) -> Option<K>) -> Self {
+        Self {
+            key,
+            operations: HashSet::new(),
+            max_buffered_size: DEFAULT_MAX_BUFFERED_SIZE,
+        }
+    }
+
+    /// Replaces the size, in bytes, of the largest response body that is buffered to be shared. Defaults to 1 MiB.
+    ///
+    /// Larger responses are passed through to the request that they're for, and the requests that were waiting on
+    /// it are handled independently.
+    pub fn max_buffered_size(mut self, max_buffered_size: usize) -> Self {
+        self.max_buffered_size = max_buffered_size;
+        self
+    }
+
+    /// Coalesces the requests of the operation named `operation_name`.
+    ///
+    /// The name is compared against [`OperationShape::NAME`].
+    pub fn operation(mut self, operation_name: &'static str) -> Self {
+        self.operations.insert(operation_name);
+        self
+    }
+}
+
+impl<P, Op, S, L, K> Plugin<P, Op, S, L> for DedupPlugin<K>
+where
+    Op: OperationShape,
+{
+    type Service = S;
+    type Layer = Stack<L, Either<DedupLayer<K>, Identity>>;
+
+    fn map(&self, input: Operation<S, L>) -> Operation<Self::Service, Self::Layer> {
+        let layer = if self.operations.contains(Op::NAME) {
+            Either::Left {
+                value: DedupLayer {
+                    operation_name: Op::NAME,
+                    key: self.key,
+                    max_buffered_size: self.max_buffered_size,
+                    in_flight: Arc::new(Mutex::new(HashMap::new())),
+                },
+            }
+        } else {
+            Either::Right { value: Identity::new() }
+        };
+        input.layer(layer)
+    }
+}
+
+/// A [`Layer`] used to apply [`DedupService`].
+///
+/// The services it creates share their in-flight requests.
+pub struct DedupLayer<K> {
+    operation_name: &'static str,
+    key: fn(&request::Parts) -> Option<K>,
+    max_buffered_size: usize,
+    in_flight: InFlightRequests<K>,
+}
+
+impl<K> Clone for DedupLayer<K> {
+    fn clone(&self) -> Self {
+        Self {
+            operation_name: self.operation_name,
+            key: self.key,
+            max_buffered_size: self.max_buffered_size,
+            in_flight: self.in_flight.clone(),
+        }
+    }
+}
+
+impl<K> fmt::Debug for DedupLayer<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DedupLayer")
+            .field("operation_name", &self.operation_name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, K> Layer<S> for DedupLayer<K> {
+    type Service = DedupService<S, K>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DedupService {
+            inner,
+            operation_name: self.operation_name,
+            key: self.key,
+            max_buffered_size: self.max_buffered_size,
+            in_flight: self.in_flight.clone(),
+        }
+    }
+}
+
+/// A middleware [`Service`] which shares the response of a request with the identical requests that arrive while
+/// it's being handled.
+pub struct DedupService<S, K> {
+    inner: S,
+    operation_name: &'static str,
+    key: fn(&request::Parts) -> Option<K>,
+    max_buffered_size: usize,
+    in_flight: InFlightRequests<K>,
+}
+
+impl<S: Clone, K> Clone for DedupService<S, K> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            operation_name: self.operation_name,
+            key: self.key,
+            max_buffered_size: self.max_buffered_size,
+            in_flight: self.in_flight.clone(),
+        }
+    }
+}
+
+impl<S: fmt::Debug, K> fmt::Debug for DedupService<S, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DedupService")
+            .field("inner", &self.inner)
+            .field("operation_name", &self.operation_name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, K, B> Service<Request<B>> for DedupService<S, K>
+where
+    S: Service<Request<B>, Response = Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send,
+    S::Error: Send,
+    K: Hash + Eq + Clone + Send + 'static,
+    B: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        // The inner service is ready, so it's the one that must be called.
+        let clone = self.inner.clone();
+        let inner = std::mem::replace(&mut self.inner, clone);
+
+        let (parts, body) = req.into_parts();
+        let key = (self.key)(&parts);
+        let req = Request::from_parts(parts, body);
+        let key = match key {
+            Some(key) => key,
+            None => return Box::pin(inner.oneshot(req)),
+        };
+
+        // Whether this request is the first with its key is decided under the lock, so that every request that
+        // waits on another one is subscribed before its response is sent.
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(sender) = in_flight.get(&key) {
+            let mut receiver = sender.subscribe();
+            drop(in_flight);
+            let operation = self.operation_name;
+            return Box::pin(async move {
+                match receiver.recv().await {
+                    Ok(response) => Ok(response.into_response()),
+                    Err(_) => {
+                        tracing::debug!(
+                            operation,
+                            "the coalesced request failed, so the request is being handled on its own"
+                        );
+                        inner.oneshot(req).await
+                    }
+                }
+            });
+        }
+        let (sender, _) = broadcast::channel(1);
+        in_flight.insert(key.clone(), sender);
+        drop(in_flight);
+
+        let guard = InFlight {
+            key: Some(key),
+            in_flight: self.in_flight.clone(),
+        };
+        let operation = self.operation_name;
+        let max_buffered_size = self.max_buffered_size;
+        Box::pin(async move {
+            // If the handler fails, or this future is dropped, the guard removes the request without sending a
+            // response.
+            let response = inner.oneshot(req).await?;
+            // Errors may be specific to the request, or transient, so they're never shared.
+            if !response.status().is_success() {
+                return Ok(response);
+            }
+            let (parts, body) = response.into_parts();
+            let body = match read_body(body, max_buffered_size).await {
+                Ok(Ok(body)) => body,
+                // The guard is dropped, so the waiting requests are handled independently.
+                Ok(Err(body)) => {
+                    tracing::debug!(operation, "the response body is too large to be shared");
+                    return Ok(Response::from_parts(parts, body));
+                }
+                Err(err) => {
+                    tracing::debug!(operation, error = %err, "failed to read the response body");
+                    return Ok(empty_response(StatusCode::INTERNAL_SERVER_ERROR));
+                }
+            };
+            guard.complete(BufferedResponse {
+                status: parts.status,
+                version: parts.version,
+                headers: parts.headers.clone(),
+                body: body.clone(),
+            });
+            Ok(Response::from_parts(parts, crate::body::to_boxed(body)))
+        })
+    }
+}
+
+/// Reads `body` into memory, unless it's larger than `max_size` bytes, in which case it's returned as if it hadn't
+/// been read.
+async fn read_body(mut body: BoxBody, max_size: usize) -> Result<Result<Bytes, BoxBody>, crate::Error> {
+    if body.size_hint().lower() > max_size as u64 {
+        return Ok(Err(body));
+    }
+    let mut buffered = BytesMut::new();
+    while let Some(data) = body.data().await {
+        buffered.extend_from_slice(&data?);
+        if buffered.len() > max_size {
+            return Ok(Err(crate::body::boxed(PartiallyReadBody {
+                read: Some(buffered.freeze()),
+                rest: body,
+            })));
+        }
+    }
+    Ok(Ok(buffered.freeze()))
+}
+
+/// A body whose beginning has already been read.
+struct PartiallyReadBody {
+    read: Option<Bytes>,
+    rest: BoxBody,
+}
+
+impl http_body::Body for PartiallyReadBody {
+    type Data = Bytes;
+    type Error = crate::Error;
+
+    fn poll_data(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, crate::Error>>> {
+        match self.read.take() {
+            Some(read) => Poll::Ready(Some(Ok(read))),
+            None => Pin::new(&mut self.rest).poll_data(cx),
+        }
+    }
+
+    fn poll_trailers(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<Option<HeaderMap>, crate::Error>> {
+        Pin::new(&mut self.rest).poll_trailers(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.read.is_none() && self.rest.is_end_stream()
+    }
+}
+
+/// A response that has been read into memory, so that it can be shared between requests.
+#[derive(Clone, Debug)]
+struct BufferedResponse {
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl BufferedResponse {
+    fn into_response(self) -> Response<BoxBody> {
+        let mut response = Response::new(crate::body::to_boxed(self.body));
+        *response.status_mut() = self.status;
+        *response.version_mut() = self.version;
+        *response.headers_mut() = self.headers;
+        response
+    }
+}
+
+/// Removes an in-flight request once it completes or is dropped.
+struct InFlight<K: Hash + Eq> {
+    key: Option<K>,
+    in_flight: InFlightRequests<K>,
+}
+
+impl<K: Hash + Eq> InFlight<K> {
+    fn remove(&mut self) -> Option<broadcast::Sender<BufferedResponse>> {
+        let key = self.key.take()?;
+        self.in_flight.lock().unwrap().remove(&key)
+    }
+
+    /// Sends `response` to the requests waiting on this one.
+    fn complete(mut self, response: BufferedResponse) {
+        if let Some(sender) = self.remove() {
+            // There may not be any waiting requests
+            let _ = sender.send(response);
+        }
+    }
+}
+
+impl<K: Hash + Eq> Drop for InFlight<K> {
+    fn drop(&mut self) {
+        self.remove();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use tokio::sync::Semaphore;
+    use tower::service_fn;
+
+    use crate::body::Body;
+    use crate::plugin::test_operations::{layer_operation, GetPokemonSpecies};
+
+    use super::*;
+
+    fn by_path(parts: &request::Parts) -> Option<String> {
+        Some(parts.uri.path().to_owned())
+    }
+
+    /// Applies `plugin` to an operation which counts its calls in `calls`, fails its first `failures` calls, and
+    /// responds with the request path once it acquires a permit from `gate`. Requests for `/missingno` are
+    /// responded to with a 404.
+    fn apply(
+        plugin: &DedupPlugin<String>,
+        calls: Arc<AtomicUsize>,
+        failures: usize,
+        gate: Arc<Semaphore>,
+    ) -> DedupService<
+        impl Service<Request<Body>, Response = Response<BoxBody>, Error = &'static str, Future = impl Send> + Clone,
+        String,
+    > {
+        let svc = layer_operation::<GetPokemonSpecies, _, _>(
+            plugin,
+            service_fn(move |req: Request<Body>| {
+                let call = calls.fetch_add(1, Ordering::SeqCst);
+                let gate = gate.clone();
+                async move {
+                    gate.acquire().await.unwrap().forget();
+                    if call < failures {
+                        return Err("handler failed");
+                    }
+                    let status = match req.uri().path() {
+                        "/missingno" => StatusCode::NOT_FOUND,
+                        _ => StatusCode::OK,
+                    };
+                    Ok(Response::builder()
+                        .status(status)
+                        .header("x-call", call.to_string())
+                        .body(crate::body::to_boxed(req.uri().path().to_owned()))
+                        .unwrap())
+                }
+            }),
+        );
+        match svc {
+            Either::Left { value } => value,
+            Either::Right { .. } => panic!("`GetPokemonSpecies` is deduplicated"),
+        }
+    }
+
+    fn request(path: &str) -> Request<Body> {
+        Request::builder().uri(path).body(Body::empty()).unwrap()
+    }
+
+    async fn read(response: Response<BoxBody>) -> (String, Bytes) {
+        let call = response.headers()["x-call"].to_str().unwrap().to_owned();
+        (call, hyper::body::to_bytes(response.into_body()).await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn concurrent_identical_requests_share_one_call() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let gate = Arc::new(Semaphore::new(0));
+        let plugin = DedupPlugin::new(by_path).operation(GetPokemonSpecies::NAME);
+        let svc = apply(&plugin, calls.clone(), 0, gate.clone());
+
+        // Every request is received before the handler is allowed to respond.
+        let (first, second, third, fourth, ()) = tokio::join!(
+            svc.clone().oneshot(request("/pikachu")),
+            svc.clone().oneshot(request("/pikachu")),
+            svc.clone().oneshot(request("/pikachu")),
+            svc.clone().oneshot(request("/pikachu")),
+            async { gate.add_permits(1) },
+        );
+
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+        for response in [first, second, third, fourth] {
+            let response = response.unwrap();
+            assert_eq!(StatusCode::OK, response.status());
+            assert_eq!(("0".to_owned(), Bytes::from("/pikachu")), read(response).await);
+        }
+        assert!(svc.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn distinct_keys_are_handled_independently() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let gate = Arc::new(Semaphore::new(0));
+        let plugin = DedupPlugin::new(by_path).operation(GetPokemonSpecies::NAME);
+        let svc = apply(&plugin, calls.clone(), 0, gate.clone());
+
+        let (pikachu, bulbasaur, ()) = tokio::join!(
+            svc.clone().oneshot(request("/pikachu")),
+            svc.clone().oneshot(request("/bulbasaur")),
+            async { gate.add_permits(2) },
+        );
+
+        assert_eq!(2, calls.load(Ordering::SeqCst));
+        assert_eq!(Bytes::from("/pikachu"), read(pikachu.unwrap()).await.1);
+        assert_eq!(Bytes::from("/bulbasaur"), read(bulbasaur.unwrap()).await.1);
+    }
+
+    #[tokio::test]
+    async fn requests_without_a_key_are_handled_independently() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let gate = Arc::new(Semaphore::new(0));
+        let plugin = DedupPlugin::new(|_| None).operation(GetPokemonSpecies::NAME);
+        let svc = apply(&plugin, calls.clone(), 0, gate.clone());
+
+        let (first, second, ()) = tokio::join!(
+            svc.clone().oneshot(request("/pikachu")),
+            svc.clone().oneshot(request("/pikachu")),
+            async { gate.add_permits(2) },
+        );
+
+        assert_eq!(2, calls.load(Ordering::SeqCst));
+        first.unwrap();
+        second.unwrap();
+    }
+
+    #[tokio::test]
+    async fn sequential_identical_requests_are_not_coalesced() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let plugin = DedupPlugin::new(by_path).operation(GetPokemonSpecies::NAME);
+        let svc = apply(&plugin, calls.clone(), 0, Arc::new(Semaphore::new(2)));
+
+        let first = svc.clone().oneshot(request("/pikachu")).await.unwrap();
+        let second = svc.clone().oneshot(request("/pikachu")).await.unwrap();
+
+        assert_eq!(2, calls.load(Ordering::SeqCst));
+        assert_eq!("0", read(first).await.0);
+        assert_eq!("1", read(second).await.0);
+    }
+
+    #[tokio::test]
+    async fn waiting_requests_are_handled_independently_if_the_call_fails() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let gate = Arc::new(Semaphore::new(0));
+        let plugin = DedupPlugin::new(by_path).operation(GetPokemonSpecies::NAME);
+        let svc = apply(&plugin, calls.clone(), 1, gate.clone());
+
+        let (first, second, ()) = tokio::join!(
+            svc.clone().oneshot(request("/pikachu")),
+            svc.clone().oneshot(request("/pikachu")),
+            async { gate.add_permits(2) },
+        );
+
+        assert_eq!("handler failed", first.unwrap_err());
+        assert_eq!("1", read(second.unwrap()).await.0);
+        assert_eq!(2, calls.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn waiting_requests_are_handled_independently_if_the_call_is_unsuccessful() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let gate = Arc::new(Semaphore::new(0));
+        let plugin = DedupPlugin::new(by_path).operation(GetPokemonSpecies::NAME);
+        let svc = apply(&plugin, calls.clone(), 0, gate.clone());
+
+        let (first, second, ()) = tokio::join!(
+            svc.clone().oneshot(request("/missingno")),
+            svc.clone().oneshot(request("/missingno")),
+            async { gate.add_permits(2) },
+        );
+
+        assert_eq!(2, calls.load(Ordering::SeqCst));
+        let (first, second) = (first.unwrap(), second.unwrap());
+        assert_eq!(StatusCode::NOT_FOUND, first.status());
+        assert_eq!(StatusCode::NOT_FOUND, second.status());
+        assert_ne!(read(first).await.0, read(second).await.0);
+        assert!(svc.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn responses_larger_than_the_max_buffered_size_are_not_shared() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let gate = Arc::new(Semaphore::new(0));
+        let plugin = DedupPlugin::new(by_path)
+            .operation(GetPokemonSpecies::NAME)
+            .max_buffered_size(4);
+        let svc = apply(&plugin, calls.clone(), 0, gate.clone());
+
+        let (first, second, ()) = tokio::join!(
+            svc.clone().oneshot(request("/pikachu")),
+            svc.clone().oneshot(request("/pikachu")),
+            async { gate.add_permits(2) },
+        );
+
+        assert_eq!(2, calls.load(Ordering::SeqCst));
+        let (first, second) = (read(first.unwrap()).await, read(second.unwrap()).await);
+        assert_ne!(first.0, second.0);
+        assert_eq!(Bytes::from("/pikachu"), first.1);
+        assert_eq!(Bytes::from("/pikachu"), second.1);
+        assert!(svc.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn bodies_of_unknown_size_are_only_read_up_to_the_max_buffered_size() {
+        let streamed = |chunks: &'static [&'static str]| {
+            crate::body::boxed(hyper::Body::wrap_stream(futures_util::stream::iter(
+                chunks.iter().map(|chunk| Ok::<_, std::io::Error>(*chunk)),
+            )))
+        };
+
+        let body = read_body(streamed(&["/pik", "achu"]), 8).await.unwrap();
+        assert_eq!(Bytes::from("/pikachu"), body.unwrap());
+
+        let body = read_body(streamed(&["/pik", "achu"]), 4).await.unwrap();
+        let body = body.expect_err("the body is larger than 4 bytes");
+        assert_eq!(Bytes::from("/pikachu"), hyper::body::to_bytes(body).await.unwrap());
+    }
+
+    #[test]
+    fn operations_that_are_not_opted_in_are_left_untouched() {
+        assert!(
+            layer_operation::<GetPokemonSpecies, _, _>(&DedupPlugin::new(by_path), ())
+                .right()
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,320 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! A [`Plugin`] which lets a server finish the requests it is handling before it shuts down.
+//!
+//! The [`DrainPlugin`] counts the requests that are in flight for every operation. Once [`DrainPlugin::drain`] is
+//! called, new requests are rejected with a `503 Service Unavailable` response, without calling the operation, and
+//! the returned future resolves when the last in-flight request has completed. A request whose future is dropped
+//! before completing, for example because the client disconnected, no longer counts as in flight.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use aws_smithy_http_server::plugin::{PluginPipeline, drain::DrainPlugin};
+//! # async fn shutdown_signal() {}
+//! let drain = DrainPlugin::new();
+//! let plugins = PluginPipeline::new().push(drain.clone());
+//! // Build the service with `plugins` and serve it.
+//!
+//! # async {
+//! shutdown_signal().await;
+//! // Wait for the in-flight requests before stopping the server.
+//! drain.drain().await;
+//! # };
+//! ```
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use http::{Request, Response, StatusCode};
+use pin_project_lite::pin_project;
+use tokio::sync::Notify;
+use tower::{layer::util::Stack, Layer, Service};
+
+use crate::body::BoxBody;
+use crate::operation::{Operation, OperationShape};
+
+use super::{empty_response, Plugin};
+
+#[derive(Debug, Default)]
+struct InFlight {
+    draining: bool,
+    by_operation: HashMap<&'static str, usize>,
+}
+
+#[derive(Debug, Default)]
+struct DrainState {
+    in_flight: Mutex<InFlight>,
+    // Notified whenever the last in-flight request completes.
+    idle: Notify,
+}
+
+impl DrainState {
+    /// Counts a new request to `operation_name` as in flight, or returns `None` if the server is draining.
+    fn try_acquire(self: &Arc<Self>, operation_name: &'static str) -> Option<InFlightGuard> {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if in_flight.draining {
+            return None;
+        }
+        *in_flight.by_operation.entry(operation_name).or_default() += 1;
+        Some(InFlightGuard {
+            state: self.clone(),
+            operation_name,
+        })
+    }
+
+    fn total(&self) -> usize {
+        self.in_flight.lock().unwrap().by_operation.values().sum()
+    }
+}
+
+/// Marks a request as in flight until it is dropped.
+#[derive(Debug)]
+struct InFlightGuard {
+    state: Arc<DrainState>,
+    operation_name: &'static str,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        let mut in_flight = self.state.in_flight.lock().unwrap();
+        if let Some(count) = in_flight.by_operation.get_mut(self.operation_name) {
+            *count -= 1;
+        }
+        if in_flight.by_operation.values().all(|count| *count == 0) {
+            self.state.idle.notify_waiters();
+        }
+    }
+}
+
+/// A [`Plugin`] which applies a [`DrainLayer`] to every operation.
+///
+/// Clones of the plugin share their state, so a clone can be kept around to [`drain`](DrainPlugin::drain) the
+/// server. See the [module](crate::plugin::drain) documentation for more information.
+#[derive(Clone, Debug, Default)]
+pub struct DrainPlugin {
+    state: Arc<DrainState>,
+}
+
+impl DrainPlugin {
+    /// Creates a new [`DrainPlugin`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of requests to the operation named `operation_name` that are in flight.
+    ///
+    /// The name is compared against [`OperationShape::NAME`].
+    pub fn in_flight(&self, operation_name: &str) -> usize {
+        let in_flight = self.state.in_flight.lock().unwrap();
+        in_flight.by_operation.get(operation_name).copied().unwrap_or_default()
+    }
+
+    /// Returns `true` once [`drain`](DrainPlugin::drain) has been called.
+    pub fn is_draining(&self) -> bool {
+        self.state.in_flight.lock().unwrap().draining
+    }
+
+    /// Rejects all new requests, and resolves once every in-flight request has completed.
+    pub async fn drain(&self) {
+        self.state.in_flight.lock().unwrap().draining = true;
+        loop {
+            // The future is created before checking, so that it can't miss the last request completing.
+            let idle = self.state.idle.notified();
+            if self.state.total() == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+}
+
+impl<P, Op, S, L> Plugin<P, Op, S, L> for DrainPlugin
+where
+    Op: OperationShape,
+{
+    type Service = S;
+    type Layer = Stack<L, DrainLayer>;
+
+    fn map(&self, input: Operation<S, L>) -> Operation<Self::Service, Self::Layer> {
+        input.layer(DrainLayer {
+            state: self.state.clone(),
+            operation_name: Op::NAME,
+        })
+    }
+}
+
+/// A [`Layer`] used to apply [`DrainService`].
+#[derive(Clone, Debug)]
+pub struct DrainLayer {
+    state: Arc<DrainState>,
+    operation_name: &'static str,
+}
+
+impl<S> Layer<S> for DrainLayer {
+    type Service = DrainService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DrainService {
+            inner,
+            state: self.state.clone(),
+            operation_name: self.operation_name,
+        }
+    }
+}
+
+/// A middleware [`Service`] which counts in-flight requests, and rejects new requests while the server is draining.
+#[derive(Clone, Debug)]
+pub struct DrainService<S> {
+    inner: S,
+    state: Arc<DrainState>,
+    operation_name: &'static str,
+}
+
+impl<S, B> Service<Request<B>> for DrainService<S>
+where
+    S: Service<Request<B>, Response = Response<BoxBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = DrainFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let guard = self.state.try_acquire(self.operation_name);
+        let inner = guard.as_ref().map(|_| self.inner.call(req));
+        DrainFuture { inner, _guard: guard }
+    }
+}
+
+pin_project! {
+    /// Future for [`DrainService`].
+    pub struct DrainFuture<F> {
+        // `None` if the request was rejected because the server is draining.
+        #[pin]
+        inner: Option<F>,
+        _guard: Option<InFlightGuard>,
+    }
+}
+
+impl<F, E> Future for DrainFuture<F>
+where
+    F: Future<Output = Result<Response<BoxBody>, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project().inner.as_pin_mut() {
+            Some(future) => future.poll(cx),
+            None => Poll::Ready(Ok(empty_response(StatusCode::SERVICE_UNAVAILABLE))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use futures_util::FutureExt;
+    use tokio::sync::oneshot;
+    use tower::{service_fn, ServiceExt};
+
+    use crate::body::Body;
+    use crate::plugin::test_operations::{layer_operation, GetPokemon};
+
+    use super::*;
+
+    /// Applies `plugin` to an operation which responds once `release` is sent a value.
+    fn apply(
+        plugin: &DrainPlugin,
+        release: oneshot::Receiver<()>,
+    ) -> impl Service<Request<Body>, Response = Response<BoxBody>, Error = Infallible, Future = impl Send> {
+        let release = Arc::new(Mutex::new(Some(release)));
+        layer_operation::<GetPokemon, _, _>(
+            plugin,
+            service_fn(move |_req: Request<Body>| {
+                let release = release.lock().unwrap().take();
+                async move {
+                    if let Some(release) = release {
+                        let _ = release.await;
+                    }
+                    Ok::<_, Infallible>(Response::new(crate::body::empty()))
+                }
+            }),
+        )
+    }
+
+    #[tokio::test]
+    async fn new_requests_are_rejected_while_draining() {
+        let plugin = DrainPlugin::new();
+        let (_release, receiver) = oneshot::channel();
+        let svc = apply(&plugin, receiver);
+
+        plugin.drain().await;
+        assert!(plugin.is_draining());
+        let response = svc.oneshot(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, response.status());
+        assert_eq!(0, plugin.in_flight(GetPokemon::NAME));
+    }
+
+    #[tokio::test]
+    async fn in_flight_requests_finish_while_draining() {
+        let plugin = DrainPlugin::new();
+        let (release, receiver) = oneshot::channel();
+        let svc = apply(&plugin, receiver);
+
+        let in_flight = tokio::spawn(svc.oneshot(Request::new(Body::empty())));
+        while plugin.in_flight(GetPokemon::NAME) == 0 {
+            tokio::task::yield_now().await;
+        }
+        let drain = tokio::spawn({
+            let plugin = plugin.clone();
+            async move { plugin.drain().await }
+        });
+        while !plugin.is_draining() {
+            tokio::task::yield_now().await;
+        }
+
+        release.send(()).unwrap();
+        let response = in_flight.await.unwrap().unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+        drain.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn drain_resolves_after_the_last_in_flight_request() {
+        let plugin = DrainPlugin::new();
+        let (first_release, first_receiver) = oneshot::channel();
+        let (second_release, second_receiver) = oneshot::channel();
+        let first = tokio::spawn(apply(&plugin, first_receiver).oneshot(Request::new(Body::empty())));
+        let second = tokio::spawn(apply(&plugin, second_receiver).oneshot(Request::new(Body::empty())));
+        while plugin.in_flight(GetPokemon::NAME) < 2 {
+            tokio::task::yield_now().await;
+        }
+
+        let drain = plugin.drain();
+        tokio::pin!(drain);
+        assert!(drain.as_mut().now_or_never().is_none());
+
+        first_release.send(()).unwrap();
+        first.await.unwrap().unwrap();
+        assert_eq!(1, plugin.in_flight(GetPokemon::NAME));
+        assert!(drain.as_mut().now_or_never().is_none());
+
+        second_release.send(()).unwrap();
+        second.await.unwrap().unwrap();
+        drain.await;
+        assert_eq!(0, plugin.in_flight(GetPokemon::NAME));
+    }
+}
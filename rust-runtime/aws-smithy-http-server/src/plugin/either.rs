@@ -27,6 +27,13 @@ pin_project! {
     /// # Notes on [`Service`]
     ///
     /// The [`Service::Response`] and [`Service::Error`] must be identical.
+    ///
+    /// # Notes on [`Clone`]
+    ///
+    /// `Either<L, R>` is only [`Clone`] when both `L` and `R` are. This can't be relaxed by a manual
+    /// implementation: which variant is active is only known at runtime, so [`Clone::clone`] must be able
+    /// to clone either one. If one side doesn't implement [`Clone`], wrap it in an [`Arc`](std::sync::Arc),
+    /// which is [`Clone`] regardless of its contents and implements [`Plugin`] whenever its contents do.
     #[derive(Clone, Debug)]
     #[project = EitherProj]
     pub enum Either<L, R> {
@@ -37,6 +44,60 @@ pin_project! {
     }
 }
 
+impl<L, R> Either<L, R> {
+    /// Returns a reference to the `Left` value, or `None` if this is `Right`.
+    pub fn left(&self) -> Option<&L> {
+        match self {
+            Either::Left { value } => Some(value),
+            Either::Right { .. } => None,
+        }
+    }
+
+    /// Returns a reference to the `Right` value, or `None` if this is `Left`.
+    pub fn right(&self) -> Option<&R> {
+        match self {
+            Either::Left { .. } => None,
+            Either::Right { value } => Some(value),
+        }
+    }
+
+    /// Converts into the `Left` value, or `None` if this is `Right`.
+    ///
+    /// The `Right` value is dropped in that case.
+    pub fn into_left(self) -> Option<L> {
+        match self {
+            Either::Left { value } => Some(value),
+            Either::Right { .. } => None,
+        }
+    }
+
+    /// Converts into the `Right` value, or `None` if this is `Left`.
+    ///
+    /// The `Left` value is dropped in that case.
+    pub fn into_right(self) -> Option<R> {
+        match self {
+            Either::Left { .. } => None,
+            Either::Right { value } => Some(value),
+        }
+    }
+
+    /// Converts from `&Either<L, R>` to `Either<&L, &R>`.
+    pub fn as_ref(&self) -> Either<&L, &R> {
+        match self {
+            Either::Left { value } => Either::Left { value },
+            Either::Right { value } => Either::Right { value },
+        }
+    }
+
+    /// Converts from `&mut Either<L, R>` to `Either<&mut L, &mut R>`.
+    pub fn as_mut(&mut self) -> Either<&mut L, &mut R> {
+        match self {
+            Either::Left { value } => Either::Left { value },
+            Either::Right { value } => Either::Right { value },
+        }
+    }
+}
+
 impl<L, R> Future for Either<L, R>
 where
     L: Future,
@@ -130,3 +191,64 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::operation::Operation;
+    use crate::plugin::{IdentityPlugin, Plugin};
+
+    use super::Either;
+
+    #[derive(Clone)]
+    struct ClonePlugin;
+
+    impl<P, Op, S, L> Plugin<P, Op, S, L> for ClonePlugin {
+        type Service = S;
+        type Layer = L;
+
+        fn map(&self, input: Operation<S, L>) -> Operation<S, L> {
+            input
+        }
+    }
+
+    #[test]
+    fn clone_with_non_clone_side_wrapped_in_arc() {
+        // `IdentityPlugin` doesn't implement `Clone`
+        let either: Either<ClonePlugin, Arc<IdentityPlugin>> = Either::Left { value: ClonePlugin };
+        let cloned = either.clone();
+        assert!(matches!(cloned, Either::Left { .. }));
+
+        let either: Either<ClonePlugin, Arc<IdentityPlugin>> = Either::Right {
+            value: Arc::new(IdentityPlugin),
+        };
+        let cloned = either.clone();
+        let Operation { inner, layer } = Plugin::<(), (), _, _>::map(&cloned, Operation { inner: 1, layer: 2 });
+        assert!(matches!(inner, Either::Right { value: 1 }));
+        assert!(matches!(layer, Either::Right { value: 2 }));
+    }
+
+    #[test]
+    fn accessors_return_the_active_arm() {
+        let mut left: Either<u8, &str> = Either::Left { value: 1 };
+        assert_eq!(Some(&1), left.left());
+        assert_eq!(None, left.right());
+        assert!(matches!(left.as_ref(), Either::Left { value: &1 }));
+        if let Either::Left { value } = left.as_mut() {
+            *value = 2;
+        }
+        assert_eq!(None, left.clone().into_right());
+        assert_eq!(Some(2), left.into_left());
+
+        let mut right: Either<u8, &str> = Either::Right { value: "right" };
+        assert_eq!(None, right.left());
+        assert_eq!(Some(&"right"), right.right());
+        assert!(matches!(right.as_ref(), Either::Right { value: &"right" }));
+        if let Either::Right { value } = right.as_mut() {
+            *value = "changed";
+        }
+        assert_eq!(None, right.clone().into_left());
+        assert_eq!(Some("changed"), right.into_right());
+    }
+}
@@ -0,0 +1,232 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Contains the [`Fallback`] service.
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::ready;
+use http::Request;
+use pin_project_lite::pin_project;
+use tower::Service;
+
+use super::Either;
+
+/// A [`Service`] which calls a `Primary` service, and re-dispatches the request to a `Secondary` service if the
+/// `Primary`'s outcome should be fallen back from.
+///
+/// Whereas [`Either`] picks one of its services when it is constructed, [`Fallback`] decides per request, based
+/// on a user supplied predicate over the result of the `Primary` service.
+///
+/// # Notes on the request
+///
+/// The request is copied before it is passed to the `Primary` service, so that the `Secondary` service is called
+/// with the original request. This requires the body to be [`Clone`]. The [extensions](http::Extensions) of the
+/// request can't be copied, so the `Secondary` service is called without them.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::convert::Infallible;
+/// # use aws_smithy_http_server::plugin::Fallback;
+/// # use http::{Request, Response, StatusCode};
+/// # use tower::service_fn;
+/// # let primary = service_fn(|_req: Request<String>| async { Ok::<_, Infallible>(Response::new(())) });
+/// # let secondary = primary.clone();
+/// // Falls back to `secondary` when `primary` is unavailable.
+/// let svc = Fallback::new(primary, secondary, |result: &Result<Response<()>, Infallible>| {
+///     matches!(result, Ok(response) if response.status() == StatusCode::SERVICE_UNAVAILABLE)
+/// });
+/// ```
+#[derive(Clone)]
+pub struct Fallback<Primary, Secondary, F> {
+    primary: Primary,
+    secondary: Secondary,
+    should_fall_back: F,
+}
+
+impl<Primary, Secondary, F> Fallback<Primary, Secondary, F> {
+    /// Creates a new [`Fallback`], which calls `secondary` whenever `should_fall_back` returns `true` for the
+    /// result of `primary`.
+    pub fn new(primary: Primary, secondary: Secondary, should_fall_back: F) -> Self {
+        Self {
+            primary,
+            secondary,
+            should_fall_back,
+        }
+    }
+}
+
+impl<Primary, Secondary, F> fmt::Debug for Fallback<Primary, Secondary, F>
+where
+    Primary: fmt::Debug,
+    Secondary: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fallback")
+            .field("primary", &self.primary)
+            .field("secondary", &self.secondary)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Primary, Secondary, F, B> Service<Request<B>> for Fallback<Primary, Secondary, F>
+where
+    Primary: Service<Request<B>>,
+    Secondary: Service<Request<B>, Response = Primary::Response, Error = Primary::Error> + Clone,
+    F: Fn(&Result<Primary::Response, Primary::Error>) -> bool + Clone,
+    B: Clone,
+{
+    type Response = Primary::Response;
+    type Error = Primary::Error;
+    type Future = FallbackFuture<Primary::Future, Secondary, B, F>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        ready!(self.primary.poll_ready(cx))?;
+        self.secondary.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let original_request = copy_request(&request);
+        // Take the secondary service that was driven to readiness, leaving a clone in its place.
+        let secondary = self.secondary.clone();
+        let secondary = std::mem::replace(&mut self.secondary, secondary);
+        FallbackFuture {
+            future: Either::Left {
+                value: self.primary.call(request),
+            },
+            fallback: Some((secondary, original_request, self.should_fall_back.clone())),
+        }
+    }
+}
+
+fn copy_request<B: Clone>(request: &Request<B>) -> Request<B> {
+    let mut copy = Request::new(request.body().clone());
+    *copy.method_mut() = request.method().clone();
+    *copy.uri_mut() = request.uri().clone();
+    *copy.version_mut() = request.version();
+    *copy.headers_mut() = request.headers().clone();
+    copy
+}
+
+pin_project! {
+    /// Future for [`Fallback`].
+    pub struct FallbackFuture<PrimaryFuture, Secondary, B, F>
+    where
+        Secondary: Service<Request<B>>,
+    {
+        #[pin]
+        future: Either<PrimaryFuture, Secondary::Future>,
+        // `None` once the primary service's result has been classified.
+        fallback: Option<(Secondary, Request<B>, F)>,
+    }
+}
+
+impl<PrimaryFuture, Secondary, B, F> Future for FallbackFuture<PrimaryFuture, Secondary, B, F>
+where
+    PrimaryFuture: Future<Output = Result<Secondary::Response, Secondary::Error>>,
+    Secondary: Service<Request<B>>,
+    F: Fn(&Result<Secondary::Response, Secondary::Error>) -> bool,
+{
+    type Output = PrimaryFuture::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+        loop {
+            let result = ready!(this.future.as_mut().poll(cx));
+            match this.fallback.take() {
+                Some((mut secondary, request, should_fall_back)) if should_fall_back(&result) => {
+                    this.future.set(Either::Right {
+                        value: secondary.call(request),
+                    });
+                }
+                _ => return Poll::Ready(result),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use http::{Response, StatusCode};
+    use tower::{service_fn, ServiceExt};
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum TestError {
+        Retryable,
+        Fatal,
+    }
+
+    fn is_retryable(result: &Result<Response<String>, TestError>) -> bool {
+        matches!(result, Err(TestError::Retryable))
+    }
+
+    /// A secondary service which echoes the request and counts how many times it was called.
+    fn secondary(
+        calls: Arc<AtomicUsize>,
+    ) -> impl Service<Request<String>, Response = Response<String>, Error = TestError, Future = impl Send> + Clone {
+        service_fn(move |req: Request<String>| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async move {
+                Ok(Response::builder()
+                    .status(StatusCode::OK)
+                    .header("x-served-by", "secondary")
+                    .body(format!("{} {} {}", req.method(), req.uri(), req.body()))
+                    .unwrap())
+            }
+        })
+    }
+
+    fn request() -> Request<String> {
+        Request::builder()
+            .method("POST")
+            .uri("/pokemon")
+            .body("pikachu".to_string())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn falls_back_on_a_classified_error() {
+        let primary = service_fn(|_req: Request<String>| async { Err(TestError::Retryable) });
+        let calls = Arc::new(AtomicUsize::new(0));
+        let svc = Fallback::new(primary, secondary(calls.clone()), is_retryable);
+
+        let response = svc.oneshot(request()).await.expect("the secondary succeeds");
+        assert_eq!("secondary", response.headers()["x-served-by"]);
+        assert_eq!("POST /pokemon pikachu", response.body());
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn does_not_fall_back_on_other_errors() {
+        let primary = service_fn(|_req: Request<String>| async { Err(TestError::Fatal) });
+        let calls = Arc::new(AtomicUsize::new(0));
+        let svc = Fallback::new(primary, secondary(calls.clone()), is_retryable);
+
+        assert_eq!(TestError::Fatal, svc.oneshot(request()).await.unwrap_err());
+        assert_eq!(0, calls.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn does_not_fall_back_on_success() {
+        let primary =
+            service_fn(|_req: Request<String>| async { Ok::<_, TestError>(Response::new("primary".to_string())) });
+        let calls = Arc::new(AtomicUsize::new(0));
+        let svc = Fallback::new(primary, secondary(calls.clone()), is_retryable);
+
+        assert_eq!("primary", svc.oneshot(request()).await.unwrap().body());
+        assert_eq!(0, calls.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,271 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! A [`Plugin`] which lets operations be disabled at runtime with feature flags.
+//!
+//! Before every request, the [`FeatureFlagService`] asks a [`FlagSource`] whether the operation is enabled. If it is,
+//! the request is passed through to the operation. Otherwise the operation isn't called, and the request is rejected
+//! with a `501 Not Implemented` response, or the status set with [`FeatureFlagPlugin::disabled_status`]. Disabled
+//! operations stay in the router, so they can be enabled again without rebuilding the service.
+//!
+//! [`FeatureFlags`] is an in-memory [`FlagSource`] whose clones share their flags, so it can be updated while the
+//! server is running. Other flag sources, such as a remote configuration service, can implement [`FlagSource`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use aws_smithy_http_server::plugin::{PluginPipeline, feature_flag::{FeatureFlagPlugin, FeatureFlags}};
+//! # struct GetPokemonSpecies;
+//! # impl GetPokemonSpecies { const NAME: &'static str = ""; }
+//! let flags = FeatureFlags::new();
+//! let plugins = PluginPipeline::new().push(FeatureFlagPlugin::new(flags.clone()));
+//! // Build the service with `plugins` and serve it.
+//!
+//! // Turn `GetPokemonSpecies` off while it's being investigated.
+//! flags.disable(GetPokemonSpecies::NAME);
+//! ```
+
+use std::{
+    collections::HashSet,
+    fmt,
+    future::{ready, Ready},
+    sync::{Arc, RwLock},
+    task::{Context, Poll},
+};
+
+use http::{Request, Response, StatusCode};
+use tower::{layer::util::Stack, Layer, Service};
+
+use crate::body::BoxBody;
+use crate::operation::{Operation, OperationShape};
+
+use super::{empty_response, Either, Plugin};
+
+/// A source of feature flags, which decides whether operations are enabled.
+///
+/// It's asked for every request, so it should answer quickly, without blocking.
+pub trait FlagSource: Send + Sync {
+    /// Returns `true` if the operation named `operation_name` should handle requests.
+    ///
+    /// The name is an [`OperationShape::NAME`].
+    fn is_enabled(&self, operation_name: &str) -> bool;
+}
+
+/// An in-memory [`FlagSource`] in which every operation is enabled until it's [disabled](FeatureFlags::disable).
+///
+/// Clones share their flags, so a clone can be kept to update them at runtime.
+#[derive(Clone, Debug, Default)]
+pub struct FeatureFlags {
+    disabled: Arc<RwLock<HashSet<String>>>,
+}
+
+impl FeatureFlags {
+    /// Creates [`FeatureFlags`] in which every operation is enabled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Disables the operation named `operation_name`.
+    pub fn disable(&self, operation_name: impl Into<String>) {
+        self.disabled.write().unwrap().insert(operation_name.into());
+    }
+
+    /// Enables the operation named `operation_name` again.
+    pub fn enable(&self, operation_name: &str) {
+        self.disabled.write().unwrap().remove(operation_name);
+    }
+}
+
+impl FlagSource for FeatureFlags {
+    fn is_enabled(&self, operation_name: &str) -> bool {
+        !self.disabled.read().unwrap().contains(operation_name)
+    }
+}
+
+/// A [`Plugin`] which applies a [`FeatureFlagLayer`] to every operation.
+///
+/// See the [module](crate::plugin::feature_flag) documentation for more information.
+#[derive(Clone)]
+pub struct FeatureFlagPlugin {
+    source: Arc<dyn FlagSource>,
+    disabled_status: StatusCode,
+}
+
+impl fmt::Debug for FeatureFlagPlugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FeatureFlagPlugin")
+            .field("disabled_status", &self.disabled_status)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FeatureFlagPlugin {
+    /// Creates a [`FeatureFlagPlugin`] which enables operations according to `source`.
+    pub fn new(source: impl FlagSource + 'static) -> Self {
+        Self {
+            source: Arc::new(source),
+            disabled_status: StatusCode::NOT_IMPLEMENTED,
+        }
+    }
+
+    /// Sets the status of the response to requests for disabled operations, such as `404 Not Found` to hide them.
+    ///
+    /// Defaults to `501 Not Implemented`.
+    pub fn disabled_status(mut self, status: StatusCode) -> Self {
+        self.disabled_status = status;
+        self
+    }
+}
+
+impl<P, Op, S, L> Plugin<P, Op, S, L> for FeatureFlagPlugin
+where
+    Op: OperationShape,
+{
+    type Service = S;
+    type Layer = Stack<L, FeatureFlagLayer>;
+
+    fn map(&self, input: Operation<S, L>) -> Operation<Self::Service, Self::Layer> {
+        input.layer(FeatureFlagLayer {
+            operation_name: Op::NAME,
+            source: self.source.clone(),
+            disabled_status: self.disabled_status,
+        })
+    }
+}
+
+/// A [`Layer`] used to apply [`FeatureFlagService`].
+#[derive(Clone)]
+pub struct FeatureFlagLayer {
+    operation_name: &'static str,
+    source: Arc<dyn FlagSource>,
+    disabled_status: StatusCode,
+}
+
+impl fmt::Debug for FeatureFlagLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FeatureFlagLayer")
+            .field("operation_name", &self.operation_name)
+            .field("disabled_status", &self.disabled_status)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> Layer<S> for FeatureFlagLayer {
+    type Service = FeatureFlagService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        FeatureFlagService {
+            inner,
+            operation_name: self.operation_name,
+            source: self.source.clone(),
+            disabled_status: self.disabled_status,
+        }
+    }
+}
+
+/// A middleware [`Service`] which only calls the inner service while its operation is enabled, and responds with an
+/// empty response otherwise.
+#[derive(Clone)]
+pub struct FeatureFlagService<S> {
+    inner: S,
+    operation_name: &'static str,
+    source: Arc<dyn FlagSource>,
+    disabled_status: StatusCode,
+}
+
+impl<S: fmt::Debug> fmt::Debug for FeatureFlagService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FeatureFlagService")
+            .field("inner", &self.inner)
+            .field("operation_name", &self.operation_name)
+            .field("disabled_status", &self.disabled_status)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, B> Service<Request<B>> for FeatureFlagService<S>
+where
+    S: Service<Request<B>, Response = Response<BoxBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Either<S::Future, Ready<Result<Response<BoxBody>, S::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        if self.source.is_enabled(self.operation_name) {
+            return Either::Left {
+                value: self.inner.call(req),
+            };
+        }
+        tracing::debug!(
+            operation = self.operation_name,
+            "rejecting a request to a disabled operation"
+        );
+        Either::Right {
+            value: ready(Ok(empty_response(self.disabled_status))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use tower::{service_fn, ServiceExt};
+
+    use crate::body::Body;
+    use crate::plugin::test_operations::{layer_operation, GetPokemon};
+
+    use super::*;
+
+    /// Applies `plugin` to an operation which always responds with `200 OK`, and calls it once.
+    async fn call(plugin: &FeatureFlagPlugin) -> StatusCode {
+        let svc = layer_operation::<GetPokemon, _, _>(
+            plugin,
+            service_fn(|_req: Request<Body>| async { Ok::<_, Infallible>(Response::new(crate::body::empty())) }),
+        );
+        svc.oneshot(Request::new(Body::empty())).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn disabled_operations_are_not_implemented_until_reenabled() {
+        let flags = FeatureFlags::new();
+        let plugin = FeatureFlagPlugin::new(flags.clone());
+        assert_eq!(StatusCode::OK, call(&plugin).await);
+
+        flags.disable(GetPokemon::NAME);
+        assert_eq!(StatusCode::NOT_IMPLEMENTED, call(&plugin).await);
+
+        flags.enable(GetPokemon::NAME);
+        assert_eq!(StatusCode::OK, call(&plugin).await);
+    }
+
+    #[tokio::test]
+    async fn other_operations_are_unaffected() {
+        let flags = FeatureFlags::new();
+        flags.disable("GetStorage");
+        let plugin = FeatureFlagPlugin::new(flags);
+        assert_eq!(StatusCode::OK, call(&plugin).await);
+    }
+
+    #[derive(Debug)]
+    struct NothingEnabled;
+
+    impl FlagSource for NothingEnabled {
+        fn is_enabled(&self, _operation_name: &str) -> bool {
+            false
+        }
+    }
+
+    #[tokio::test]
+    async fn disabled_status_is_configurable() {
+        let plugin = FeatureFlagPlugin::new(NothingEnabled).disabled_status(StatusCode::NOT_FOUND);
+        assert_eq!(StatusCode::NOT_FOUND, call(&plugin).await);
+    }
+}
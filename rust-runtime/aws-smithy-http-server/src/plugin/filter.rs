@@ -43,6 +43,32 @@ where
     FilterByOperationName::new(plugins, predicate)
 }
 
+/// Applies an inner [`Plugin`] only if `enabled` is `true`, leaving every operation untouched otherwise.
+///
+/// This allows plugins, such as ones used for debugging, to be toggled at runtime without recompiling.
+///
+/// # Example
+///
+/// ```rust
+/// use aws_smithy_http_server::plugin::enabled_if;
+/// # use aws_smithy_http_server::{plugin::Plugin, operation::Operation};
+/// # struct Pl;
+/// # impl Plugin<(), (), (), ()> for Pl { type Service = (); type Layer = (); fn map(&self, input: Operation<(), ()>) -> Operation<(), ()> { input }}
+/// # let plugin = Pl;
+/// # let operation = Operation { inner: (), layer: () };
+/// // Only applies `plugin` if `VERBOSE_LOGGING` is set.
+/// let verbose = std::env::var("VERBOSE_LOGGING").is_ok();
+/// let toggled_plugin = enabled_if(plugin, verbose);
+/// let new_operation = toggled_plugin.map(operation);
+/// ```
+pub fn enabled_if<Inner>(plugin: Inner, enabled: bool) -> Either<Inner, IdentityPlugin> {
+    if enabled {
+        Either::Left { value: plugin }
+    } else {
+        Either::Right { value: IdentityPlugin }
+    }
+}
+
 impl<Inner, F> FilterByOperationName<Inner, F> {
     /// Creates a new [`FilterByOperationName`].
     fn new(inner: Inner, predicate: F) -> Self {
@@ -68,3 +94,40 @@ where
         either_plugin.map(input)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::operation::Operation;
+    use crate::plugin::{Either, Plugin};
+
+    use super::enabled_if;
+
+    /// Marks the layer of every operation it is applied to.
+    struct MarkerPlugin;
+
+    impl<P, Op, S, L> Plugin<P, Op, S, L> for MarkerPlugin {
+        type Service = S;
+        type Layer = (L, &'static str);
+
+        fn map(&self, input: Operation<S, L>) -> Operation<S, Self::Layer> {
+            Operation {
+                inner: input.inner,
+                layer: (input.layer, "marker"),
+            }
+        }
+    }
+
+    #[test]
+    fn enabled_plugin_is_applied() {
+        let plugin = enabled_if(MarkerPlugin, true);
+        let Operation { layer, .. } = Plugin::<(), (), _, _>::map(&plugin, Operation { inner: 1, layer: 2 });
+        assert!(matches!(layer, Either::Left { value: (2, "marker") }));
+    }
+
+    #[test]
+    fn disabled_plugin_is_not_applied() {
+        let plugin = enabled_if(MarkerPlugin, false);
+        let Operation { layer, .. } = Plugin::<(), (), _, _>::map(&plugin, Operation { inner: 1, layer: 2 });
+        assert!(matches!(layer, Either::Right { value: 2 }));
+    }
+}
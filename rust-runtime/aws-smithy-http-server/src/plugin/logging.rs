@@ -0,0 +1,435 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! A [`Plugin`] which logs every request and response of an operation.
+//!
+//! For each request, the method, path and headers are logged when it's received, and the status, latency and
+//! headers of its response are logged when it's sent. The values of sensitive headers are replaced with
+//! `{redacted}`; `authorization`, `proxy-authorization`, `cookie` and `set-cookie` are always redacted, and more
+//! can be added with [`LoggingPlugin::redact_header`].
+//!
+//! Bodies aren't logged unless [`LoggingPlugin::log_bodies`] is enabled, since it requires buffering them in
+//! memory and they may contain sensitive data of their own.
+//!
+//! Events are emitted at the `INFO` level with the `aws_smithy_http_server::plugin::logging` target.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use aws_smithy_http_server::plugin::{PluginPipeline, logging::LoggingPlugin};
+//! # use http::header::HeaderName;
+//! # struct HealthCheck;
+//! # impl HealthCheck { const NAME: &'static str = ""; }
+//! let plugins = PluginPipeline::new().push(
+//!     LoggingPlugin::new()
+//!         .redact_header(HeaderName::from_static("x-api-key"))
+//!         .skip_operation(HealthCheck::NAME),
+//! );
+//! ```
+
+use std::{
+    collections::HashSet,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use http::{header::HeaderName, HeaderMap, Request, Response, StatusCode};
+use hyper::Body;
+use tokio::time::Instant;
+use tower::{
+    layer::util::{Identity, Stack},
+    Layer, Service, ServiceExt,
+};
+
+use crate::body::BoxBody;
+use crate::operation::{Operation, OperationShape};
+
+use super::{empty_response, Either, Plugin};
+
+/// The headers that are redacted by every [`LoggingPlugin`].
+const ALWAYS_REDACTED: [HeaderName; 4] = [
+    http::header::AUTHORIZATION,
+    http::header::PROXY_AUTHORIZATION,
+    http::header::COOKIE,
+    http::header::SET_COOKIE,
+];
+
+/// A [`Plugin`] which applies a [`LoggingLayer`] to every operation that isn't skipped.
+///
+/// See the [module](crate::plugin::logging) documentation for more information.
+#[derive(Clone, Debug)]
+pub struct LoggingPlugin {
+    redacted_headers: HashSet<HeaderName>,
+    log_bodies: bool,
+    skipped_operations: HashSet<&'static str>,
+}
+
+impl Default for LoggingPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LoggingPlugin {
+    /// Creates a [`LoggingPlugin`] which logs every operation without its bodies.
+    pub fn new() -> Self {
+        Self {
+            redacted_headers: ALWAYS_REDACTED.into_iter().collect(),
+            log_bodies: false,
+            skipped_operations: HashSet::new(),
+        }
+    }
+
+    /// Redacts the value of the header `name` in requests and responses.
+    pub fn redact_header(mut self, name: HeaderName) -> Self {
+        self.redacted_headers.insert(name);
+        self
+    }
+
+    /// Sets whether request and response bodies are logged. Defaults to `false`.
+    pub fn log_bodies(mut self, log_bodies: bool) -> Self {
+        self.log_bodies = log_bodies;
+        self
+    }
+
+    /// Stops logging the operation named `operation_name`.
+    ///
+    /// The name is compared against [`OperationShape::NAME`].
+    pub fn skip_operation(mut self, operation_name: &'static str) -> Self {
+        self.skipped_operations.insert(operation_name);
+        self
+    }
+}
+
+impl<P, Op, S, L> Plugin<P, Op, S, L> for LoggingPlugin
+where
+    Op: OperationShape,
+{
+    type Service = S;
+    type Layer = Stack<L, Either<LoggingLayer, Identity>>;
+
+    fn map(&self, input: Operation<S, L>) -> Operation<Self::Service, Self::Layer> {
+        let layer = if self.skipped_operations.contains(Op::NAME) {
+            Either::Right { value: Identity::new() }
+        } else {
+            Either::Left {
+                value: LoggingLayer {
+                    operation_name: Op::NAME,
+                    redacted_headers: Arc::new(self.redacted_headers.clone()),
+                    log_bodies: self.log_bodies,
+                },
+            }
+        };
+        input.layer(layer)
+    }
+}
+
+/// A [`Layer`] used to apply [`LoggingService`].
+#[derive(Clone, Debug)]
+pub struct LoggingLayer {
+    operation_name: &'static str,
+    redacted_headers: Arc<HashSet<HeaderName>>,
+    log_bodies: bool,
+}
+
+impl<S> Layer<S> for LoggingLayer {
+    type Service = LoggingService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LoggingService {
+            inner,
+            operation_name: self.operation_name,
+            redacted_headers: self.redacted_headers.clone(),
+            log_bodies: self.log_bodies,
+        }
+    }
+}
+
+/// A middleware [`Service`] which logs the requests and responses of an operation.
+#[derive(Clone, Debug)]
+pub struct LoggingService<S> {
+    inner: S,
+    operation_name: &'static str,
+    redacted_headers: Arc<HashSet<HeaderName>>,
+    log_bodies: bool,
+}
+
+/// Formats headers with the values of the redacted ones replaced.
+struct RedactedHeaders<'a> {
+    headers: &'a HeaderMap,
+    redacted_headers: &'a HashSet<HeaderName>,
+}
+
+impl fmt::Debug for RedactedHeaders<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut map = f.debug_map();
+        for (name, value) in self.headers {
+            if self.redacted_headers.contains(name) {
+                map.entry(name, &"{redacted}");
+            } else {
+                map.entry(name, &String::from_utf8_lossy(value.as_bytes()));
+            }
+        }
+        map.finish()
+    }
+}
+
+impl<S> Service<Request<Body>> for LoggingService<S>
+where
+    S: Service<Request<Body>, Response = Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send,
+    S::Error: Send,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        // The inner service is ready, so it's the one that must be called.
+        let clone = self.inner.clone();
+        let inner = std::mem::replace(&mut self.inner, clone);
+        let operation = self.operation_name;
+        let redacted_headers = self.redacted_headers.clone();
+        let log_bodies = self.log_bodies;
+
+        Box::pin(async move {
+            let started_at = Instant::now();
+            let req = if log_bodies {
+                let (parts, body) = req.into_parts();
+                let body = match hyper::body::to_bytes(body).await {
+                    Ok(body) => body,
+                    Err(err) => {
+                        tracing::info!(operation, error = %err, "failed to read the request body");
+                        return Ok(empty_response(StatusCode::BAD_REQUEST));
+                    }
+                };
+                tracing::info!(
+                    operation,
+                    method = %parts.method,
+                    path = parts.uri.path(),
+                    headers = ?RedactedHeaders {
+                        headers: &parts.headers,
+                        redacted_headers: &redacted_headers,
+                    },
+                    body = %String::from_utf8_lossy(&body),
+                    "received request"
+                );
+                Request::from_parts(parts, Body::from(body))
+            } else {
+                tracing::info!(
+                    operation,
+                    method = %req.method(),
+                    path = req.uri().path(),
+                    headers = ?RedactedHeaders {
+                        headers: req.headers(),
+                        redacted_headers: &redacted_headers,
+                    },
+                    "received request"
+                );
+                req
+            };
+
+            let response = inner.oneshot(req).await?;
+            if !log_bodies {
+                tracing::info!(
+                    operation,
+                    status = response.status().as_u16(),
+                    latency = ?started_at.elapsed(),
+                    headers = ?RedactedHeaders {
+                        headers: response.headers(),
+                        redacted_headers: &redacted_headers,
+                    },
+                    "sent response"
+                );
+                return Ok(response);
+            }
+
+            let (parts, body) = response.into_parts();
+            let body: Bytes = match hyper::body::to_bytes(body).await {
+                Ok(body) => body,
+                Err(err) => {
+                    tracing::info!(operation, error = %err, "failed to read the response body");
+                    return Ok(empty_response(StatusCode::INTERNAL_SERVER_ERROR));
+                }
+            };
+            tracing::info!(
+                operation,
+                status = parts.status.as_u16(),
+                latency = ?started_at.elapsed(),
+                headers = ?RedactedHeaders {
+                    headers: &parts.headers,
+                    redacted_headers: &redacted_headers,
+                },
+                body = %String::from_utf8_lossy(&body),
+                "sent response"
+            );
+            Ok(Response::from_parts(
+                parts,
+                crate::body::boxed(http_body::Full::new(body)),
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, sync::Mutex, time::Duration};
+
+    use tower::service_fn;
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Metadata, Subscriber,
+    };
+
+    use crate::plugin::test_operations::{layer_operation, GetPokemon};
+
+    use super::*;
+
+    /// Records the fields of every event as `name=value` strings.
+    #[derive(Clone, Default)]
+    struct CaptureEvents {
+        events: Arc<Mutex<Vec<Vec<String>>>>,
+    }
+
+    struct FieldsVisitor<'a>(&'a mut Vec<String>);
+
+    impl Visit for FieldsVisitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.push(format!("{}={:?}", field.name(), value));
+        }
+    }
+
+    impl Subscriber for CaptureEvents {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+            span::Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Vec::new();
+            event.record(&mut FieldsVisitor(&mut fields));
+            self.events.lock().unwrap().push(fields);
+        }
+
+        fn enter(&self, _span: &span::Id) {}
+
+        fn exit(&self, _span: &span::Id) {}
+    }
+
+    /// Applies `plugin` to an operation which takes a second to respond with a `201 Created`, and returns the
+    /// fields of the events logged while sending it `req`.
+    async fn send(plugin: &LoggingPlugin, req: Request<Body>) -> (Response<BoxBody>, Vec<Vec<String>>) {
+        let svc = layer_operation::<GetPokemon, _, _>(
+            plugin,
+            service_fn(|req: Request<Body>| async move {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+                Ok::<_, Infallible>(
+                    Response::builder()
+                        .status(StatusCode::CREATED)
+                        .header("set-cookie", "session=secret-session")
+                        .header("x-request-id", "abc")
+                        .body(crate::body::boxed(http_body::Full::new(body)))
+                        .unwrap(),
+                )
+            }),
+        );
+
+        let capture = CaptureEvents::default();
+        let _guard = tracing::subscriber::set_default(capture.clone());
+        let response = svc.oneshot(req).await.unwrap();
+        let events = capture.events.lock().unwrap().clone();
+        (response, events)
+    }
+
+    fn request(body: &'static str) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri("/pokemon?name=pikachu")
+            .header("authorization", "Bearer secret-token")
+            .header("x-api-key", "secret-key")
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn request_and_response_are_logged() {
+        let (response, events) = send(&LoggingPlugin::new(), request("{}")).await;
+
+        assert_eq!(StatusCode::CREATED, response.status());
+        assert_eq!(2, events.len(), "{events:?}");
+        let (received, sent) = (&events[0], &events[1]);
+        assert!(
+            received.contains(&"operation=\"GetPokemon\"".to_string()),
+            "{received:?}"
+        );
+        assert!(received.contains(&"method=POST".to_string()), "{received:?}");
+        assert!(received.contains(&"path=\"/pokemon\"".to_string()), "{received:?}");
+        assert!(sent.contains(&"status=201".to_string()), "{sent:?}");
+        assert!(sent.contains(&"latency=1s".to_string()), "{sent:?}");
+        assert!(sent.iter().any(|field| field.contains("x-request-id")), "{sent:?}");
+    }
+
+    #[tokio::test]
+    async fn redacted_headers_are_never_logged() {
+        tokio::time::pause();
+        let plugin = LoggingPlugin::new().redact_header(HeaderName::from_static("x-api-key"));
+        let (_, events) = send(&plugin, request("{}")).await;
+
+        let logged = format!("{events:?}");
+        for secret in ["secret-token", "secret-key", "secret-session"] {
+            assert!(!logged.contains(secret), "{secret} was logged: {logged}");
+        }
+        assert!(logged.contains("authorization"), "{logged}");
+        assert!(logged.contains("application/json"), "{logged}");
+    }
+
+    #[tokio::test]
+    async fn bodies_are_only_logged_when_enabled() {
+        tokio::time::pause();
+        let (_, events) = send(&LoggingPlugin::new(), request("{\"name\":\"pikachu\"}")).await;
+        assert!(!format!("{events:?}").contains("body="), "{events:?}");
+
+        let plugin = LoggingPlugin::new().log_bodies(true);
+        let (response, events) = send(&plugin, request("{\"name\":\"pikachu\"}")).await;
+        assert!(
+            events[0].contains(&"body={\"name\":\"pikachu\"}".to_string()),
+            "{events:?}"
+        );
+        assert!(
+            events[1].contains(&"body={\"name\":\"pikachu\"}".to_string()),
+            "{events:?}"
+        );
+        // The buffered bodies are passed on
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&b"{\"name\":\"pikachu\"}"[..], &body[..]);
+    }
+
+    #[tokio::test]
+    async fn skipped_operations_are_not_logged() {
+        tokio::time::pause();
+        let plugin = LoggingPlugin::new().skip_operation(GetPokemon::NAME);
+        let (response, events) = send(&plugin, request("{}")).await;
+
+        assert_eq!(StatusCode::CREATED, response.status());
+        assert!(events.is_empty(), "{events:?}");
+    }
+}
@@ -22,6 +22,16 @@
 //! let plugin = filter_by_operation_name(plugin, |name| name == GetPokemonSpecies::NAME);
 //! ```
 //!
+//! # Toggle a [`Plugin`] at runtime
+//!
+//! ```
+//! # use aws_smithy_http_server::plugin::*;
+//! # let plugin = ();
+//! # let debug_mode = false;
+//! // Only apply `plugin` when running in debug mode
+//! let plugin = enabled_if(plugin, debug_mode);
+//! ```
+//!
 //! # Construct a [`Plugin`] from a closure that takes as input the operation name
 //!
 //! ```
@@ -118,20 +128,46 @@
 //! ```
 //!
 
+#[cfg(feature = "ab-test")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ab-test")))]
+pub mod ab_test;
 pub mod alb_health_check;
+pub mod body_limit;
+pub mod circuit_breaker;
 mod closure;
+pub mod concurrency_limit;
+pub mod constraint_validation;
+pub mod dedup;
+pub mod drain;
 mod either;
+mod fallback;
+pub mod feature_flag;
 mod filter;
 mod identity;
 mod layer;
+pub mod logging;
 mod pipeline;
+#[cfg(feature = "request-id")]
+#[cfg_attr(docsrs, doc(cfg(feature = "request-id")))]
+pub mod request_id;
+pub mod required_headers;
+pub mod response_validation;
 mod stack;
+#[cfg(feature = "timeout")]
+#[cfg_attr(docsrs, doc(cfg(feature = "timeout")))]
+pub mod timeout;
+
+use std::sync::Arc;
 
+use http::{Response, StatusCode};
+
+use crate::body::BoxBody;
 use crate::operation::Operation;
 
 pub use closure::{plugin_from_operation_name_fn, OperationNameFn};
 pub use either::Either;
-pub use filter::{filter_by_operation_name, FilterByOperationName};
+pub use fallback::{Fallback, FallbackFuture};
+pub use filter::{enabled_if, filter_by_operation_name, FilterByOperationName};
 pub use identity::IdentityPlugin;
 pub use layer::HttpLayer;
 pub use pipeline::PluginPipeline;
@@ -164,3 +200,74 @@ where
         <Pl as Plugin<P, Op, S, L>>::map(*self, input)
     }
 }
+
+impl<P, Op, S, L, Pl> Plugin<P, Op, S, L> for Arc<Pl>
+where
+    Pl: Plugin<P, Op, S, L>,
+{
+    type Service = Pl::Service;
+    type Layer = Pl::Layer;
+
+    fn map(&self, input: Operation<S, L>) -> Operation<Self::Service, Self::Layer> {
+        <Pl as Plugin<P, Op, S, L>>::map(self, input)
+    }
+}
+
+/// A response with `status` and an empty body, for plugins which reject requests without calling the operation.
+pub(crate) fn empty_response(status: StatusCode) -> Response<BoxBody> {
+    Response::builder()
+        .status(status)
+        .body(crate::body::empty())
+        .expect("valid response")
+}
+
+/// Operations without any input, output or errors, for plugins that only need an operation's name.
+#[cfg(test)]
+pub(crate) mod test_operations {
+    use tower::layer::util::Identity;
+    use tower::Layer;
+
+    use crate::operation::{Operation, OperationShape};
+
+    use super::Plugin;
+
+    macro_rules! test_operations {
+        ($($name:ident),*) => {
+            $(
+                // Some are only used by the tests of plugins behind a feature
+                #[allow(dead_code)]
+                pub(crate) struct $name;
+
+                impl OperationShape for $name {
+                    const NAME: &'static str = stringify!($name);
+
+                    type Input = ();
+                    type Output = ();
+                    type Error = ();
+                }
+            )*
+        };
+    }
+
+    test_operations!(
+        CheckHealth,
+        GetPokemon,
+        GetPokemonSpecies,
+        GetStorage,
+        HealthCheck,
+        UploadPicture
+    );
+
+    /// Applies `plugin` to the operation `Op`, and wraps `svc` in the layer it maps the operation to.
+    pub(crate) fn layer_operation<Op, P, S>(plugin: &P, svc: S) -> <P::Layer as Layer<S>>::Service
+    where
+        P: Plugin<(), Op, (), Identity>,
+        P::Layer: Layer<S>,
+    {
+        let operation = plugin.map(Operation {
+            inner: (),
+            layer: Identity::new(),
+        });
+        operation.layer.layer(svc)
+    }
+}
@@ -0,0 +1,184 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! A [`Plugin`] which gives every request an id that can be shared with other services, for distributed tracing.
+//!
+//! The id is taken from the request's `x-request-id` header, or generated as a UUID when the header is absent or
+//! isn't a valid request id. It is stored in the request extensions as a
+//! [`ServerRequestId`](crate::request::request_id::ServerRequestId), where handlers can access it, and echoed on the
+//! response under the same header.
+//!
+//! The plugin applies a [`ServerRequestIdProviderLayer`] which
+//! [honors the request header](ServerRequestIdProviderLayer::with_request_header), so handlers take the id as they
+//! would any other `ServerRequestId`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use aws_smithy_http_server::plugin::{PluginPipeline, request_id::RequestIdPlugin};
+//! # struct CheckHealth;
+//! # impl CheckHealth { const NAME: &'static str = ""; }
+//! let plugins = PluginPipeline::new().push(
+//!     // Every operation except `CheckHealth` gets a request id.
+//!     RequestIdPlugin::new().except(CheckHealth::NAME),
+//! );
+//! ```
+
+use std::collections::HashSet;
+
+use http::header::HeaderName;
+use tower::layer::util::{Identity, Stack};
+
+use crate::operation::{Operation, OperationShape};
+use crate::request::request_id::ServerRequestIdProviderLayer;
+
+use super::{Either, Plugin};
+
+/// A [`Plugin`] which applies a [`ServerRequestIdProviderLayer`] to every operation that isn't excluded.
+///
+/// See the [module](crate::plugin::request_id) documentation for more information.
+#[derive(Clone, Debug)]
+pub struct RequestIdPlugin {
+    header_name: HeaderName,
+    excluded_operations: HashSet<&'static str>,
+}
+
+impl Default for RequestIdPlugin {
+    fn default() -> Self {
+        Self {
+            header_name: HeaderName::from_static("x-request-id"),
+            excluded_operations: HashSet::new(),
+        }
+    }
+}
+
+impl RequestIdPlugin {
+    /// Creates a [`RequestIdPlugin`] that reads and writes the `x-request-id` header.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the header that the request id is read from and written to.
+    pub fn header_name(mut self, header_name: HeaderName) -> Self {
+        self.header_name = header_name;
+        self
+    }
+
+    /// Leaves the operation named `operation_name` untouched.
+    ///
+    /// The name is compared against [`OperationShape::NAME`].
+    pub fn except(mut self, operation_name: &'static str) -> Self {
+        self.excluded_operations.insert(operation_name);
+        self
+    }
+}
+
+impl<P, Op, S, L> Plugin<P, Op, S, L> for RequestIdPlugin
+where
+    Op: OperationShape,
+{
+    type Service = S;
+    type Layer = Stack<L, Either<ServerRequestIdProviderLayer, Identity>>;
+
+    fn map(&self, input: Operation<S, L>) -> Operation<Self::Service, Self::Layer> {
+        let layer = if self.excluded_operations.contains(Op::NAME) {
+            Either::Right { value: Identity::new() }
+        } else {
+            Either::Left {
+                value: ServerRequestIdProviderLayer::new_with_response_header(self.header_name.clone())
+                    .with_request_header(self.header_name.clone()),
+            }
+        };
+        input.layer(layer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use http::{Request, Response};
+    use tower::{service_fn, ServiceExt};
+    use uuid::Uuid;
+
+    use crate::body::{Body, BoxBody};
+    use crate::plugin::test_operations::{layer_operation, CheckHealth, GetPokemon};
+    use crate::request::request_id::ServerRequestId;
+
+    use super::*;
+
+    /// Applies `plugin` to an operation `Op` which responds with the request id it was handed in `x-seen-id`.
+    async fn send<Op>(plugin: &RequestIdPlugin, request: Request<Body>) -> Response<BoxBody>
+    where
+        Op: OperationShape,
+    {
+        let svc = layer_operation::<Op, _, _>(
+            plugin,
+            service_fn(|req: Request<Body>| async move {
+                let mut response = Response::new(crate::body::empty());
+                if let Some(request_id) = req.extensions().get::<ServerRequestId>() {
+                    response.headers_mut().insert("x-seen-id", request_id.to_header());
+                }
+                Ok::<_, Infallible>(response)
+            }),
+        );
+        svc.oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn incoming_request_id_is_preserved() {
+        let request = Request::builder()
+            .header("x-request-id", "incoming-id")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = send::<GetPokemon>(&RequestIdPlugin::new(), request).await;
+        assert_eq!("incoming-id", response.headers()["x-seen-id"]);
+        assert_eq!("incoming-id", response.headers()["x-request-id"]);
+    }
+
+    #[tokio::test]
+    async fn request_id_is_generated_when_absent() {
+        let response = send::<GetPokemon>(&RequestIdPlugin::new(), Request::new(Body::empty())).await;
+        let request_id = response.headers()["x-request-id"].to_str().unwrap();
+        assert!(Uuid::parse_str(request_id).is_ok(), "{request_id}");
+        assert_eq!(request_id, response.headers()["x-seen-id"]);
+    }
+
+    #[tokio::test]
+    async fn invalid_request_id_is_replaced() {
+        let request = Request::builder()
+            .header("x-request-id", "incoming id")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = send::<GetPokemon>(&RequestIdPlugin::new(), request).await;
+        let request_id = response.headers()["x-request-id"].to_str().unwrap();
+        assert!(Uuid::parse_str(request_id).is_ok(), "{request_id}");
+        assert_eq!(request_id, response.headers()["x-seen-id"]);
+    }
+
+    #[tokio::test]
+    async fn header_name_is_configurable() {
+        let plugin = RequestIdPlugin::new().header_name(HeaderName::from_static("x-trace-id"));
+        let request = Request::builder()
+            .header("x-trace-id", "incoming-id")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = send::<GetPokemon>(&plugin, request).await;
+        assert_eq!("incoming-id", response.headers()["x-trace-id"]);
+        assert!(response.headers().get("x-request-id").is_none());
+    }
+
+    #[tokio::test]
+    async fn excluded_operations_are_left_untouched() {
+        let plugin = RequestIdPlugin::new().except(CheckHealth::NAME);
+
+        let response = send::<CheckHealth>(&plugin, Request::new(Body::empty())).await;
+        assert!(response.headers().get("x-request-id").is_none());
+        assert!(response.headers().get("x-seen-id").is_none());
+    }
+}
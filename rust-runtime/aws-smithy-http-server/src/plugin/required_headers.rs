@@ -0,0 +1,255 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! A [`Plugin`] which rejects requests that are missing required headers.
+//!
+//! Before a request reaches the operation, the [`RequiredHeadersService`] checks that it has every required header.
+//! If one is missing, the operation isn't called, and the request is rejected with a `400 Bad Request` response.
+//! The response can be replaced using [`RequiredHeadersPlugin::rejection_response`], e.g. with a modeled error that
+//! names the missing header.
+//!
+//! Each operation may be given its own required headers. Operations without any are left untouched.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use aws_smithy_http_server::plugin::{PluginPipeline, required_headers::RequiredHeadersPlugin};
+//! # use http::HeaderName;
+//! # struct HealthCheck;
+//! # impl HealthCheck { const NAME: &'static str = ""; }
+//! let plugins = PluginPipeline::new().push(
+//!     // Requests must have an API key...
+//!     RequiredHeadersPlugin::new([HeaderName::from_static("x-api-key")])
+//!         // ...except for `HealthCheck`, which is public.
+//!         .operation_headers(HealthCheck::NAME, []),
+//! );
+//! ```
+
+use std::{
+    collections::HashMap,
+    future::{ready, Ready},
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use http::{HeaderName, Request, Response, StatusCode};
+use tower::{
+    layer::util::{Identity, Stack},
+    Layer, Service,
+};
+
+use crate::body::BoxBody;
+use crate::operation::{Operation, OperationShape};
+
+use super::{empty_response, Either, Plugin};
+
+/// A [`Plugin`] which applies a [`RequiredHeadersLayer`] to every operation that has required headers.
+///
+/// See the [module](crate::plugin::required_headers) documentation for more information.
+#[derive(Clone, Debug)]
+pub struct RequiredHeadersPlugin {
+    default_headers: Arc<[HeaderName]>,
+    operation_headers: HashMap<&'static str, Arc<[HeaderName]>>,
+    rejection_response: fn(HeaderName) -> Response<BoxBody>,
+}
+
+impl Default for RequiredHeadersPlugin {
+    /// Creates a [`RequiredHeadersPlugin`] that only requires the headers set with
+    /// [`RequiredHeadersPlugin::operation_headers`].
+    fn default() -> Self {
+        Self {
+            default_headers: Arc::new([]),
+            operation_headers: HashMap::new(),
+            rejection_response: bad_request,
+        }
+    }
+}
+
+impl RequiredHeadersPlugin {
+    /// Requires every operation's requests to have all of `headers`.
+    pub fn new(headers: impl IntoIterator<Item = HeaderName>) -> Self {
+        Self {
+            default_headers: headers.into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the headers that the requests of the operation named `operation_name` must have, replacing the default
+    /// ones. An operation given no headers is left untouched.
+    ///
+    /// The name is compared against [`OperationShape::NAME`].
+    pub fn operation_headers(
+        mut self,
+        operation_name: &'static str,
+        headers: impl IntoIterator<Item = HeaderName>,
+    ) -> Self {
+        self.operation_headers
+            .insert(operation_name, headers.into_iter().collect());
+        self
+    }
+
+    /// Replaces the `400 Bad Request` response returned when a request is missing a required header. The response is
+    /// built from the name of the first header that's missing.
+    pub fn rejection_response(mut self, rejection_response: fn(HeaderName) -> Response<BoxBody>) -> Self {
+        self.rejection_response = rejection_response;
+        self
+    }
+}
+
+impl<P, Op, S, L> Plugin<P, Op, S, L> for RequiredHeadersPlugin
+where
+    Op: OperationShape,
+{
+    type Service = S;
+    type Layer = Stack<L, Either<RequiredHeadersLayer, Identity>>;
+
+    fn map(&self, input: Operation<S, L>) -> Operation<Self::Service, Self::Layer> {
+        let headers = self.operation_headers.get(Op::NAME).unwrap_or(&self.default_headers);
+        let layer = if headers.is_empty() {
+            Either::Right { value: Identity::new() }
+        } else {
+            Either::Left {
+                value: RequiredHeadersLayer {
+                    headers: headers.clone(),
+                    rejection_response: self.rejection_response,
+                },
+            }
+        };
+        input.layer(layer)
+    }
+}
+
+/// A [`Layer`] used to apply [`RequiredHeadersService`].
+#[derive(Clone, Debug)]
+pub struct RequiredHeadersLayer {
+    headers: Arc<[HeaderName]>,
+    rejection_response: fn(HeaderName) -> Response<BoxBody>,
+}
+
+impl<S> Layer<S> for RequiredHeadersLayer {
+    type Service = RequiredHeadersService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequiredHeadersService {
+            inner,
+            headers: self.headers.clone(),
+            rejection_response: self.rejection_response,
+        }
+    }
+}
+
+/// A middleware [`Service`] which only calls the inner service if the request has every required header, and responds
+/// with the rejection response otherwise.
+#[derive(Clone, Debug)]
+pub struct RequiredHeadersService<S> {
+    inner: S,
+    headers: Arc<[HeaderName]>,
+    rejection_response: fn(HeaderName) -> Response<BoxBody>,
+}
+
+impl<S, B> Service<Request<B>> for RequiredHeadersService<S>
+where
+    S: Service<Request<B>, Response = Response<BoxBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Either<S::Future, Ready<Result<Response<BoxBody>, S::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        match self.headers.iter().find(|header| !req.headers().contains_key(*header)) {
+            None => Either::Left {
+                value: self.inner.call(req),
+            },
+            Some(missing) => {
+                tracing::debug!(header = %missing, "rejecting a request that is missing a required header");
+                Either::Right {
+                    value: ready(Ok((self.rejection_response)(missing.clone()))),
+                }
+            }
+        }
+    }
+}
+
+fn bad_request(_missing: HeaderName) -> Response<BoxBody> {
+    empty_response(StatusCode::BAD_REQUEST)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use tower::{service_fn, ServiceExt};
+
+    use crate::body::Body;
+    use crate::plugin::test_operations::{layer_operation, GetPokemon, HealthCheck};
+
+    use super::*;
+
+    fn api_key() -> HeaderName {
+        HeaderName::from_static("x-api-key")
+    }
+
+    fn tenant() -> HeaderName {
+        HeaderName::from_static("x-tenant")
+    }
+
+    /// Applies `plugin` to an operation `Op` which always responds with `200 OK`, and sends it `request`.
+    async fn send<Op>(plugin: &RequiredHeadersPlugin, request: http::request::Builder) -> Response<BoxBody>
+    where
+        Op: OperationShape,
+    {
+        let svc = layer_operation::<Op, _, _>(
+            plugin,
+            service_fn(|_req: Request<Body>| async { Ok::<_, Infallible>(Response::new(crate::body::empty())) }),
+        );
+        svc.oneshot(request.body(Body::empty()).unwrap()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn requests_with_every_required_header_pass_through() {
+        let plugin = RequiredHeadersPlugin::new([api_key(), tenant()]);
+
+        let request = Request::builder().header(api_key(), "secret").header(tenant(), "acme");
+        let response = send::<GetPokemon>(&plugin, request).await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[tokio::test]
+    async fn requests_missing_a_required_header_are_rejected() {
+        let plugin = RequiredHeadersPlugin::new([api_key(), tenant()]);
+
+        let response = send::<GetPokemon>(&plugin, Request::builder().header(api_key(), "secret")).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    #[tokio::test]
+    async fn operation_headers_override_the_default() {
+        let plugin = RequiredHeadersPlugin::new([api_key()]).operation_headers(HealthCheck::NAME, []);
+
+        let response = send::<HealthCheck>(&plugin, Request::builder()).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let response = send::<GetPokemon>(&plugin, Request::builder()).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    #[tokio::test]
+    async fn rejection_response_is_configurable() {
+        let plugin = RequiredHeadersPlugin::new([api_key()]).rejection_response(|missing| {
+            Response::builder()
+                .status(StatusCode::UNAUTHORIZED)
+                .header("x-missing-header", missing.as_str())
+                .body(crate::body::empty())
+                .unwrap()
+        });
+
+        let response = send::<GetPokemon>(&plugin, Request::builder()).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+        assert_eq!("x-api-key", response.headers()["x-missing-header"]);
+    }
+}
@@ -0,0 +1,299 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! A [`Plugin`] which checks operation outputs against their modeled [constraint traits] before they're serialized.
+//!
+//! Handlers are trusted to return valid outputs, so this is meant to catch handler bugs in tests and staging
+//! environments, such as a handler returning a string that doesn't satisfy its `@pattern`. Like the
+//! [`ConstraintValidationPlugin`](super::constraint_validation::ConstraintValidationPlugin), it wraps the
+//! operation's inner service, and each operation describes the constraints of its output by implementing
+//! [`ValidateOutputConstraints`] with [`check_length`](super::constraint_validation::check_length),
+//! [`check_range`](super::constraint_validation::check_range) and
+//! [`check_pattern`](super::constraint_validation::check_pattern).
+//!
+//! By default violations are logged and the output is returned anyway. With [`ViolationBehavior::Fail`], the error
+//! returned by [`ValidateOutputConstraints::violation_error`] is returned instead.
+//!
+//! [constraint traits]: https://awslabs.github.io/smithy/2.0/spec/constraint-traits.html
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use aws_smithy_http_server::plugin::PluginPipeline;
+//! use aws_smithy_http_server::plugin::response_validation::{ResponseValidationPlugin, ViolationBehavior};
+//!
+//! let plugins = PluginPipeline::new().push(ResponseValidationPlugin::new().on_violation(ViolationBehavior::Fail));
+//! ```
+
+use std::{
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::ready;
+use pin_project_lite::pin_project;
+use tower::Service;
+
+use crate::operation::{Operation, OperationError, OperationShape};
+
+use super::{constraint_validation::ConstraintViolation, Plugin};
+
+/// Checks an operation's output against the constraints modeled on its members.
+pub trait ValidateOutputConstraints: OperationShape {
+    /// Returns the first constraint that `output` violates, if any.
+    fn validate_output(output: &Self::Output) -> Result<(), ConstraintViolation>;
+
+    /// Returns the error to respond with when the output violates its constraints and the plugin is configured to
+    /// [fail](ViolationBehavior::Fail). This is typically the operation's internal server error.
+    fn violation_error(violation: ConstraintViolation) -> Self::Error;
+}
+
+/// What a [`ResponseValidationService`] does when an output violates its constraints.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ViolationBehavior {
+    /// Log the violation at the `WARN` level and return the output anyway.
+    #[default]
+    Log,
+    /// Log the violation at the `ERROR` level and return the operation's
+    /// [violation error](ValidateOutputConstraints::violation_error) instead of the output.
+    Fail,
+}
+
+/// A [`Plugin`] which wraps the inner service of every operation in a [`ResponseValidationService`].
+///
+/// See the [module](crate::plugin::response_validation) documentation for more information.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ResponseValidationPlugin {
+    behavior: ViolationBehavior,
+}
+
+impl ResponseValidationPlugin {
+    /// Creates a [`ResponseValidationPlugin`] which logs violations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets what happens when an output violates its constraints.
+    pub fn on_violation(mut self, behavior: ViolationBehavior) -> Self {
+        self.behavior = behavior;
+        self
+    }
+}
+
+impl<P, Op, S, L> Plugin<P, Op, S, L> for ResponseValidationPlugin
+where
+    Op: ValidateOutputConstraints,
+{
+    type Service = ResponseValidationService<Op, S>;
+    type Layer = L;
+
+    fn map(&self, input: Operation<S, L>) -> Operation<Self::Service, Self::Layer> {
+        Operation {
+            inner: ResponseValidationService {
+                inner: input.inner,
+                behavior: self.behavior,
+                _operation: PhantomData,
+            },
+            layer: input.layer,
+        }
+    }
+}
+
+/// A middleware [`Service`] which validates the output of the operation `Op` returned by the inner service.
+pub struct ResponseValidationService<Op, S> {
+    inner: S,
+    behavior: ViolationBehavior,
+    _operation: PhantomData<Op>,
+}
+
+impl<Op, S> Clone for ResponseValidationService<Op, S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            behavior: self.behavior,
+            _operation: PhantomData,
+        }
+    }
+}
+
+impl<Op, S> fmt::Debug for ResponseValidationService<Op, S>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseValidationService")
+            .field("inner", &self.inner)
+            .field("behavior", &self.behavior)
+            .finish()
+    }
+}
+
+impl<Op, S, Exts, PollError> Service<(Op::Input, Exts)> for ResponseValidationService<Op, S>
+where
+    Op: ValidateOutputConstraints,
+    S: Service<(Op::Input, Exts), Response = Op::Output, Error = OperationError<Op::Error, PollError>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseValidationFuture<Op, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: (Op::Input, Exts)) -> Self::Future {
+        ResponseValidationFuture {
+            inner: self.inner.call(req),
+            behavior: self.behavior,
+            _operation: PhantomData,
+        }
+    }
+}
+
+pin_project! {
+    /// Future for [`ResponseValidationService`], which validates the output of the inner service's future.
+    pub struct ResponseValidationFuture<Op, F> {
+        #[pin]
+        inner: F,
+        behavior: ViolationBehavior,
+        _operation: PhantomData<Op>,
+    }
+}
+
+impl<Op, F, PollError> Future for ResponseValidationFuture<Op, F>
+where
+    Op: ValidateOutputConstraints,
+    F: Future<Output = Result<Op::Output, OperationError<Op::Error, PollError>>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let output = match ready!(this.inner.poll(cx)) {
+            Ok(output) => output,
+            Err(err) => return Poll::Ready(Err(err)),
+        };
+        let violation = match Op::validate_output(&output) {
+            Ok(()) => return Poll::Ready(Ok(output)),
+            Err(violation) => violation,
+        };
+        match this.behavior {
+            ViolationBehavior::Log => {
+                tracing::warn!(operation = Op::NAME, %violation, "the handler's output violates its constraints");
+                Poll::Ready(Ok(output))
+            }
+            ViolationBehavior::Fail => {
+                tracing::error!(operation = Op::NAME, %violation, "the handler's output violates its constraints");
+                Poll::Ready(Err(OperationError::Model(Op::violation_error(violation))))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use once_cell::sync::Lazy;
+    use regex::Regex;
+    use tower::{service_fn, ServiceExt};
+
+    use crate::plugin::constraint_validation::check_pattern;
+
+    use super::*;
+
+    struct GetTrainer;
+
+    #[derive(Debug, PartialEq)]
+    struct GetTrainerOutput {
+        trainer_id: String,
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum GetTrainerError {
+        Internal(String),
+    }
+
+    impl OperationShape for GetTrainer {
+        const NAME: &'static str = "GetTrainer";
+
+        type Input = String;
+        type Output = GetTrainerOutput;
+        type Error = GetTrainerError;
+    }
+
+    static TRAINER_ID: Lazy<Regex> = Lazy::new(|| Regex::new("^[a-z]+-[0-9]+$").unwrap());
+
+    impl ValidateOutputConstraints for GetTrainer {
+        fn validate_output(output: &Self::Output) -> Result<(), ConstraintViolation> {
+            check_pattern("trainer_id", &output.trainer_id, &TRAINER_ID)
+        }
+
+        fn violation_error(violation: ConstraintViolation) -> Self::Error {
+            GetTrainerError::Internal(violation.to_string())
+        }
+    }
+
+    /// Calls an operation whose handler responds with the trainer ID it's given.
+    async fn get_trainer(
+        plugin: ResponseValidationPlugin,
+        trainer_id: &str,
+    ) -> Result<GetTrainerOutput, GetTrainerError> {
+        let operation = Plugin::<(), GetTrainer, _, ()>::map(
+            &plugin,
+            Operation {
+                inner: service_fn(|(trainer_id, ()): (String, ())| async move {
+                    Ok::<_, OperationError<GetTrainerError, Infallible>>(GetTrainerOutput { trainer_id })
+                }),
+                layer: (),
+            },
+        );
+        match operation.inner.oneshot((trainer_id.to_string(), ())).await {
+            Ok(output) => Ok(output),
+            Err(OperationError::Model(err)) => Err(err),
+            Err(OperationError::PollReady(_)) => unreachable!("the handler is always ready"),
+        }
+    }
+
+    #[tokio::test]
+    async fn valid_output_is_returned() {
+        let plugin = ResponseValidationPlugin::new().on_violation(ViolationBehavior::Fail);
+        assert_eq!(
+            Ok(GetTrainerOutput {
+                trainer_id: "ash-1".to_string()
+            }),
+            get_trainer(plugin, "ash-1").await
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_output_is_returned_when_violations_are_logged() {
+        assert_eq!(
+            Ok(GetTrainerOutput {
+                trainer_id: "Ash".to_string()
+            }),
+            get_trainer(ResponseValidationPlugin::new(), "Ash").await
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_output_is_replaced_when_violations_fail() {
+        let plugin = ResponseValidationPlugin::new().on_violation(ViolationBehavior::Fail);
+        assert_eq!(
+            Err(GetTrainerError::Internal(
+                "Value at '/trainer_id' failed to satisfy constraint: Member must satisfy regular expression \
+                pattern: ^[a-z]+-[0-9]+$"
+                    .to_string()
+            )),
+            get_trainer(plugin, "Ash").await
+        );
+    }
+}
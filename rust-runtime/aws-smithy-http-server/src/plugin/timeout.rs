@@ -0,0 +1,337 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! A [`Plugin`] which bounds how long an operation may take to respond.
+//!
+//! If an operation doesn't respond within its timeout, the request is abandoned and a `504 Gateway Timeout`
+//! response is returned in its place. The response can be replaced using [`TimeoutPlugin::timeout_response`].
+//!
+//! Each operation may be given its own timeout. Operations without a timeout are left untouched. The timeouts of many
+//! operations, such as ones read from a timeout trait in the model, can be set at once from a map keyed by the
+//! operations' shape IDs with [`TimeoutPlugin::operation_timeouts`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use aws_smithy_http_server::plugin::{PluginPipeline, timeout::TimeoutPlugin};
+//! # struct UploadPicture;
+//! # impl UploadPicture { const NAME: &'static str = ""; }
+//! let plugins = PluginPipeline::new().push(
+//!     // Operations must respond within 5 seconds...
+//!     TimeoutPlugin::new(Duration::from_secs(5))
+//!         // ...except for `UploadPicture`, which is allowed a minute.
+//!         .operation_timeout(UploadPicture::NAME, Duration::from_secs(60)),
+//! );
+//! ```
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use aws_smithy_async::future::timeout::Timeout;
+use aws_smithy_async::rt::sleep::{AsyncSleep, Sleep, TokioSleep};
+use futures_util::ready;
+use http::{Request, Response, StatusCode};
+use pin_project_lite::pin_project;
+use tower::{
+    layer::util::{Identity, Stack},
+    Layer, Service,
+};
+
+use crate::body::BoxBody;
+use crate::operation::{Operation, OperationShape};
+
+use super::{empty_response, Either, Plugin};
+
+/// A [`Plugin`] which applies a [`TimeoutLayer`] to every operation that has a timeout.
+///
+/// See the [module](crate::plugin::timeout) documentation for more information.
+#[derive(Clone, Debug)]
+pub struct TimeoutPlugin {
+    default_timeout: Option<Duration>,
+    operation_timeouts: HashMap<Cow<'static, str>, Duration>,
+    sleep_impl: Arc<dyn AsyncSleep>,
+    timeout_response: fn() -> Response<BoxBody>,
+}
+
+impl Default for TimeoutPlugin {
+    /// Creates a [`TimeoutPlugin`] that only applies the timeouts set with [`TimeoutPlugin::operation_timeout`].
+    fn default() -> Self {
+        Self {
+            default_timeout: None,
+            operation_timeouts: HashMap::new(),
+            sleep_impl: Arc::new(TokioSleep::new()),
+            timeout_response: gateway_timeout,
+        }
+    }
+}
+
+impl TimeoutPlugin {
+    /// Times out every operation that takes longer than `timeout` to respond.
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            default_timeout: Some(timeout),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the timeout of the operation named `operation_name`, overriding the default timeout.
+    ///
+    /// The name is compared against [`OperationShape::NAME`].
+    pub fn operation_timeout(mut self, operation_name: impl Into<Cow<'static, str>>, timeout: Duration) -> Self {
+        self.operation_timeouts.insert(operation_name.into(), timeout);
+        self
+    }
+
+    /// Sets the timeouts of many operations at once, overriding the default timeout for each of them.
+    ///
+    /// The keys are compared against [`OperationShape::NAME`], which is the operation's shape ID (e.g.
+    /// `com.example#GetPokemon`), so a map of the timeouts modeled for each operation can be passed as is.
+    pub fn operation_timeouts<K>(mut self, timeouts: impl IntoIterator<Item = (K, Duration)>) -> Self
+    where
+        K: Into<Cow<'static, str>>,
+    {
+        self.operation_timeouts
+            .extend(timeouts.into_iter().map(|(name, timeout)| (name.into(), timeout)));
+        self
+    }
+
+    /// Replaces the [`AsyncSleep`] implementation used to measure the timeouts.
+    pub fn sleep_impl(mut self, sleep_impl: Arc<dyn AsyncSleep>) -> Self {
+        self.sleep_impl = sleep_impl;
+        self
+    }
+
+    /// Replaces the `504 Gateway Timeout` response returned when an operation times out.
+    pub fn timeout_response(mut self, timeout_response: fn() -> Response<BoxBody>) -> Self {
+        self.timeout_response = timeout_response;
+        self
+    }
+}
+
+impl<P, Op, S, L> Plugin<P, Op, S, L> for TimeoutPlugin
+where
+    Op: OperationShape,
+{
+    type Service = S;
+    type Layer = Stack<L, Either<TimeoutLayer, Identity>>;
+
+    fn map(&self, input: Operation<S, L>) -> Operation<Self::Service, Self::Layer> {
+        let timeout = self.operation_timeouts.get(Op::NAME).copied().or(self.default_timeout);
+        let layer = match timeout {
+            Some(timeout) => Either::Left {
+                value: TimeoutLayer {
+                    timeout,
+                    sleep_impl: self.sleep_impl.clone(),
+                    timeout_response: self.timeout_response,
+                },
+            },
+            None => Either::Right { value: Identity::new() },
+        };
+        input.layer(layer)
+    }
+}
+
+/// A [`Layer`] used to apply [`TimeoutService`].
+#[derive(Clone, Debug)]
+pub struct TimeoutLayer {
+    timeout: Duration,
+    sleep_impl: Arc<dyn AsyncSleep>,
+    timeout_response: fn() -> Response<BoxBody>,
+}
+
+impl<S> Layer<S> for TimeoutLayer {
+    type Service = TimeoutService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TimeoutService {
+            inner,
+            timeout: self.timeout,
+            sleep_impl: self.sleep_impl.clone(),
+            timeout_response: self.timeout_response,
+        }
+    }
+}
+
+/// A middleware [`Service`] which responds with a timeout response if the inner service takes too long.
+#[derive(Clone, Debug)]
+pub struct TimeoutService<S> {
+    inner: S,
+    timeout: Duration,
+    sleep_impl: Arc<dyn AsyncSleep>,
+    timeout_response: fn() -> Response<BoxBody>,
+}
+
+impl<S, B> Service<Request<B>> for TimeoutService<S>
+where
+    S: Service<Request<B>, Response = Response<BoxBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = TimeoutFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        TimeoutFuture {
+            inner: Timeout::new(self.inner.call(req), self.sleep_impl.sleep(self.timeout)),
+            timeout_response: self.timeout_response,
+        }
+    }
+}
+
+pin_project! {
+    /// Future for [`TimeoutService`].
+    pub struct TimeoutFuture<F> {
+        #[pin]
+        inner: Timeout<F, Sleep>,
+        timeout_response: fn() -> Response<BoxBody>,
+    }
+}
+
+impl<F, E> Future for TimeoutFuture<F>
+where
+    F: Future<Output = Result<Response<BoxBody>, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        match ready!(this.inner.poll(cx)) {
+            Ok(result) => Poll::Ready(result),
+            Err(_timed_out) => Poll::Ready(Ok((this.timeout_response)())),
+        }
+    }
+}
+
+fn gateway_timeout() -> Response<BoxBody> {
+    empty_response(StatusCode::GATEWAY_TIMEOUT)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use tower::{service_fn, ServiceExt};
+
+    use crate::body::Body;
+    use crate::plugin::test_operations::{layer_operation, GetPokemon, GetStorage, UploadPicture};
+
+    use super::*;
+
+    /// Applies `plugin` to an operation `Op` which takes `delay` to respond with a `200 OK`.
+    async fn send<Op>(plugin: &TimeoutPlugin, delay: Duration) -> Response<BoxBody>
+    where
+        Op: OperationShape,
+    {
+        let svc = layer_operation::<Op, _, _>(
+            plugin,
+            service_fn(move |_req: Request<Body>| async move {
+                tokio::time::sleep(delay).await;
+                Ok::<_, Infallible>(
+                    Response::builder()
+                        .header("x-handled", "true")
+                        .body(crate::body::empty())
+                        .unwrap(),
+                )
+            }),
+        );
+        svc.oneshot(Request::new(Body::empty())).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn slow_operation_times_out() {
+        tokio::time::pause();
+        let plugin = TimeoutPlugin::new(Duration::from_secs(1));
+
+        let response = send::<GetPokemon>(&plugin, Duration::from_secs(10)).await;
+        assert_eq!(StatusCode::GATEWAY_TIMEOUT, response.status());
+        assert!(response.headers().get("x-handled").is_none());
+    }
+
+    #[tokio::test]
+    async fn fast_operation_passes_through() {
+        tokio::time::pause();
+        let plugin = TimeoutPlugin::new(Duration::from_secs(1));
+
+        let response = send::<GetPokemon>(&plugin, Duration::from_millis(500)).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!("true", response.headers()["x-handled"]);
+    }
+
+    #[tokio::test]
+    async fn operation_timeout_overrides_the_default() {
+        tokio::time::pause();
+        let plugin =
+            TimeoutPlugin::new(Duration::from_secs(1)).operation_timeout(UploadPicture::NAME, Duration::from_secs(60));
+
+        let response = send::<UploadPicture>(&plugin, Duration::from_secs(10)).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let response = send::<GetPokemon>(&plugin, Duration::from_secs(10)).await;
+        assert_eq!(StatusCode::GATEWAY_TIMEOUT, response.status());
+    }
+
+    #[tokio::test]
+    async fn timeout_response_is_configurable() {
+        tokio::time::pause();
+        let plugin = TimeoutPlugin::new(Duration::from_secs(1)).timeout_response(|| {
+            Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .body(crate::body::empty())
+                .unwrap()
+        });
+
+        let response = send::<GetPokemon>(&plugin, Duration::from_secs(10)).await;
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, response.status());
+    }
+
+    #[tokio::test]
+    async fn operations_without_a_timeout_are_left_untouched() {
+        tokio::time::pause();
+        let plugin = TimeoutPlugin::default().operation_timeout(UploadPicture::NAME, Duration::from_secs(1));
+
+        let response = send::<UploadPicture>(&plugin, Duration::from_secs(10)).await;
+        assert_eq!(StatusCode::GATEWAY_TIMEOUT, response.status());
+        let response = send::<GetPokemon>(&plugin, Duration::from_secs(10)).await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[tokio::test]
+    async fn timeouts_are_read_from_a_map_of_shape_ids() {
+        tokio::time::pause();
+        let timeouts: HashMap<String, Duration> = [
+            (GetPokemon::NAME.to_string(), Duration::from_secs(2)),
+            (UploadPicture::NAME.to_string(), Duration::from_secs(30)),
+        ]
+        .into_iter()
+        .collect();
+        let plugin = TimeoutPlugin::new(Duration::from_secs(5)).operation_timeouts(timeouts);
+
+        // Each listed operation enforces its own timeout...
+        let response = send::<GetPokemon>(&plugin, Duration::from_secs(1)).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let response = send::<GetPokemon>(&plugin, Duration::from_secs(3)).await;
+        assert_eq!(StatusCode::GATEWAY_TIMEOUT, response.status());
+        let response = send::<UploadPicture>(&plugin, Duration::from_secs(20)).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let response = send::<UploadPicture>(&plugin, Duration::from_secs(40)).await;
+        assert_eq!(StatusCode::GATEWAY_TIMEOUT, response.status());
+
+        // ...and the default applies to unlisted ones
+        let response = send::<GetStorage>(&plugin, Duration::from_secs(3)).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let response = send::<GetStorage>(&plugin, Duration::from_secs(6)).await;
+        assert_eq!(StatusCode::GATEWAY_TIMEOUT, response.status());
+    }
+}
@@ -19,6 +19,10 @@
 //!
 //! The [`ServerRequestId`] is not meant to be propagated to downstream dependencies of the service. You should rely on a distributed tracing implementation for correlation purposes (e.g. OpenTelemetry).
 //!
+//! When the caller already identifies its requests, use [`ServerRequestIdProviderLayer::with_request_header`] to
+//! take the [`ServerRequestId`] from a request header instead. The header's value is only used if it is a valid
+//! request ID, see [`ServerRequestId::from_header`]; otherwise one is generated as usual.
+//!
 //! ## Examples
 //!
 //! Your handler can now optionally take as input a [`ServerRequestId`].
@@ -65,11 +69,15 @@ use super::{internal_server_error, FromParts};
 /// Opaque type for Server Request IDs.
 ///
 /// If it is missing, the request will be rejected with a `500 Internal Server Error` response.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ServerRequestId {
-    id: Uuid,
+    // Only ever holds a UUID or a value that passed `is_valid_request_id`, so it is always visible ASCII
+    id: HeaderValue,
 }
 
+/// The longest request ID that is accepted from a request header.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
 /// The server request ID has not been added to the [`Request`](http::Request) or has been previously removed.
 #[non_exhaustive]
 #[derive(Debug, Error)]
@@ -78,17 +86,34 @@ pub struct MissingServerRequestId;
 
 impl ServerRequestId {
     pub fn new() -> Self {
-        Self { id: Uuid::new_v4() }
+        Self {
+            id: HeaderValue::from_str(&Uuid::new_v4().to_string()).expect("This string contains only valid ASCII"),
+        }
+    }
+
+    /// Uses the request ID sent by the caller in a request header.
+    ///
+    /// Returns `None` if the value isn't a valid request ID: it must be between 1 and 128 characters long, and only
+    /// contain ASCII letters, digits, `-`, `_` and `.`, so that it can be safely logged and echoed back.
+    pub fn from_header(value: &HeaderValue) -> Option<Self> {
+        is_valid_request_id(value.as_bytes()).then(|| Self { id: value.clone() })
     }
 
     pub(crate) fn to_header(&self) -> HeaderValue {
-        HeaderValue::from_str(&self.id.to_string()).expect("This string contains only valid ASCII")
+        self.id.clone()
     }
 }
 
+fn is_valid_request_id(id: &[u8]) -> bool {
+    (1..=MAX_REQUEST_ID_LEN).contains(&id.len())
+        && id
+            .iter()
+            .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.'))
+}
+
 impl Display for ServerRequestId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.id.fmt(f)
+        f.write_str(self.id.to_str().expect("This string contains only valid ASCII"))
     }
 }
 
@@ -110,6 +135,7 @@ impl Default for ServerRequestId {
 pub struct ServerRequestIdProvider<S> {
     inner: S,
     header_key: Option<HeaderName>,
+    request_header_key: Option<HeaderName>,
 }
 
 /// A layer that provides services with a unique request ID instance
@@ -117,21 +143,35 @@ pub struct ServerRequestIdProvider<S> {
 #[non_exhaustive]
 pub struct ServerRequestIdProviderLayer {
     header_key: Option<HeaderName>,
+    request_header_key: Option<HeaderName>,
 }
 
 impl ServerRequestIdProviderLayer {
     /// Generate a new unique request ID and do not add it as a response header
     /// Use [`ServerRequestIdProviderLayer::new_with_response_header`] to also add it as a response header
     pub fn new() -> Self {
-        Self { header_key: None }
+        Self {
+            header_key: None,
+            request_header_key: None,
+        }
     }
 
     /// Generate a new unique request ID and add it as a response header
     pub fn new_with_response_header(header_key: HeaderName) -> Self {
         Self {
             header_key: Some(header_key),
+            request_header_key: None,
         }
     }
+
+    /// Use the request ID sent by the caller in the `header_key` request header, rather than generating one
+    ///
+    /// A new request ID is still generated if the header is missing, or if its value isn't a valid request ID. See
+    /// [`ServerRequestId::from_header`].
+    pub fn with_request_header(mut self, header_key: HeaderName) -> Self {
+        self.request_header_key = Some(header_key);
+        self
+    }
 }
 
 impl Default for ServerRequestIdProviderLayer {
@@ -147,6 +187,7 @@ impl<S> Layer<S> for ServerRequestIdProviderLayer {
         ServerRequestIdProvider {
             inner,
             header_key: self.header_key.clone(),
+            request_header_key: self.request_header_key.clone(),
         }
     }
 }
@@ -165,7 +206,12 @@ where
     }
 
     fn call(&mut self, mut req: http::Request<Body>) -> Self::Future {
-        let request_id = ServerRequestId::new();
+        let request_id = self
+            .request_header_key
+            .as_ref()
+            .and_then(|header_key| req.headers().get(header_key))
+            .and_then(ServerRequestId::from_header)
+            .unwrap_or_default();
         match &self.header_key {
             Some(header_key) => {
                 req.extensions_mut().insert(request_id.clone());
@@ -259,6 +305,52 @@ mod tests {
         assert!(HeaderValue::from_str(request_id).is_ok());
     }
 
+    #[test]
+    fn request_ids_from_headers_are_validated() {
+        let from_header = |value: &str| ServerRequestId::from_header(&HeaderValue::from_str(value).unwrap());
+
+        assert_eq!(
+            "0b7ad4b1-c8d3.trace_1",
+            from_header("0b7ad4b1-c8d3.trace_1").unwrap().to_string()
+        );
+        assert_eq!(None, from_header(""));
+        assert_eq!(None, from_header("id with spaces"));
+        assert_eq!(None, from_header("id\twith\ttabs"));
+        assert_eq!(None, from_header("id;injected=1"));
+        assert!(from_header(&"a".repeat(128)).is_some());
+        assert_eq!(None, from_header(&"a".repeat(129)));
+    }
+
+    #[tokio::test]
+    async fn test_request_id_from_request_header() {
+        let header_key = HeaderName::from_static("x-request-id");
+        let svc = ServiceBuilder::new()
+            .layer(
+                &ServerRequestIdProviderLayer::new_with_response_header(header_key.clone())
+                    .with_request_header(header_key),
+            )
+            .service(service_fn(|req: Request<Body>| async move {
+                let request_id = req.extensions().get::<ServerRequestId>().unwrap().to_string();
+                Ok::<_, Infallible>(Response::new(crate::body::to_boxed(request_id)))
+            }));
+
+        let req = Request::builder()
+            .header("x-request-id", "incoming-id")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.clone().oneshot(req).await.unwrap();
+        assert_eq!("incoming-id", res.headers()["x-request-id"]);
+
+        // An invalid request ID is replaced with a generated one
+        let req = Request::builder()
+            .header("x-request-id", "incoming id")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.oneshot(req).await.unwrap();
+        let request_id = res.headers()["x-request-id"].to_str().unwrap();
+        assert!(Uuid::parse_str(request_id).is_ok(), "{request_id}");
+    }
+
     #[tokio::test]
     async fn test_request_id_not_in_response_header() {
         let svc = ServiceBuilder::new()
@@ -8,6 +8,7 @@
 use bytes::Bytes;
 use http::{HeaderMap, HeaderValue};
 use http_body::{Body, SizeHint};
+use once_cell::sync::Lazy;
 use pin_project_lite::pin_project;
 use std::error::Error as StdError;
 use std::fmt::{self, Debug, Formatter};
@@ -34,7 +35,7 @@ pin_project! {
         //
         // In the event of retry, this function will be called to generate a new body. See
         // [`try_clone()`](SdkBody::try_clone)
-        rebuild: Option<Arc<dyn (Fn() -> Inner) + Send + Sync>>,
+        rebuild: Option<Rebuild>,
     }
 }
 
@@ -47,6 +48,11 @@ impl Debug for SdkBody {
     }
 }
 
+type Rebuild = Arc<dyn (Fn() -> Inner) + Send + Sync>;
+
+// Shared by every empty body, so that creating or cloning one doesn't allocate.
+static EMPTY_REBUILD: Lazy<Rebuild> = Lazy::new(|| Arc::new(|| Inner::Once { inner: None }));
+
 /// A boxed generic HTTP body that, when consumed, will result in [`Bytes`] or an [`Error`].
 pub type BoxBody = http_body::combinators::BoxBody<Bytes, Error>;
 
@@ -126,10 +132,17 @@ impl SdkBody {
     pub fn empty() -> Self {
         Self {
             inner: Inner::Once { inner: None },
-            rebuild: Some(Arc::new(|| Inner::Once { inner: None })),
+            rebuild: Some(EMPTY_REBUILD.clone()),
         }
     }
 
+    /// Returns `true` if this body is known to be empty without reading it.
+    ///
+    /// Streaming bodies always return `false`, even if they won't produce any data.
+    pub fn is_known_empty(&self) -> bool {
+        matches!(self.bytes(), Some(bytes) if bytes.is_empty())
+    }
+
     fn poll_inner(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
@@ -252,9 +265,13 @@ impl http_body::Body for SdkBody {
 
     fn poll_trailers(
         self: Pin<&mut Self>,
-        _cx: &mut Context<'_>,
+        cx: &mut Context<'_>,
     ) -> Poll<Result<Option<HeaderMap<HeaderValue>>, Self::Error>> {
-        Poll::Ready(Ok(None))
+        match self.project().inner.project() {
+            InnerProj::Streaming { inner: body } => body.poll_trailers(cx).map_err(|e| e.into()),
+            InnerProj::Dyn { inner: box_body } => box_body.poll_trailers(cx),
+            InnerProj::Once { .. } | InnerProj::Taken => Poll::Ready(Ok(None)),
+        }
     }
 
     fn is_end_stream(&self) -> bool {
@@ -283,6 +300,7 @@ mod test {
     use crate::body::{BoxBody, SdkBody};
     use http_body::Body;
     use std::pin::Pin;
+    use std::sync::Arc;
 
     #[test]
     fn valid_size_hint() {
@@ -339,6 +357,22 @@ mod test {
         let _ = format!("{:?}", body);
     }
 
+    #[test]
+    fn empty_bodies_share_their_rebuild_function() {
+        let first = SdkBody::empty();
+        let second = first.try_clone().expect("empty bodies are retryable");
+        let third = SdkBody::empty();
+        assert!(second.is_known_empty());
+        assert!(Arc::ptr_eq(
+            first.rebuild.as_ref().unwrap(),
+            third.rebuild.as_ref().unwrap()
+        ));
+        assert!(Arc::ptr_eq(
+            second.rebuild.as_ref().unwrap(),
+            third.rebuild.as_ref().unwrap()
+        ));
+    }
+
     #[test]
     fn sdk_body_is_send() {
         fn is_send<T: Send>() {}
@@ -3,6 +3,8 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
+//! Content-encoding tools, such as the `aws-chunked` encoding of request bodies with trailers.
+
 use bytes::{Bytes, BytesMut};
 use http::{HeaderMap, HeaderValue};
 use http_body::{Body, SizeHint};
@@ -199,10 +201,10 @@ fn total_rendered_length_of_trailers(trailer_map: Option<&HeaderMap>) -> u64 {
 
 impl<Inner> Body for AwsChunkedBody<Inner>
 where
-    Inner: Body<Data = Bytes, Error = aws_smithy_http::body::Error>,
+    Inner: Body<Data = Bytes, Error = crate::body::Error>,
 {
     type Data = Bytes;
-    type Error = aws_smithy_http::body::Error;
+    type Error = crate::body::Error;
 
     fn poll_data(
         self: Pin<&mut Self>,
@@ -354,7 +356,7 @@ mod tests {
         AwsChunkedBodyOptions, CHUNK_TERMINATOR, CRLF,
     };
 
-    use aws_smithy_http::body::SdkBody;
+    use crate::body::SdkBody;
     use bytes::{Buf, Bytes};
     use bytes_utils::SegmentedBuf;
     use http::{HeaderMap, HeaderValue};
@@ -382,7 +384,7 @@ mod tests {
 
     impl Body for SputteringBody {
         type Data = Bytes;
-        type Error = aws_smithy_http::body::Error;
+        type Error = crate::body::Error;
 
         fn poll_data(
             self: Pin<&mut Self>,
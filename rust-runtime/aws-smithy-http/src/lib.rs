@@ -26,6 +26,7 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
 pub mod body;
+pub mod content_encoding;
 pub mod endpoint;
 pub mod header;
 pub mod http;
@@ -8,6 +8,7 @@
 use std::error::Error;
 use std::fmt;
 use std::fmt::{Debug, Display, Formatter};
+use std::sync::Arc;
 
 use aws_smithy_types::error::metadata::{ProvideErrorMetadata, EMPTY_ERROR_METADATA};
 use aws_smithy_types::error::ErrorMetadata;
@@ -58,7 +59,7 @@ pub mod builders {
 
                 #[doc = "Builds the error context."]
                 pub fn build(self) -> $errorName {
-                    $errorName { source: self.source.expect("source is required") }
+                    $errorName { source: self.source.expect("source is required"), attempt_errors: Vec::new() }
                 }
             }
         };
@@ -119,6 +120,7 @@ pub mod builders {
             ResponseError {
                 source: self.source.expect("source is required"),
                 raw: self.raw.expect("a raw response is required"),
+                attempt_errors: Vec::new(),
             }
         }
     }
@@ -174,15 +176,62 @@ pub mod builders {
             ServiceError {
                 source: self.source.expect("source is required"),
                 raw: self.raw.expect("a raw response is required"),
+                attempt_errors: Vec::new(),
             }
         }
     }
 }
 
+/// The error that one attempt of an operation failed with.
+///
+/// When an operation fails after being retried, the [`SdkError`] it fails with carries the errors
+/// of the attempts that were retried, in order, which can be read with
+/// [`SdkError::attempt_errors`]. The error of the final attempt is the [`SdkError`] itself.
+#[derive(Clone, Debug)]
+pub struct AttemptError {
+    attempt: u32,
+    status: Option<u16>,
+    source: Arc<dyn Error + Send + Sync + 'static>,
+}
+
+impl AttemptError {
+    /// Creates a new [`AttemptError`] for the 1-based `attempt`.
+    pub fn new(attempt: u32, status: Option<u16>, source: impl Into<BoxError>) -> Self {
+        Self {
+            attempt,
+            status,
+            source: source.into().into(),
+        }
+    }
+
+    /// Returns the 1-based number of the attempt.
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// Returns the HTTP status of the response to the attempt, or `None` if there was no response.
+    pub fn status(&self) -> Option<u16> {
+        self.status
+    }
+}
+
+impl Display for AttemptError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "attempt {} failed", self.attempt)
+    }
+}
+
+impl Error for AttemptError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
 /// Error context for [`SdkError::ConstructionFailure`]
 #[derive(Debug)]
 pub struct ConstructionFailure {
     source: BoxError,
+    attempt_errors: Vec<AttemptError>,
 }
 
 impl ConstructionFailure {
@@ -196,6 +245,7 @@ impl ConstructionFailure {
 #[derive(Debug)]
 pub struct TimeoutError {
     source: BoxError,
+    attempt_errors: Vec<AttemptError>,
 }
 
 impl TimeoutError {
@@ -209,6 +259,7 @@ impl TimeoutError {
 #[derive(Debug)]
 pub struct DispatchFailure {
     source: ConnectorError,
+    attempt_errors: Vec<AttemptError>,
 }
 
 impl DispatchFailure {
@@ -241,6 +292,13 @@ impl DispatchFailure {
     pub fn as_connector_error(&self) -> Option<&ConnectorError> {
         Some(&self.source)
     }
+
+    /// Returns an iterator over the chain of errors that caused this failure
+    ///
+    /// See [`ConnectorError::source_chain`].
+    pub fn source_chain(&self) -> impl Iterator<Item = &(dyn Error + 'static)> {
+        self.source.source_chain()
+    }
 }
 
 /// Error context for [`SdkError::ResponseError`]
@@ -250,6 +308,7 @@ pub struct ResponseError<R> {
     source: BoxError,
     /// Raw response that was available
     raw: R,
+    attempt_errors: Vec<AttemptError>,
 }
 
 impl<R> ResponseError<R> {
@@ -276,6 +335,7 @@ pub struct ServiceError<E, R> {
     source: E,
     /// Raw response from the service
     raw: R,
+    attempt_errors: Vec<AttemptError>,
 }
 
 impl<E, R> ServiceError<E, R> {
@@ -348,6 +408,7 @@ impl<E, R> SdkError<E, R> {
     pub fn construction_failure(source: impl Into<BoxError>) -> Self {
         Self::ConstructionFailure(ConstructionFailure {
             source: source.into(),
+            attempt_errors: Vec::new(),
         })
     }
 
@@ -355,12 +416,16 @@ impl<E, R> SdkError<E, R> {
     pub fn timeout_error(source: impl Into<BoxError>) -> Self {
         Self::TimeoutError(TimeoutError {
             source: source.into(),
+            attempt_errors: Vec::new(),
         })
     }
 
     /// Construct a `SdkError` for a dispatch failure with a [`ConnectorError`]
     pub fn dispatch_failure(source: ConnectorError) -> Self {
-        Self::DispatchFailure(DispatchFailure { source })
+        Self::DispatchFailure(DispatchFailure {
+            source,
+            attempt_errors: Vec::new(),
+        })
     }
 
     /// Construct a `SdkError` for a response error
@@ -368,12 +433,17 @@ impl<E, R> SdkError<E, R> {
         Self::ResponseError(ResponseError {
             source: source.into(),
             raw,
+            attempt_errors: Vec::new(),
         })
     }
 
     /// Construct a `SdkError` for a service failure
     pub fn service_error(source: E, raw: R) -> Self {
-        Self::ServiceError(ServiceError { source, raw })
+        Self::ServiceError(ServiceError {
+            source,
+            raw,
+            attempt_errors: Vec::new(),
+        })
     }
 
     /// Returns the underlying service error `E` if there is one
@@ -445,6 +515,33 @@ impl<E, R> SdkError<E, R> {
         }
     }
 
+    /// Returns the errors of the attempts that were retried before the operation failed with this
+    /// error, in order.
+    ///
+    /// This is empty if the operation wasn't retried, or if nothing recorded the attempts.
+    pub fn attempt_errors(&self) -> &[AttemptError] {
+        match self {
+            Self::ConstructionFailure(context) => &context.attempt_errors,
+            Self::TimeoutError(context) => &context.attempt_errors,
+            Self::DispatchFailure(context) => &context.attempt_errors,
+            Self::ResponseError(context) => &context.attempt_errors,
+            Self::ServiceError(context) => &context.attempt_errors,
+        }
+    }
+
+    /// Attaches the errors of the attempts that were retried to this error, replacing any that
+    /// were attached before.
+    pub fn with_attempt_errors(mut self, attempt_errors: Vec<AttemptError>) -> Self {
+        *match &mut self {
+            Self::ConstructionFailure(context) => &mut context.attempt_errors,
+            Self::TimeoutError(context) => &mut context.attempt_errors,
+            Self::DispatchFailure(context) => &mut context.attempt_errors,
+            Self::ResponseError(context) => &mut context.attempt_errors,
+            Self::ServiceError(context) => &mut context.attempt_errors,
+        } = attempt_errors;
+        self
+    }
+
     /// Maps the service error type in `SdkError::ServiceError`
     #[doc(hidden)]
     pub fn map_service_error<E2>(self, map: impl FnOnce(E) -> E2) -> SdkError<E2, R> {
@@ -452,6 +549,7 @@ impl<E, R> SdkError<E, R> {
             Self::ServiceError(context) => SdkError::<E2, R>::ServiceError(ServiceError {
                 source: map(context.source),
                 raw: context.raw,
+                attempt_errors: context.attempt_errors,
             }),
             Self::ConstructionFailure(context) => SdkError::<E2, R>::ConstructionFailure(context),
             Self::DispatchFailure(context) => SdkError::<E2, R>::DispatchFailure(context),
@@ -641,6 +739,15 @@ impl ConnectorError {
         }
     }
 
+    /// Returns an iterator over the chain of errors that caused this error
+    ///
+    /// The iterator starts with the error the connector was constructed from, and then follows each
+    /// error's [`source`](Error::source) down to the root cause (e.g. a DNS error caused by an IO error).
+    pub fn source_chain(&self) -> impl Iterator<Item = &(dyn Error + 'static)> {
+        let source: &(dyn Error + 'static) = self.source.as_ref();
+        std::iter::successors(Some(source), |err| (*err).source())
+    }
+
     /// Returns metadata about the connection
     ///
     /// If a connection was established and provided by the internal connector, a connection will
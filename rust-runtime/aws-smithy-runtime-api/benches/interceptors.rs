@@ -0,0 +1,90 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use aws_smithy_runtime_api::client::interceptors::{Interceptor, InterceptorContext, Interceptors};
+use aws_smithy_runtime_api::client::orchestrator::DisabledInterceptors;
+use aws_smithy_runtime_api::config_bag::ConfigBag;
+use aws_smithy_runtime_api::type_erasure::TypedBox;
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::sync::Arc;
+
+/// An interceptor that doesn't implement any hooks.
+#[derive(Debug)]
+struct NoOpInterceptor;
+
+impl Interceptor for NoOpInterceptor {}
+
+/// Runs every hook that the orchestrator runs for a single attempt.
+fn run_hooks(interceptors: &Interceptors, context: &mut InterceptorContext, cfg: &mut ConfigBag) {
+    interceptors
+        .client_read_before_execution(context, cfg)
+        .unwrap();
+    interceptors
+        .operation_read_before_execution(context, cfg)
+        .unwrap();
+    interceptors
+        .modify_before_serialization(context, cfg)
+        .unwrap();
+    interceptors
+        .read_before_serialization(context, cfg)
+        .unwrap();
+    interceptors.read_after_serialization(context, cfg).unwrap();
+    interceptors.modify_before_retry_loop(context, cfg).unwrap();
+    interceptors.read_before_attempt(context, cfg).unwrap();
+    interceptors.modify_before_signing(context, cfg).unwrap();
+    interceptors.read_before_signing(context, cfg).unwrap();
+    interceptors.read_after_signing(context, cfg).unwrap();
+    interceptors.modify_before_transmit(context, cfg).unwrap();
+    interceptors.read_before_transmit(context, cfg).unwrap();
+    interceptors.read_after_transmit(context, cfg).unwrap();
+    interceptors
+        .modify_before_deserialization(context, cfg)
+        .unwrap();
+    interceptors
+        .read_before_deserialization(context, cfg)
+        .unwrap();
+    interceptors
+        .read_after_deserialization(context, cfg)
+        .unwrap();
+    interceptors
+        .modify_before_attempt_completion(context, cfg)
+        .unwrap();
+    interceptors.read_after_attempt(context, cfg).unwrap();
+    interceptors.modify_before_completion(context, cfg).unwrap();
+    interceptors.read_after_execution(context, cfg).unwrap();
+}
+
+fn bench_hooks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Hooks");
+
+    let cases: [(&str, usize, bool); 3] = [
+        // Without interceptors, every hook takes the fast path
+        ("fast path", 0, false),
+        // A disabled interceptor takes the slow path, with its loop and config lookups, without
+        // running any hooks, so comparing it with the fast path shows what the fast path saves
+        ("slow path", 1, true),
+        // An interceptor that does nothing adds the cost of dispatching to it
+        ("no-op interceptor", 1, false),
+    ];
+    for (name, count, disabled) in cases {
+        let mut interceptors = Interceptors::new();
+        for _ in 0..count {
+            interceptors.register_client_interceptor(Arc::new(NoOpInterceptor));
+        }
+        let mut context = InterceptorContext::new(TypedBox::new(()).erase());
+        let mut cfg = ConfigBag::base();
+        if disabled {
+            cfg.store_put(DisabledInterceptors::new().with_interceptor(NoOpInterceptor.name()));
+        }
+
+        group.bench_function(name, |b| {
+            b.iter(|| run_hooks(&interceptors, &mut context, &mut cfg))
+        });
+    }
+    group.finish()
+}
+
+criterion_group!(benches, bench_hooks);
+criterion_main!(benches);
@@ -5,7 +5,7 @@
 
 use crate::client::auth::AuthSchemeId;
 use crate::client::orchestrator::Future;
-use crate::config_bag::ConfigBag;
+use crate::config_bag::{ConfigBag, Storable, StoreReplace};
 use std::any::Any;
 use std::fmt::Debug;
 use std::sync::Arc;
@@ -16,6 +16,15 @@ pub mod http;
 
 pub trait IdentityResolver: Send + Sync + Debug {
     fn resolve_identity(&self, config_bag: &ConfigBag) -> Future<Identity>;
+
+    /// Discards any identity this resolver has cached, so that the next call to
+    /// [`resolve_identity`](Self::resolve_identity) resolves a fresh one.
+    ///
+    /// The orchestrator calls this before retrying an attempt that was rejected with a
+    /// `401 Unauthorized` or `403 Forbidden` response, since the cached credentials may have
+    /// expired or been revoked. Resolvers that don't cache have nothing to discard, so this does
+    /// nothing by default.
+    fn invalidate_cached_identity(&self) {}
 }
 
 #[derive(Clone, Debug, Default)]
@@ -35,6 +44,15 @@ impl IdentityResolvers {
             .map(|resolver| &*resolver.1)
     }
 
+    /// Discards the cached identity of every resolver.
+    ///
+    /// See [`IdentityResolver::invalidate_cached_identity`].
+    pub fn invalidate_cached_identities(&self) {
+        for (_, resolver) in &self.identity_resolvers {
+            resolver.invalidate_cached_identity();
+        }
+    }
+
     pub fn to_builder(self) -> builders::IdentityResolversBuilder {
         builders::IdentityResolversBuilder {
             identity_resolvers: self.identity_resolvers,
@@ -42,6 +60,12 @@ impl IdentityResolvers {
     }
 }
 
+// The resolvers may hold static credentials
+impl Storable for IdentityResolvers {
+    type Storer = StoreReplace<Self>;
+    const SENSITIVE: bool = true;
+}
+
 #[derive(Clone, Debug)]
 pub struct Identity {
     data: Arc<dyn Any + Send + Sync>,
@@ -65,6 +89,11 @@ impl Identity {
     }
 }
 
+impl Storable for Identity {
+    type Storer = StoreReplace<Self>;
+    const SENSITIVE: bool = true;
+}
+
 #[derive(Debug)]
 pub struct AnonymousIdentity;
 
@@ -152,4 +181,40 @@ mod tests {
         assert_eq!("bar", identity.data::<MyIdentityData>().unwrap().last);
         assert_eq!(Some(&expiration), identity.expiration());
     }
+
+    #[test]
+    fn identity_resolvers_are_redacted() {
+        use crate::client::orchestrator::ConfigBagAccessors;
+
+        #[derive(Debug)]
+        struct StaticCredentials {
+            secret_key: &'static str,
+        }
+        impl IdentityResolver for StaticCredentials {
+            fn resolve_identity(&self, _config_bag: &ConfigBag) -> Future<Identity> {
+                Future::ready(Ok(Identity::new(self.secret_key, None)))
+            }
+        }
+        let identity_resolvers = |secret_key| {
+            IdentityResolvers::builder()
+                .identity_resolver(AuthSchemeId::new("test"), StaticCredentials { secret_key })
+                .build()
+        };
+
+        let mut cfg = ConfigBag::base();
+        cfg.set_identity_resolvers(identity_resolvers("hunter2"));
+        // Replacing them with `put` in a later layer keeps them redacted
+        let mut cfg = cfg.add_layer("operation");
+        cfg.put(identity_resolvers("hunter3"));
+
+        let entries = cfg
+            .entries()
+            .filter(|entry| entry.type_name() == std::any::type_name::<IdentityResolvers>())
+            .map(|entry| entry.is_sensitive())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![true, true], entries);
+        let dump = format!("{:?}", cfg.entries().collect::<Vec<_>>());
+        assert!(!dump.contains("hunter2"), "{dump}");
+        assert!(!dump.contains("hunter3"), "{dump}");
+    }
 }
@@ -6,11 +6,13 @@
 pub mod context;
 pub mod error;
 
-use crate::config_bag::ConfigBag;
+use crate::client::orchestrator::DisabledInterceptors;
+use crate::config_bag::{ConfigBag, Storable, StoreReplace};
 use aws_smithy_types::error::display::DisplayErrorContext;
 pub use context::InterceptorContext;
 pub use error::{BoxError, InterceptorError};
 use std::sync::Arc;
+use std::time::Instant;
 
 macro_rules! interceptor_trait_fn {
     ($name:ident, $docs:tt) => {
@@ -46,6 +48,14 @@ macro_rules! interceptor_trait_fn {
 ///   to read in-flight request or response messages, or "read/write" hooks, which make it possible
 ///   to modify in-flight request or output messages.
 pub trait Interceptor: std::fmt::Debug {
+    /// Returns the name that the interceptor is identified by, for example to disable it with
+    /// [`DisabledInterceptors`](crate::client::orchestrator::DisabledInterceptors).
+    ///
+    /// Defaults to the interceptor's type name, as given by [`std::any::type_name`].
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
     interceptor_trait_fn!(
         read_before_execution,
         "
@@ -205,6 +215,11 @@ pub trait Interceptor: std::fmt::Debug {
         in previous attempts
         (e.g. by request signers or other interceptors).
 
+        This is the place to add or rewrite query parameters, such as an API
+        version that every request must carry. The endpoint has already been
+        applied to the request's URI, and any changes to its query string will
+        be covered by signers, such as SigV4, that sign the query.
+
         **Error Behavior:** If errors are raised by this
         hook, execution will jump to `modify_before_attempt_completion` with
         the raised error as the [InterceptorContext::output_or_error()].
@@ -539,12 +554,41 @@ pub trait Interceptor: std::fmt::Debug {
 
 pub type SharedInterceptor = Arc<dyn Interceptor + Send + Sync>;
 
+/// The interceptors registered for an operation.
+///
+/// Every hook runs the interceptors in the same order: first the client interceptors, in the order
+/// they were registered, and then the operation interceptors, in the order they were registered.
+/// Use [`Interceptors::interceptors`] to inspect that order.
 #[derive(Debug, Clone, Default)]
 pub struct Interceptors {
     client_interceptors: Vec<SharedInterceptor>,
     operation_interceptors: Vec<SharedInterceptor>,
 }
 
+/// Whether a `DEBUG` event is emitted when every interceptor hook is entered and exited, with the
+/// name of the hook and, on exit, how long its interceptors took.
+///
+/// Hooks are only traced while there are interceptors to run. Disabled by default, to avoid the
+/// overhead.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TraceInterceptorHooks(bool);
+
+impl TraceInterceptorHooks {
+    /// Create a new [`TraceInterceptorHooks`].
+    pub fn new(enabled: bool) -> Self {
+        Self(enabled)
+    }
+
+    /// Returns `true` if interceptor hooks are traced.
+    pub fn enabled(&self) -> bool {
+        self.0
+    }
+}
+
+impl Storable for TraceInterceptorHooks {
+    type Storer = StoreReplace<Self>;
+}
+
 macro_rules! interceptor_impl_fn {
     (context, $name:ident) => {
         interceptor_impl_fn!(context, $name, $name);
@@ -564,8 +608,34 @@ macro_rules! interceptor_impl_fn {
             $context: $context_ty,
             cfg: &mut ConfigBag,
         ) -> Result<(), InterceptorError> {
+            // Most clients don't register any interceptors, so don't pay for the loop or any
+            // config lookups in that case
+            if self.is_empty() {
+                return Ok(());
+            }
+            // Interceptors may change the setting, so it's read once for both events
+            let trace_hooks = cfg
+                .load::<TraceInterceptorHooks>()
+                .map_or(false, TraceInterceptorHooks::enabled);
+            let started_at = trace_hooks.then(|| {
+                tracing::debug!(hook = stringify!($outer_name), "entering interceptor hook");
+                Instant::now()
+            });
+            // Interceptors need the bag mutably, so the (cheap to clone) set is copied out of it
+            let disabled_interceptors = cfg.load::<DisabledInterceptors>().cloned();
             let mut result: Result<(), BoxError> = Ok(());
             for interceptor in self.interceptors() {
+                let disabled = disabled_interceptors
+                    .as_ref()
+                    .map_or(false, |disabled| disabled.contains(interceptor.name()));
+                if disabled {
+                    tracing::debug!(
+                        hook = stringify!($outer_name),
+                        interceptor = interceptor.name(),
+                        "skipping disabled interceptor"
+                    );
+                    continue;
+                }
                 if let Err(new_error) = interceptor.$inner_name($context, cfg) {
                     if let Err(last_error) = result {
                         tracing::debug!("{}", DisplayErrorContext(&*last_error));
@@ -573,6 +643,13 @@ macro_rules! interceptor_impl_fn {
                     result = Err(new_error);
                 }
             }
+            if let Some(started_at) = started_at {
+                tracing::debug!(
+                    hook = stringify!($outer_name),
+                    duration = ?started_at.elapsed(),
+                    "exiting interceptor hook"
+                );
+            }
             result.map_err(InterceptorError::$inner_name)
         }
     };
@@ -583,7 +660,11 @@ impl Interceptors {
         Self::default()
     }
 
-    fn interceptors(&self) -> impl Iterator<Item = &SharedInterceptor> {
+    /// Returns the registered interceptors in the order that every hook runs them in.
+    ///
+    /// Client interceptors always come before operation interceptors, regardless of when they
+    /// were registered.
+    pub fn interceptors(&self) -> impl Iterator<Item = &SharedInterceptor> {
         // Since interceptors can modify the interceptor list (since its in the config bag), copy the list ahead of time.
         // This should be cheap since the interceptors inside the list are Arcs.
         self.client_interceptors
@@ -591,6 +672,30 @@ impl Interceptors {
             .chain(self.operation_interceptors.iter())
     }
 
+    /// Returns `true` if no client or operation interceptors are registered, in which case every
+    /// hook does nothing.
+    pub fn is_empty(&self) -> bool {
+        self.client_interceptors.is_empty() && self.operation_interceptors.is_empty()
+    }
+
+    /// Returns the interceptors registered with [`Interceptors::register_client_interceptor`], in
+    /// the order they were registered.
+    ///
+    /// Interceptors aren't registered for individual hooks: every hook runs every interceptor, and
+    /// hooks that an interceptor doesn't implement do nothing. Client interceptors run before
+    /// operation interceptors in every hook.
+    pub fn client_interceptors(&self) -> impl Iterator<Item = &SharedInterceptor> {
+        self.client_interceptors.iter()
+    }
+
+    /// Returns the interceptors registered with [`Interceptors::register_operation_interceptor`],
+    /// in the order they were registered.
+    ///
+    /// See [`Interceptors::client_interceptors`] for how they relate to the hooks.
+    pub fn operation_interceptors(&self) -> impl Iterator<Item = &SharedInterceptor> {
+        self.operation_interceptors.iter()
+    }
+
     pub fn register_client_interceptor(&mut self, interceptor: SharedInterceptor) -> &mut Self {
         self.client_interceptors.push(interceptor);
         self
@@ -626,3 +731,171 @@ impl Interceptors {
     interceptor_impl_fn!(mut context, modify_before_completion);
     interceptor_impl_fn!(context, read_after_execution);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::type_erasure::TypedBox;
+    use std::fmt;
+    use std::sync::Mutex;
+
+    /// Records its name every time one of its hooks is run.
+    struct RecordingInterceptor {
+        name: &'static str,
+        log: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    impl fmt::Debug for RecordingInterceptor {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.name)
+        }
+    }
+
+    impl Interceptor for RecordingInterceptor {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn modify_before_signing(
+            &self,
+            _context: &mut InterceptorContext,
+            _cfg: &mut ConfigBag,
+        ) -> Result<(), BoxError> {
+            self.log.lock().unwrap().push(self.name);
+            Ok(())
+        }
+
+        fn read_before_transmit(
+            &self,
+            _context: &InterceptorContext,
+            _cfg: &mut ConfigBag,
+        ) -> Result<(), BoxError> {
+            self.log.lock().unwrap().push(self.name);
+            Ok(())
+        }
+    }
+
+    fn recorder(log: &Arc<Mutex<Vec<&'static str>>>, name: &'static str) -> SharedInterceptor {
+        Arc::new(RecordingInterceptor {
+            name,
+            log: log.clone(),
+        })
+    }
+
+    #[test]
+    fn interceptors_are_listed_in_execution_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut interceptors = Interceptors::new();
+        interceptors
+            .register_operation_interceptor(recorder(&log, "operation-1"))
+            .register_client_interceptor(recorder(&log, "client-1"))
+            .register_operation_interceptor(recorder(&log, "operation-2"))
+            .register_client_interceptor(recorder(&log, "client-2"));
+
+        let listed: Vec<String> = interceptors
+            .interceptors()
+            .map(|interceptor| format!("{interceptor:?}"))
+            .collect();
+        assert_eq!(
+            vec!["client-1", "client-2", "operation-1", "operation-2"],
+            listed
+        );
+
+        let mut context = InterceptorContext::new(TypedBox::new(()).erase());
+        let mut cfg = ConfigBag::base();
+        interceptors
+            .modify_before_signing(&mut context, &mut cfg)
+            .unwrap();
+        assert_eq!(listed, *log.lock().unwrap());
+
+        log.lock().unwrap().clear();
+        interceptors
+            .read_before_transmit(&context, &mut cfg)
+            .unwrap();
+        assert_eq!(listed, *log.lock().unwrap());
+    }
+
+    #[test]
+    fn disabled_interceptors_are_skipped_by_every_hook() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut interceptors = Interceptors::new();
+        interceptors
+            .register_client_interceptor(recorder(&log, "enabled"))
+            .register_client_interceptor(recorder(&log, "disabled"));
+
+        let mut context = InterceptorContext::new(TypedBox::new(()).erase());
+        let mut cfg = ConfigBag::base();
+        cfg.store_put(DisabledInterceptors::new().with_interceptor("disabled"));
+        interceptors
+            .modify_before_signing(&mut context, &mut cfg)
+            .unwrap();
+        interceptors
+            .read_before_transmit(&context, &mut cfg)
+            .unwrap();
+        assert_eq!(vec!["enabled", "enabled"], *log.lock().unwrap());
+    }
+
+    #[test]
+    fn hooks_succeed_without_interceptors() {
+        let mut interceptors = Interceptors::new();
+        assert!(interceptors.is_empty());
+
+        let mut context = InterceptorContext::new(TypedBox::new(()).erase());
+        let mut cfg = ConfigBag::base();
+        for trace_interceptor_hooks in [false, true] {
+            cfg.store_put(TraceInterceptorHooks::new(trace_interceptor_hooks));
+            interceptors
+                .client_read_before_execution(&context, &mut cfg)
+                .unwrap();
+            interceptors
+                .modify_before_signing(&mut context, &mut cfg)
+                .unwrap();
+            interceptors
+                .read_after_execution(&context, &mut cfg)
+                .unwrap();
+        }
+        assert!(context.input().is_ok());
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        interceptors.register_operation_interceptor(recorder(&log, "operation"));
+        assert!(!interceptors.is_empty());
+        interceptors
+            .modify_before_signing(&mut context, &mut cfg)
+            .unwrap();
+        assert_eq!(vec!["operation"], *log.lock().unwrap());
+    }
+
+    #[test]
+    fn interceptors_are_named_after_their_type_by_default() {
+        #[derive(Debug)]
+        struct UnnamedInterceptor;
+        impl Interceptor for UnnamedInterceptor {}
+
+        assert!(UnnamedInterceptor.name().ends_with("UnnamedInterceptor"));
+    }
+
+    #[test]
+    fn interceptors_are_listed_by_category() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut interceptors = Interceptors::new();
+        interceptors
+            .register_operation_interceptor(recorder(&log, "operation-1"))
+            .register_client_interceptor(recorder(&log, "client-1"))
+            .register_operation_interceptor(recorder(&log, "operation-2"));
+
+        let names = |listed: Vec<&SharedInterceptor>| -> Vec<String> {
+            listed
+                .into_iter()
+                .map(|interceptor| format!("{interceptor:?}"))
+                .collect()
+        };
+        assert_eq!(
+            vec!["client-1"],
+            names(interceptors.client_interceptors().collect())
+        );
+        assert_eq!(
+            vec!["operation-1", "operation-2"],
+            names(interceptors.operation_interceptors().collect())
+        );
+    }
+}
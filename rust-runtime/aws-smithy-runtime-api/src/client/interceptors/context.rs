@@ -4,8 +4,9 @@
  */
 
 use super::InterceptorError;
-use crate::client::orchestrator::{HttpRequest, HttpResponse};
+use crate::client::orchestrator::{HttpRequest, HttpResponse, ResponseTrailers};
 use crate::type_erasure::TypeErasedBox;
+use aws_smithy_http::body::SdkBody;
 
 pub type Input = TypeErasedBox;
 pub type Output = TypeErasedBox;
@@ -18,9 +19,39 @@ type Response = HttpResponse;
 /// A container for the data currently available to an interceptor.
 pub struct InterceptorContext {
     input: Option<Input>,
+    original_input: Option<Input>,
     output_or_error: Option<OutputOrError>,
     request: Option<Request>,
     response: Option<Response>,
+    request_checkpoint: Option<Request>,
+}
+
+/// The result of calling [`InterceptorContext::rewind`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RewindResult {
+    /// The request hasn't been dispatched yet, so there was nothing to rewind.
+    Unnecessary,
+    /// The request was restored from the checkpoint and the previous attempt's response was discarded.
+    Occurred,
+    /// The request was dispatched, but its body couldn't be cloned when the checkpoint was saved,
+    /// so it can't be sent again.
+    Impossible,
+}
+
+/// A summary of the data an [`InterceptorContext`] holds, as returned by
+/// [`InterceptorContext::snapshot`].
+///
+/// Snapshots can be compared against a recorded expectation in tests of interceptor pipelines.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ContextSnapshot {
+    /// The `Debug` representation of the input, if it's set.
+    pub input: Option<String>,
+    /// The method and URI of the request, if it's set.
+    pub request: Option<String>,
+    /// The status code of the response, if it's set.
+    pub response: Option<u16>,
+    /// The `Debug` representation of the output or error, if it's set.
+    pub output_or_error: Option<Result<String, String>>,
 }
 
 // TODO(interceptors) we could use types to ensure that people calling methods on interceptor context can't access
@@ -29,9 +60,25 @@ impl InterceptorContext {
     pub fn new(input: Input) -> Self {
         Self {
             input: Some(input),
+            original_input: None,
             output_or_error: None,
             request: None,
             response: None,
+            request_checkpoint: None,
+        }
+    }
+
+    /// Creates a context for a request that was already serialized, such as one being replayed.
+    ///
+    /// The input the request was serialized from isn't known, so the context has no input.
+    pub fn from_request(request: Request) -> Self {
+        Self {
+            input: None,
+            original_input: None,
+            output_or_error: None,
+            request: Some(request),
+            response: None,
+            request_checkpoint: None,
         }
     }
 
@@ -55,6 +102,20 @@ impl InterceptorContext {
         self.input.take()
     }
 
+    /// Retrieve the copy of the input that was kept when it was serialized, if one was kept.
+    ///
+    /// Unlike [`input`](Self::input), this remains available after serialization. A copy is only
+    /// kept when [`RetainInput`](crate::client::orchestrator::RetainInput) is set in the config bag.
+    pub fn original_input(&self) -> Option<&Input> {
+        self.original_input.as_ref()
+    }
+
+    /// Keeps a copy of the input, to be returned by [`original_input`](Self::original_input).
+    #[doc(hidden)]
+    pub fn set_original_input(&mut self, input: Input) {
+        self.original_input = Some(input);
+    }
+
     /// Retrieve the transmittable request for the operation being invoked.
     /// This will only be available once request marshalling has completed.
     pub fn request(&self) -> Result<&Request, InterceptorError> {
@@ -77,6 +138,18 @@ impl InterceptorContext {
         self.request.take()
     }
 
+    /// Retrieve the transmittable request in its serialized HTTP/1.1 form: the request line
+    /// followed by the headers, and (optionally) the body.
+    ///
+    /// This is intended for auditing the exact request that will be sent, e.g. from
+    /// `read_before_transmit`, after the request has been signed. A `host` header is derived from
+    /// the URI if the request doesn't already have one, as the connection would. Streaming bodies
+    /// can't be included without consuming them, so they're always left out.
+    pub fn request_bytes(&self, include_body: bool) -> Result<Vec<u8>, InterceptorError> {
+        self.request()
+            .map(|request| serialize_request(request, include_body))
+    }
+
     /// Retrieve the response to the transmittable response for the operation
     /// being invoked. This will only be available once transmission has
     /// completed.
@@ -95,6 +168,28 @@ impl InterceptorContext {
             .ok_or_else(InterceptorError::invalid_response_access)
     }
 
+    /// Retrieve the status code of the response, or `None` if there is no response yet.
+    ///
+    /// This is a shortcut for retry classifiers and strategies, which often only need the status.
+    pub fn response_status(&self) -> Option<u16> {
+        self.response
+            .as_ref()
+            .map(|response| response.status().as_u16())
+    }
+
+    /// Retrieve the trailing headers of the response, or `None` if there is no response yet, or
+    /// it had no trailers.
+    ///
+    /// Trailers are only available once the response body has been read into memory, so streamed
+    /// responses never have them.
+    pub fn response_trailers(&self) -> Option<&http::HeaderMap> {
+        self.response
+            .as_ref()?
+            .extensions()
+            .get::<ResponseTrailers>()
+            .map(ResponseTrailers::headers)
+    }
+
     /// Retrieve the response to the customer. This will only be available
     /// once the `response` has been unmarshalled or the attempt/execution has failed.
     pub fn output_or_error(&self) -> Result<Result<&Output, &Error>, InterceptorError> {
@@ -113,6 +208,29 @@ impl InterceptorContext {
             .ok_or_else(InterceptorError::invalid_output_access)
     }
 
+    /// Takes the response to the customer out of the context, if it's there.
+    pub fn take_output_or_error(&mut self) -> Option<OutputOrError> {
+        self.output_or_error.take()
+    }
+
+    /// Summarizes what the context currently holds.
+    pub fn snapshot(&self) -> ContextSnapshot {
+        ContextSnapshot {
+            input: self.input.as_ref().map(|input| format!("{input:?}")),
+            request: self
+                .request
+                .as_ref()
+                .map(|request| format!("{} {}", request.method(), request.uri())),
+            response: self.response_status(),
+            output_or_error: self.output_or_error.as_ref().map(|output_or_error| {
+                output_or_error
+                    .as_ref()
+                    .map(|output| format!("{output:?}"))
+                    .map_err(|error| format!("{error:?}"))
+            }),
+        }
+    }
+
     // There is no set_input method because that can only be set once, during context construction
 
     pub fn set_request(&mut self, request: Request) {
@@ -141,6 +259,43 @@ impl InterceptorContext {
         self.output_or_error = Some(output);
     }
 
+    /// Saves a copy of the current request so that it can be restored by [`rewind`](Self::rewind)
+    /// before a retry attempt.
+    ///
+    /// If the request body can't be cloned (e.g. a one-shot stream), no checkpoint is saved.
+    #[doc(hidden)]
+    pub fn save_checkpoint(&mut self) {
+        self.request_checkpoint = self.request.as_ref().and_then(try_clone);
+    }
+
+    /// Restores the request saved by [`save_checkpoint`](Self::save_checkpoint) and clears the
+    /// response and output from the previous attempt.
+    #[doc(hidden)]
+    pub fn rewind(&mut self) -> RewindResult {
+        // The request is only taken out of the context when it's dispatched. If it's still here,
+        // then this is the first attempt.
+        if self.request.is_some() {
+            return RewindResult::Unnecessary;
+        }
+
+        match self.request_checkpoint.as_ref().and_then(try_clone) {
+            Some(request) => {
+                self.request = Some(request);
+                self.response = None;
+                self.output_or_error = None;
+                RewindResult::Occurred
+            }
+            None => RewindResult::Impossible,
+        }
+    }
+
+    /// Returns `false` if the request was dispatched and can't be restored by
+    /// [`rewind`](Self::rewind), because its body couldn't be cloned when the checkpoint was saved.
+    #[doc(hidden)]
+    pub fn can_rewind(&self) -> bool {
+        self.request.is_some() || self.request_checkpoint.is_some()
+    }
+
     #[doc(hidden)]
     pub fn into_parts(
         self,
@@ -158,3 +313,161 @@ impl InterceptorContext {
         )
     }
 }
+
+fn serialize_request(request: &Request, include_body: bool) -> Vec<u8> {
+    let uri = request.uri();
+    let path_and_query = uri
+        .path_and_query()
+        .map(|path_and_query| path_and_query.as_str())
+        .unwrap_or("/");
+    let mut output = format!(
+        "{} {} {:?}\r\n",
+        request.method(),
+        path_and_query,
+        request.version()
+    )
+    .into_bytes();
+    if !request.headers().contains_key(http::header::HOST) {
+        if let Some(authority) = uri.authority() {
+            output.extend_from_slice(format!("host: {}\r\n", authority).as_bytes());
+        }
+    }
+    for (name, value) in request.headers() {
+        output.extend_from_slice(name.as_str().as_bytes());
+        output.extend_from_slice(b": ");
+        output.extend_from_slice(value.as_bytes());
+        output.extend_from_slice(b"\r\n");
+    }
+    output.extend_from_slice(b"\r\n");
+    if include_body {
+        if let Some(body) = request.body().bytes() {
+            output.extend_from_slice(body);
+        }
+    }
+    output
+}
+
+fn try_clone(request: &Request) -> Option<Request> {
+    // Requests without a body (e.g. most GETs) only need their headers cloned
+    let body = if request.body().is_known_empty() {
+        SdkBody::empty()
+    } else {
+        request.body().try_clone()?
+    };
+    let mut cloned = http::Request::builder()
+        .method(request.method().clone())
+        .uri(request.uri().clone())
+        .version(request.version())
+        .body(body)
+        .expect("a clone of a valid request is a valid request");
+    *cloned.headers_mut() = request.headers().clone();
+    Some(cloned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::type_erasure::TypedBox;
+    use aws_smithy_http::body::BoxBody;
+    use bytes::Bytes;
+    use http_body::Body;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn context_with_body(body: SdkBody) -> InterceptorContext {
+        let mut context = InterceptorContext::new(TypedBox::new("doesnt-matter").erase());
+        context.set_request(
+            http::Request::builder()
+                .header("test", "value")
+                .body(body)
+                .unwrap(),
+        );
+        context
+    }
+
+    #[test]
+    fn rewind_restores_the_checkpointed_request() {
+        let mut context = context_with_body(SdkBody::from("hello"));
+        context.save_checkpoint();
+        assert_eq!(RewindResult::Unnecessary, context.rewind());
+
+        context.take_request().expect("request was set");
+        context.set_response(http::Response::builder().body(SdkBody::empty()).unwrap());
+        assert_eq!(RewindResult::Occurred, context.rewind());
+
+        let request = context.request().expect("request was restored");
+        assert_eq!("value", request.headers().get("test").unwrap());
+        assert_eq!(Some(&b"hello"[..]), request.body().bytes());
+        assert!(context.response().is_err());
+    }
+
+    #[test]
+    fn response_status_is_only_available_once_there_is_a_response() {
+        let mut context = context_with_body(SdkBody::empty());
+        assert_eq!(None, context.response_status());
+
+        context.set_response(
+            http::Response::builder()
+                .status(503)
+                .body(SdkBody::empty())
+                .unwrap(),
+        );
+        assert_eq!(Some(503), context.response_status());
+    }
+
+    #[test]
+    fn rewinding_an_empty_body_does_not_rebuild_it() {
+        let rebuilds = Arc::new(AtomicUsize::new(0));
+        let body = SdkBody::retryable({
+            let rebuilds = rebuilds.clone();
+            move || {
+                rebuilds.fetch_add(1, Ordering::SeqCst);
+                SdkBody::empty()
+            }
+        });
+        let mut context = context_with_body(body);
+        context.save_checkpoint();
+
+        context.take_request().expect("request was set");
+        assert_eq!(RewindResult::Occurred, context.rewind());
+        let request = context.request().expect("request was restored");
+        assert_eq!("value", request.headers().get("test").unwrap());
+        assert!(request.body().is_known_empty());
+        // Only the initial body was built
+        assert_eq!(1, rebuilds.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn rewind_is_impossible_for_a_one_shot_body() {
+        let body: BoxBody = http_body::Full::new(Bytes::from_static(b"hello"))
+            .map_err(|never| match never {})
+            .boxed();
+        let mut context = context_with_body(SdkBody::from_dyn(body));
+        context.save_checkpoint();
+
+        context.take_request().expect("request was set");
+        assert_eq!(RewindResult::Impossible, context.rewind());
+    }
+
+    #[test]
+    fn request_bytes_serializes_the_request_line_and_headers() {
+        let mut context = InterceptorContext::new(TypedBox::new("doesnt-matter").erase());
+        context.set_request(
+            http::Request::builder()
+                .method("PUT")
+                .uri("https://example.com/bucket/key?acl")
+                .header("authorization", "Signature abc")
+                .body(SdkBody::from("hello"))
+                .unwrap(),
+        );
+
+        let without_body = context.request_bytes(false).expect("request is set");
+        assert_eq!(
+            "PUT /bucket/key?acl HTTP/1.1\r\nhost: example.com\r\nauthorization: Signature abc\r\n\r\n",
+            std::str::from_utf8(&without_body).unwrap()
+        );
+        let with_body = context.request_bytes(true).expect("request is set");
+        assert!(with_body.starts_with(&without_body));
+        assert!(with_body.ends_with(b"\r\n\r\nhello"));
+    }
+}
@@ -3,23 +3,55 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
+pub mod body;
+pub mod caching;
+pub mod connection;
+pub mod endpoint;
+pub mod redirect;
+pub mod retries;
+pub mod signing;
+pub mod time;
+
 use crate::client::auth::{AuthOptionResolver, AuthOptionResolverParams, HttpAuthSchemes};
 use crate::client::identity::IdentityResolvers;
-use crate::client::interceptors::context::{Input, OutputOrError};
+use crate::client::interceptors::context::{ContextSnapshot, Input, OutputOrError};
 use crate::client::retries::RetryClassifiers;
 use crate::client::retries::RetryStrategy;
-use crate::config_bag::ConfigBag;
+use crate::config_bag::{ConfigBag, Storable, StoreReplace};
 use crate::type_erasure::{TypeErasedBox, TypedBox};
 use aws_smithy_async::future::now_or_later::NowOrLater;
 use aws_smithy_async::rt::sleep::AsyncSleep;
 use aws_smithy_http::body::SdkBody;
 use aws_smithy_http::endpoint::EndpointPrefix;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::future::Future as StdFuture;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::time::SystemTime;
 
+pub use body::{
+    BufferedResponseThreshold, ByteCounts, ContentEncoding, MaxSerializedRequestSize,
+    ReplayableBody, RequestChecksumAlgorithm, RequestCompression, ResponseDecompression,
+    ResponseTrailers, ValidateResponseChecksums,
+};
+pub use caching::{ResponseCache, ResponseCaching};
+pub use connection::{
+    BufferPool, ConcurrencyHint, ConnectionConfig, ConnectionSelector, DnsTiming, KeepAliveConfig,
+};
+pub use endpoint::{AsyncEndpointResolver, EndpointFailover, PreresolvedEndpoint};
+pub use redirect::RedirectPolicy;
+pub use retries::{
+    AttemptRetryReason, BeforeRetryCallback, CancellationSignal, FinalAttempt, MaxRetryDuration,
+    NonRetryableOperations, OperationCancelled, RequestAttempt, RequestAttemptHeader,
+    RetryConcurrencyLimiter, RetryPermit,
+};
+pub use signing::{
+    ClassifyClockSkew, ClockSkew, CorrectClockSkew, SigningRetryConfig, SigningTime,
+};
+pub use time::{OperationDeadline, SystemTimeSource, TimeSource};
+
 pub type HttpRequest = http::Request<SdkBody>;
 pub type HttpResponse = http::Response<SdkBody>;
 pub type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
@@ -30,7 +62,28 @@ pub trait TraceProbe: Send + Sync + fmt::Debug {
     fn dispatch_events(&self);
 }
 
+/// Observes the [`InterceptorContext`](crate::client::interceptors::InterceptorContext) as the
+/// orchestrator enters each phase.
+///
+/// This is intended for golden-file tests of interceptor pipelines: record the snapshots of a run,
+/// and compare them against the expected sequence of phase states.
+pub trait PhaseObserver: Send + Sync + fmt::Debug {
+    /// Called with the name of the phase being entered (`"construction"`, `"dispatch"`, or
+    /// `"response_handling"`) and a snapshot of the context at that point.
+    fn observe(&self, phase: &'static str, snapshot: ContextSnapshot);
+}
+
+impl Storable for Box<dyn PhaseObserver> {
+    type Storer = StoreReplace<Self>;
+}
+
 pub trait RequestSerializer: Send + Sync + fmt::Debug {
+    /// Serializes the `input` into a request.
+    ///
+    /// This is called once per operation, before the retry loop, and every attempt is sent with a
+    /// copy of the resulting request. Values that must stay the same across retries, such as
+    /// idempotency tokens for members modeled with `@idempotencyToken`, should be generated here
+    /// so that retries can't create duplicate resources.
     fn serialize_input(&self, input: Input) -> Result<HttpRequest, BoxError>;
 }
 
@@ -40,29 +93,141 @@ pub trait ResponseDeserializer: Send + Sync + fmt::Debug {
         None
     }
 
+    /// Deserializes a response whose body has been read into memory.
+    ///
+    /// Deserializers that support streaming must also handle buffered success responses here,
+    /// since small responses are buffered when a [`BufferedResponseThreshold`] is set.
     fn deserialize_nonstreaming(&self, response: &HttpResponse) -> OutputOrError;
 }
 
+/// Deserializers for specific response statuses, used in place of the
+/// [`response_deserializer`](ConfigBagAccessors::response_deserializer).
+///
+/// This is useful for services whose error responses have an entirely different schema from
+/// their success responses. Responses with any other status are handled by the default
+/// deserializer.
+#[derive(Clone, Debug, Default)]
+pub struct StatusDeserializers {
+    deserializers: HashMap<u16, Arc<dyn ResponseDeserializer>>,
+}
+
+impl StatusDeserializers {
+    /// Create a new [`StatusDeserializers`] without any deserializers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deserializes responses with the given `status` with `deserializer`.
+    pub fn with_deserializer(
+        mut self,
+        status: u16,
+        deserializer: impl ResponseDeserializer + 'static,
+    ) -> Self {
+        self.deserializers.insert(status, Arc::new(deserializer));
+        self
+    }
+
+    /// Returns the deserializer for responses with the given `status`, if there is one.
+    pub fn deserializer(&self, status: u16) -> Option<&dyn ResponseDeserializer> {
+        self.deserializers
+            .get(&status)
+            .map(|deserializer| deserializer.as_ref())
+    }
+}
+
+impl Storable for StatusDeserializers {
+    type Storer = StoreReplace<Self>;
+}
+
 pub trait Connection: Send + Sync + fmt::Debug {
+    /// Sends the request and returns the response.
+    ///
+    /// When the request has an `Expect: 100-continue` header, the connection must send the
+    /// headers first and wait for the server's interim `100 Continue` response before sending
+    /// the body. If the server replies with a final response instead, such as
+    /// `417 Expectation Failed`, the body must not be sent, and that response is returned.
+    ///
+    /// Connections that resolve host names themselves should report how long resolution took
+    /// through the [`DnsTiming`] in the request's extensions.
     fn call(&self, request: HttpRequest) -> BoxFuture<HttpResponse>;
+
+    /// Sends the request over a newly established connection, bypassing any connection pool.
+    ///
+    /// This is used for retry attempts that follow a connection-level failure when
+    /// [`ConnectionConfig::fresh_connection_after_dispatch_failure`] is enabled, so that a
+    /// stale pooled connection isn't reused. Connections that don't pool can rely on the
+    /// default implementation, which calls [`Connection::call`].
+    fn call_on_fresh_connection(&self, request: HttpRequest) -> BoxFuture<HttpResponse> {
+        self.call(request)
+    }
+
+    /// Establishes a connection to the host of `uri` ahead of time, so that the first request to
+    /// it can reuse a ready connection.
+    ///
+    /// This is only called when [`ConnectionConfig::prewarm`] is enabled. Failures
+    /// are ignored, since they will surface on the actual call. Connections that don't pool can
+    /// rely on the default implementation, which does nothing.
+    fn prewarm(&self, uri: &http::Uri) -> BoxFuture<()> {
+        let _uri = uri;
+        Box::pin(async { Ok(()) })
+    }
 }
 
 impl Connection for Box<dyn Connection> {
     fn call(&self, request: HttpRequest) -> BoxFuture<HttpResponse> {
         (**self).call(request)
     }
+
+    fn call_on_fresh_connection(&self, request: HttpRequest) -> BoxFuture<HttpResponse> {
+        (**self).call_on_fresh_connection(request)
+    }
+
+    fn prewarm(&self, uri: &http::Uri) -> BoxFuture<()> {
+        (**self).prewarm(uri)
+    }
+}
+
+/// The parameters that endpoints are resolved with.
+///
+/// The parameters are included in endpoint resolution errors to help diagnose misconfiguration,
+/// unless they were created with [`EndpointResolverParams::sensitive`], in which case only their
+/// type is.
+pub struct EndpointResolverParams {
+    params: TypeErasedBox,
+    sensitive: bool,
 }
 
-#[derive(Debug)]
-pub struct EndpointResolverParams(TypeErasedBox);
+impl fmt::Debug for EndpointResolverParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_tuple("EndpointResolverParams");
+        if self.sensitive {
+            debug.field(&format_args!("{}: ** redacted **", self.params.type_name()));
+        } else {
+            debug.field(&self.params);
+        }
+        debug.finish()
+    }
+}
 
 impl EndpointResolverParams {
     pub fn new<T: fmt::Debug + Send + Sync + 'static>(params: T) -> Self {
-        Self(TypedBox::new(params).erase())
+        Self {
+            params: TypedBox::new(params).erase(),
+            sensitive: false,
+        }
+    }
+
+    /// Creates parameters whose values must never be printed, for example because they include
+    /// credentials.
+    pub fn sensitive<T: fmt::Debug + Send + Sync + 'static>(params: T) -> Self {
+        Self {
+            params: TypedBox::new(params).erase(),
+            sensitive: true,
+        }
     }
 
     pub fn get<T: fmt::Debug + Send + Sync + 'static>(&self) -> Option<&T> {
-        self.0.downcast_ref()
+        self.params.downcast_ref()
     }
 }
 
@@ -98,6 +263,187 @@ impl RequestTime {
     }
 }
 
+/// Whether the query string is included in the `http.path` recorded on the span of every attempt.
+///
+/// Disabled by default, since query strings may contain sensitive values, in which case the query
+/// is redacted.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LogQueryString(bool);
+
+impl LogQueryString {
+    /// Create a new [`LogQueryString`].
+    pub fn new(enabled: bool) -> Self {
+        Self(enabled)
+    }
+
+    /// Returns `true` if the query string is recorded.
+    pub fn enabled(&self) -> bool {
+        self.0
+    }
+}
+
+impl Storable for LogQueryString {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Whether the operation has an event stream input or output. Disabled by default.
+///
+/// Events are exchanged over the connection for as long as the streams are open, so the operation
+/// completes as soon as the initial response is received. The output of a successful response is
+/// always produced by [`deserialize_streaming`](ResponseDeserializer::deserialize_streaming), which
+/// is expected to hand the response body to the output event stream, and the request body is
+/// expected to be fed by the input event stream. Event stream operations are never retried, since
+/// the events that were sent can't be replayed.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct EventStreamOperation(bool);
+
+impl EventStreamOperation {
+    /// Create a new [`EventStreamOperation`].
+    pub fn new(enabled: bool) -> Self {
+        Self(enabled)
+    }
+
+    /// Returns `true` if the operation has an event stream input or output.
+    pub fn enabled(&self) -> bool {
+        self.0
+    }
+}
+
+impl Storable for EventStreamOperation {
+    type Storer = StoreReplace<Self>;
+}
+
+/// The type that an operation's response deserializer is expected to produce as its output.
+///
+/// When set in the [`ConfigBag`], the orchestrator checks every deserialized output against it,
+/// so that a misconfigured [`ResponseDeserializer`] fails with an error naming both types, rather
+/// than with an opaque downcast failure once the output reaches the caller.
+#[derive(Clone, Debug)]
+pub struct ExpectedOutputType {
+    type_name: &'static str,
+    matches: fn(&TypeErasedBox) -> bool,
+}
+
+impl ExpectedOutputType {
+    /// Create a new [`ExpectedOutputType`] for the output type `T`.
+    pub fn of<T: fmt::Debug + Send + Sync + 'static>() -> Self {
+        Self {
+            type_name: std::any::type_name::<T>(),
+            matches: |output| output.downcast_ref::<T>().is_some(),
+        }
+    }
+
+    /// Returns the name of the expected type.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns `true` if `output` is of the expected type.
+    pub fn matches(&self, output: &TypeErasedBox) -> bool {
+        (self.matches)(output)
+    }
+}
+
+impl Storable for ExpectedOutputType {
+    type Storer = StoreReplace<Self>;
+}
+
+/// The shape ID of the operation being invoked, such as `com.example#GetObject`.
+///
+/// This is put in the [`ConfigBag`] before the operation runtime plugins are applied, so that
+/// they can look up per-operation configuration by it.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct OperationId(&'static str);
+
+impl OperationId {
+    /// Create a new [`OperationId`] from the operation's absolute shape ID.
+    pub fn new(shape_id: &'static str) -> Self {
+        Self(shape_id)
+    }
+
+    /// Returns the absolute shape ID, including the namespace.
+    pub fn as_str(&self) -> &'static str {
+        self.0
+    }
+
+    /// Returns the name of the operation, without the namespace.
+    pub fn name(&self) -> &'static str {
+        self.0.rsplit_once('#').map_or(self.0, |(_, name)| name)
+    }
+}
+
+impl Storable for OperationId {
+    type Storer = StoreReplace<Self>;
+}
+
+impl fmt::Display for OperationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+/// Interceptors that are skipped by every hook, for example to find out whether one of them is
+/// causing a problem without recompiling.
+///
+/// Interceptors are matched by their [`name`](crate::client::interceptors::Interceptor::name),
+/// which is their type name unless they override it. Cloning is cheap, since the names are
+/// shared between clones.
+#[derive(Clone, Debug, Default)]
+pub struct DisabledInterceptors {
+    names: Arc<HashSet<Cow<'static, str>>>,
+}
+
+impl DisabledInterceptors {
+    /// Create a new [`DisabledInterceptors`] without any interceptors.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the interceptor with the given name.
+    pub fn with_interceptor(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        Arc::make_mut(&mut self.names).insert(name.into());
+        self
+    }
+
+    /// Returns `true` if the interceptor with the given name is disabled.
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+}
+
+impl Storable for DisabledInterceptors {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Opts in to keeping a copy of the operation's input after it's serialized.
+///
+/// The input is consumed by serialization, so interceptors that run afterwards can't see it. When
+/// this is set in the [`ConfigBag`], the input is cloned before it's serialized, and the clone is
+/// available from [`InterceptorContext::original_input`](crate::client::interceptors::InterceptorContext::original_input)
+/// for the rest of the operation.
+#[derive(Clone, Debug)]
+pub struct RetainInput {
+    clone: fn(&TypeErasedBox) -> Option<TypeErasedBox>,
+}
+
+impl RetainInput {
+    /// Create a new [`RetainInput`] for inputs of type `T`.
+    pub fn of<T: Clone + fmt::Debug + Send + Sync + 'static>() -> Self {
+        Self {
+            clone: |input| input.downcast_ref::<T>().cloned().map(TypeErasedBox::new),
+        }
+    }
+
+    /// Returns a clone of `input`, or `None` if it isn't of the type this was created for.
+    pub fn clone_input(&self, input: &Input) -> Option<Input> {
+        (self.clone)(input)
+    }
+}
+
+impl Storable for RetainInput {
+    type Storer = StoreReplace<Self>;
+}
+
 pub trait ConfigBagAccessors {
     fn auth_option_resolver_params(&self) -> &AuthOptionResolverParams;
     fn set_auth_option_resolver_params(
@@ -135,6 +481,11 @@ pub trait ConfigBagAccessors {
     fn retry_classifiers(&self) -> &RetryClassifiers;
     fn set_retry_classifiers(&mut self, retry_classifier: RetryClassifiers);
 
+    /// Returns the retry strategy.
+    ///
+    /// The orchestrator looks the strategy up again for every retry decision, so an interceptor
+    /// that replaces it in `modify_before_attempt_completion` changes how the remaining attempts
+    /// are retried, e.g. to switch to an adaptive strategy after a throttling response.
     fn retry_strategy(&self) -> &dyn RetryStrategy;
     fn set_retry_strategy(&mut self, retry_strategy: impl RetryStrategy + 'static);
 
@@ -194,12 +545,12 @@ impl ConfigBagAccessors for ConfigBag {
     }
 
     fn identity_resolvers(&self) -> &IdentityResolvers {
-        self.get::<IdentityResolvers>()
+        self.load::<IdentityResolvers>()
             .expect("identity resolvers must be configured")
     }
 
     fn set_identity_resolvers(&mut self, identity_resolvers: IdentityResolvers) {
-        self.put::<IdentityResolvers>(identity_resolvers);
+        self.store_put(identity_resolvers);
     }
 
     fn connection(&self) -> &dyn Connection {
@@ -0,0 +1,328 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Configuration of how request and response bodies are compressed, checksummed, sized, and
+//! counted.
+
+use crate::config_bag::{Storable, StoreReplace};
+use aws_smithy_http::body::SdkBody;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// The trailing headers of a response, sent after its body.
+///
+/// When the orchestrator reads a response body into memory, it stores the body's trailers (if it
+/// has any) in the response's extensions, where deserializers can read them. Interceptors can read
+/// them with [`InterceptorContext::response_trailers`](crate::client::interceptors::InterceptorContext::response_trailers).
+#[derive(Clone, Debug)]
+pub struct ResponseTrailers(http::HeaderMap);
+
+impl ResponseTrailers {
+    /// Create a new [`ResponseTrailers`].
+    pub fn new(trailers: http::HeaderMap) -> Self {
+        Self(trailers)
+    }
+
+    /// Returns the trailers.
+    pub fn headers(&self) -> &http::HeaderMap {
+        &self.0
+    }
+}
+
+/// Counts the bytes of the request bodies sent and the response bodies read by an operation,
+/// summed across all of its attempts.
+///
+/// The orchestrator adds a `ByteCounts` to the [`ConfigBag`](crate::config_bag::ConfigBag) before
+/// the first attempt, unless one was already set. Since clones share their counts, the counts of an
+/// operation can be read once it completes by setting a `ByteCounts` in a runtime plugin and
+/// keeping a clone of it. Streaming bodies are counted as they're polled, so bytes that were never
+/// read aren't counted.
+#[derive(Clone, Debug, Default)]
+pub struct ByteCounts {
+    sent: Arc<AtomicU64>,
+    received: Arc<AtomicU64>,
+}
+
+impl ByteCounts {
+    /// Create a new [`ByteCounts`] with nothing counted.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `bytes` to the number of request body bytes sent.
+    pub fn record_sent(&self, bytes: u64) {
+        self.sent.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Adds `bytes` to the number of response body bytes received.
+    pub fn record_received(&self, bytes: u64) {
+        self.received.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Returns the number of request body bytes sent.
+    pub fn bytes_sent(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of response body bytes received.
+    pub fn bytes_received(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
+}
+
+impl Storable for ByteCounts {
+    type Storer = StoreReplace<Self>;
+}
+
+/// The `Content-Length`, in bytes, below which responses are read into memory and passed to
+/// [`ResponseDeserializer::deserialize_nonstreaming`](crate::client::orchestrator::ResponseDeserializer::deserialize_nonstreaming),
+/// even if the deserializer could stream them.
+///
+/// Unset by default, in which case streaming is always preferred.
+#[derive(Copy, Clone, Debug)]
+pub struct BufferedResponseThreshold(u64);
+
+impl BufferedResponseThreshold {
+    /// Create a new [`BufferedResponseThreshold`] of `threshold` bytes.
+    pub fn new(threshold: u64) -> Self {
+        Self(threshold)
+    }
+
+    /// Returns the threshold, in bytes.
+    pub fn threshold(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Storable for BufferedResponseThreshold {
+    type Storer = StoreReplace<Self>;
+}
+
+/// The size, in bytes, above which a serialized request body fails the operation before any
+/// request is sent.
+///
+/// Unset by default, in which case any size is allowed. Only bodies of a known size are checked.
+/// Streaming bodies of unknown size are sent as-is.
+#[derive(Copy, Clone, Debug)]
+pub struct MaxSerializedRequestSize(u64);
+
+impl MaxSerializedRequestSize {
+    /// Create a new [`MaxSerializedRequestSize`] of `max_size` bytes.
+    pub fn new(max_size: u64) -> Self {
+        Self(max_size)
+    }
+
+    /// Returns the maximum size, in bytes.
+    pub fn max_size(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Storable for MaxSerializedRequestSize {
+    type Storer = StoreReplace<Self>;
+}
+
+/// The default [`ResponseDecompression::max_decompressed_size`] of 64 MiB.
+const DEFAULT_MAX_DECOMPRESSED_RESPONSE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Whether response bodies that are read into memory are validated against the checksum in their
+/// `x-amz-checksum-*` or `Content-MD5` header, if they have one.
+///
+/// Reading a body that doesn't match its checksum fails with a checksum mismatch error. Streaming
+/// responses aren't validated. Disabled by default.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ValidateResponseChecksums(bool);
+
+impl ValidateResponseChecksums {
+    /// Create a new [`ValidateResponseChecksums`].
+    pub fn new(enabled: bool) -> Self {
+        Self(enabled)
+    }
+
+    /// Returns `true` if response bodies are validated against their checksums.
+    pub fn enabled(&self) -> bool {
+        self.0
+    }
+}
+
+impl Storable for ValidateResponseChecksums {
+    type Storer = StoreReplace<Self>;
+}
+
+/// A `Content-Encoding` that responses can be decompressed from.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ContentEncoding {
+    /// The `gzip` encoding.
+    Gzip,
+    /// The `deflate` encoding, which is zlib-wrapped DEFLATE.
+    Deflate,
+}
+
+impl ContentEncoding {
+    /// Returns the name of the encoding, as used in the `Content-Encoding` header.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentEncoding::Gzip => "gzip",
+            ContentEncoding::Deflate => "deflate",
+        }
+    }
+}
+
+/// Generates the body of a request afresh for every attempt.
+///
+/// When a [`ReplayableBody`] is set in the [`ConfigBag`](crate::config_bag::ConfigBag), it replaces
+/// the body produced by the [`RequestSerializer`](crate::client::orchestrator::RequestSerializer).
+/// Instead of keeping a copy of the body around for retries, the factory is called once at the
+/// start of every attempt, including the first. This suits bodies that are expensive to hold in
+/// memory but cheap to regenerate, such as a stream read from a file.
+#[derive(Clone)]
+pub struct ReplayableBody {
+    factory: Arc<dyn Fn() -> SdkBody + Send + Sync>,
+}
+
+impl ReplayableBody {
+    /// Create a new [`ReplayableBody`] that generates bodies with the given `factory`.
+    pub fn new(factory: impl Fn() -> SdkBody + Send + Sync + 'static) -> Self {
+        Self {
+            factory: Arc::new(factory),
+        }
+    }
+
+    /// Generates a new body.
+    pub fn make_body(&self) -> SdkBody {
+        (self.factory)()
+    }
+}
+
+impl Storable for ReplayableBody {
+    type Storer = StoreReplace<Self>;
+}
+
+impl fmt::Debug for ReplayableBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReplayableBody").finish_non_exhaustive()
+    }
+}
+
+/// How request bodies are compressed before they're signed and sent.
+///
+/// Only bodies that are held in memory and are at least [`min_size`](RequestCompression::min_size)
+/// bytes long are compressed. Requests that already have a `Content-Encoding` are left alone.
+#[non_exhaustive]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RequestCompression {
+    algorithm: ContentEncoding,
+    min_size: u64,
+}
+
+impl RequestCompression {
+    /// Create a new [`RequestCompression`] that compresses bodies of at least `min_size` bytes
+    /// with `algorithm`.
+    pub fn new(algorithm: ContentEncoding, min_size: u64) -> Self {
+        Self {
+            algorithm,
+            min_size,
+        }
+    }
+
+    /// Returns the encoding that request bodies are compressed with.
+    pub fn algorithm(&self) -> ContentEncoding {
+        self.algorithm
+    }
+
+    /// Returns the size, in bytes, below which request bodies aren't compressed.
+    pub fn min_size(&self) -> u64 {
+        self.min_size
+    }
+}
+
+impl Storable for RequestCompression {
+    type Storer = StoreReplace<Self>;
+}
+
+/// The algorithm that request checksums are computed with.
+///
+/// When one is set in the [`ConfigBag`](crate::config_bag::ConfigBag), the checksum of the request
+/// body is sent in the `x-amz-checksum-*` header for the algorithm. It's computed after the body is
+/// compressed and before the request is signed. Bodies that aren't held in memory have their
+/// checksum computed as they're sent instead, and send it in a trailer of the same name, which is
+/// announced in the `x-amz-trailer` header. Since the trailer is sent as part of an `aws-chunked`
+/// body, these bodies must have a known length. Requests that already have a checksum header are
+/// left alone.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RequestChecksumAlgorithm {
+    /// CRC32
+    Crc32,
+    /// CRC32C
+    Crc32c,
+    /// SHA-1
+    Sha1,
+    /// SHA-256
+    Sha256,
+}
+
+impl RequestChecksumAlgorithm {
+    /// Returns the name of the algorithm, as used in the `x-amz-checksum-*` header.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RequestChecksumAlgorithm::Crc32 => "crc32",
+            RequestChecksumAlgorithm::Crc32c => "crc32c",
+            RequestChecksumAlgorithm::Sha1 => "sha1",
+            RequestChecksumAlgorithm::Sha256 => "sha256",
+        }
+    }
+}
+
+impl Storable for RequestChecksumAlgorithm {
+    type Storer = StoreReplace<Self>;
+}
+
+/// How buffered responses are decompressed before they're passed to
+/// [`ResponseDeserializer::deserialize_nonstreaming`](crate::client::orchestrator::ResponseDeserializer::deserialize_nonstreaming).
+///
+/// When no `ResponseDecompression` is set in the [`ConfigBag`](crate::config_bag::ConfigBag), or it
+/// has no encodings, responses are passed as they were received. Otherwise, responses with any
+/// other `Content-Encoding` fail to deserialize, rather than being passed to the deserializer still
+/// encoded.
+#[derive(Clone, Debug)]
+pub struct ResponseDecompression {
+    encodings: Vec<ContentEncoding>,
+    max_decompressed_size: u64,
+}
+
+impl ResponseDecompression {
+    /// Create a new [`ResponseDecompression`] from the `encodings` that responses may be
+    /// decompressed from.
+    pub fn new(encodings: Vec<ContentEncoding>) -> Self {
+        Self {
+            encodings,
+            max_decompressed_size: DEFAULT_MAX_DECOMPRESSED_RESPONSE_SIZE,
+        }
+    }
+
+    /// Sets the size, in bytes, above which a decompressed response fails to deserialize, so that
+    /// a small compressed response can't exhaust memory. Defaults to 64 MiB.
+    pub fn with_max_decompressed_size(mut self, max_size: u64) -> Self {
+        self.max_decompressed_size = max_size;
+        self
+    }
+
+    /// Returns the encodings that responses may be decompressed from.
+    pub fn encodings(&self) -> &[ContentEncoding] {
+        &self.encodings
+    }
+
+    /// Returns the size, in bytes, above which a decompressed response fails to deserialize.
+    pub fn max_decompressed_size(&self) -> u64 {
+        self.max_decompressed_size
+    }
+}
+
+impl Storable for ResponseDecompression {
+    type Storer = StoreReplace<Self>;
+}
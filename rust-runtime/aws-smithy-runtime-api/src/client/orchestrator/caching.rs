@@ -0,0 +1,58 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Caching of the outputs of successful operations.
+
+use crate::client::interceptors::context::{Input, Output};
+use crate::config_bag::{Storable, StoreReplace};
+use std::fmt;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// A cache of the outputs of successful operations.
+///
+/// Implementations are responsible for expiring entries; `now` comes from the configured
+/// [`TimeSource`](crate::client::orchestrator::TimeSource).
+pub trait ResponseCache: Send + Sync + fmt::Debug {
+    /// Returns the cached output for `key`, or `None` if there is none or it has expired.
+    fn get(&self, key: &str, now: SystemTime) -> Option<Output>;
+
+    /// Caches the `output` of a successful operation for `key`.
+    fn put(&self, key: String, output: &Output, now: SystemTime);
+}
+
+/// Opts an operation in to response caching.
+///
+/// When set in the [`ConfigBag`](crate::config_bag::ConfigBag), the orchestrator computes a cache
+/// key from the input before serializing it. On a cache hit, the cached output is returned without
+/// sending a request, or running any of the interceptor hooks that follow serialization. On a miss,
+/// a successful output is cached once the operation completes.
+#[derive(Clone, Debug)]
+pub struct ResponseCaching {
+    cache: Arc<dyn ResponseCache>,
+    cache_key: fn(&Input) -> Option<String>,
+}
+
+impl ResponseCaching {
+    /// Create a new [`ResponseCaching`] that stores outputs in `cache`. `cache_key` returns the key
+    /// for an input, or `None` if the operation shouldn't be cached for that input.
+    pub fn new(cache: Arc<dyn ResponseCache>, cache_key: fn(&Input) -> Option<String>) -> Self {
+        Self { cache, cache_key }
+    }
+
+    /// Returns the cache.
+    pub fn cache(&self) -> &dyn ResponseCache {
+        self.cache.as_ref()
+    }
+
+    /// Returns the cache key for `input`, if it should be cached.
+    pub fn cache_key(&self, input: &Input) -> Option<String> {
+        (self.cache_key)(input)
+    }
+}
+
+impl Storable for ResponseCaching {
+    type Storer = StoreReplace<Self>;
+}
@@ -0,0 +1,216 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Configuration of the connections that requests are sent over.
+
+use crate::client::orchestrator::Connection;
+use crate::config_bag::{Storable, StoreReplace};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How the orchestrator uses the [`Connection`]. Every option is disabled by default.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ConnectionConfig {
+    fresh_connection_after_dispatch_failure: bool,
+    prewarm: bool,
+}
+
+impl ConnectionConfig {
+    /// Create a new [`ConnectionConfig`] with every option disabled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether retry attempts that follow a connection-level failure are sent with
+    /// [`Connection::call_on_fresh_connection`].
+    pub fn with_fresh_connection_after_dispatch_failure(mut self, enabled: bool) -> Self {
+        self.fresh_connection_after_dispatch_failure = enabled;
+        self
+    }
+
+    /// Returns `true` if retry attempts that follow a connection-level failure are sent with
+    /// [`Connection::call_on_fresh_connection`].
+    pub fn fresh_connection_after_dispatch_failure(&self) -> bool {
+        self.fresh_connection_after_dispatch_failure
+    }
+
+    /// Sets whether the [`Connection`] is asked to establish a connection to the resolved
+    /// endpoint before the first attempt, with [`Connection::prewarm`].
+    pub fn with_prewarm(mut self, enabled: bool) -> Self {
+        self.prewarm = enabled;
+        self
+    }
+
+    /// Returns `true` if the [`Connection`] is asked to establish a connection to the resolved
+    /// endpoint before the first attempt, with [`Connection::prewarm`].
+    pub fn prewarm(&self) -> bool {
+        self.prewarm
+    }
+}
+
+impl Storable for ConnectionConfig {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Selects the connection that each attempt is sent over.
+///
+/// This is intended for failover to a different transport, such as sending retries over a fallback
+/// connection when the first attempt failed. When no selector is set in the
+/// [`ConfigBag`](crate::config_bag::ConfigBag), every attempt uses the [`Connection`] set with
+/// [`ConfigBagAccessors::set_connection`](crate::client::orchestrator::ConfigBagAccessors::set_connection).
+pub trait ConnectionSelector: Send + Sync + fmt::Debug {
+    /// Returns the connection for attempt number `attempt` (the first retry is attempt `2`), or
+    /// `None` to use the configured connection.
+    fn select(&self, attempt: u32) -> Option<&dyn Connection>;
+}
+
+impl Storable for Box<dyn ConnectionSelector> {
+    type Storer = StoreReplace<Self>;
+}
+
+/// A pool of byte buffers that response bodies are read into.
+///
+/// Reusing buffers across requests reduces pressure on the allocator for high-throughput clients.
+/// When no pool is set in the [`ConfigBag`](crate::config_bag::ConfigBag), a new buffer is
+/// allocated for every response.
+pub trait BufferPool: Send + Sync + fmt::Debug {
+    /// Returns a buffer to read a response body into. The buffer is cleared before use.
+    fn acquire(&self) -> Vec<u8>;
+
+    /// Returns a buffer to the pool once the orchestrator is done with the response that was read
+    /// into it, or once reading the response fails.
+    ///
+    /// Buffers are released whether or not the response was deserialized successfully, including
+    /// for attempts that are retried. Buffers that are still referred to, e.g. by a clone of the
+    /// response body, aren't released.
+    fn release(&self, buffer: Vec<u8>);
+}
+
+impl Storable for Arc<dyn BufferPool> {
+    type Storer = StoreReplace<Self>;
+}
+
+/// A hint about how many requests are expected to be in flight at the same time.
+///
+/// When set in the [`ConfigBag`](crate::config_bag::ConfigBag), the hint is attached to the
+/// extensions of every request handed to the [`Connection`], so that HTTP/2-aware connections can
+/// open enough streams ahead of time. Connections are free to ignore it.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ConcurrencyHint {
+    expected_concurrent_requests: u32,
+}
+
+impl ConcurrencyHint {
+    /// Create a new [`ConcurrencyHint`].
+    pub fn new(expected_concurrent_requests: u32) -> Self {
+        Self {
+            expected_concurrent_requests,
+        }
+    }
+
+    /// Returns the number of requests that are expected to be in flight at the same time.
+    pub fn expected_concurrent_requests(&self) -> u32 {
+        self.expected_concurrent_requests
+    }
+}
+
+impl Storable for ConcurrencyHint {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Keepalive settings for the connections that requests are sent on.
+///
+/// When set in the [`ConfigBag`](crate::config_bag::ConfigBag), the settings are attached to the
+/// extensions of every request handed to the [`Connection`], so that keepalive-aware connections
+/// can apply them, e.g. to keep long-lived streaming downloads from being dropped by idle timeouts
+/// along the way. Connections are free to ignore them.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct KeepAliveConfig {
+    tcp_keepalive: Option<Duration>,
+    http2_ping_interval: Option<Duration>,
+    http2_ping_timeout: Option<Duration>,
+}
+
+impl KeepAliveConfig {
+    /// Create a new [`KeepAliveConfig`] with every setting left to the connection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the idle time after which TCP keepalive probes are sent.
+    pub fn with_tcp_keepalive(mut self, tcp_keepalive: Duration) -> Self {
+        self.tcp_keepalive = Some(tcp_keepalive);
+        self
+    }
+
+    /// Sets the interval at which HTTP/2 `PING` frames are sent.
+    pub fn with_http2_ping_interval(mut self, http2_ping_interval: Duration) -> Self {
+        self.http2_ping_interval = Some(http2_ping_interval);
+        self
+    }
+
+    /// Sets how long to wait for the acknowledgement of an HTTP/2 `PING` before the connection is
+    /// considered dead.
+    pub fn with_http2_ping_timeout(mut self, http2_ping_timeout: Duration) -> Self {
+        self.http2_ping_timeout = Some(http2_ping_timeout);
+        self
+    }
+
+    /// Returns the idle time after which TCP keepalive probes are sent, if it was set.
+    pub fn tcp_keepalive(&self) -> Option<Duration> {
+        self.tcp_keepalive
+    }
+
+    /// Returns the interval at which HTTP/2 `PING` frames are sent, if it was set.
+    pub fn http2_ping_interval(&self) -> Option<Duration> {
+        self.http2_ping_interval
+    }
+
+    /// Returns how long to wait for the acknowledgement of an HTTP/2 `PING`, if it was set.
+    pub fn http2_ping_timeout(&self) -> Option<Duration> {
+        self.http2_ping_timeout
+    }
+}
+
+impl Storable for KeepAliveConfig {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Records how long a [`Connection`] spent resolving the host name of a request.
+///
+/// The orchestrator attaches a new `DnsTiming` to the extensions of every request it hands to the
+/// [`Connection`], and keeps a handle to it in the [`ConfigBag`](crate::config_bag::ConfigBag).
+/// Connections that support it should call [`DnsTiming::record`] once resolution completes, even if
+/// the request then fails. The duration is then available from the `DnsTiming` in the
+/// [`ConfigBag`](crate::config_bag::ConfigBag). For connections that don't support it, the duration
+/// is `None`.
+#[derive(Clone, Debug, Default)]
+pub struct DnsTiming {
+    duration: Arc<Mutex<Option<Duration>>>,
+}
+
+impl DnsTiming {
+    /// Create a new [`DnsTiming`] with no duration recorded.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records how long host name resolution took.
+    pub fn record(&self, duration: Duration) {
+        *self.duration.lock().unwrap() = Some(duration);
+    }
+
+    /// Returns the recorded duration, if any.
+    pub fn duration(&self) -> Option<Duration> {
+        *self.duration.lock().unwrap()
+    }
+}
+
+impl Storable for DnsTiming {
+    type Storer = StoreReplace<Self>;
+}
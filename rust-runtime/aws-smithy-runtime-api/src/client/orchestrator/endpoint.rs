@@ -0,0 +1,91 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Endpoint resolution beyond the [`EndpointResolver`](super::EndpointResolver): asynchronous
+//! resolvers, failover, and preresolved endpoints.
+
+use crate::client::orchestrator::{EndpointResolverParams, Future};
+use crate::config_bag::{Storable, StoreReplace};
+use aws_smithy_types::endpoint::Endpoint;
+use std::fmt;
+
+/// Resolves endpoints asynchronously, for example by looking them up in a service registry.
+///
+/// When an [`AsyncEndpointResolver`] is set in the [`ConfigBag`](crate::config_bag::ConfigBag), it
+/// is used instead of the [`EndpointResolver`](crate::client::orchestrator::EndpointResolver).
+/// Endpoints are resolved within each attempt, so resolution counts towards the attempt timeout.
+/// The returned future can't borrow the params, so any values it needs should be copied out of them
+/// before it is created.
+pub trait AsyncEndpointResolver: Send + Sync + fmt::Debug {
+    fn resolve_endpoint(&self, params: &EndpointResolverParams) -> Future<Endpoint>;
+}
+
+impl Storable for Box<dyn AsyncEndpointResolver> {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Hosts to fail over to when the resolved endpoint can't be connected to.
+///
+/// After an attempt fails to connect, the next attempt is sent to the next host in the list, with
+/// the scheme and authority of the resolved endpoint replaced by the host's. Once every host has
+/// been tried, attempts start over with the resolved endpoint. The endpoint prefix of the
+/// operation, if it has one, isn't applied to the failover hosts.
+///
+/// Failover happens between attempts rather than within one, so that the request is signed for
+/// the host it's sent to, and every host is tried within its own attempt timeout. How many hosts
+/// are tried is up to the retry strategy, which must retry connection failures.
+#[derive(Clone, Debug)]
+pub struct EndpointFailover {
+    hosts: Vec<http::Uri>,
+}
+
+impl EndpointFailover {
+    /// Creates a new [`EndpointFailover`] that fails over to `hosts`, in order.
+    pub fn new(hosts: Vec<http::Uri>) -> Self {
+        Self { hosts }
+    }
+
+    /// Returns the hosts to fail over to, in order.
+    pub fn hosts(&self) -> &[http::Uri] {
+        &self.hosts
+    }
+
+    /// Returns the host to send the request to after `connection_failures` attempts have failed
+    /// to connect, or `None` if it should be sent to the resolved endpoint.
+    pub fn host(&self, connection_failures: usize) -> Option<&http::Uri> {
+        match connection_failures % (self.hosts.len() + 1) {
+            0 => None,
+            index => Some(&self.hosts[index - 1]),
+        }
+    }
+}
+
+impl Storable for EndpointFailover {
+    type Storer = StoreReplace<Self>;
+}
+
+/// An endpoint that requests are sent to without running the endpoint resolvers.
+///
+/// This is for tests, and for clients that resolve their endpoints themselves. The endpoint is
+/// applied like a resolved one: its URL, the operation's endpoint prefix and its headers. Its
+/// properties, such as the auth schemes it supports, are available to signers from here.
+#[derive(Clone, Debug)]
+pub struct PreresolvedEndpoint(Endpoint);
+
+impl PreresolvedEndpoint {
+    /// Create a new [`PreresolvedEndpoint`].
+    pub fn new(endpoint: Endpoint) -> Self {
+        Self(endpoint)
+    }
+
+    /// Returns the endpoint.
+    pub fn endpoint(&self) -> &Endpoint {
+        &self.0
+    }
+}
+
+impl Storable for PreresolvedEndpoint {
+    type Storer = StoreReplace<Self>;
+}
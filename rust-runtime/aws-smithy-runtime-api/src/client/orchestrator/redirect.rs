@@ -0,0 +1,76 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Configuration of how redirects are followed.
+
+use crate::config_bag::{Storable, StoreReplace};
+
+/// Settings for following redirects.
+///
+/// By default, `3xx` responses are returned to the response deserializer like any other response.
+/// When a [`RedirectPolicy`] is set in the [`ConfigBag`](crate::config_bag::ConfigBag), the
+/// orchestrator follows redirects that have a `Location` header instead, up to
+/// [`max_redirects`](Self::max_redirects) of them per attempt. The request is signed again for its
+/// new location.
+///
+/// `303 See Other` responses, and `301` and `302` responses to `POST` requests, are followed
+/// with a `GET` request without a body. Other redirects are followed with the same method and
+/// body. Redirects from `https` to `http` are refused unless
+/// [allowed](Self::allow_https_downgrade), and a redirect back to a location that was already
+/// visited is treated as a redirect loop.
+///
+/// Redirects to another host are refused unless [allowed](Self::allow_cross_host), since the
+/// request would carry its credentials there. When they're allowed, the `Authorization`,
+/// `Proxy-Authorization`, and `Cookie` headers of the original request aren't sent to the other
+/// host, and auth is resolved again to sign the request for it.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RedirectPolicy {
+    max_redirects: u32,
+    allow_https_downgrade: bool,
+    allow_cross_host: bool,
+}
+
+impl RedirectPolicy {
+    /// Creates a [`RedirectPolicy`] that follows up to `max_redirects` redirects.
+    pub fn new(max_redirects: u32) -> Self {
+        Self {
+            max_redirects,
+            allow_https_downgrade: false,
+            allow_cross_host: false,
+        }
+    }
+
+    /// Sets whether redirects from `https` to `http` locations are followed.
+    pub fn with_https_downgrade(mut self, allow_https_downgrade: bool) -> Self {
+        self.allow_https_downgrade = allow_https_downgrade;
+        self
+    }
+
+    /// Sets whether redirects to a different host are followed.
+    pub fn with_cross_host(mut self, allow_cross_host: bool) -> Self {
+        self.allow_cross_host = allow_cross_host;
+        self
+    }
+
+    /// Returns the maximum number of redirects that are followed for one attempt.
+    pub fn max_redirects(&self) -> u32 {
+        self.max_redirects
+    }
+
+    /// Returns true if redirects from `https` to `http` locations are followed.
+    pub fn allow_https_downgrade(&self) -> bool {
+        self.allow_https_downgrade
+    }
+
+    /// Returns true if redirects to a different host are followed.
+    pub fn allow_cross_host(&self) -> bool {
+        self.allow_cross_host
+    }
+}
+
+impl Storable for RedirectPolicy {
+    type Storer = StoreReplace<Self>;
+}
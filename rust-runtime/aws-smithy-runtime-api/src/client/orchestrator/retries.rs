@@ -0,0 +1,278 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Configuration of the attempts of an operation, and of when they're retried.
+
+use crate::client::orchestrator::OperationId;
+use crate::client::retries::RetryReason;
+use crate::config_bag::{Storable, StoreReplace};
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Notified before every retry, with the chance to veto it.
+///
+/// This is intended for applications that show progress while retries are delayed, or that let
+/// their users give up on a slow operation rather than wait for the next attempt.
+pub trait BeforeRetryCallback: Send + Sync + fmt::Debug {
+    /// Called once the orchestrator has decided to retry, just before it waits `delay` to make
+    /// attempt number `attempt` (the first retry is attempt `2`). Returns `false` to veto the
+    /// retry, in which case the operation completes with the result of the previous attempt.
+    fn before_retry(&self, delay: Duration, attempt: u32) -> bool;
+}
+
+impl Storable for Box<dyn BeforeRetryCallback> {
+    type Storer = StoreReplace<Self>;
+}
+
+/// A signal that an operation should stop retrying, e.g. because the application is shutting down.
+///
+/// The signal is checked between attempts, so an attempt that is in flight when the operation is
+/// cancelled runs to completion, but no further attempts are made.
+pub trait CancellationSignal: Send + Sync + fmt::Debug {
+    /// Returns true once the operation has been cancelled.
+    fn is_cancelled(&self) -> bool;
+}
+
+impl Storable for Box<dyn CancellationSignal> {
+    type Storer = StoreReplace<Self>;
+}
+
+/// The error returned when a [`CancellationSignal`] stops an operation from retrying.
+#[non_exhaustive]
+#[derive(Debug, Default)]
+pub struct OperationCancelled;
+
+impl fmt::Display for OperationCancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the operation was cancelled before it could be retried")
+    }
+}
+
+impl std::error::Error for OperationCancelled {}
+
+/// The attempt of an operation that is currently being made, or that produced the final response
+/// once the operation has completed.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RequestAttempt(u32);
+
+impl RequestAttempt {
+    /// Create a new [`RequestAttempt`] for the given 1-based attempt number.
+    pub fn new(attempt: u32) -> Self {
+        Self(attempt)
+    }
+
+    /// Returns the 1-based attempt number, e.g. `2` for the first retry.
+    pub fn attempt(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns true if this attempt is a retry, i.e. not the first attempt.
+    pub fn is_retry(&self) -> bool {
+        self.0 > 1
+    }
+}
+
+impl Storable for RequestAttempt {
+    type Storer = StoreReplace<Self>;
+}
+
+/// A header that is set on the request of every attempt to tell the service which attempt it is,
+/// so that the service can correlate retries.
+///
+/// The header is only set when configured in the [`ConfigBag`](crate::config_bag::ConfigBag).
+#[derive(Clone, Debug)]
+pub struct RequestAttemptHeader {
+    name: http::HeaderName,
+    format: fn(u32, Option<u32>) -> String,
+}
+
+impl RequestAttemptHeader {
+    /// Create a new [`RequestAttemptHeader`] named `name`.
+    ///
+    /// Its value is produced by `format` from the 1-based attempt number and the maximum number
+    /// of attempts, if a [`RetryConfig`](aws_smithy_types::retry::RetryConfig) is set.
+    pub fn new(name: http::HeaderName, format: fn(u32, Option<u32>) -> String) -> Self {
+        Self { name, format }
+    }
+
+    /// The `amz-sdk-request` header used by AWS services, e.g. `amz-sdk-request: attempt=2; max=3`.
+    pub fn amz_sdk_request() -> Self {
+        Self::new(
+            http::HeaderName::from_static("amz-sdk-request"),
+            |attempt, max_attempts| match max_attempts {
+                Some(max_attempts) => format!("attempt={attempt}; max={max_attempts}"),
+                None => format!("attempt={attempt}"),
+            },
+        )
+    }
+
+    /// Returns the name of the header.
+    pub fn name(&self) -> &http::HeaderName {
+        &self.name
+    }
+
+    /// Returns the value of the header for the given attempt.
+    pub fn value(&self, attempt: u32, max_attempts: Option<u32>) -> String {
+        (self.format)(attempt, max_attempts)
+    }
+}
+
+impl Storable for RequestAttemptHeader {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Whether the current attempt is the last one that the
+/// [`RetryStrategy`](crate::client::retries::RetryStrategy) allows, according to its
+/// [`max_attempts`](crate::client::retries::RetryStrategy::max_attempts).
+///
+/// The orchestrator sets it before every attempt. It's never final when the number of attempts is
+/// unbounded.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FinalAttempt(bool);
+
+impl FinalAttempt {
+    /// Create a new [`FinalAttempt`].
+    pub fn new(is_final: bool) -> Self {
+        Self(is_final)
+    }
+
+    /// Returns `true` if the current attempt is the final one.
+    pub fn is_final(&self) -> bool {
+        self.0
+    }
+}
+
+impl Storable for FinalAttempt {
+    type Storer = StoreReplace<Self>;
+}
+
+/// The longest time that may be spent on failed attempts and the delays between them, measured by
+/// the [`TimeSource`](crate::client::orchestrator::TimeSource) from the start of the first attempt.
+///
+/// A retry whose delay would take the operation past it isn't made, however many attempts the
+/// retry strategy allows. Unlike the operation timeout, this doesn't bound the attempt that's in
+/// flight, so a retry made within the budget may still succeed after it has run out. When none is
+/// set, retries are bounded by the retry strategy alone.
+#[derive(Copy, Clone, Debug)]
+pub struct MaxRetryDuration(Duration);
+
+impl MaxRetryDuration {
+    /// Create a new [`MaxRetryDuration`].
+    pub fn new(max_retry_duration: Duration) -> Self {
+        Self(max_retry_duration)
+    }
+
+    /// Returns the longest time that may be spent retrying.
+    pub fn duration(&self) -> Duration {
+        self.0
+    }
+}
+
+impl Storable for MaxRetryDuration {
+    type Storer = StoreReplace<Self>;
+}
+
+/// How the [`RetryClassifiers`](crate::client::retries::RetryClassifiers) classified the error of
+/// the latest attempt.
+///
+/// The orchestrator classifies every attempt before asking the retry strategy whether to retry it,
+/// so that retry and backoff strategies can tell modeled throttling errors apart from transient
+/// ones without knowing the operation's error type.
+#[derive(Clone, Debug)]
+pub struct AttemptRetryReason(Option<RetryReason>);
+
+impl AttemptRetryReason {
+    /// Create a new [`AttemptRetryReason`].
+    pub fn new(retry_reason: Option<RetryReason>) -> Self {
+        Self(retry_reason)
+    }
+
+    /// Returns the retry reason, or `None` if the attempt succeeded or its error isn't retryable.
+    pub fn retry_reason(&self) -> Option<&RetryReason> {
+        self.0.as_ref()
+    }
+}
+
+impl Storable for AttemptRetryReason {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Limits how many operations can be retrying at the same time.
+///
+/// An operation holds a permit from before it waits to retry until the retry attempt completes,
+/// so when every permit is taken, retries queue rather than piling onto a recovering service. The
+/// limiter is cheap to clone, and clones share their permits, so the same limiter should be set
+/// for every operation of a client.
+#[derive(Clone, Debug)]
+pub struct RetryConcurrencyLimiter {
+    semaphore: Arc<Semaphore>,
+}
+
+impl RetryConcurrencyLimiter {
+    /// Create a new [`RetryConcurrencyLimiter`] that allows up to `max_concurrent_retries`
+    /// operations to retry at the same time.
+    pub fn new(max_concurrent_retries: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrent_retries)),
+        }
+    }
+
+    /// Waits for a permit to retry. The permit is released when it's dropped.
+    pub async fn acquire(&self) -> RetryPermit {
+        let permit = self
+            .semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("the semaphore is never closed");
+        RetryPermit { _permit: permit }
+    }
+}
+
+impl Storable for RetryConcurrencyLimiter {
+    type Storer = StoreReplace<Self>;
+}
+
+/// A permit to retry, acquired from a [`RetryConcurrencyLimiter`].
+#[derive(Debug)]
+pub struct RetryPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+/// Operations that are never retried, whatever the retry strategy decides.
+///
+/// This is meant to be set at the client level, for operations that aren't safe to retry, such as
+/// writes that aren't idempotent. Operations are matched by the [`OperationId`] that the
+/// [`RuntimePlugins`](crate::client::runtime_plugin::RuntimePlugins) were given.
+#[derive(Clone, Debug, Default)]
+pub struct NonRetryableOperations {
+    operation_ids: HashSet<OperationId>,
+}
+
+impl NonRetryableOperations {
+    /// Create a new [`NonRetryableOperations`] without any operations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the operation with the given ID.
+    pub fn with_operation(mut self, operation_id: OperationId) -> Self {
+        self.operation_ids.insert(operation_id);
+        self
+    }
+
+    /// Returns `true` if the operation with the given ID is never retried.
+    pub fn contains(&self, operation_id: &OperationId) -> bool {
+        self.operation_ids.contains(operation_id)
+    }
+}
+
+impl Storable for NonRetryableOperations {
+    type Storer = StoreReplace<Self>;
+}
@@ -0,0 +1,186 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Configuration of the time that requests are signed at, and of the identity resolution that
+//! precedes signing.
+
+use crate::client::interceptors::context::Error;
+use crate::config_bag::{Storable, StoreReplace};
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+/// How far the local clock is from the service's clock, used to correct the time that requests
+/// are signed at.
+///
+/// Services reject requests whose signing time is too far from their own clock, so a client on a
+/// skewed clock can set the skew, e.g. as measured from the `Date` header of a response, for its
+/// requests to be signed at the service's time.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ClockSkew {
+    /// The local clock is ahead of the service's clock by the given duration.
+    Ahead(Duration),
+    /// The local clock is behind the service's clock by the given duration.
+    Behind(Duration),
+}
+
+impl ClockSkew {
+    /// Returns the service's time when the local clock reads `time`.
+    pub fn correct(&self, time: SystemTime) -> SystemTime {
+        match *self {
+            ClockSkew::Ahead(skew) => time - skew,
+            ClockSkew::Behind(skew) => time + skew,
+        }
+    }
+}
+
+impl Storable for ClockSkew {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Classifies the errors that a service returns when it rejects a request for being signed at a
+/// time too far from its own clock.
+///
+/// Only the protocol knows how such rejections are identified, so it's up to the protocol to
+/// store a classifier for the orchestrator to [correct the clock skew](CorrectClockSkew) with.
+pub trait ClassifyClockSkew: Send + Sync + fmt::Debug {
+    /// Returns `true` if the request was rejected because of clock skew.
+    fn is_clock_skew_error(&self, error: &Error) -> bool;
+}
+
+impl Storable for Box<dyn ClassifyClockSkew> {
+    type Storer = StoreReplace<Self>;
+}
+
+/// The time that the request of the current attempt is signed at.
+///
+/// The orchestrator records it before the auth of every attempt, from the
+/// [`request_time`](crate::client::orchestrator::ConfigBagAccessors::request_time) if one was set
+/// and the [`TimeSource`](crate::client::orchestrator::TimeSource) otherwise, so it can be read by
+/// `read_after_signing` hooks, for example to debug signatures that were rejected as expired.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SigningTime {
+    raw: SystemTime,
+    corrected: Option<SystemTime>,
+}
+
+impl SigningTime {
+    /// Creates a new [`SigningTime`] from the `raw` time read from the clock, with the
+    /// [`ClockSkew`] correction applied to it, if there is one.
+    pub fn new(raw: SystemTime, clock_skew: Option<ClockSkew>) -> Self {
+        Self {
+            raw,
+            corrected: clock_skew.map(|clock_skew| clock_skew.correct(raw)),
+        }
+    }
+
+    /// Returns the time read from the clock, before any clock skew correction.
+    pub fn raw(&self) -> SystemTime {
+        self.raw
+    }
+
+    /// Returns the time corrected for the [`ClockSkew`], or `None` if no correction was applied.
+    pub fn corrected(&self) -> Option<SystemTime> {
+        self.corrected
+    }
+
+    /// Returns the time that the request is signed at: the corrected time if there is one, and
+    /// the raw time otherwise.
+    pub fn time(&self) -> SystemTime {
+        self.corrected.unwrap_or(self.raw)
+    }
+}
+
+impl Storable for SigningTime {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Settings for retrying identity resolution when auth is orchestrated for an attempt.
+///
+/// Resolving an identity, such as credentials, can fail transiently, e.g. while a credential
+/// provider is momentarily unavailable. By default, such a failure fails the attempt right away.
+/// When a [`SigningRetryConfig`] is set in the [`ConfigBag`](crate::config_bag::ConfigBag),
+/// resolution is retried with an exponential backoff before the request is signed. These retries
+/// happen within a single attempt, and are independent of the retries made by the
+/// [`RetryStrategy`](crate::client::retries::RetryStrategy) once a request has been sent.
+///
+/// Only transient failures are retried: those whose source chain includes a timeout or I/O
+/// [`ConnectorError`](aws_smithy_http::result::ConnectorError), or a [`std::io::Error`] such as a
+/// timeout or a reset connection. Other failures fail the attempt right away.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SigningRetryConfig {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl SigningRetryConfig {
+    /// Create a new [`SigningRetryConfig`] that makes at most `max_attempts` to resolve the
+    /// identity, including the first one. The backoff starts at 50 milliseconds, and doubles
+    /// after every failure up to 1 second.
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+
+    /// Sets how long to wait after the first failure.
+    pub fn with_initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Sets the longest time to wait between two attempts.
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Returns the most attempts made to resolve the identity, including the first one.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Returns how long to wait after the first failure.
+    pub fn initial_backoff(&self) -> Duration {
+        self.initial_backoff
+    }
+
+    /// Returns the longest time to wait between two attempts.
+    pub fn max_backoff(&self) -> Duration {
+        self.max_backoff
+    }
+}
+
+impl Storable for SigningRetryConfig {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Whether the orchestrator corrects the [`ClockSkew`] when a request is rejected because of it.
+///
+/// A rejection is only corrected for when the stored [`ClassifyClockSkew`] classifies its error as
+/// a clock skew error. The skew is then measured from the `Date` header of the rejection, and if
+/// the retry strategy retries the request, the retry is signed at the corrected time. Skews of up
+/// to four minutes aren't corrected. Disabled by default.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CorrectClockSkew(bool);
+
+impl CorrectClockSkew {
+    /// Create a new [`CorrectClockSkew`].
+    pub fn new(enabled: bool) -> Self {
+        Self(enabled)
+    }
+
+    /// Returns `true` if clock skew is corrected.
+    pub fn enabled(&self) -> bool {
+        self.0
+    }
+}
+
+impl Storable for CorrectClockSkew {
+    type Storer = StoreReplace<Self>;
+}
@@ -0,0 +1,74 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Sources of the current time, and the deadlines measured with them.
+
+use crate::config_bag::{Storable, StoreReplace};
+use aws_smithy_async::rt::sleep::Sleep;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+/// A source of the current time, so that it can be overridden in the
+/// [`ConfigBag`](crate::config_bag::ConfigBag) (e.g. in tests).
+///
+/// The orchestrator uses it both for deadline calculations and to wait for timeouts and retry
+/// delays, so a mock time source makes them deterministic without a runtime timer.
+pub trait TimeSource: Send + Sync + fmt::Debug {
+    /// Returns the current time.
+    fn now(&self) -> SystemTime;
+
+    /// Returns a future that completes once `duration` has elapsed according to this time source.
+    ///
+    /// Returns `None` by default, in which case the
+    /// [`sleep_impl`](crate::client::orchestrator::ConfigBagAccessors::sleep_impl) is used instead.
+    fn sleep(&self, duration: Duration) -> Option<Sleep> {
+        let _ = duration;
+        None
+    }
+}
+
+/// A [`TimeSource`] backed by [`SystemTime::now`]. This is used when no other time source is set.
+#[derive(Debug, Default)]
+pub struct SystemTimeSource;
+
+impl TimeSource for SystemTimeSource {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+impl Storable for Box<dyn TimeSource> {
+    type Storer = StoreReplace<Self>;
+}
+
+/// The time by which the operation must complete for its operation timeout not to elapse.
+///
+/// The orchestrator records it in the [`ConfigBag`](crate::config_bag::ConfigBag) when the
+/// operation has an operation timeout, so that interceptors that make calls of their own can avoid
+/// overshooting the operation's budget.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct OperationDeadline(SystemTime);
+
+impl OperationDeadline {
+    /// Create a new [`OperationDeadline`] at `deadline`.
+    pub fn new(deadline: SystemTime) -> Self {
+        Self(deadline)
+    }
+
+    /// Returns the time by which the operation must complete.
+    pub fn deadline(&self) -> SystemTime {
+        self.0
+    }
+
+    /// Returns how much time is left before the deadline, according to the `time_source`.
+    pub fn remaining(&self, time_source: &dyn TimeSource) -> Duration {
+        // Once the deadline has passed, there is no budget left
+        self.0.duration_since(time_source.now()).unwrap_or_default()
+    }
+}
+
+impl Storable for OperationDeadline {
+    type Storer = StoreReplace<Self>;
+}
@@ -6,11 +6,15 @@
 use crate::client::interceptors::context::Error;
 use crate::client::interceptors::InterceptorContext;
 use crate::client::orchestrator::BoxError;
-use crate::config_bag::ConfigBag;
+use crate::config_bag::{ConfigBag, Storable, StoreReplace};
 use aws_smithy_types::retry::ErrorKind;
+use std::borrow::Cow;
+use std::fmt;
 use std::fmt::Debug;
 use std::time::Duration;
 
+pub mod rate_limiting;
+
 /// An answer to the question "should I make a request attempt?"
 pub enum ShouldAttempt {
     Yes,
@@ -26,15 +30,74 @@ pub trait RetryStrategy: Send + Sync + Debug {
         context: &InterceptorContext,
         cfg: &ConfigBag,
     ) -> Result<ShouldAttempt, BoxError>;
+
+    /// Returns the most attempts, including the initial request, that this strategy will make,
+    /// or `None` if it isn't bounded by a number of attempts.
+    fn max_attempts(&self) -> Option<u32> {
+        None
+    }
+}
+
+/// Computes how long to wait before making a retry attempt.
+///
+/// A [`RetryStrategy`] decides _whether_ a request should be retried. When it answers
+/// [`ShouldAttempt::Yes`], the orchestrator asks the `BackoffStrategy` in the config bag (if
+/// any) how long to sleep before the next attempt. This makes it possible to swap the backoff
+/// algorithm without reimplementing the retry decision. An explicit delay returned with
+/// [`ShouldAttempt::YesAfterDelay`] takes precedence over the backoff strategy.
+pub trait BackoffStrategy: Send + Sync + Debug {
+    /// Returns how long to wait after the given attempt failed, before making the next one.
+    ///
+    /// `attempt` is the number of the attempt that just failed, starting at `1` for the
+    /// initial request.
+    fn backoff(&self, attempt: u32, cfg: &ConfigBag) -> Duration;
+}
+
+impl Storable for Box<dyn BackoffStrategy> {
+    type Storer = StoreReplace<Self>;
 }
 
 #[non_exhaustive]
-#[derive(Eq, PartialEq, Debug)]
+#[derive(Clone, Eq, PartialEq, Debug)]
 pub enum RetryReason {
     Error(ErrorKind),
     Explicit(Duration),
 }
 
+/// The error a [`ResponseDeserializer`](crate::client::orchestrator::ResponseDeserializer) returns
+/// to ask for the request to be retried.
+///
+/// This is for responses that are valid, but that indicate the request should be made again,
+/// such as an empty result that should be polled for. [`RetryClassifiers`] classify it as a
+/// transient error, so it's retried by any retry strategy that consults them. If the request
+/// isn't retried, this is the error that the operation fails with.
+#[derive(Debug)]
+pub struct RetryRequested {
+    reason: Cow<'static, str>,
+}
+
+impl RetryRequested {
+    /// Create a new [`RetryRequested`], explaining why the response should be retried.
+    pub fn new(reason: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            reason: reason.into(),
+        }
+    }
+
+    /// Returns why the response should be retried.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+impl fmt::Display for RetryRequested {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the response asked to be retried: {}", self.reason)
+    }
+}
+
+impl std::error::Error for RetryRequested {}
+
 /// Classifies what kind of retry is needed for a given [`Error`].
 pub trait ClassifyRetry: Send + Sync + Debug {
     /// Run this classifier against an error to determine if it should be retried. Returns
@@ -68,6 +131,10 @@ impl RetryClassifiers {
 
 impl ClassifyRetry for RetryClassifiers {
     fn classify_retry(&self, error: &Error) -> Option<RetryReason> {
+        // A deserializer that asked for a retry knows best, so it takes precedence
+        if error.downcast_ref::<RetryRequested>().is_some() {
+            return Some(RetryReason::Error(ErrorKind::TransientError));
+        }
         // return the first non-None result
         self.inner.iter().find_map(|cr| cr.classify_retry(error))
     }
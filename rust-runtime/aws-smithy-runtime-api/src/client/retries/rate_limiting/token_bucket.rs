@@ -8,6 +8,7 @@
 use super::error::RateLimitingError;
 use super::token;
 use super::Token;
+use crate::config_bag::{Storable, StoreReplace};
 use aws_smithy_types::retry::{ErrorKind, RetryKind};
 use std::sync::Arc;
 use tokio::sync::Semaphore;
@@ -19,6 +20,8 @@ const STANDARD_INITIAL_RETRY_TOKENS: usize = 500;
 const STANDARD_TIMEOUT_ERROR_RETRY_COST: u32 = 10;
 /// The amount of tokens to remove from the bucket when a throttling error occurs
 const STANDARD_RETRYABLE_ERROR_RETRY_COST: u32 = 5;
+/// The amount of tokens to add back to the bucket when a request succeeds on the first try
+const STANDARD_SUCCESS_ON_FIRST_TRY_REFILL_AMOUNT: usize = 1;
 
 /// This trait is implemented by types that act as token buckets. Token buckets are used to regulate
 /// the amount of requests sent by clients. Different token buckets may apply different strategies
@@ -52,12 +55,19 @@ pub trait TokenBucket {
 ///     are removed from the bucket.
 ///
 /// The number of tokens in the bucket will always be >= `0` and <= `<max_tokens>`.
+///
+/// When stored in the [`ConfigBag`](crate::config_bag::ConfigBag), the orchestrator takes tokens
+/// from the bucket before every retry, and stops retrying once it's empty. The tokens are returned
+/// if the retry succeeds, and the bucket is topped up when a request succeeds on the first try.
+/// Since the bucket is shared by its clones, storing it in the client's config makes every
+/// operation of the client draw from it.
 #[derive(Clone, Debug)]
 pub struct Standard {
     inner: Arc<Semaphore>,
     max_tokens: usize,
     timeout_error_cost: u32,
     retryable_error_cost: u32,
+    success_on_first_try_refill_amount: usize,
 }
 
 impl Standard {
@@ -65,6 +75,11 @@ impl Standard {
     pub fn builder() -> Builder {
         Builder::default()
     }
+
+    /// The number of tokens to add back to the bucket when a request succeeds on the first try.
+    pub fn success_on_first_try_refill_amount(&self) -> usize {
+        self.success_on_first_try_refill_amount
+    }
 }
 
 /// A builder for `TokenBucket`s.
@@ -74,6 +89,7 @@ pub struct Builder {
     max_tokens: Option<usize>,
     timeout_error_cost: Option<u32>,
     retryable_error_cost: Option<u32>,
+    success_on_first_try_refill_amount: Option<usize>,
 }
 
 impl Builder {
@@ -104,6 +120,16 @@ impl Builder {
         self
     }
 
+    /// How many tokens to add back to the bucket when a request succeeds on the first try.
+    /// Defaults to 1.
+    pub fn success_on_first_try_refill_amount(
+        mut self,
+        success_on_first_try_refill_amount: usize,
+    ) -> Self {
+        self.success_on_first_try_refill_amount = Some(success_on_first_try_refill_amount);
+        self
+    }
+
     /// Build this builder. Unset fields will be set to their default values.
     pub fn build(self) -> Standard {
         let starting_tokens = self
@@ -116,16 +142,24 @@ impl Builder {
         let retryable_error_cost = self
             .retryable_error_cost
             .unwrap_or(STANDARD_RETRYABLE_ERROR_RETRY_COST);
+        let success_on_first_try_refill_amount = self
+            .success_on_first_try_refill_amount
+            .unwrap_or(STANDARD_SUCCESS_ON_FIRST_TRY_REFILL_AMOUNT);
 
         Standard {
             inner: Arc::new(Semaphore::new(starting_tokens)),
             max_tokens,
             timeout_error_cost,
             retryable_error_cost,
+            success_on_first_try_refill_amount,
         }
     }
 }
 
+impl Storable for Standard {
+    type Storer = StoreReplace<Self>;
+}
+
 impl TokenBucket for Standard {
     type Token = token::Standard;
 
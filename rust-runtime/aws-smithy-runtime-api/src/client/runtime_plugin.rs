@@ -4,6 +4,7 @@
  */
 
 use crate::client::interceptors::Interceptors;
+use crate::client::orchestrator::OperationId;
 use crate::config_bag::ConfigBag;
 
 pub type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
@@ -30,6 +31,7 @@ impl RuntimePlugin for Box<dyn RuntimePlugin> {
 pub struct RuntimePlugins {
     client_plugins: Vec<Box<dyn RuntimePlugin>>,
     operation_plugins: Vec<Box<dyn RuntimePlugin>>,
+    operation_id: Option<OperationId>,
 }
 
 impl RuntimePlugins {
@@ -47,6 +49,13 @@ impl RuntimePlugins {
         self
     }
 
+    /// Sets the ID of the operation that these plugins configure. It's put in the [`ConfigBag`]
+    /// before the operation plugins are applied.
+    pub fn with_operation_id(mut self, operation_id: OperationId) -> Self {
+        self.operation_id = Some(operation_id);
+        self
+    }
+
     pub fn apply_client_configuration(
         &self,
         cfg: &mut ConfigBag,
@@ -64,6 +73,9 @@ impl RuntimePlugins {
         cfg: &mut ConfigBag,
         interceptors: &mut Interceptors,
     ) -> Result<(), BoxError> {
+        if let Some(operation_id) = &self.operation_id {
+            cfg.store_put(operation_id.clone());
+        }
         for plugin in self.operation_plugins.iter() {
             plugin.configure(cfg, interceptors)?;
         }
@@ -76,6 +88,7 @@ impl RuntimePlugins {
 mod tests {
     use super::{BoxError, RuntimePlugin, RuntimePlugins};
     use crate::client::interceptors::Interceptors;
+    use crate::client::orchestrator::OperationId;
     use crate::config_bag::ConfigBag;
 
     struct SomeStruct;
@@ -94,4 +107,32 @@ mod tests {
     fn can_add_runtime_plugin_implementors_to_runtime_plugins() {
         RuntimePlugins::new().with_client_plugin(SomeStruct);
     }
+
+    #[test]
+    fn operation_id_is_set_before_operation_plugins_run() {
+        struct ExpectsOperationId;
+
+        impl RuntimePlugin for ExpectsOperationId {
+            fn configure(
+                &self,
+                cfg: &mut ConfigBag,
+                _inters: &mut Interceptors,
+            ) -> Result<(), BoxError> {
+                let operation_id = cfg.load::<OperationId>().expect("operation ID is set");
+                assert_eq!("GetObject", operation_id.name());
+                Ok(())
+            }
+        }
+
+        let mut cfg = ConfigBag::base();
+        RuntimePlugins::new()
+            .with_operation_id(OperationId::new("com.example#GetObject"))
+            .with_operation_plugin(ExpectsOperationId)
+            .apply_operation_configuration(&mut cfg, &mut Interceptors::new())
+            .unwrap();
+        assert_eq!(
+            Some(&OperationId::new("com.example#GetObject")),
+            cfg.load::<OperationId>()
+        );
+    }
 }
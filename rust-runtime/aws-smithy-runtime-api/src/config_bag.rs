@@ -78,7 +78,34 @@ impl<T: Default> Default for Value<T> {
 
 pub struct Layer {
     name: Cow<'static, str>,
-    props: TypeIdMap<TypeErasedBox>,
+    props: TypeIdMap<Entry>,
+}
+
+/// A value stored in a [`Layer`], along with what's needed to describe it for debugging
+struct Entry {
+    type_name: &'static str,
+    sensitive: bool,
+    value: TypeErasedBox,
+}
+
+impl Entry {
+    fn new<T: Store>(value: T::StoredType, sensitive: bool) -> Self {
+        Self {
+            type_name: T::item_type_name(),
+            sensitive,
+            value: TypeErasedBox::new(value),
+        }
+    }
+}
+
+impl Debug for Entry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.sensitive {
+            write!(f, "{}: ** redacted **", self.type_name)
+        } else {
+            write!(f, "{}: {:?}", self.type_name, self.value)
+        }
+    }
 }
 
 /// Trait defining how types can be stored and loaded from the config bag
@@ -88,6 +115,9 @@ pub trait Store: Sized + Send + Sync + 'static {
 
     /// Create a returned type from an iterable of items
     fn merge_iter(iter: ItemIter<'_, Self>) -> Self::ReturnedType<'_>;
+
+    /// The name of the type of the items that are stored, for debugging
+    fn item_type_name() -> &'static str;
 }
 
 /// Store an item in the config bag by replacing the existing value
@@ -100,6 +130,12 @@ pub struct StoreAppend<U>(PhantomData<U>);
 
 pub trait Storable: Send + Sync + Debug + 'static {
     type Storer: Store;
+
+    /// Whether values of this type must not be printed, such as credentials
+    ///
+    /// Sensitive values stored with [`ConfigBag::store_put`], [`ConfigBag::store_or_unset`], or
+    /// [`ConfigBag::store_append`] are redacted from [`ConfigBag::entries`].
+    const SENSITIVE: bool = false;
 }
 
 impl<U: Send + Sync + Debug + 'static> Store for StoreReplace<U> {
@@ -112,6 +148,10 @@ impl<U: Send + Sync + Debug + 'static> Store for StoreReplace<U> {
             Value::ExplicitlyUnset(_) => None,
         })
     }
+
+    fn item_type_name() -> &'static str {
+        type_name::<U>()
+    }
 }
 
 impl<U: Send + Sync + Debug + 'static> Store for StoreAppend<U> {
@@ -124,6 +164,10 @@ impl<U: Send + Sync + Debug + 'static> Store for StoreAppend<U> {
             cur: None,
         }
     }
+
+    fn item_type_name() -> &'static str {
+        type_name::<U>()
+    }
 }
 
 /// Iterator of items returned by [`StoreAppend`]
@@ -161,7 +205,10 @@ impl Debug for Layer {
         struct Items<'a>(&'a Layer);
         impl Debug for Items<'_> {
             fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-                f.debug_list().entries(self.0.props.values()).finish()
+                // Only the type names are printed, so that values are never leaked
+                f.debug_list()
+                    .entries(self.0.props.values().map(|entry| entry.type_name))
+                    .finish()
             }
         }
         f.debug_struct("Layer")
@@ -172,22 +219,40 @@ impl Debug for Layer {
 }
 
 impl Layer {
+    /// Insert `value` for `T`
+    ///
+    /// `T` may not be [`Storable`], so this can't tell if the value is sensitive. If the value
+    /// replaces one that was stored as sensitive, it stays redacted.
     pub fn put<T: Store>(&mut self, value: T::StoredType) -> &mut Self {
+        let sensitive = self.is_sensitive::<T>();
         self.props
-            .insert(TypeId::of::<T>(), TypeErasedBox::new(value));
+            .insert(TypeId::of::<T>(), Entry::new::<T>(value, sensitive));
+        self
+    }
+
+    fn is_sensitive<T: Store>(&self) -> bool {
+        self.props
+            .get(&TypeId::of::<T>())
+            .map_or(false, |entry| entry.sensitive)
+    }
+
+    /// Insert `value` for the [`Storable`] `T`, redacting it if `T` is sensitive
+    fn store<T: Storable>(&mut self, value: <T::Storer as Store>::StoredType) -> &mut Self {
+        let entry = Entry::new::<T::Storer>(value, T::SENSITIVE);
+        self.props.insert(TypeId::of::<T::Storer>(), entry);
         self
     }
 
     pub fn get<T: Send + Sync + Store + 'static>(&self) -> Option<&T::StoredType> {
         self.props
             .get(&TypeId::of::<T>())
-            .map(|t| t.downcast_ref().expect("typechecked"))
+            .map(|t| t.value.downcast_ref().expect("typechecked"))
     }
 
     pub fn get_mut<T: Send + Sync + Store + 'static>(&mut self) -> Option<&mut T::StoredType> {
         self.props
             .get_mut(&TypeId::of::<T>())
-            .map(|t| t.downcast_mut().expect("typechecked"))
+            .map(|t| t.value.downcast_mut().expect("typechecked"))
     }
 
     pub fn get_mut_or_default<T: Send + Sync + Store + 'static>(&mut self) -> &mut T::StoredType
@@ -196,7 +261,8 @@ impl Layer {
     {
         self.props
             .entry(TypeId::of::<T>())
-            .or_insert_with(|| TypeErasedBox::new(T::StoredType::default()))
+            .or_insert_with(|| Entry::new::<T>(T::StoredType::default(), false))
+            .value
             .downcast_mut()
             .expect("typechecked")
     }
@@ -281,7 +347,7 @@ impl ConfigBag {
     where
         T: Storable<Storer = StoreReplace<T>>,
     {
-        self.head.put::<StoreReplace<T>>(Value::Set(item));
+        self.head.store::<T>(Value::Set(item));
         self
     }
 
@@ -293,7 +359,7 @@ impl ConfigBag {
             Some(item) => Value::Set(item),
             None => Value::ExplicitlyUnset(type_name::<T>()),
         };
-        self.head.put::<StoreReplace<T>>(item);
+        self.head.store::<T>(item);
         self
     }
 
@@ -319,9 +385,11 @@ impl ConfigBag {
     where
         T: Storable<Storer = StoreAppend<T>>,
     {
-        match self.head.get_mut_or_default::<StoreAppend<T>>() {
-            Value::Set(list) => list.push(item),
-            v @ Value::ExplicitlyUnset(_) => *v = Value::Set(vec![item]),
+        match self.head.get_mut::<StoreAppend<T>>() {
+            Some(Value::Set(list)) => list.push(item),
+            _ => {
+                self.head.store::<T>(Value::Set(vec![item]));
+            }
         }
         self
     }
@@ -331,7 +399,7 @@ impl ConfigBag {
         T: Storable<Storer = StoreAppend<T>>,
     {
         self.head
-            .put::<StoreAppend<T>>(Value::ExplicitlyUnset(type_name::<T>()));
+            .store::<T>(Value::ExplicitlyUnset(type_name::<T>()));
     }
 
     pub fn load<T: Storable>(&self) -> <T::Storer as Store>::ReturnedType<'_> {
@@ -409,18 +477,34 @@ impl ConfigBag {
     }
 
     /// Insert `value` into the bag
+    ///
+    /// Unlike [`store_put`](Self::store_put), this doesn't know if `T` is
+    /// [sensitive](Storable::SENSITIVE), so the value is only redacted from
+    /// [`entries`](Self::entries) if a value of `T` was already stored as sensitive. Sensitive
+    /// values, such as credentials, should be stored with [`store_put`](Self::store_put).
     pub fn put<T: Send + Sync + Debug + 'static>(&mut self, value: T) -> &mut Self {
-        self.head.put::<StoreReplace<T>>(Value::Set(value));
+        self.put_replace::<T>(Value::Set(value));
         self
     }
 
     /// Remove `T` from this bag
     pub fn unset<T: Send + Sync + Debug + 'static>(&mut self) -> &mut Self {
-        self.head
-            .put::<StoreReplace<T>>(Value::ExplicitlyUnset(type_name::<T>()));
+        self.put_replace::<T>(Value::ExplicitlyUnset(type_name::<T>()));
         self
     }
 
+    /// Insert `value` for `T` into the top layer, keeping it redacted if an earlier value of `T`
+    /// was stored as sensitive
+    fn put_replace<T: Send + Sync + Debug + 'static>(&mut self, value: Value<T>) {
+        let sensitive = self
+            .layers()
+            .any(|layer| layer.is_sensitive::<StoreReplace<T>>());
+        let entry = Entry::new::<StoreReplace<T>>(value, sensitive);
+        self.head
+            .props
+            .insert(TypeId::of::<StoreReplace<T>>(), entry);
+    }
+
     /// Freeze this layer by wrapping it in an `Arc`
     ///
     /// This prevents further items from being added to this layer, but additional layers can be
@@ -461,11 +545,57 @@ impl ConfigBag {
         T::merge_iter(stored_type_iter)
     }
 
+    /// Lists the entries in every layer of the bag, starting with the most recent layer
+    ///
+    /// This is meant for debugging, for example by logging the entries from an interceptor
+    /// right before a request is sent. Entries that are shadowed by a more recent layer, or that
+    /// were explicitly unset, are listed too.
+    pub fn entries(&self) -> impl Iterator<Item = ConfigBagEntry<'_>> {
+        self.layers().flat_map(|layer| {
+            layer.props.values().map(move |entry| ConfigBagEntry {
+                layer: &layer.name,
+                entry,
+            })
+        })
+    }
+
     fn layers(&self) -> BagIter<'_> {
         BagIter { bag: Some(self) }
     }
 }
 
+/// An entry in a [`ConfigBag`], as listed by [`ConfigBag::entries`]
+///
+/// The [`Debug`] implementation prints the type name and value of the entry, unless the type
+/// of the value is [sensitive](Storable::SENSITIVE).
+pub struct ConfigBagEntry<'a> {
+    layer: &'a str,
+    entry: &'a Entry,
+}
+
+impl<'a> ConfigBagEntry<'a> {
+    /// The name of the layer that the entry was stored in
+    pub fn layer(&self) -> &'a str {
+        self.layer
+    }
+
+    /// The name of the type of the entry
+    pub fn type_name(&self) -> &'static str {
+        self.entry.type_name
+    }
+
+    /// Returns `true` if the entry is redacted, since its type is [sensitive](Storable::SENSITIVE)
+    pub fn is_sensitive(&self) -> bool {
+        self.entry.sensitive
+    }
+}
+
+impl Debug for ConfigBagEntry<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {:?}", self.layer, self.entry)
+    }
+}
+
 /// Iterator of items returned from config_bag
 pub struct ItemIter<'a, T> {
     inner: BagIter<'a>,
@@ -519,6 +649,7 @@ pub enum SourceInfo {
 mod test {
     use super::ConfigBag;
     use crate::config_bag::{Storable, StoreAppend, StoreReplace};
+    use std::any::type_name;
 
     #[test]
     fn layered_property_bag() {
@@ -677,4 +808,48 @@ mod test {
         assert_eq!(next.get_mut::<Foo>(), None);
         assert_eq!(next.get_mut_or_default::<Foo>(), &Foo(0));
     }
+
+    #[test]
+    fn entries() {
+        #[derive(Debug)]
+        struct Region(&'static str);
+        #[derive(Debug)]
+        struct SecretKey(&'static str);
+        impl Storable for SecretKey {
+            type Storer = StoreReplace<SecretKey>;
+            const SENSITIVE: bool = true;
+        }
+
+        let bag = ConfigBag::base().with_fn("client", |bag: &mut ConfigBag| {
+            bag.put(Region("us-east-1"));
+            bag.store_put(SecretKey("hunter2"));
+        });
+        let mut bag = bag.add_layer("operation");
+        bag.put(Region("us-west-2"));
+
+        let entries = bag
+            .entries()
+            .map(|entry| (entry.layer(), entry.type_name(), entry.is_sensitive()))
+            .collect::<Vec<_>>();
+        let region = type_name::<Region>();
+        let secret_key = type_name::<SecretKey>();
+        assert_eq!(entries[0], ("operation", region, false));
+        assert!(entries.contains(&("client", region, false)));
+        assert!(entries.contains(&("client", secret_key, true)));
+        assert_eq!(bag.get::<SecretKey>().unwrap().0, "hunter2");
+        assert_eq!(bag.get::<Region>().unwrap().0, "us-west-2");
+
+        let dump = format!("{:?}", bag.entries().collect::<Vec<_>>());
+        assert!(dump.contains("Region(\"us-west-2\")"), "{dump}");
+        assert!(
+            dump.contains(&format!("{secret_key}: ** redacted **")),
+            "{dump}"
+        );
+        assert!(!dump.contains("hunter2"), "{dump}");
+        // Printing the bag itself only prints the type names
+        let bag_dump = format!("{bag:?}");
+        assert!(bag_dump.contains(secret_key), "{bag_dump}");
+        assert!(!bag_dump.contains("hunter2"), "{bag_dump}");
+        assert!(!bag_dump.contains("us-west-2"), "{bag_dump}");
+    }
 }
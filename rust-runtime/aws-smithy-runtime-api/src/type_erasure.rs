@@ -89,7 +89,6 @@ impl<T: fmt::Debug + Send + Sync + 'static> DerefMut for TypedBox<T> {
 /// A new-type around `Box<dyn Debug + Send + Sync>`
 pub struct TypeErasedBox {
     field: Box<dyn Any + Send + Sync>,
-    #[allow(dead_code)]
     type_name: &'static str,
     #[allow(clippy::type_complexity)]
     debug: Box<dyn Fn(&TypeErasedBox, &mut fmt::Formatter<'_>) -> fmt::Result + Send + Sync>,
@@ -115,6 +114,11 @@ impl TypeErasedBox {
         }
     }
 
+    /// Returns the name of the erased type, for diagnostics.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
     // Downcast into a `Box<T>`, or return `Self` if it is not a `T`.
     pub fn downcast<T: fmt::Debug + Send + Sync + 'static>(self) -> Result<Box<T>, Self> {
         let TypeErasedBox {
@@ -16,4 +16,10 @@ pub mod connections;
 /// used to limit the rate at which requests are sent.
 pub mod retries;
 
+/// An in-memory cache of operation outputs.
+pub mod response_cache;
+
+/// Tracking of operation latency percentiles.
+pub mod latency;
+
 mod timeout;
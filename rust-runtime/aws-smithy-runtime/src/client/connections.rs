@@ -3,6 +3,14 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
+/// A connection that replays recorded responses, for deterministic tests
+#[cfg(any(feature = "test-util", test))]
+pub mod replay;
+
+/// Connections that inject faults and latency into requests, for resilience and load testing
+#[cfg(any(feature = "test-util", test))]
+pub mod fault_injection;
+
 #[cfg(feature = "test-util")]
 pub mod test_connection;
 
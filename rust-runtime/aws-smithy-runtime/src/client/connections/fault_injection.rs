@@ -0,0 +1,307 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use aws_smithy_async::rt::sleep::{default_async_sleep, AsyncSleep};
+use aws_smithy_http::body::SdkBody;
+use aws_smithy_http::result::ConnectorError;
+use aws_smithy_runtime_api::client::orchestrator::{
+    BoxFuture, Connection, HttpRequest, HttpResponse,
+};
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A fault that a [`FaultInjector`] injects into a request.
+#[non_exhaustive]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Fault {
+    /// Waits for the given duration before the request is sent. Combined with an attempt timeout,
+    /// this simulates a request that times out.
+    Delay(Duration),
+    /// Fails the request with an IO error, as if the connection was dropped. The request is never
+    /// sent.
+    Drop,
+    /// Replaces the body of the response with bytes that can't be deserialized.
+    CorruptResponse,
+}
+
+enum Trigger {
+    Attempts(Vec<u32>),
+    Probability {
+        probability: f64,
+        rng: Mutex<fastrand::Rng>,
+    },
+}
+
+impl fmt::Debug for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Attempts(attempts) => f.debug_tuple("Attempts").field(attempts).finish(),
+            Self::Probability { probability, .. } => f
+                .debug_struct("Probability")
+                .field("probability", probability)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+/// A [`Connection`] that injects a [`Fault`] into some of the requests sent over another
+/// connection, for resilience testing.
+///
+/// Requests are numbered from 1 in the order they're sent, so without redirects, a request's
+/// number is the attempt it belongs to.
+#[derive(Debug)]
+pub struct FaultInjector {
+    connection: Arc<dyn Connection>,
+    fault: Fault,
+    trigger: Trigger,
+    requests: AtomicU32,
+    sleep_impl: Option<Arc<dyn AsyncSleep>>,
+}
+
+impl FaultInjector {
+    /// Creates a [`FaultInjector`] that injects `fault` into the given 1-based `attempts` of the
+    /// requests sent over `connection`.
+    pub fn for_attempts(
+        connection: impl Connection + 'static,
+        fault: Fault,
+        attempts: impl IntoIterator<Item = u32>,
+    ) -> Self {
+        Self::new(
+            connection,
+            fault,
+            Trigger::Attempts(attempts.into_iter().collect()),
+        )
+    }
+
+    /// Creates a [`FaultInjector`] that injects `fault` into each request sent over `connection`
+    /// with the given `probability`, between `0.0` and `1.0`.
+    ///
+    /// Requests are chosen with a random number generator seeded with `seed`, so that a failing
+    /// test can be reproduced by reusing its seed.
+    pub fn with_probability(
+        connection: impl Connection + 'static,
+        fault: Fault,
+        probability: f64,
+        seed: u64,
+    ) -> Self {
+        Self::new(
+            connection,
+            fault,
+            Trigger::Probability {
+                probability,
+                rng: Mutex::new(fastrand::Rng::with_seed(seed)),
+            },
+        )
+    }
+
+    fn new(connection: impl Connection + 'static, fault: Fault, trigger: Trigger) -> Self {
+        Self {
+            connection: Arc::new(connection),
+            fault,
+            trigger,
+            requests: AtomicU32::new(0),
+            sleep_impl: default_async_sleep(),
+        }
+    }
+
+    /// Sets the sleep implementation that a [`Fault::Delay`] is waited out with.
+    ///
+    /// Defaults to the sleep implementation of the enabled async runtime, if any.
+    pub fn sleep_impl(mut self, sleep_impl: Arc<dyn AsyncSleep>) -> Self {
+        self.sleep_impl = Some(sleep_impl);
+        self
+    }
+
+    /// Returns the fault to inject into the given 1-based `attempt`, if any.
+    pub fn fault_for_attempt(&self, attempt: u32) -> Option<&Fault> {
+        let inject = match &self.trigger {
+            Trigger::Attempts(attempts) => attempts.contains(&attempt),
+            Trigger::Probability { probability, rng } => rng.lock().unwrap().f64() < *probability,
+        };
+        inject.then_some(&self.fault)
+    }
+
+    fn call_with_fault(
+        &self,
+        request: HttpRequest,
+        fresh_connection: bool,
+    ) -> BoxFuture<HttpResponse> {
+        let attempt = self.requests.fetch_add(1, Ordering::SeqCst) + 1;
+        let connection = self.connection.clone();
+        let call = move |request| {
+            if fresh_connection {
+                connection.call_on_fresh_connection(request)
+            } else {
+                connection.call(request)
+            }
+        };
+        let fault = self.fault_for_attempt(attempt).cloned();
+        let sleep_impl = self.sleep_impl.clone();
+        Box::pin(async move {
+            match fault {
+                None => call(request).await,
+                Some(Fault::Delay(delay)) => {
+                    sleep_impl
+                        .ok_or("a sleep implementation is required to inject a delay")?
+                        .sleep(delay)
+                        .await;
+                    call(request).await
+                }
+                Some(Fault::Drop) => {
+                    tracing::debug!(attempt, "injecting a dropped connection");
+                    Err(
+                        ConnectorError::io("injected fault: the connection was dropped".into())
+                            .into(),
+                    )
+                }
+                Some(Fault::CorruptResponse) => {
+                    let mut response = call(request).await?;
+                    *response.body_mut() = SdkBody::from(&b"\xff\xfe<corrupted"[..]);
+                    response.headers_mut().remove(http::header::CONTENT_LENGTH);
+                    Ok(response)
+                }
+            }
+        })
+    }
+}
+
+impl Connection for FaultInjector {
+    fn call(&self, request: HttpRequest) -> BoxFuture<HttpResponse> {
+        self.call_with_fault(request, false)
+    }
+
+    fn call_on_fresh_connection(&self, request: HttpRequest) -> BoxFuture<HttpResponse> {
+        self.call_with_fault(request, true)
+    }
+
+    fn prewarm(&self, uri: &http::Uri) -> BoxFuture<()> {
+        self.connection.prewarm(uri)
+    }
+}
+
+/// A [`Connection`] that delays every request sent over another connection by a fixed duration,
+/// to simulate a slow network when load testing the systems a client calls.
+///
+/// Unlike [`Fault::Delay`], the latency is added to every request. Since the connection is
+/// called within an attempt, the latency counts towards the attempt timeout.
+#[derive(Debug)]
+pub struct SyntheticLatency {
+    connection: Arc<dyn Connection>,
+    latency: Duration,
+    sleep_impl: Option<Arc<dyn AsyncSleep>>,
+}
+
+impl SyntheticLatency {
+    /// Creates a [`SyntheticLatency`] that delays every request sent over `connection` by
+    /// `latency`.
+    pub fn new(connection: impl Connection + 'static, latency: Duration) -> Self {
+        Self {
+            connection: Arc::new(connection),
+            latency,
+            sleep_impl: default_async_sleep(),
+        }
+    }
+
+    /// Sets the sleep implementation that the latency is waited out with.
+    ///
+    /// Defaults to the sleep implementation of the enabled async runtime, if any.
+    pub fn sleep_impl(mut self, sleep_impl: Arc<dyn AsyncSleep>) -> Self {
+        self.sleep_impl = Some(sleep_impl);
+        self
+    }
+
+    /// Returns the latency added to every request.
+    pub fn latency(&self) -> Duration {
+        self.latency
+    }
+
+    fn call_after_latency(
+        &self,
+        request: HttpRequest,
+        fresh_connection: bool,
+    ) -> BoxFuture<HttpResponse> {
+        let connection = self.connection.clone();
+        let latency = self.latency;
+        let sleep_impl = self.sleep_impl.clone();
+        Box::pin(async move {
+            tracing::debug!(latency = ?latency, "adding synthetic latency");
+            sleep_impl
+                .ok_or("a sleep implementation is required to add synthetic latency")?
+                .sleep(latency)
+                .await;
+            if fresh_connection {
+                connection.call_on_fresh_connection(request).await
+            } else {
+                connection.call(request).await
+            }
+        })
+    }
+}
+
+impl Connection for SyntheticLatency {
+    fn call(&self, request: HttpRequest) -> BoxFuture<HttpResponse> {
+        self.call_after_latency(request, false)
+    }
+
+    fn call_on_fresh_connection(&self, request: HttpRequest) -> BoxFuture<HttpResponse> {
+        self.call_after_latency(request, true)
+    }
+
+    fn prewarm(&self, uri: &http::Uri) -> BoxFuture<()> {
+        self.connection.prewarm(uri)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::ready;
+
+    #[derive(Debug)]
+    struct NeverCalled;
+
+    impl Connection for NeverCalled {
+        fn call(&self, _request: HttpRequest) -> BoxFuture<HttpResponse> {
+            Box::pin(ready(Err("unexpected call".into())))
+        }
+    }
+
+    fn injected_attempts(fault_injector: &FaultInjector) -> Vec<u32> {
+        (1..=20)
+            .filter(|&attempt| fault_injector.fault_for_attempt(attempt).is_some())
+            .collect()
+    }
+
+    #[test]
+    fn seeded_injectors_are_reproducible() {
+        let first = injected_attempts(&FaultInjector::with_probability(
+            NeverCalled,
+            Fault::Drop,
+            0.5,
+            42,
+        ));
+        let second = injected_attempts(&FaultInjector::with_probability(
+            NeverCalled,
+            Fault::Drop,
+            0.5,
+            42,
+        ));
+        assert_eq!(first, second);
+        assert!(!first.is_empty() && first.len() < 20, "{first:?}");
+    }
+
+    #[test]
+    fn faults_are_injected_into_the_given_attempts() {
+        let fault_injector =
+            FaultInjector::for_attempts(NeverCalled, Fault::CorruptResponse, [2, 3]);
+        assert_eq!(vec![2, 3], injected_attempts(&fault_injector));
+        assert_eq!(
+            Some(&Fault::CorruptResponse),
+            fault_injector.fault_for_attempt(2)
+        );
+    }
+}
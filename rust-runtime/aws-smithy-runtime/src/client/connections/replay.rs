@@ -0,0 +1,448 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! A [`Connection`] that replays recorded responses, for deterministic integration tests.
+//!
+//! Responses are recorded in a [`Cassette`], which can be saved to and loaded from a JSON file.
+//! A [`ReplayConnection`] responds to each request with a recorded response to a matching
+//! request, without any network traffic. Requests match when they have the same method, path,
+//! and query, and the same values for the headers selected with
+//! [`ReplayConnection::match_headers`]. Request bodies aren't compared.
+//!
+//! To record a cassette, give the connection a real connection with
+//! [`ReplayConnection::recording`]: requests without a recorded response are sent with it, and
+//! their responses are added to the cassette, which can then be saved with [`Cassette::save`].
+
+use aws_smithy_http::body::SdkBody;
+use aws_smithy_http::byte_stream::ByteStream;
+use aws_smithy_http::result::ConnectorError;
+use aws_smithy_runtime_api::client::orchestrator::{
+    BoxError, BoxFuture, Connection, HttpRequest, HttpResponse,
+};
+use aws_smithy_types::base64;
+use http::header::HeaderName;
+use http::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// The parts of a request that responses are matched by.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    method: String,
+    path: String,
+    headers: BTreeMap<String, Vec<String>>,
+}
+
+impl RecordedRequest {
+    /// Returns the request's method.
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Returns the request's path, including its query.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl fmt::Display for RecordedRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.method, self.path)?;
+        for (name, values) in &self.headers {
+            write!(f, " {}={:?}", name, values)?;
+        }
+        Ok(())
+    }
+}
+
+/// A recorded response, with its complete body.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    status: u16,
+    headers: BTreeMap<String, Vec<String>>,
+    /// The base64-encoded body, so that binary bodies can be recorded.
+    body: String,
+}
+
+impl RecordedResponse {
+    fn to_response(&self) -> Result<HttpResponse, BoxError> {
+        let mut response = http::Response::builder().status(self.status);
+        for (name, values) in &self.headers {
+            for value in values {
+                response = response.header(name, value);
+            }
+        }
+        let body = base64::decode(&self.body)?;
+        Ok(response.body(SdkBody::from(body))?)
+    }
+}
+
+/// A request and the response it received.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+/// A recording of requests and the responses they received, in the order they were made.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cassette {
+    interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    /// Creates an empty [`Cassette`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a cassette from the JSON file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, BoxError> {
+        let json = std::fs::read(path)?;
+        Ok(serde_json::from_slice(&json)?)
+    }
+
+    /// Saves the cassette to the JSON file at `path`, replacing it if it exists.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), BoxError> {
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Returns the recorded interactions.
+    pub fn interactions(&self) -> &[Interaction] {
+        &self.interactions
+    }
+}
+
+fn recorded_headers(
+    headers: &HeaderMap,
+    names: Option<&[HeaderName]>,
+) -> BTreeMap<String, Vec<String>> {
+    let mut recorded = BTreeMap::new();
+    for name in headers.keys() {
+        match names {
+            Some(names) if !names.contains(name) => continue,
+            _ => {}
+        }
+        let values = headers
+            .get_all(name)
+            .iter()
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+            .collect();
+        recorded.insert(name.as_str().to_string(), values);
+    }
+    recorded
+}
+
+#[derive(Debug, Default)]
+struct ReplayState {
+    cassette: Cassette,
+    // How many times each interaction was replayed
+    replays: Vec<usize>,
+}
+
+impl ReplayState {
+    /// Returns the first matching response that hasn't been replayed yet. If they all have been,
+    /// returns the last one if `repeat_last` is set, or `None` otherwise.
+    fn replay(
+        &mut self,
+        request: &RecordedRequest,
+        repeat_last: bool,
+    ) -> Option<&RecordedResponse> {
+        self.replays.resize(self.cassette.interactions.len(), 0);
+        let matching: Vec<usize> = (0..self.cassette.interactions.len())
+            .filter(|index| &self.cassette.interactions[*index].request == request)
+            .collect();
+        let index = matching
+            .iter()
+            .copied()
+            .find(|index| self.replays[*index] == 0)
+            .or_else(|| matching.last().copied().filter(|_| repeat_last))?;
+        self.replays[index] += 1;
+        Some(&self.cassette.interactions[index].response)
+    }
+}
+
+/// A [`Connection`] that responds with the responses recorded in a [`Cassette`].
+///
+/// When a request was made more than once while recording, its responses are replayed in the
+/// order they were recorded, and unless the connection is
+/// [recording](ReplayConnection::recording), the last one is repeated once they've all been
+/// replayed. By default, a request without a recorded response fails with a [`ConnectorError`]. See the
+/// [module](crate::client::connections::replay) documentation for more information.
+#[derive(Clone)]
+pub struct ReplayConnection {
+    state: Arc<Mutex<ReplayState>>,
+    match_headers: Vec<HeaderName>,
+    recording: Option<Arc<dyn Connection>>,
+}
+
+impl fmt::Debug for ReplayConnection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReplayConnection")
+            .field(
+                "interactions",
+                &self.state.lock().unwrap().cassette.interactions.len(),
+            )
+            .field("match_headers", &self.match_headers)
+            .field("recording", &self.recording)
+            .finish()
+    }
+}
+
+impl ReplayConnection {
+    /// Creates a [`ReplayConnection`] that replays the responses recorded in `cassette`.
+    pub fn new(cassette: Cassette) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ReplayState {
+                cassette,
+                replays: Vec::new(),
+            })),
+            match_headers: Vec::new(),
+            recording: None,
+        }
+    }
+
+    /// Creates a [`ReplayConnection`] that replays the responses recorded in the cassette at
+    /// `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, BoxError> {
+        Ok(Self::new(Cassette::load(path)?))
+    }
+
+    /// Only replays responses to requests whose values for the headers `names` are the same as
+    /// the recorded request's.
+    ///
+    /// Other headers, such as ones with signatures or timestamps that change with every request,
+    /// are ignored, and aren't recorded.
+    pub fn match_headers(mut self, names: impl IntoIterator<Item = HeaderName>) -> Self {
+        self.match_headers.extend(names);
+        self
+    }
+
+    /// Sends requests that don't have a recorded response with `connection`, and records its
+    /// responses, instead of failing them.
+    ///
+    /// Recorded responses are still replayed, but aren't repeated: once every recorded response
+    /// to a request has been replayed, the request is sent with `connection` again.
+    pub fn recording(mut self, connection: impl Connection + 'static) -> Self {
+        self.recording = Some(Arc::new(connection));
+        self
+    }
+
+    /// Returns the cassette, including the responses recorded so far.
+    ///
+    /// The connection's clones share their cassette.
+    pub fn cassette(&self) -> Cassette {
+        self.state.lock().unwrap().cassette.clone()
+    }
+
+    fn recorded_request(&self, request: &HttpRequest) -> RecordedRequest {
+        RecordedRequest {
+            method: request.method().to_string(),
+            path: request
+                .uri()
+                .path_and_query()
+                .map(|path| path.as_str())
+                .unwrap_or("/")
+                .to_string(),
+            headers: recorded_headers(request.headers(), Some(&self.match_headers)),
+        }
+    }
+}
+
+impl Connection for ReplayConnection {
+    fn call(&self, request: HttpRequest) -> BoxFuture<HttpResponse> {
+        let recorded_request = self.recorded_request(&request);
+        let replayed = self
+            .state
+            .lock()
+            .unwrap()
+            .replay(&recorded_request, self.recording.is_none())
+            .map(RecordedResponse::to_response);
+        if let Some(response) = replayed {
+            return Box::pin(async move { response });
+        }
+
+        let connection = match &self.recording {
+            Some(connection) => connection.clone(),
+            None => {
+                let err = ConnectorError::other(
+                    format!("no response was recorded for the request `{recorded_request}`").into(),
+                    None,
+                );
+                return Box::pin(async move { Err(err.into()) });
+            }
+        };
+        let state = self.state.clone();
+        Box::pin(async move {
+            let response = connection.call(request).await?;
+            let (parts, body) = response.into_parts();
+            let body = ByteStream::new(body).collect().await?.into_bytes();
+            let recorded_response = RecordedResponse {
+                status: parts.status.as_u16(),
+                headers: recorded_headers(&parts.headers, None),
+                body: base64::encode(&body),
+            };
+            let mut state = state.lock().unwrap();
+            state.cassette.interactions.push(Interaction {
+                request: recorded_request,
+                response: recorded_response,
+            });
+            // The response was just returned, so it counts as replayed
+            state.replays.push(1);
+            Ok(http::Response::from_parts(parts, SdkBody::from(body)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, uri: &str, headers: &[(&str, &str)]) -> HttpRequest {
+        let mut request = http::Request::builder().method(method).uri(uri);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        request.body(SdkBody::empty()).unwrap()
+    }
+
+    fn body(response: &HttpResponse) -> &str {
+        std::str::from_utf8(
+            response
+                .body()
+                .bytes()
+                .expect("replayed bodies are in memory"),
+        )
+        .unwrap()
+    }
+
+    fn cassette() -> Cassette {
+        serde_json::from_str(
+            r#"{
+                "interactions": [
+                    {
+                        "request": {
+                            "method": "GET",
+                            "path": "/pokemon/pikachu?lang=en",
+                            "headers": { "x-trainer": ["ash"] }
+                        },
+                        "response": {
+                            "status": 200,
+                            "headers": { "content-type": ["text/plain"] },
+                            "body": "ZWxlY3RyaWM="
+                        }
+                    }
+                ]
+            }"#,
+        )
+        .expect("valid cassette")
+    }
+
+    #[tokio::test]
+    async fn recorded_responses_are_replayed() {
+        let connection =
+            ReplayConnection::new(cassette()).match_headers([HeaderName::from_static("x-trainer")]);
+
+        for _ in 0..2 {
+            let response = connection
+                .call(request(
+                    "GET",
+                    "https://example.com/pokemon/pikachu?lang=en",
+                    &[("x-trainer", "ash"), ("x-amz-date", "20230101T000000Z")],
+                ))
+                .await
+                .expect("a response was recorded");
+            assert_eq!(200, response.status().as_u16());
+            assert_eq!("text/plain", response.headers()["content-type"]);
+            assert_eq!("electric", body(&response));
+        }
+    }
+
+    #[tokio::test]
+    async fn unrecorded_requests_fail() {
+        let connection =
+            ReplayConnection::new(cassette()).match_headers([HeaderName::from_static("x-trainer")]);
+        let unrecorded = [
+            request(
+                "POST",
+                "https://example.com/pokemon/pikachu?lang=en",
+                &[("x-trainer", "ash")],
+            ),
+            request(
+                "GET",
+                "https://example.com/pokemon/pikachu",
+                &[("x-trainer", "ash")],
+            ),
+            request(
+                "GET",
+                "https://example.com/pokemon/pikachu?lang=en",
+                &[("x-trainer", "misty")],
+            ),
+        ];
+
+        for request in unrecorded {
+            let err = connection
+                .call(request)
+                .await
+                .expect_err("no response was recorded");
+            let err = err
+                .downcast_ref::<ConnectorError>()
+                .expect("misses are connector errors");
+            let source = std::error::Error::source(err)
+                .expect("the miss is the source")
+                .to_string();
+            assert!(source.contains("no response was recorded"), "{source}");
+        }
+    }
+
+    #[derive(Debug)]
+    struct LiveConnection(Mutex<Vec<&'static str>>);
+
+    impl Connection for LiveConnection {
+        fn call(&self, _request: HttpRequest) -> BoxFuture<HttpResponse> {
+            let body = self.0.lock().unwrap().remove(0);
+            Box::pin(async move {
+                Ok(http::Response::builder()
+                    .status(200)
+                    .body(SdkBody::from(body))
+                    .unwrap())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn recorded_cassettes_can_be_saved_and_replayed() {
+        let live = LiveConnection(Mutex::new(vec!["first", "second"]));
+        let recorder = ReplayConnection::new(Cassette::new()).recording(live);
+        for expected in ["first", "second"] {
+            let response = recorder
+                .call(request("GET", "https://example.com/counter", &[]))
+                .await
+                .unwrap();
+            assert_eq!(expected, body(&response));
+        }
+
+        let path = std::env::temp_dir().join(format!(
+            "replay-connection-test-{}.json",
+            std::process::id()
+        ));
+        recorder.cassette().save(&path).unwrap();
+        let replay = ReplayConnection::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(recorder.cassette(), replay.cassette());
+        for expected in ["first", "second", "second"] {
+            let response = replay
+                .call(request("GET", "https://example.com/counter", &[]))
+                .await
+                .unwrap();
+            assert_eq!(expected, body(&response));
+        }
+    }
+}
@@ -0,0 +1,460 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use crate::client::timeout::time_source;
+use aws_smithy_runtime_api::client::interceptors::error::BoxError;
+use aws_smithy_runtime_api::client::interceptors::{Interceptor, InterceptorContext, Interceptors};
+use aws_smithy_runtime_api::client::runtime_plugin::RuntimePlugin;
+use aws_smithy_runtime_api::config_bag::{ConfigBag, Storable, StoreReplace};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// The number of latencies kept by [`LatencyTracker::new`].
+const DEFAULT_WINDOW: usize = 1000;
+
+/// The number of latencies needed before an [`AdaptiveAttemptTimeout`] takes effect by default.
+const DEFAULT_MIN_SAMPLES: usize = 20;
+
+/// Values are bucketed by their 7 most significant bits, so a bucket's values are within
+/// 1/64 (about 1.6%) of each other.
+const SUB_BUCKET_BITS: u32 = 7;
+const SUB_BUCKET_COUNT: u64 = 1 << SUB_BUCKET_BITS;
+const SUB_BUCKET_HALF_COUNT: u64 = SUB_BUCKET_COUNT / 2;
+
+/// Returns the index of the bucket that `value` is counted in.
+///
+/// Values below [`SUB_BUCKET_COUNT`] have a bucket each. Above that, every power of two is split
+/// into [`SUB_BUCKET_HALF_COUNT`] buckets of equal width.
+fn bucket_index(value: u64) -> usize {
+    if value < SUB_BUCKET_COUNT {
+        return value as usize;
+    }
+    let shift = (63 - value.leading_zeros()) - (SUB_BUCKET_BITS - 1);
+    (shift as u64 * SUB_BUCKET_HALF_COUNT + (value >> shift)) as usize
+}
+
+/// Returns the highest value counted in the bucket at `index`.
+fn highest_value_in_bucket(index: usize) -> u64 {
+    let index = index as u64;
+    if index < SUB_BUCKET_COUNT {
+        return index;
+    }
+    let shift = index / SUB_BUCKET_HALF_COUNT - 1;
+    let sub_bucket = index - shift * SUB_BUCKET_HALF_COUNT;
+    ((sub_bucket + 1) << shift) - 1
+}
+
+/// A histogram of the most recent latencies, in microseconds.
+#[derive(Debug)]
+struct LatencyWindow {
+    capacity: usize,
+    counts: Vec<u64>,
+    recent: VecDeque<usize>,
+}
+
+impl LatencyWindow {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            counts: Vec::new(),
+            recent: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    fn record(&mut self, latency: Duration) {
+        let index = bucket_index(latency.as_micros().min(u64::MAX as u128) as u64);
+        if self.counts.len() <= index {
+            self.counts.resize(index + 1, 0);
+        }
+        self.counts[index] += 1;
+        self.recent.push_back(index);
+        if self.recent.len() > self.capacity {
+            let oldest = self.recent.pop_front().expect("the window isn't empty");
+            self.counts[oldest] -= 1;
+        }
+    }
+
+    fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.recent.is_empty() {
+            return None;
+        }
+        let percentile = percentile.clamp(0.0, 100.0);
+        let rank = ((percentile / 100.0) * self.recent.len() as f64)
+            .ceil()
+            .max(1.0) as u64;
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Some(Duration::from_micros(highest_value_in_bucket(index)));
+            }
+        }
+        unreachable!("the counts add up to the number of recorded latencies")
+    }
+}
+
+/// Tracks percentiles of the total latency of recent operations.
+///
+/// When registered as a [`RuntimePlugin`], it records how long every operation takes, from the
+/// start of the execution until it completes, according to the
+/// [`TimeSource`](aws_smithy_runtime_api::client::orchestrator::TimeSource). Latencies are kept
+/// in a histogram whose percentiles are accurate to within about 1.6%, and only the most recent
+/// latencies are kept so that the percentiles follow changes in the service's behavior.
+///
+/// The tracker is cheap to clone, and clones share their latencies, so a clone can be kept to
+/// query the percentiles after it's been registered with a client. It's also put in the
+/// [`ConfigBag`] so that other parts of the orchestrator can read it.
+///
+/// With [`adaptive_attempt_timeout`](LatencyTracker::adaptive_attempt_timeout), the tracked
+/// latencies also determine the operation attempt timeout.
+#[derive(Clone)]
+pub struct LatencyTracker {
+    window: Arc<Mutex<LatencyWindow>>,
+    adaptive_attempt_timeout: Option<AdaptiveAttemptTimeout>,
+}
+
+impl LatencyTracker {
+    /// Creates a [`LatencyTracker`] that keeps the latencies of the last 1000 operations.
+    pub fn new() -> Self {
+        Self::with_window(DEFAULT_WINDOW)
+    }
+
+    /// Creates a [`LatencyTracker`] that keeps the latencies of the last `window` operations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is zero.
+    pub fn with_window(window: usize) -> Self {
+        assert!(window > 0, "the latency window must not be empty");
+        Self {
+            window: Arc::new(Mutex::new(LatencyWindow::new(window))),
+            adaptive_attempt_timeout: None,
+        }
+    }
+
+    /// Derives the operation attempt timeout from the tracked latencies, as configured by
+    /// `adaptive`, instead of using the static
+    /// [`operation_attempt_timeout`](aws_smithy_types::timeout::TimeoutConfig::operation_attempt_timeout).
+    pub fn adaptive_attempt_timeout(mut self, adaptive: AdaptiveAttemptTimeout) -> Self {
+        self.adaptive_attempt_timeout = Some(adaptive);
+        self
+    }
+
+    /// Records the latency of an operation.
+    pub fn record(&self, latency: Duration) {
+        self.window.lock().unwrap().record(latency);
+    }
+
+    /// Returns the number of latencies that percentiles are currently computed from.
+    pub fn len(&self) -> usize {
+        self.window.lock().unwrap().recent.len()
+    }
+
+    /// Returns true if no latencies have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the latency that `percentile` percent of the recorded latencies are at or below,
+    /// or `None` if no latencies have been recorded.
+    ///
+    /// `percentile` is clamped to the range `0.0..=100.0`.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        self.window.lock().unwrap().percentile(percentile)
+    }
+
+    /// Returns the median latency, or `None` if no latencies have been recorded.
+    pub fn p50(&self) -> Option<Duration> {
+        self.percentile(50.0)
+    }
+
+    /// Returns the 90th percentile latency, or `None` if no latencies have been recorded.
+    pub fn p90(&self) -> Option<Duration> {
+        self.percentile(90.0)
+    }
+
+    /// Returns the 99th percentile latency, or `None` if no latencies have been recorded.
+    pub fn p99(&self) -> Option<Duration> {
+        self.percentile(99.0)
+    }
+}
+
+impl Default for LatencyTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for LatencyTracker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let window = self.window.lock().unwrap();
+        f.debug_struct("LatencyTracker")
+            .field("window", &window.capacity)
+            .field("len", &window.recent.len())
+            .field("adaptive_attempt_timeout", &self.adaptive_attempt_timeout)
+            .finish()
+    }
+}
+
+impl Storable for LatencyTracker {
+    type Storer = StoreReplace<Self>;
+}
+
+impl RuntimePlugin for LatencyTracker {
+    fn configure(
+        &self,
+        cfg: &mut ConfigBag,
+        interceptors: &mut Interceptors,
+    ) -> Result<(), BoxError> {
+        cfg.store_put(self.clone());
+        if let Some(adaptive) = &self.adaptive_attempt_timeout {
+            cfg.store_put(adaptive.clone());
+        }
+        interceptors.register_client_interceptor(Arc::new(LatencyInterceptor {
+            tracker: self.clone(),
+        }));
+        Ok(())
+    }
+}
+
+/// Computes the operation attempt timeout from a percentile of the latencies in a
+/// [`LatencyTracker`], multiplied by a factor.
+///
+/// A fixed attempt timeout has to be long enough for the slowest requests, so it either cuts off
+/// requests that would have succeeded or leaves a stuck attempt hanging for much longer than
+/// necessary. An adaptive timeout follows the service's latency instead. Until the tracker has
+/// enough latencies for the percentile to be meaningful, the static
+/// [`operation_attempt_timeout`](aws_smithy_types::timeout::TimeoutConfig::operation_attempt_timeout)
+/// is used.
+///
+/// By default, the timeout is twice the 99th percentile latency once 20 latencies were recorded.
+#[derive(Clone, Debug)]
+pub struct AdaptiveAttemptTimeout {
+    percentile: f64,
+    factor: f64,
+    min_samples: usize,
+    min_timeout: Option<Duration>,
+    max_timeout: Option<Duration>,
+}
+
+impl AdaptiveAttemptTimeout {
+    /// Creates an [`AdaptiveAttemptTimeout`] with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the percentile of the tracked latencies that the timeout is computed from.
+    pub fn percentile(mut self, percentile: f64) -> Self {
+        self.percentile = percentile;
+        self
+    }
+
+    /// Sets the factor that the percentile latency is multiplied by.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `factor` is negative or not finite.
+    pub fn factor(mut self, factor: f64) -> Self {
+        assert!(
+            factor.is_finite() && factor >= 0.0,
+            "the adaptive timeout factor must be a non-negative number"
+        );
+        self.factor = factor;
+        self
+    }
+
+    /// Sets how many latencies must be tracked before the adaptive timeout is used.
+    pub fn min_samples(mut self, min_samples: usize) -> Self {
+        self.min_samples = min_samples;
+        self
+    }
+
+    /// Sets the shortest timeout that will be computed.
+    pub fn min_timeout(mut self, min_timeout: Duration) -> Self {
+        self.min_timeout = Some(min_timeout);
+        self
+    }
+
+    /// Sets the longest timeout that will be computed.
+    pub fn max_timeout(mut self, max_timeout: Duration) -> Self {
+        self.max_timeout = Some(max_timeout);
+        self
+    }
+
+    /// Returns the attempt timeout for the latencies in `tracker`, or `None` if it doesn't have
+    /// enough of them yet.
+    pub(crate) fn timeout(&self, tracker: &LatencyTracker) -> Option<Duration> {
+        if tracker.len() < self.min_samples.max(1) {
+            return None;
+        }
+        let mut timeout = tracker.percentile(self.percentile)?.mul_f64(self.factor);
+        if let Some(min_timeout) = self.min_timeout {
+            timeout = timeout.max(min_timeout);
+        }
+        if let Some(max_timeout) = self.max_timeout {
+            timeout = timeout.min(max_timeout);
+        }
+        Some(timeout)
+    }
+}
+
+impl Storable for AdaptiveAttemptTimeout {
+    type Storer = StoreReplace<Self>;
+}
+
+impl Default for AdaptiveAttemptTimeout {
+    fn default() -> Self {
+        Self {
+            percentile: 99.0,
+            factor: 2.0,
+            min_samples: DEFAULT_MIN_SAMPLES,
+            min_timeout: None,
+            max_timeout: None,
+        }
+    }
+}
+
+/// Returns the adaptive attempt timeout if it's enabled in `cfg` and there are enough latencies to
+/// compute it.
+pub(crate) fn adaptive_attempt_timeout(cfg: &ConfigBag) -> Option<Duration> {
+    cfg.load::<AdaptiveAttemptTimeout>()?
+        .timeout(cfg.load::<LatencyTracker>()?)
+}
+
+/// When the current execution started.
+#[derive(Debug)]
+struct ExecutionStart(SystemTime);
+
+impl Storable for ExecutionStart {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Records the latency of every execution in a [`LatencyTracker`].
+#[derive(Debug)]
+struct LatencyInterceptor {
+    tracker: LatencyTracker,
+}
+
+impl Interceptor for LatencyInterceptor {
+    fn read_before_execution(
+        &self,
+        _context: &InterceptorContext,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let now = time_source(cfg).now();
+        cfg.store_put(ExecutionStart(now));
+        Ok(())
+    }
+
+    fn read_after_execution(
+        &self,
+        _context: &InterceptorContext,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        if let Some(ExecutionStart(start)) = cfg.load::<ExecutionStart>() {
+            // A clock that moved backwards gives no useful latency
+            if let Ok(latency) = time_source(cfg).now().duration_since(*start) {
+                self.tracker.record(latency);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_smithy_runtime_api::client::orchestrator::TimeSource;
+    use aws_smithy_runtime_api::type_erasure::TypedBox;
+    use std::time::UNIX_EPOCH;
+
+    /// Asserts that `actual` is within the precision of the histogram's buckets of `expected`.
+    fn assert_close(expected: Duration, actual: Option<Duration>) {
+        let actual = actual.expect("latencies were recorded");
+        let tolerance = expected / (SUB_BUCKET_HALF_COUNT as u32);
+        assert!(
+            actual >= expected && actual <= expected + tolerance,
+            "expected {actual:?} to be within {tolerance:?} above {expected:?}"
+        );
+    }
+
+    #[test]
+    fn bucket_indexes_are_contiguous() {
+        let mut expected_index = 0;
+        for value in 0..1_000_000 {
+            let index = bucket_index(value);
+            if index != expected_index {
+                assert_eq!(expected_index + 1, index, "value {value}");
+                assert_eq!(value - 1, highest_value_in_bucket(expected_index));
+                expected_index = index;
+            }
+        }
+    }
+
+    #[test]
+    fn percentiles_of_known_latencies() {
+        let tracker = LatencyTracker::new();
+        assert_eq!(None, tracker.p50());
+
+        for millis in (1..=100).rev() {
+            tracker.record(Duration::from_millis(millis));
+        }
+
+        assert_eq!(100, tracker.len());
+        assert_close(Duration::from_millis(1), tracker.percentile(0.0));
+        assert_close(Duration::from_millis(50), tracker.p50());
+        assert_close(Duration::from_millis(90), tracker.p90());
+        assert_close(Duration::from_millis(99), tracker.p99());
+        assert_close(Duration::from_millis(100), tracker.percentile(100.0));
+    }
+
+    #[test]
+    fn only_the_most_recent_latencies_are_kept() {
+        let tracker = LatencyTracker::with_window(10);
+        for _ in 0..10 {
+            tracker.record(Duration::from_secs(5));
+        }
+        for _ in 0..10 {
+            tracker.record(Duration::from_millis(20));
+        }
+
+        assert_eq!(10, tracker.len());
+        assert_close(Duration::from_millis(20), tracker.p99());
+    }
+
+    #[derive(Debug)]
+    struct ManualTimeSource(Arc<Mutex<SystemTime>>);
+
+    impl TimeSource for ManualTimeSource {
+        fn now(&self) -> SystemTime {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    #[test]
+    fn the_runtime_plugin_records_execution_latency() {
+        let now = Arc::new(Mutex::new(UNIX_EPOCH));
+        let mut cfg = ConfigBag::base();
+        cfg.store_put::<Box<dyn TimeSource>>(Box::new(ManualTimeSource(now.clone())));
+        let mut interceptors = Interceptors::new();
+        let tracker = LatencyTracker::new();
+        tracker.configure(&mut cfg, &mut interceptors).unwrap();
+        assert!(cfg.load::<LatencyTracker>().is_some());
+
+        let context = InterceptorContext::new(TypedBox::new("doesnt-matter").erase());
+        interceptors
+            .client_read_before_execution(&context, &mut cfg)
+            .unwrap();
+        *now.lock().unwrap() += Duration::from_millis(250);
+        interceptors
+            .read_after_execution(&context, &mut cfg)
+            .unwrap();
+
+        assert_eq!(1, tracker.len());
+        assert_close(Duration::from_millis(250), tracker.p50());
+    }
+}
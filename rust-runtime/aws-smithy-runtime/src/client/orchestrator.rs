@@ -4,17 +4,51 @@
  */
 
 use self::auth::orchestrate_auth;
-use crate::client::orchestrator::endpoints::orchestrate_endpoint;
-use crate::client::orchestrator::http::read_body;
+use crate::client::orchestrator::endpoints::{
+    orchestrate_async_endpoint, render_host_prefix, resolve_endpoint_uri, ConnectionFailures,
+};
+use crate::client::orchestrator::http::{
+    add_request_checksum, check_expectation, check_output_type, check_serialized_request_size,
+    compress_request_body, count_request_body, count_response_body, decompress_body,
+    expects_continue, make_replayable_body, read_body, record_request_body_size,
+    record_request_ids, record_request_line, release_body, set_request_attempt_header,
+    should_buffer, validate_response_checksum,
+};
 use crate::client::orchestrator::phase::Phase;
-use crate::client::timeout::{MaybeTimeout, ProvideMaybeTimeoutConfig, TimeoutKind};
-use aws_smithy_http::result::SdkError;
+use crate::client::orchestrator::redirect::{follow_redirects, save_unsigned_request};
+use crate::client::timeout::{
+    self, time_source, MaybeTimeout, ProvideMaybeTimeoutConfig, TimeoutKind,
+};
+use aws_smithy_http::body::SdkBody;
+use aws_smithy_http::result::{AttemptError, ConnectorError, SdkError};
 use aws_smithy_runtime_api::client::interceptors::context::{Error, Input, Output};
 use aws_smithy_runtime_api::client::interceptors::{InterceptorContext, Interceptors};
-use aws_smithy_runtime_api::client::orchestrator::{BoxError, ConfigBagAccessors, HttpResponse};
-use aws_smithy_runtime_api::client::retries::ShouldAttempt;
+use aws_smithy_runtime_api::client::orchestrator::{
+    AttemptRetryReason, BeforeRetryCallback, BoxError, BufferPool, ByteCounts, CancellationSignal,
+    ClassifyClockSkew, ClockSkew, ConcurrencyHint, ConfigBagAccessors, Connection,
+    ConnectionConfig, ConnectionSelector, CorrectClockSkew, DnsTiming, EventStreamOperation,
+    FinalAttempt, HttpRequest, HttpResponse, KeepAliveConfig, MaxRetryDuration,
+    NonRetryableOperations, OperationCancelled, OperationDeadline, OperationId, ReplayableBody,
+    RequestAttempt, ResponseCaching, RetainInput, RetryConcurrencyLimiter, SigningTime,
+    StatusDeserializers,
+};
+use aws_smithy_runtime_api::client::retries::rate_limiting::error::RateLimitingError;
+use aws_smithy_runtime_api::client::retries::rate_limiting::{
+    token, token_bucket, Token, TokenBucket,
+};
+use aws_smithy_runtime_api::client::retries::{
+    BackoffStrategy, ClassifyRetry, RetryClassifiers, RetryReason, ShouldAttempt,
+};
 use aws_smithy_runtime_api::client::runtime_plugin::RuntimePlugins;
-use aws_smithy_runtime_api::config_bag::ConfigBag;
+use aws_smithy_runtime_api::config_bag::{ConfigBag, FrozenConfigBag, Storable, StoreReplace};
+use aws_smithy_runtime_api::type_erasure::TypedBox;
+use aws_smithy_types::date_time::Format;
+use aws_smithy_types::retry::{ErrorKind, RetryKind};
+use aws_smithy_types::DateTime;
+use futures_util::stream::{self, Stream, StreamExt};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use tracing::{debug_span, Instrument};
 
 mod auth;
@@ -22,7 +56,17 @@ mod auth;
 pub mod endpoints;
 mod http;
 pub(self) mod phase;
+mod redirect;
+/// Utilities for testing interceptors without sending requests
+#[cfg(any(feature = "test-util", test))]
+pub mod test_util;
 
+/// Invokes an operation with the given `input`, configured by the `runtime_plugins`.
+///
+/// The operation timeout, if any, starts once the runtime plugins have been applied. Applying
+/// the client and operation configuration doesn't count against it, but everything after that
+/// does: serialization, every attempt (including endpoint resolution, identity resolution, and
+/// signing), the delays between attempts, and deserialization.
 pub async fn invoke(
     input: Input,
     runtime_plugins: &RuntimePlugins,
@@ -32,6 +76,67 @@ pub async fn invoke(
         .await
 }
 
+/// Invokes an operation like [`invoke`], converting the error it fails with using `map_error`.
+///
+/// This lets callers convert every kind of [`SdkError`], such as timeouts, dispatch failures, and
+/// service errors, into their own error type in one place, rather than wherever an operation is
+/// invoked. `map_error` is given the complete error, so once its type-erased service error is
+/// converted with [`SdkError::map_service_error`], it can be kept as the source of the new error
+/// to preserve the source chain.
+pub async fn invoke_with_error_map<E>(
+    input: Input,
+    runtime_plugins: &RuntimePlugins,
+    map_error: impl FnOnce(SdkError<Error, HttpResponse>) -> E,
+) -> Result<Output, E> {
+    invoke(input, runtime_plugins).await.map_err(map_error)
+}
+
+/// Sends an already serialized and signed `request`, such as one captured from a previous
+/// invocation, and deserializes its response.
+///
+/// This is intended for reproducing the behavior of a captured request offline, e.g. against a
+/// mock connection. Serialization, endpoint resolution, signing, and retries are skipped, so only
+/// the interceptor hooks from `modify_before_transmit` onwards run, except for the per-attempt
+/// hooks. The `runtime_plugins` must still configure the connection and response deserializer.
+pub async fn replay(
+    request: HttpRequest,
+    runtime_plugins: &RuntimePlugins,
+) -> Result<Output, SdkError<Error, HttpResponse>> {
+    replay_inner(request, runtime_plugins)
+        .instrument(debug_span!("replay"))
+        .await
+}
+
+async fn replay_inner(
+    request: HttpRequest,
+    runtime_plugins: &RuntimePlugins,
+) -> Result<Output, SdkError<Error, HttpResponse>> {
+    let mut cfg = ConfigBag::base();
+    let cfg = &mut cfg;
+    let mut interceptors = Interceptors::new();
+
+    let context = InterceptorContext::from_request(request);
+
+    let context = Phase::construction(context)
+        .include(|_| runtime_plugins.apply_client_configuration(cfg, &mut interceptors))?
+        .include(|_| runtime_plugins.apply_operation_configuration(cfg, &mut interceptors))?
+        .finish();
+    let context = Phase::dispatch(context)
+        .include_mut(|ctx| interceptors.modify_before_transmit(ctx, cfg))?
+        .include(|ctx| interceptors.read_before_transmit(ctx, cfg))?
+        .finish();
+    let context = transmit(context, cfg, &interceptors, false).await?.finish();
+
+    Phase::response_handling(context)
+        .include_mut(|ctx| interceptors.modify_before_completion(ctx, cfg))?
+        .include(|ctx| interceptors.read_after_execution(ctx, cfg))?
+        .include_mut(|ctx| {
+            release_body(ctx);
+            Result::<_, BoxError>::Ok(())
+        })?
+        .finalize()
+}
+
 async fn invoke_pre_config(
     input: Input,
     runtime_plugins: &RuntimePlugins,
@@ -40,20 +145,112 @@ async fn invoke_pre_config(
     let cfg = &mut cfg;
 
     let mut interceptors = Interceptors::new();
+    let context = apply_configuration(
+        InterceptorContext::new(input),
+        cfg,
+        &mut interceptors,
+        runtime_plugins,
+    )?;
+    invoke_configured(cfg, context, interceptors).await
+}
 
+/// Invokes an operation for every one of the `inputs`, running at most `concurrency_limit` of
+/// them at once, and yields their results in the same order as the inputs.
+///
+/// The client configuration is applied once, up front, and shared by every operation, so only the
+/// operation configuration is applied for each of them. Apart from that, each operation goes
+/// through the same steps as it would with [`invoke`], and fails on its own without affecting the
+/// others.
+///
+/// Fails without invoking any operation if `concurrency_limit` is zero, or if the client
+/// configuration can't be applied.
+pub fn invoke_all<'a>(
+    inputs: impl IntoIterator<Item = Input> + 'a,
+    runtime_plugins: &'a RuntimePlugins,
+    concurrency_limit: usize,
+) -> Result<
+    impl Stream<Item = Result<Output, SdkError<Error, HttpResponse>>> + 'a,
+    SdkError<Error, HttpResponse>,
+> {
+    if concurrency_limit == 0 {
+        return Err(SdkError::construction_failure(
+            "the concurrency limit must allow at least one operation to run at a time",
+        ));
+    }
+    let mut client_cfg = ConfigBag::base();
+    let mut client_interceptors = Interceptors::new();
+    runtime_plugins
+        .apply_client_configuration(&mut client_cfg, &mut client_interceptors)
+        .map_err(SdkError::construction_failure)?;
+    let client_cfg = client_cfg.freeze();
+
+    Ok(stream::iter(inputs)
+        .map(move |input| {
+            let client_cfg = client_cfg.clone();
+            let client_interceptors = client_interceptors.clone();
+            async move {
+                invoke_with_client_config(input, runtime_plugins, &client_cfg, client_interceptors)
+                    .instrument(debug_span!("invoke"))
+                    .await
+            }
+        })
+        .buffered(concurrency_limit))
+}
+
+/// Invokes an operation like [`invoke`], but with client configuration that has already been
+/// applied.
+async fn invoke_with_client_config(
+    input: Input,
+    runtime_plugins: &RuntimePlugins,
+    client_cfg: &FrozenConfigBag,
+    mut interceptors: Interceptors,
+) -> Result<Output, SdkError<Error, HttpResponse>> {
+    let mut cfg = client_cfg.add_layer("operation");
+    let cfg = &mut cfg;
     let context = Phase::construction(InterceptorContext::new(input))
-        // Client configuration
-        .include(|_| runtime_plugins.apply_client_configuration(cfg, &mut interceptors))?
         .include(|ctx| interceptors.client_read_before_execution(ctx, cfg))?
-        // Operation configuration
         .include(|_| runtime_plugins.apply_operation_configuration(cfg, &mut interceptors))?
         .include(|ctx| interceptors.operation_read_before_execution(ctx, cfg))?
         .finish();
+    invoke_configured(cfg, context, interceptors).await
+}
 
+/// Invokes an operation whose client and operation configuration have been applied.
+async fn invoke_configured(
+    cfg: &mut ConfigBag,
+    context: InterceptorContext,
+    interceptors: Interceptors,
+) -> Result<Output, SdkError<Error, HttpResponse>> {
+    // The operation timeout clock starts here, once the client and operation configuration have
+    // been applied. Everything that follows, starting with serialization, counts against it.
     let operation_timeout_config = cfg.maybe_timeout_config(TimeoutKind::Operation);
+    // Record when the operation timeout will elapse, so that interceptors can tell how much of
+    // the operation's budget is left
+    if let Some(operation_timeout) = operation_timeout_config.timeout() {
+        let deadline = time_source(cfg).now() + operation_timeout;
+        cfg.store_put(OperationDeadline::new(deadline));
+    }
     invoke_post_config(cfg, context, interceptors)
         .maybe_timeout_with_config(operation_timeout_config)
         .await
+        // Attached outside of the operation timeout, so that they aren't lost when it elapses
+        .map_err(|err| attach_attempt_errors(err, cfg))
+}
+
+fn apply_configuration(
+    context: InterceptorContext,
+    cfg: &mut ConfigBag,
+    interceptors: &mut Interceptors,
+    runtime_plugins: &RuntimePlugins,
+) -> Result<InterceptorContext, SdkError<Error, HttpResponse>> {
+    Ok(Phase::construction(context)
+        // Client configuration
+        .include(|_| runtime_plugins.apply_client_configuration(cfg, interceptors))?
+        .include(|ctx| interceptors.client_read_before_execution(ctx, cfg))?
+        // Operation configuration
+        .include(|_| runtime_plugins.apply_operation_configuration(cfg, interceptors))?
+        .include(|ctx| interceptors.operation_read_before_execution(ctx, cfg))?
+        .finish())
 }
 
 async fn invoke_post_config(
@@ -61,23 +258,97 @@ async fn invoke_post_config(
     context: InterceptorContext,
     interceptors: Interceptors,
 ) -> Result<Output, SdkError<Error, HttpResponse>> {
-    let context = Phase::construction(context)
-        // Before serialization
-        .include(|ctx| interceptors.read_before_serialization(ctx, cfg))?
-        .include_mut(|ctx| interceptors.modify_before_serialization(ctx, cfg))?
-        // Serialization
-        .include_mut(|ctx| {
-            let request_serializer = cfg.request_serializer();
-            let request = request_serializer
-                .serialize_input(ctx.take_input().expect("input set at this point"))?;
-            ctx.set_request(request);
-            Result::<(), BoxError>::Ok(())
-        })?
-        // After serialization
-        .include(|ctx| interceptors.read_after_serialization(ctx, cfg))?
-        // Before retry loop
-        .include_mut(|ctx| interceptors.modify_before_retry_loop(ctx, cfg))?
-        .finish();
+    // The cache key is computed from the input, so it has to be done before serialization
+    let cache_key = match (cfg.load::<ResponseCaching>(), context.input()) {
+        // The output of an event stream is a live stream, which can't be shared
+        _ if is_event_stream_operation(cfg) => None,
+        (Some(response_caching), Ok(input)) => response_caching.cache_key(input),
+        _ => None,
+    };
+    if let (Some(response_caching), Some(cache_key)) = (cfg.load::<ResponseCaching>(), &cache_key) {
+        if let Some(output) = response_caching
+            .cache()
+            .get(cache_key, time_source(cfg).now())
+        {
+            tracing::debug!(cache_key, "returning a cached response");
+            return Ok(output);
+        }
+    }
+
+    let output = invoke_attempts(cfg, context, interceptors).await;
+    if let (Some(response_caching), Some(cache_key), Ok(output)) =
+        (cfg.load::<ResponseCaching>(), cache_key, &output)
+    {
+        response_caching
+            .cache()
+            .put(cache_key, output, time_source(cfg).now());
+    }
+    output
+}
+
+/// The errors of the attempts that were retried so far, in order.
+#[derive(Clone, Debug, Default)]
+struct AttemptErrors(Vec<AttemptError>);
+
+impl Storable for AttemptErrors {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Records the error of the previous attempt, if it failed, before it's discarded by a retry.
+fn record_attempt_error(attempt: u32, context: &mut InterceptorContext, cfg: &mut ConfigBag) {
+    let status = context.response_status();
+    let err = match context.take_output_or_error() {
+        Some(Err(err)) => err,
+        _ => return,
+    };
+    let mut attempt_errors = cfg.load::<AttemptErrors>().cloned().unwrap_or_default();
+    attempt_errors
+        .0
+        .push(AttemptError::new(attempt, status, into_attempt_source(err)));
+    cfg.store_put(attempt_errors);
+}
+
+/// Converts an attempt's type-erased error back into the error it was created from.
+fn into_attempt_source(err: Error) -> BoxError {
+    let err = match err.downcast::<ConnectorError>() {
+        Ok(connector_error) => return connector_error,
+        Err(err) => err,
+    };
+    match err.downcast::<BoxError>() {
+        Ok(err) => *err,
+        Err(err) => Box::new(ModeledError(err)),
+    }
+}
+
+/// A modeled error, which is only known to be `Debug` once it's been type-erased.
+#[derive(Debug)]
+struct ModeledError(Error);
+
+impl fmt::Display for ModeledError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+impl std::error::Error for ModeledError {}
+
+/// Attaches the errors of the attempts that were retried to the error the operation failed with.
+fn attach_attempt_errors(
+    err: SdkError<Error, HttpResponse>,
+    cfg: &ConfigBag,
+) -> SdkError<Error, HttpResponse> {
+    match cfg.load::<AttemptErrors>() {
+        Some(attempt_errors) => err.with_attempt_errors(attempt_errors.0.clone()),
+        None => err,
+    }
+}
+
+async fn invoke_attempts(
+    cfg: &mut ConfigBag,
+    context: InterceptorContext,
+    interceptors: Interceptors,
+) -> Result<Output, SdkError<Error, HttpResponse>> {
+    let context = serialize_input(context, cfg, &interceptors)?;
 
     {
         let retry_strategy = cfg.retry_strategy();
@@ -99,75 +370,580 @@ async fn invoke_post_config(
     }
 
     let mut context = context;
+    checkpoint_request(&mut context, cfg);
+    if cfg
+        .load::<ConnectionConfig>()
+        .map(ConnectionConfig::prewarm)
+        .unwrap_or_default()
+    {
+        prewarm_connection(&context, cfg)
+            .instrument(debug_span!("prewarm_connection"))
+            .await;
+    }
+    let mut attempt: u32 = 0;
+    // Held from before waiting to retry until the retry attempt completes
+    let mut retry_permit = None;
+    let mut retry_token = None;
+    let first_attempt_started_at = time_source(cfg).now();
+    let mut connection_failures = 0;
     let handling_phase = loop {
+        attempt += 1;
+        let fresh_connection = cfg
+            .load::<ConnectionConfig>()
+            .map(|config| config.fresh_connection_after_dispatch_failure())
+            .unwrap_or_default()
+            && dispatch_failed(&context);
+        if dispatch_failed(&context) {
+            connection_failures += 1;
+            cfg.store_put(ConnectionFailures(connection_failures));
+        }
+        // The previous attempt is being retried, so its error is kept for the final error
+        record_attempt_error(attempt - 1, &mut context, cfg);
+        // Whether the request can be rewound was checked before the retry was made
+        context.rewind();
+
         let attempt_timeout_config = cfg.maybe_timeout_config(TimeoutKind::OperationAttempt);
-        let dispatch_phase = Phase::dispatch(context);
-        context = make_an_attempt(dispatch_phase, cfg, &interceptors)
-            .instrument(debug_span!("make_an_attempt"))
+        context = make_an_attempt(attempt, context, cfg, &interceptors, fresh_connection)
+            .instrument(debug_span!(
+                "make_an_attempt",
+                request_body_size = tracing::field::Empty,
+                http.method = tracing::field::Empty,
+                http.path = tracing::field::Empty,
+                request_id = tracing::field::Empty,
+                extended_request_id = tracing::field::Empty
+            ))
             .maybe_timeout_with_config(attempt_timeout_config)
             .await?
             .include(|ctx| interceptors.read_after_attempt(ctx, cfg))?
             .include_mut(|ctx| interceptors.modify_before_attempt_completion(ctx, cfg))?
             .finish();
+        let retry_reason = classify_attempt(&context, cfg);
+        cfg.store_put(AttemptRetryReason::new(retry_reason));
+        // Release the permit before waiting for the next one, so that limiters with a single
+        // permit don't deadlock
+        drop(retry_permit.take());
+        let succeeded = matches!(context.output_or_error(), Ok(Ok(_)));
+        settle_retry_token(retry_token.take(), attempt, succeeded, cfg);
 
-        let retry_strategy = cfg.retry_strategy();
-        match retry_strategy.should_attempt_retry(&context, cfg) {
-            // Yes, let's retry the request
-            Ok(ShouldAttempt::Yes) => continue,
+        let should_attempt = if is_non_retryable_operation(cfg) || is_event_stream_operation(cfg) {
+            Ok(ShouldAttempt::No)
+        } else {
+            // If the retry strategy retries a clock skew rejection, the retry is signed at the
+            // corrected time
+            correct_clock_skew(&context, cfg);
+            // The strategy is looked up for every attempt, since interceptors may have replaced it
+            cfg.retry_strategy().should_attempt_retry(&context, cfg)
+        };
+        let delay = match should_attempt {
+            // Yes, let's retry the request after the backoff strategy's delay (if there is one)
+            Ok(ShouldAttempt::Yes) => Some(
+                cfg.load::<Box<dyn BackoffStrategy>>()
+                    .map(|backoff_strategy| backoff_strategy.backoff(attempt, cfg))
+                    .unwrap_or_default(),
+            ),
+            // Yes, let's retry the request after the delay the retry strategy asked for
+            Ok(ShouldAttempt::YesAfterDelay(delay)) => Some(delay),
             // No, this request shouldn't be retried
-            Ok(ShouldAttempt::No) => {}
-            Ok(ShouldAttempt::YesAfterDelay(_delay)) => {
-                todo!("implement retries with an explicit delay.")
-            }
+            Ok(ShouldAttempt::No) => None,
             // I couldn't determine if the request should be retried because an error occurred.
             Err(err) => {
                 return Err(Phase::response_handling(context).fail(err));
             }
+        };
+        let delay =
+            delay.filter(|delay| within_retry_duration(first_attempt_started_at, *delay, cfg));
+        let delay = match delay {
+            Some(delay) => match acquire_retry_token(cfg) {
+                Ok(token) => {
+                    retry_token = token;
+                    Some(delay)
+                }
+                // Retrying while the bucket is empty would feed a retry storm
+                Err(err) => {
+                    tracing::debug!(
+                        error = %err,
+                        "not retrying because the retry token bucket is empty"
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
+        let delay = delay.filter(|delay| {
+            let approved = cfg
+                .load::<Box<dyn BeforeRetryCallback>>()
+                .map_or(true, |callback| callback.before_retry(*delay, attempt + 1));
+            if !approved {
+                tracing::debug!("not retrying because the retry was vetoed");
+                // The retry won't be made, so the tokens paid for it are returned
+                if let Some(retry_token) = retry_token.take() {
+                    retry_token.release();
+                }
+            }
+            approved
+        });
+        if let Some(delay) = delay {
+            // Check that the request can be sent again before waiting to retry it, so that the
+            // failure keeps this attempt's response
+            if !context.can_rewind() {
+                if let Some(retry_token) = retry_token.take() {
+                    retry_token.release();
+                }
+                return Err(Phase::dispatch(context).fail(
+                    "the request can't be retried because its body is a one-shot stream that was \
+                    consumed by the previous attempt; use a body that can be replayed (e.g. \
+                    `SdkBody::retryable`) to enable retries",
+                ));
+            }
+            // The response of this attempt is discarded, so its buffer can be reused right away
+            release_body(&mut context);
+            // The attempt may have been rejected because the cached credentials expired, in
+            // which case retrying with them would fail again
+            if matches!(context.response_status(), Some(401) | Some(403)) {
+                cfg.identity_resolvers().invalidate_cached_identities();
+            }
+            // Between attempts is a safe point to stop at, so check for cancellation on both
+            // sides of the (potentially long) delay
+            check_cancellation(cfg)?;
+            if let Some(limiter) = cfg.load::<RetryConcurrencyLimiter>().cloned() {
+                retry_permit = Some(limiter.acquire().await);
+            }
+            if let Err(err) = sleep_before_retry(cfg, delay).await {
+                return Err(Phase::response_handling(context).fail(err));
+            }
+            check_cancellation(cfg)?;
+            continue;
         }
 
         let handling_phase = Phase::response_handling(context)
             .include_mut(|ctx| interceptors.modify_before_completion(ctx, cfg))?;
         cfg.trace_probe().dispatch_events();
 
-        break handling_phase.include(|ctx| interceptors.read_after_execution(ctx, cfg))?;
+        break handling_phase
+            .include(|ctx| interceptors.read_after_execution(ctx, cfg))?
+            .include_mut(|ctx| {
+                release_body(ctx);
+                Result::<_, BoxError>::Ok(())
+            })?;
     };
 
     handling_phase.finalize()
 }
 
-// Making an HTTP request can fail for several reasons, but we still need to
-// call lifecycle events when that happens. Therefore, we define this
-// `make_an_attempt` function to make error handling simpler.
-async fn make_an_attempt(
+/// Returns `false` if retrying after `delay` would take the time spent since the first attempt
+/// started past the [`MaxRetryDuration`].
+fn within_retry_duration(
+    first_attempt_started_at: SystemTime,
+    delay: Duration,
+    cfg: &ConfigBag,
+) -> bool {
+    let max_retry_duration = match cfg.load::<MaxRetryDuration>() {
+        Some(max_retry_duration) => max_retry_duration.duration(),
+        None => return true,
+    };
+    let elapsed = time_source(cfg)
+        .now()
+        .duration_since(first_attempt_started_at)
+        .unwrap_or_default();
+    if elapsed + delay > max_retry_duration {
+        tracing::debug!(
+            elapsed = ?elapsed,
+            delay = ?delay,
+            max_retry_duration = ?max_retry_duration,
+            "not retrying because the retry duration budget would be exceeded"
+        );
+        return false;
+    }
+    true
+}
+
+/// Skews up to this size are within what services tolerate, so they aren't corrected.
+const CLOCK_SKEW_THRESHOLD: Duration = Duration::from_secs(4 * 60);
+
+/// If the attempt was rejected because of clock skew, measures the skew from the `Date` header of
+/// the response and stores it in the `cfg` for the next attempt to be signed with. Whether that
+/// attempt is made is still up to the retry strategy.
+fn correct_clock_skew(context: &InterceptorContext, cfg: &mut ConfigBag) {
+    if !cfg
+        .load::<CorrectClockSkew>()
+        .map(CorrectClockSkew::enabled)
+        .unwrap_or_default()
+    {
+        return;
+    }
+    let is_clock_skew_error = match (
+        context.output_or_error(),
+        cfg.load::<Box<dyn ClassifyClockSkew>>(),
+    ) {
+        (Ok(Err(error)), Some(classifier)) => classifier.is_clock_skew_error(error),
+        _ => false,
+    };
+    let response = match context.response() {
+        Ok(response) if is_clock_skew_error => response,
+        _ => return,
+    };
+    let server_time = response
+        .headers()
+        .get(::http::header::DATE)
+        .and_then(|date| date.to_str().ok())
+        .and_then(|date| DateTime::from_str(date, Format::HttpDate).ok())
+        .and_then(|date| SystemTime::try_from(date).ok());
+    let server_time = match server_time {
+        Some(server_time) => server_time,
+        None => {
+            tracing::debug!("can't correct the clock skew without a valid `Date` response header");
+            return;
+        }
+    };
+    let local_time = time_source(cfg).now();
+    let clock_skew = match server_time.duration_since(local_time) {
+        Ok(behind) if behind > CLOCK_SKEW_THRESHOLD => ClockSkew::Behind(behind),
+        Err(ahead) if ahead.duration() > CLOCK_SKEW_THRESHOLD => ClockSkew::Ahead(ahead.duration()),
+        _ => {
+            tracing::debug!("not correcting the clock skew, since it's within the threshold");
+            return;
+        }
+    };
+    tracing::debug!(clock_skew = ?clock_skew, "correcting the clock skew");
+    cfg.store_put(clock_skew);
+}
+
+/// Returns `true` if the operation being invoked is an [`EventStreamOperation`].
+fn is_event_stream_operation(cfg: &ConfigBag) -> bool {
+    cfg.load::<EventStreamOperation>()
+        .map_or(false, EventStreamOperation::enabled)
+}
+
+/// Returns `true` if the operation being invoked is one of the [`NonRetryableOperations`], in
+/// which case the retry strategy isn't consulted.
+fn is_non_retryable_operation(cfg: &ConfigBag) -> bool {
+    match (
+        cfg.load::<NonRetryableOperations>(),
+        cfg.load::<OperationId>(),
+    ) {
+        (Some(non_retryable_operations), Some(operation_id)) => {
+            non_retryable_operations.contains(operation_id)
+        }
+        _ => false,
+    }
+}
+
+/// Classifies the error of the attempt in `context` with the
+/// [`retry_classifiers`](ConfigBagAccessors::retry_classifiers), if there are any.
+fn classify_attempt(context: &InterceptorContext, cfg: &ConfigBag) -> Option<RetryReason> {
+    match (context.output_or_error(), cfg.get::<RetryClassifiers>()) {
+        (Ok(Err(error)), Some(retry_classifiers)) => retry_classifiers.classify_retry(error),
+        _ => None,
+    }
+}
+
+/// Takes the tokens that retrying the failed attempt costs from the retry
+/// [token bucket](token_bucket::Standard), if there is one.
+fn acquire_retry_token(cfg: &ConfigBag) -> Result<Option<token::Standard>, RateLimitingError> {
+    let token_bucket = match cfg.load::<token_bucket::Standard>() {
+        Some(token_bucket) => token_bucket,
+        None => return Ok(None),
+    };
+    let retry_kind = match cfg
+        .load::<AttemptRetryReason>()
+        .and_then(AttemptRetryReason::retry_reason)
+    {
+        Some(RetryReason::Explicit(delay)) => RetryKind::Explicit(*delay),
+        Some(RetryReason::Error(kind)) if *kind != ErrorKind::ClientError => {
+            RetryKind::Error(*kind)
+        }
+        // The retry strategy retried an error that isn't classified as retryable, so charge it
+        // as a server error
+        _ => RetryKind::Error(ErrorKind::ServerError),
+    };
+    token_bucket.try_acquire(Some(retry_kind)).map(Some)
+}
+
+/// Returns the tokens spent on a retry to the bucket if the retry succeeded, and tops the bucket
+/// up if the first attempt succeeded.
+fn settle_retry_token(
+    retry_token: Option<token::Standard>,
+    attempt: u32,
+    succeeded: bool,
+    cfg: &ConfigBag,
+) {
+    match (retry_token, succeeded) {
+        (Some(retry_token), true) => retry_token.release(),
+        (Some(retry_token), false) => retry_token.forget(),
+        (None, true) if attempt == 1 => {
+            if let Some(token_bucket) = cfg.load::<token_bucket::Standard>() {
+                token_bucket.refill(token_bucket.success_on_first_try_refill_amount());
+            }
+        }
+        (None, _) => {}
+    }
+}
+
+fn check_cancellation(cfg: &ConfigBag) -> Result<(), SdkError<Error, HttpResponse>> {
+    match cfg.load::<Box<dyn CancellationSignal>>() {
+        Some(signal) if signal.is_cancelled() => Err(SdkError::dispatch_failure(
+            ConnectorError::other(OperationCancelled::default().into(), None),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn serialize_input(
+    context: InterceptorContext,
+    cfg: &mut ConfigBag,
+    interceptors: &Interceptors,
+) -> Result<InterceptorContext, SdkError<Error, HttpResponse>> {
+    Ok(Phase::construction(context)
+        .observe(cfg)
+        // Before serialization
+        .include(|ctx| interceptors.read_before_serialization(ctx, cfg))?
+        .include_mut(|ctx| interceptors.modify_before_serialization(ctx, cfg))?
+        // Serialization. This only happens once, so that values generated by the serializer
+        // (e.g. idempotency tokens) are the same for every attempt.
+        .include_mut(|ctx| {
+            render_host_prefix(ctx.input()?, cfg)?;
+            if let Some(retain_input) = cfg.load::<RetainInput>() {
+                if let Some(input) = retain_input.clone_input(ctx.input()?) {
+                    ctx.set_original_input(input);
+                }
+            }
+            let request_serializer = cfg.request_serializer();
+            let request = request_serializer
+                .serialize_input(ctx.take_input().expect("input set at this point"))?;
+            check_serialized_request_size(&request, cfg)?;
+            ctx.set_request(request);
+            Result::<(), BoxError>::Ok(())
+        })?
+        // After serialization
+        .include(|ctx| interceptors.read_after_serialization(ctx, cfg))?
+        // Before retry loop
+        .include_mut(|ctx| interceptors.modify_before_retry_loop(ctx, cfg))?
+        .finish())
+}
+
+// Runs everything in an attempt that happens before the request is handed to the connection:
+// endpoint resolution, signing, and the interceptor hooks surrounding them.
+async fn prepare_transmit(
     dispatch_phase: Phase,
     cfg: &mut ConfigBag,
     interceptors: &Interceptors,
-) -> Result<Phase, SdkError<Error, HttpResponse>> {
-    let dispatch_phase = dispatch_phase
-        .include(|ctx| interceptors.read_before_attempt(ctx, cfg))?
-        .include_mut(|ctx| orchestrate_endpoint(ctx, cfg))?
+) -> Result<InterceptorContext, SdkError<Error, HttpResponse>> {
+    let dispatch_phase =
+        dispatch_phase.include(|ctx| interceptors.read_before_attempt(ctx, cfg))?;
+    let dispatch_phase = orchestrate_async_endpoint(dispatch_phase, cfg)
+        .await?
+        // Compress the body before signing, since the signature may cover it, and checksum it
+        // as it will be sent
+        .include_mut(|ctx| compress_request_body(ctx, cfg))?
+        .include_mut(|ctx| add_request_checksum(ctx, cfg))?
         .include_mut(|ctx| interceptors.modify_before_signing(ctx, cfg))?
-        .include(|ctx| interceptors.read_before_signing(ctx, cfg))?;
+        .include(|ctx| interceptors.read_before_signing(ctx, cfg))?
+        .include(|ctx| save_unsigned_request(ctx, cfg))?;
 
-    let dispatch_phase = orchestrate_auth(dispatch_phase, cfg).await?;
+    record_signing_time(cfg);
+    let auth_timeout_config = cfg.maybe_timeout_config(TimeoutKind::Auth);
+    let dispatch_phase = orchestrate_auth(dispatch_phase, cfg)
+        .maybe_timeout_with_config(auth_timeout_config)
+        .await?;
 
-    let mut context = dispatch_phase
+    Ok(dispatch_phase
         .include(|ctx| interceptors.read_after_signing(ctx, cfg))?
         .include_mut(|ctx| interceptors.modify_before_transmit(ctx, cfg))?
         .include(|ctx| interceptors.read_before_transmit(ctx, cfg))?
-        .finish();
+        .finish())
+}
+
+/// Records the time that the request of the current attempt is signed at, so that signers and
+/// `read_after_signing` hooks agree on it.
+fn record_signing_time(cfg: &mut ConfigBag) {
+    let raw = match cfg.request_time() {
+        Some(request_time) => request_time.system_time(),
+        None => time_source(cfg).now(),
+    };
+    let signing_time = SigningTime::new(raw, cfg.load::<ClockSkew>().copied());
+    tracing::trace!(signing_time = ?signing_time, "recorded the signing time");
+    cfg.store_put(signing_time);
+}
+
+// Asks the connection to connect to the request's endpoint ahead of the first attempt. Failures
+// are ignored, since the first attempt will run into them again and report them.
+async fn prewarm_connection(context: &InterceptorContext, cfg: &ConfigBag) {
+    let uri = match resolve_endpoint_uri(context, cfg).await {
+        Ok(uri) => uri,
+        Err(err) => {
+            tracing::debug!(error = %err, "failed to resolve the endpoint to prewarm");
+            return;
+        }
+    };
+    if let Err(err) = attempt_connection(cfg, 1).prewarm(&uri).await {
+        tracing::debug!(error = %err, "failed to prewarm a connection to {uri}");
+    }
+}
+
+/// Returns `true` if the last attempt failed before a response was received.
+fn dispatch_failed(context: &InterceptorContext) -> bool {
+    matches!(
+        context.output_or_error(),
+        Ok(Err(err)) if err.downcast_ref::<ConnectorError>().is_some()
+    )
+}
+
+async fn sleep_before_retry(cfg: &ConfigBag, delay: Duration) -> Result<(), BoxError> {
+    if delay.is_zero() {
+        return Ok(());
+    }
+    timeout::sleep(cfg, delay)
+        .ok_or("a sleep implementation is required to wait before retrying a request")?
+        .await;
+    Ok(())
+}
+
+/// Saves a copy of the serialized request, so that it can be restored for every attempt after the
+/// first.
+fn checkpoint_request(context: &mut InterceptorContext, cfg: &ConfigBag) {
+    // A replayable body is generated for every attempt, so the serialized body doesn't need to be
+    // kept around. An empty body is checkpointed in its place.
+    if cfg.load::<ReplayableBody>().is_some() {
+        if let Ok(request) = context.request_mut() {
+            *request.body_mut() = SdkBody::empty();
+        }
+    }
+    context.save_checkpoint();
+}
+
+/// Takes attempt number `attempt` up to the point where its request is handed to the connection.
+///
+/// This is shared with [`test_util::invoke_until_transmit`], so that the request it returns is
+/// prepared exactly like the request of an attempt.
+async fn prepare_attempt(
+    attempt: u32,
+    context: InterceptorContext,
+    cfg: &mut ConfigBag,
+    interceptors: &Interceptors,
+) -> Result<InterceptorContext, SdkError<Error, HttpResponse>> {
+    // Recorded for every attempt so that, once the operation completes, it identifies the
+    // attempt that produced the final response
+    cfg.store_put(RequestAttempt::new(attempt));
+    let final_attempt = matches!(
+        cfg.retry_strategy().max_attempts(),
+        Some(max_attempts) if attempt >= max_attempts
+    );
+    cfg.store_put(FinalAttempt::new(final_attempt));
+    // The counts are summed across attempts, so they're only initialized by the first one
+    if cfg.load::<ByteCounts>().is_none() {
+        cfg.store_put(ByteCounts::new());
+    }
+    let dispatch_phase = Phase::dispatch(context)
+        .observe(cfg)
+        .include_mut(|ctx| set_request_attempt_header(ctx, cfg))?
+        .include_mut(|ctx| make_replayable_body(ctx, cfg))?;
+    let context = prepare_transmit(dispatch_phase, cfg, interceptors).await?;
+    if let Ok(request) = context.request() {
+        record_request_body_size(request);
+        record_request_line(request, cfg);
+    }
+    Ok(context)
+}
 
-    // The connection consumes the request but we need to keep a copy of it
-    // within the interceptor context, so we clone it here.
-    let call_result = {
-        let request = context.take_request().expect("request has been set");
-        let connection = cfg.connection();
+// Making an HTTP request can fail for several reasons, but we still need to
+// call lifecycle events when that happens. Therefore, we define this
+// `make_an_attempt` function to make error handling simpler.
+async fn make_an_attempt(
+    attempt: u32,
+    context: InterceptorContext,
+    cfg: &mut ConfigBag,
+    interceptors: &Interceptors,
+    fresh_connection: bool,
+) -> Result<Phase, SdkError<Error, HttpResponse>> {
+    let context = prepare_attempt(attempt, context, cfg, interceptors).await?;
+    transmit(context, cfg, interceptors, fresh_connection).await
+}
+
+/// Returns the connection that attempt number `attempt` is sent over.
+fn attempt_connection(cfg: &ConfigBag, attempt: u32) -> &dyn Connection {
+    cfg.load::<Box<dyn ConnectionSelector>>()
+        .and_then(|connection_selector| connection_selector.select(attempt))
+        .unwrap_or_else(|| cfg.connection())
+}
+
+async fn call_connection(
+    request: HttpRequest,
+    fresh_connection: bool,
+    cfg: &ConfigBag,
+) -> Result<HttpResponse, BoxError> {
+    // Requests that are replayed aren't part of an attempt, so they're treated as the first one
+    let attempt = cfg
+        .load::<RequestAttempt>()
+        .map(|attempt| attempt.attempt())
+        .unwrap_or(1);
+    let connection = attempt_connection(cfg, attempt);
+    if fresh_connection {
+        connection.call_on_fresh_connection(request).await
+    } else {
         connection.call(request).await
+    }
+}
+
+// Sends the request and deserializes the response. On success, this returns a response handling
+// phase; if the connection failed, it returns the dispatch phase with the failure recorded.
+async fn transmit(
+    mut context: InterceptorContext,
+    cfg: &mut ConfigBag,
+    interceptors: &Interceptors,
+    fresh_connection: bool,
+) -> Result<Phase, SdkError<Error, HttpResponse>> {
+    // The connection consumes the request. A copy of it was checkpointed before the
+    // retry loop so that it can be restored for the next attempt.
+    let mut request = context.take_request().expect("request has been set");
+    let expected_continue = expects_continue(&request);
+    if let Some(concurrency_hint) = cfg.load::<ConcurrencyHint>().cloned() {
+        request.extensions_mut().insert(concurrency_hint);
+    }
+    if let Some(keep_alive_config) = cfg.load::<KeepAliveConfig>().cloned() {
+        request.extensions_mut().insert(keep_alive_config);
+    }
+    // A new timing is used for every attempt, so that a connection that doesn't report one
+    // isn't attributed the previous attempt's duration
+    let dns_timing = DnsTiming::new();
+    cfg.store_put(dns_timing.clone());
+    request.extensions_mut().insert(dns_timing);
+    count_request_body(&mut request, cfg);
+    let uri = request.uri().clone();
+    let call_result = call_connection(request, fresh_connection, cfg).await;
+    let call_result = match call_result {
+        Ok(response) => follow_redirects(response, uri, fresh_connection, cfg).await,
+        Err(err) => Err(err),
+    };
+    let response = match call_result {
+        Ok(mut response) => {
+            count_response_body(&mut response, cfg);
+            response
+        }
+        // Record the connection-level failure as the attempt's error so that the retry strategy
+        // can decide whether to retry it. If it isn't retried, it becomes a dispatch failure.
+        Err(err) => {
+            let connector_error = match err.downcast::<ConnectorError>() {
+                Ok(connector_error) => *connector_error,
+                // Wrap rather than stringify the error so that its source chain is preserved
+                Err(err) => ConnectorError::other(err, None),
+            };
+            context.set_output_or_error(Err(TypedBox::new(connector_error).erase()));
+            return Ok(Phase::dispatch(context));
+        }
     };
 
     let mut context = Phase::dispatch(context)
         .include_mut(move |ctx| {
-            ctx.set_response(call_result?);
+            record_request_ids(&response);
+            ctx.set_response(response);
+            Result::<(), BoxError>::Ok(())
+        })?
+        // If the server rejected the expectation, the body was never sent, so fail early
+        // rather than deserializing the rejection as the operation's response
+        .include(|ctx| {
+            if expected_continue {
+                check_expectation(ctx.response().expect("response has been set"))?;
+            }
             Result::<(), BoxError>::Ok(())
         })?
         .include(|ctx| interceptors.read_after_transmit(ctx, cfg))?
@@ -177,20 +953,50 @@ async fn make_an_attempt(
 
     let output_or_error = {
         let response = context.response_mut().expect("response has been set");
-        let response_deserializer = cfg.response_deserializer();
-        match response_deserializer.deserialize_streaming(response) {
+        let status = response.status().as_u16();
+        let response_deserializer = cfg
+            .load::<StatusDeserializers>()
+            .and_then(|status_deserializers| status_deserializers.deserializer(status))
+            .unwrap_or_else(|| cfg.response_deserializer());
+        // Small responses are cheaper to buffer than to set up a stream for. The body of an event
+        // stream carries its output events though, so it's never buffered.
+        let event_stream = is_event_stream_operation(cfg) && response.status().is_success();
+        let streamed = if should_buffer(response, cfg) && !event_stream {
+            None
+        } else {
+            response_deserializer.deserialize_streaming(response)
+        };
+        match streamed {
             Some(output_or_error) => Ok(output_or_error),
-            None => read_body(response)
-                .instrument(debug_span!("read_body"))
-                .await
-                .map(|_| response_deserializer.deserialize_nonstreaming(response)),
+            None if event_stream => Err(BoxError::from(
+                "the response deserializer of an event stream operation must deserialize the \
+                streaming response",
+            )),
+            None => match validate_response_checksum(response, cfg) {
+                Ok(()) => {
+                    read_body(response, cfg.load::<Arc<dyn BufferPool>>().cloned())
+                        .instrument(debug_span!("read_body"))
+                        .await
+                }
+                Err(err) => Err(err.into()),
+            }
+            .and_then(|_| decompress_body(response, cfg))
+            .map(|_| response_deserializer.deserialize_nonstreaming(response)),
         }
     };
+    let output_or_error = output_or_error.and_then(|output_or_error| {
+        check_output_type(&output_or_error, cfg)?;
+        Ok(output_or_error)
+    });
 
     Phase::response_handling(context)
+        .observe(cfg)
         .include_mut(move |ctx| {
             ctx.set_output_or_error(output_or_error?);
             Result::<(), BoxError>::Ok(())
         })?
         .include(|ctx| interceptors.read_after_deserialization(ctx, cfg))
 }
+
+#[cfg(test)]
+mod tests;
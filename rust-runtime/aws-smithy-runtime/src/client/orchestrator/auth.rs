@@ -4,24 +4,47 @@
  */
 
 use super::phase::Phase;
-use aws_smithy_http::result::SdkError;
+use crate::client::timeout;
+use aws_smithy_http::result::{ConnectorError, SdkError};
+use aws_smithy_runtime_api::client::auth::HttpRequestSigner;
+use aws_smithy_runtime_api::client::identity::{Identity, IdentityResolver};
 use aws_smithy_runtime_api::client::interceptors::context::Error;
-use aws_smithy_runtime_api::client::orchestrator::{BoxError, ConfigBagAccessors, HttpResponse};
+use aws_smithy_runtime_api::client::orchestrator::{
+    BoxError, ConfigBagAccessors, HttpRequest, HttpResponse, SigningRetryConfig,
+};
 use aws_smithy_runtime_api::config_bag::ConfigBag;
+use std::io;
+use std::time::Duration;
 
 pub(super) async fn orchestrate_auth(
     dispatch_phase: Phase,
     cfg: &ConfigBag,
 ) -> Result<Phase, SdkError<Error, HttpResponse>> {
-    fn construction_failure(err: impl Into<BoxError>) -> SdkError<Error, HttpResponse> {
-        SdkError::construction_failure(err)
-    }
+    let (request_signer, identity) = resolve_signer(cfg)
+        .await
+        .map_err(SdkError::construction_failure)?;
+    dispatch_phase.include_mut(|ctx| {
+        let request = ctx.request_mut()?;
+        request_signer.sign_request(request, &identity, cfg)?;
+        Result::<_, BoxError>::Ok(())
+    })
+}
 
+/// Signs a request outside of the orchestration of an attempt, e.g. one that a response
+/// redirected to, with the same auth scheme and identity that [`orchestrate_auth`] would use.
+pub(super) async fn sign_request(
+    request: &mut HttpRequest,
+    cfg: &ConfigBag,
+) -> Result<(), BoxError> {
+    let (request_signer, identity) = resolve_signer(cfg).await?;
+    request_signer.sign_request(request, &identity, cfg)
+}
+
+/// Returns the signer of the first auth option with a matching auth scheme and identity
+/// resolver, and the identity that it resolved.
+async fn resolve_signer(cfg: &ConfigBag) -> Result<(&dyn HttpRequestSigner, Identity), BoxError> {
     let params = cfg.auth_option_resolver_params();
-    let auth_options = cfg
-        .auth_option_resolver()
-        .resolve_auth_options(params)
-        .map_err(construction_failure)?;
+    let auth_options = cfg.auth_option_resolver().resolve_auth_options(params)?;
     let identity_resolvers = cfg.identity_resolvers();
 
     tracing::trace!(
@@ -33,24 +56,87 @@ pub(super) async fn orchestrate_auth(
     for &scheme_id in auth_options.as_ref() {
         if let Some(auth_scheme) = cfg.http_auth_schemes().scheme(scheme_id) {
             if let Some(identity_resolver) = auth_scheme.identity_resolver(identity_resolvers) {
-                let request_signer = auth_scheme.request_signer();
-
-                let identity = identity_resolver
-                    .resolve_identity(cfg)
-                    .await
-                    .map_err(construction_failure)?;
-                return dispatch_phase.include_mut(|ctx| {
-                    let request = ctx.request_mut()?;
-                    request_signer.sign_request(request, &identity, cfg)?;
-                    Result::<_, BoxError>::Ok(())
-                });
+                let identity = resolve_identity(identity_resolver, cfg).await?;
+                return Ok((auth_scheme.request_signer(), identity));
             }
         }
     }
 
-    Err(construction_failure(
-        "no auth scheme matched auth options. This is a bug. Please file an issue.",
-    ))
+    Err("no auth scheme matched auth options. This is a bug. Please file an issue.".into())
+}
+
+/// Resolves the identity, retrying failures according to the [`SigningRetryConfig`], if any.
+async fn resolve_identity(
+    identity_resolver: &dyn IdentityResolver,
+    cfg: &ConfigBag,
+) -> Result<Identity, BoxError> {
+    let signing_retry_config = cfg.load::<SigningRetryConfig>().copied();
+    let max_attempts = signing_retry_config.map_or(1, |config| config.max_attempts());
+    let mut attempt = 1;
+    loop {
+        let err = match identity_resolver.resolve_identity(cfg).await {
+            Ok(identity) => return Ok(identity),
+            Err(err) => err,
+        };
+        let backoff = match signing_retry_config {
+            Some(config) if attempt < max_attempts && is_transient(&err) => {
+                backoff(&config, attempt)
+            }
+            _ => return Err(err),
+        };
+        tracing::debug!(
+            attempt,
+            backoff = ?backoff,
+            error = %err,
+            "failed to resolve the identity to sign the request with; retrying"
+        );
+        if !backoff.is_zero() {
+            timeout::sleep(cfg, backoff)
+                .ok_or("a sleep implementation is required to retry identity resolution")?
+                .await;
+        }
+        attempt += 1;
+    }
+}
+
+/// Returns `true` if resolving the identity failed because of a timeout or an I/O error, in which
+/// case resolving it again may succeed.
+fn is_transient(err: &BoxError) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(&**err);
+    while let Some(err) = source {
+        if let Some(err) = err.downcast_ref::<ConnectorError>() {
+            if err.is_timeout() || err.is_io() {
+                return true;
+            }
+        }
+        if let Some(err) = err.downcast_ref::<io::Error>() {
+            if matches!(
+                err.kind(),
+                io::ErrorKind::TimedOut
+                    | io::ErrorKind::Interrupted
+                    | io::ErrorKind::ConnectionRefused
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::NotConnected
+                    | io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::UnexpectedEof
+            ) {
+                return true;
+            }
+        }
+        source = err.source();
+    }
+    false
+}
+
+/// Returns how long to wait after attempt number `attempt` failed to resolve the identity.
+fn backoff(config: &SigningRetryConfig, attempt: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+    config
+        .initial_backoff()
+        .checked_mul(factor)
+        .unwrap_or(Duration::MAX)
+        .min(config.max_backoff())
 }
 
 #[cfg(test)]
@@ -66,6 +152,18 @@ mod tests {
     use aws_smithy_runtime_api::client::orchestrator::{Future, HttpRequest};
     use aws_smithy_runtime_api::type_erasure::TypedBox;
 
+    #[test]
+    fn signing_retry_backoff_doubles_up_to_the_max() {
+        let config = SigningRetryConfig::new(10)
+            .with_initial_backoff(Duration::from_millis(100))
+            .with_max_backoff(Duration::from_millis(500));
+        let backoffs: Vec<_> = (1..=5)
+            .map(|attempt| backoff(&config, attempt).as_millis())
+            .collect();
+        assert_eq!(vec![100, 200, 400, 500, 500], backoffs);
+        assert_eq!(Duration::from_millis(500), backoff(&config, u32::MAX));
+    }
+
     #[tokio::test]
     async fn basic_case() {
         #[derive(Debug)]
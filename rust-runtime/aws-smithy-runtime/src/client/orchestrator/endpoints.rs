@@ -3,15 +3,21 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
+use crate::client::orchestrator::phase::Phase;
+use aws_smithy_http::body::SdkBody;
 use aws_smithy_http::endpoint::error::ResolveEndpointError;
 use aws_smithy_http::endpoint::{
     apply_endpoint, EndpointPrefix, ResolveEndpoint, SharedEndpointResolver,
 };
+use aws_smithy_http::result::SdkError;
+use aws_smithy_runtime_api::client::interceptors::context::{Error, Input};
 use aws_smithy_runtime_api::client::interceptors::InterceptorContext;
 use aws_smithy_runtime_api::client::orchestrator::{
-    BoxError, ConfigBagAccessors, EndpointResolver, EndpointResolverParams, HttpRequest,
+    AsyncEndpointResolver, BoxError, ConfigBagAccessors, EndpointFailover, EndpointResolver,
+    EndpointResolverParams, HttpRequest, HttpResponse, PreresolvedEndpoint,
 };
-use aws_smithy_runtime_api::config_bag::ConfigBag;
+use aws_smithy_runtime_api::config_bag::{ConfigBag, Storable, StoreReplace};
+use aws_smithy_types::endpoint::Endpoint;
 use http::header::HeaderName;
 use http::{HeaderValue, Uri};
 use std::fmt::Debug;
@@ -79,36 +85,119 @@ where
             }
         };
 
-        let uri: Uri = endpoint.url().parse().map_err(|err| {
-            ResolveEndpointError::from_source("endpoint did not have a valid uri", err)
-        })?;
+        apply_resolved_endpoint(&endpoint, endpoint_prefix, request)
+    }
+}
 
-        apply_endpoint(request.uri_mut(), &uri, endpoint_prefix).map_err(|err| {
-            ResolveEndpointError::message(format!(
-                "failed to apply endpoint `{:?}` to request `{:?}`",
-                uri, request,
-            ))
-            .with_source(Some(err.into()))
-        })?;
-
-        for (header_name, header_values) in endpoint.headers() {
-            request.headers_mut().remove(header_name);
-            for value in header_values {
-                request.headers_mut().insert(
-                    HeaderName::from_str(header_name).map_err(|err| {
-                        ResolveEndpointError::message("invalid header name")
-                            .with_source(Some(err.into()))
-                    })?,
-                    HeaderValue::from_str(value).map_err(|err| {
-                        ResolveEndpointError::message("invalid header value")
-                            .with_source(Some(err.into()))
-                    })?,
-                );
-            }
+/// Applies a resolved `endpoint` to the request: its URL, with the `endpoint_prefix` if there is
+/// one, and its headers.
+fn apply_resolved_endpoint(
+    endpoint: &Endpoint,
+    endpoint_prefix: Option<&EndpointPrefix>,
+    request: &mut HttpRequest,
+) -> Result<(), BoxError> {
+    let uri: Uri = endpoint.url().parse().map_err(|err| {
+        ResolveEndpointError::from_source("endpoint did not have a valid uri", err)
+    })?;
+
+    apply_endpoint(request.uri_mut(), &uri, endpoint_prefix).map_err(|err| {
+        ResolveEndpointError::message(format!(
+            "failed to apply endpoint `{:?}` to request `{:?}`",
+            uri, request,
+        ))
+        .with_source(Some(err.into()))
+    })?;
+
+    for (header_name, header_values) in endpoint.headers() {
+        request.headers_mut().remove(header_name);
+        for value in header_values {
+            request.headers_mut().insert(
+                HeaderName::from_str(header_name).map_err(|err| {
+                    ResolveEndpointError::message("invalid header name")
+                        .with_source(Some(err.into()))
+                })?,
+                HeaderValue::from_str(value).map_err(|err| {
+                    ResolveEndpointError::message("invalid header value")
+                        .with_source(Some(err.into()))
+                })?,
+            );
         }
+    }
 
-        Ok(())
+    Ok(())
+}
+
+/// The host prefix template of an operation with the `@endpoint(hostPrefix: ...)` trait.
+///
+/// Labels in the template, such as `{AccountId}` in `{AccountId}.data.`, are substituted with the
+/// value of the matching input member. When a [`HostPrefixTemplate`] is in the [`ConfigBag`], it's
+/// rendered from the input before serialization, and the resulting [`EndpointPrefix`] is
+/// prepended to the host of the resolved endpoint.
+#[derive(Clone, Debug)]
+pub struct HostPrefixTemplate {
+    template: &'static str,
+    label_value: fn(&Input, &str) -> Option<String>,
+}
+
+impl HostPrefixTemplate {
+    /// Creates a new [`HostPrefixTemplate`]. `label_value` returns the value of the input member
+    /// that the named label is bound to, or `None` if the member isn't set.
+    pub fn new(template: &'static str, label_value: fn(&Input, &str) -> Option<String>) -> Self {
+        Self {
+            template,
+            label_value,
+        }
     }
+
+    /// Renders the template with the label values from `input`.
+    ///
+    /// Fails if a label isn't set or is empty, since the request would otherwise be sent to the
+    /// wrong host.
+    pub fn render(&self, input: &Input) -> Result<EndpointPrefix, BoxError> {
+        let mut prefix = String::with_capacity(self.template.len());
+        let mut rest = self.template;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..].find('}').ok_or_else(|| {
+                ResolveEndpointError::message(format!(
+                    "host prefix template `{}` has an unclosed label",
+                    self.template
+                ))
+            })? + start;
+            let label = &rest[start + 1..end];
+            let value = (self.label_value)(input, label)
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| {
+                    ResolveEndpointError::message(format!(
+                        "`{label}` is required to build the host prefix, and must not be empty"
+                    ))
+                })?;
+            prefix.push_str(&rest[..start]);
+            prefix.push_str(&value);
+            rest = &rest[end + 1..];
+        }
+        prefix.push_str(rest);
+        Ok(EndpointPrefix::new(prefix).map_err(|err| {
+            ResolveEndpointError::message("the rendered host prefix is invalid")
+                .with_source(Some(err.into()))
+        })?)
+    }
+}
+
+impl Storable for HostPrefixTemplate {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Renders the [`HostPrefixTemplate`] in the `cfg`, if there is one, and stores the resulting
+/// [`EndpointPrefix`] for endpoint resolution to use.
+///
+/// This has to happen before serialization, since the input is consumed by it.
+pub(super) fn render_host_prefix(input: &Input, cfg: &mut ConfigBag) -> Result<(), BoxError> {
+    let endpoint_prefix = match cfg.load::<HostPrefixTemplate>() {
+        Some(template) => template.render(input)?,
+        None => return Ok(()),
+    };
+    cfg.put(endpoint_prefix);
+    Ok(())
 }
 
 pub(super) fn orchestrate_endpoint(
@@ -118,9 +207,247 @@ pub(super) fn orchestrate_endpoint(
     let params = cfg.endpoint_resolver_params();
     let endpoint_prefix = cfg.get::<EndpointPrefix>();
     let request = ctx.request_mut()?;
+    if let Some(endpoint) = cfg
+        .load::<PreresolvedEndpoint>()
+        .map(PreresolvedEndpoint::endpoint)
+    {
+        return apply_resolved_endpoint(endpoint, endpoint_prefix, request);
+    }
 
     let endpoint_resolver = cfg.endpoint_resolver();
-    endpoint_resolver.resolve_and_apply_endpoint(params, endpoint_prefix, request)?;
+    endpoint_resolver
+        .resolve_and_apply_endpoint(params, endpoint_prefix, request)
+        .map_err(|err| resolution_failure(params, err))?;
+
+    Ok(())
+}
+
+/// Resolves and applies the endpoint of the request, awaiting the [`AsyncEndpointResolver`] if one
+/// is configured, and falling back to [`orchestrate_endpoint`] otherwise. A
+/// [preresolved endpoint](PreresolvedEndpoint) takes precedence over both.
+pub(super) async fn orchestrate_async_endpoint(
+    dispatch_phase: Phase,
+    cfg: &ConfigBag,
+) -> Result<Phase, SdkError<Error, HttpResponse>> {
+    let async_endpoint_resolver = cfg
+        .load::<Box<dyn AsyncEndpointResolver>>()
+        .filter(|_| cfg.load::<PreresolvedEndpoint>().is_none());
+    let dispatch_phase = match async_endpoint_resolver {
+        Some(async_endpoint_resolver) => {
+            let params = cfg.endpoint_resolver_params();
+            let endpoint = async_endpoint_resolver.resolve_endpoint(params).await;
+            dispatch_phase.include_mut(|ctx| {
+                let endpoint = endpoint.map_err(|err| resolution_failure(params, err))?;
+                apply_resolved_endpoint(&endpoint, cfg.get::<EndpointPrefix>(), ctx.request_mut()?)
+            })?
+        }
+        None => dispatch_phase.include_mut(|ctx| orchestrate_endpoint(ctx, cfg))?,
+    };
+    dispatch_phase.include_mut(|ctx| apply_endpoint_failover(ctx, cfg))
+}
+
+/// The number of attempts of the operation that have failed to connect so far.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct ConnectionFailures(pub(super) usize);
+
+impl Storable for ConnectionFailures {
+    type Storer = StoreReplace<Self>;
+}
 
+/// Sends the request to the [`EndpointFailover`] host that's next in line, if previous attempts
+/// failed to connect.
+fn apply_endpoint_failover(ctx: &mut InterceptorContext, cfg: &ConfigBag) -> Result<(), BoxError> {
+    let connection_failures = cfg
+        .load::<ConnectionFailures>()
+        .map(|connection_failures| connection_failures.0)
+        .unwrap_or_default();
+    let host = match cfg
+        .load::<EndpointFailover>()
+        .and_then(|endpoint_failover| endpoint_failover.host(connection_failures))
+    {
+        Some(host) => host,
+        None => return Ok(()),
+    };
+    let request = ctx.request_mut()?;
+    let mut parts = request.uri().clone().into_parts();
+    parts.scheme = host.scheme().cloned();
+    parts.authority = host.authority().cloned();
+    *request.uri_mut() = Uri::from_parts(parts).map_err(|err| {
+        ResolveEndpointError::message(format!("failover host `{host}` is invalid"))
+            .with_source(Some(err.into()))
+    })?;
+    tracing::debug!(
+        host = %host,
+        connection_failures,
+        "failing over to another host after failing to connect"
+    );
     Ok(())
 }
+
+fn resolution_failure(params: &EndpointResolverParams, err: BoxError) -> ResolveEndpointError {
+    // Sensitive params are redacted by their `Debug` implementation
+    ResolveEndpointError::message(format!(
+        "failed to resolve an endpoint with the parameters {:?}",
+        params
+    ))
+    .with_source(Some(err))
+}
+
+/// Resolves the endpoint that the request in `ctx` will be sent to, without modifying the request.
+pub(super) async fn resolve_endpoint_uri(
+    ctx: &InterceptorContext,
+    cfg: &ConfigBag,
+) -> Result<Uri, BoxError> {
+    let mut request = HttpRequest::new(SdkBody::empty());
+    *request.uri_mut() = ctx.request()?.uri().clone();
+    let params = cfg.endpoint_resolver_params();
+    let endpoint_prefix = cfg.get::<EndpointPrefix>();
+    if let Some(endpoint) = cfg
+        .load::<PreresolvedEndpoint>()
+        .map(PreresolvedEndpoint::endpoint)
+    {
+        apply_resolved_endpoint(endpoint, endpoint_prefix, &mut request)?;
+        return Ok(request.uri().clone());
+    }
+    match cfg.load::<Box<dyn AsyncEndpointResolver>>() {
+        Some(async_endpoint_resolver) => {
+            let endpoint = async_endpoint_resolver.resolve_endpoint(params).await?;
+            apply_resolved_endpoint(&endpoint, endpoint_prefix, &mut request)?;
+        }
+        None => cfg.endpoint_resolver().resolve_and_apply_endpoint(
+            params,
+            endpoint_prefix,
+            &mut request,
+        )?,
+    }
+    Ok(request.uri().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_smithy_http::body::SdkBody;
+    use aws_smithy_runtime_api::type_erasure::TypedBox;
+    use aws_smithy_types::endpoint::Endpoint;
+    use aws_smithy_types::error::display::DisplayErrorContext;
+
+    #[derive(Debug)]
+    struct TestParams {
+        region: Option<String>,
+        use_fips: bool,
+    }
+
+    struct RegionalResolver;
+
+    impl ResolveEndpoint<TestParams> for RegionalResolver {
+        fn resolve_endpoint(&self, params: &TestParams) -> aws_smithy_http::endpoint::Result {
+            let region = params
+                .region
+                .as_ref()
+                .ok_or_else(|| ResolveEndpointError::message("a region must be set"))?;
+            let fips = if params.use_fips { "-fips" } else { "" };
+            Ok(Endpoint::builder()
+                .url(format!("https://{region}{fips}.example.com"))
+                .build())
+        }
+    }
+
+    #[test]
+    fn endpoint_resolution_errors_include_the_params() {
+        let mut cfg = ConfigBag::base();
+        cfg.set_endpoint_resolver(DefaultEndpointResolver::new(SharedEndpointResolver::new(
+            RegionalResolver,
+        )));
+        cfg.set_endpoint_resolver_params(EndpointResolverParams::new(TestParams {
+            region: None,
+            use_fips: true,
+        }));
+        let mut ctx = InterceptorContext::new(TypedBox::new(()).erase());
+        ctx.set_request(
+            http::Request::builder()
+                .uri("/")
+                .body(SdkBody::empty())
+                .unwrap(),
+        );
+
+        let err = orchestrate_endpoint(&mut ctx, &cfg).expect_err("region is missing");
+        let message = format!("{}", DisplayErrorContext(&*err));
+        assert!(
+            message.contains("TestParams { region: None, use_fips: true }"),
+            "{message}"
+        );
+        assert!(message.contains("a region must be set"), "{message}");
+    }
+
+    #[test]
+    fn sensitive_params_are_redacted_from_endpoint_resolution_errors() {
+        let mut cfg = ConfigBag::base();
+        cfg.set_endpoint_resolver(DefaultEndpointResolver::new(SharedEndpointResolver::new(
+            RegionalResolver,
+        )));
+        cfg.set_endpoint_resolver_params(EndpointResolverParams::sensitive(TestParams {
+            region: None,
+            use_fips: true,
+        }));
+        let mut ctx = InterceptorContext::new(TypedBox::new(()).erase());
+        ctx.set_request(
+            http::Request::builder()
+                .uri("/")
+                .body(SdkBody::empty())
+                .unwrap(),
+        );
+
+        let err = orchestrate_endpoint(&mut ctx, &cfg).expect_err("region is missing");
+        let message = format!("{}", DisplayErrorContext(&*err));
+        assert!(message.contains("TestParams: ** redacted **"), "{message}");
+        assert!(!message.contains("use_fips"), "{message}");
+        assert!(message.contains("a region must be set"), "{message}");
+    }
+
+    fn account_id(input: &Input, label: &str) -> Option<String> {
+        match label {
+            "AccountId" => input.downcast_ref::<Option<String>>().cloned().flatten(),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn host_prefix_labels_are_substituted_into_the_host() {
+        let mut cfg = ConfigBag::base();
+        cfg.set_endpoint_resolver(DefaultEndpointResolver::new(SharedEndpointResolver::new(
+            RegionalResolver,
+        )));
+        cfg.set_endpoint_resolver_params(EndpointResolverParams::new(TestParams {
+            region: Some("us-east-1".into()),
+            use_fips: false,
+        }));
+        cfg.store_put(HostPrefixTemplate::new("{AccountId}.data.", account_id));
+        let input = TypedBox::new(Some("123456789012".to_string())).erase();
+        render_host_prefix(&input, &mut cfg).expect("the label is set");
+
+        let mut ctx = InterceptorContext::new(input);
+        ctx.set_request(
+            http::Request::builder()
+                .uri("/")
+                .body(SdkBody::empty())
+                .unwrap(),
+        );
+        orchestrate_endpoint(&mut ctx, &cfg).expect("the endpoint resolves");
+        assert_eq!(
+            "https://123456789012.data.us-east-1.example.com/",
+            ctx.request().unwrap().uri().to_string()
+        );
+    }
+
+    #[test]
+    fn host_prefix_labels_must_be_set_and_non_empty() {
+        let template = HostPrefixTemplate::new("{AccountId}.data.", account_id);
+        for account in [None, Some(String::new())] {
+            let err = template
+                .render(&TypedBox::new(account).erase())
+                .expect_err("the label is missing");
+            let message = format!("{}", DisplayErrorContext(&*err));
+            assert!(message.contains("`AccountId` is required"), "{message}");
+        }
+    }
+}
@@ -3,14 +3,543 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use aws_smithy_http::body::SdkBody;
-use aws_smithy_runtime_api::client::orchestrator::HttpResponse;
+use aws_smithy_checksums::body::{calculate, validate};
+use aws_smithy_checksums::error::UnknownChecksumAlgorithmError;
+use aws_smithy_checksums::http::{HttpChecksum, CHECKSUM_ALGORITHMS_IN_PRIORITY_ORDER};
+use aws_smithy_checksums::ChecksumAlgorithm;
+use aws_smithy_http::body::{BoxBody, SdkBody};
+use aws_smithy_http::content_encoding::header_value::AWS_CHUNKED;
+use aws_smithy_http::content_encoding::{AwsChunkedBody, AwsChunkedBodyOptions};
+use aws_smithy_runtime_api::client::interceptors::context::OutputOrError;
+use aws_smithy_runtime_api::client::interceptors::InterceptorContext;
+use aws_smithy_runtime_api::client::orchestrator::{
+    BoxError, BufferPool, BufferedResponseThreshold, ByteCounts, ContentEncoding,
+    ExpectedOutputType, HttpRequest, HttpResponse, LogQueryString, MaxSerializedRequestSize,
+    ReplayableBody, RequestAttempt, RequestAttemptHeader, RequestChecksumAlgorithm,
+    RequestCompression, ResponseDecompression, ResponseTrailers, ValidateResponseChecksums,
+};
+use aws_smithy_runtime_api::config_bag::ConfigBag;
+use aws_smithy_types::base64;
+use aws_smithy_types::retry::RetryConfig;
 use bytes::{Buf, Bytes};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+use http::HeaderMap;
 use http_body::Body;
+use pin_project_lite::pin_project;
 use pin_utils::pin_mut;
+use std::io::{Read, Write};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
 
-async fn body_to_bytes(body: SdkBody) -> Result<Bytes, <SdkBody as Body>::Error> {
-    let mut output = Vec::new();
+/// The error returned when the server rejects a request's `Expect: 100-continue` header.
+#[derive(Debug)]
+pub(crate) struct ExpectationFailed;
+
+impl std::fmt::Display for ExpectationFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the server responded to `Expect: 100-continue` with `417 Expectation Failed`, \
+            so the request body was not sent"
+        )
+    }
+}
+
+impl std::error::Error for ExpectationFailed {}
+
+/// The error returned when a response is encoded with a `Content-Encoding` that isn't in the
+/// [`ResponseDecompression`] allowlist.
+#[derive(Debug)]
+pub(crate) struct UnsupportedContentEncoding(String);
+
+impl std::fmt::Display for UnsupportedContentEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the response has an unsupported `Content-Encoding` of `{}`, so it can't be \
+            decompressed",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedContentEncoding {}
+
+/// The error returned when requests are configured to be compressed with an encoding that the
+/// orchestrator doesn't know how to compress with.
+#[derive(Debug)]
+pub(crate) struct UnsupportedCompressionAlgorithm(ContentEncoding);
+
+impl std::fmt::Display for UnsupportedCompressionAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "requests can't be compressed with the `{}` encoding",
+            self.0.as_str()
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedCompressionAlgorithm {}
+
+/// The error returned when a response decompresses to more than the
+/// [`max_decompressed_size`](ResponseDecompression::max_decompressed_size).
+#[derive(Debug)]
+pub(crate) struct DecompressedResponseTooLarge {
+    max_size: u64,
+}
+
+impl std::fmt::Display for DecompressedResponseTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the response decompresses to more than the maximum of {} bytes",
+            self.max_size
+        )
+    }
+}
+
+impl std::error::Error for DecompressedResponseTooLarge {}
+
+/// The error returned when the response deserializer produces an output of a different type than
+/// the [`ExpectedOutputType`].
+#[derive(Debug)]
+pub(crate) struct OutputTypeMismatch {
+    expected: &'static str,
+    actual: &'static str,
+}
+
+impl std::fmt::Display for OutputTypeMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the response deserializer returned an output of type `{}`, but `{}` was expected; \
+            the response deserializer is likely misconfigured",
+            self.actual, self.expected
+        )
+    }
+}
+
+impl std::error::Error for OutputTypeMismatch {}
+
+/// Checks that a deserialized output is of the [`ExpectedOutputType`], if one is set.
+pub(crate) fn check_output_type(
+    output_or_error: &OutputOrError,
+    cfg: &ConfigBag,
+) -> Result<(), OutputTypeMismatch> {
+    match (output_or_error, cfg.load::<ExpectedOutputType>()) {
+        (Ok(output), Some(expected)) if !expected.matches(output) => Err(OutputTypeMismatch {
+            expected: expected.type_name(),
+            actual: output.type_name(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// The error returned when a serialized request body is larger than the
+/// [`MaxSerializedRequestSize`].
+#[derive(Debug)]
+pub(crate) struct RequestTooLarge {
+    size: u64,
+    max_size: u64,
+}
+
+impl std::fmt::Display for RequestTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the serialized request body is {} bytes, which is larger than the maximum of {} bytes",
+            self.size, self.max_size
+        )
+    }
+}
+
+impl std::error::Error for RequestTooLarge {}
+
+/// Checks that the serialized request body isn't larger than the [`MaxSerializedRequestSize`], if
+/// one is set. Bodies of unknown size, such as most streams, aren't checked.
+pub(crate) fn check_serialized_request_size(
+    request: &HttpRequest,
+    cfg: &ConfigBag,
+) -> Result<(), RequestTooLarge> {
+    match (
+        request.body().content_length(),
+        cfg.load::<MaxSerializedRequestSize>()
+            .map(MaxSerializedRequestSize::max_size),
+    ) {
+        (Some(size), Some(max_size)) if size > max_size => Err(RequestTooLarge { size, max_size }),
+        _ => Ok(()),
+    }
+}
+
+/// Returns true if the request asks the server to confirm, with an interim `100 Continue`
+/// response, that it will accept the request before the body is sent.
+pub(crate) fn expects_continue(request: &HttpRequest) -> bool {
+    request
+        .headers()
+        .get(http::header::EXPECT)
+        .map(|value| value.as_bytes().eq_ignore_ascii_case(b"100-continue"))
+        .unwrap_or(false)
+}
+
+/// Checks whether the server rejected a request that was sent with `Expect: 100-continue`.
+///
+/// Connections wait for the interim `100 Continue` before sending the body. If the server replies
+/// with `417 Expectation Failed` instead, the connection returns that response without having
+/// sent the body. Any other final response is left to the response deserializer, since it is
+/// the service's answer to the request.
+pub(crate) fn check_expectation(response: &HttpResponse) -> Result<(), ExpectationFailed> {
+    if response.status() == http::StatusCode::EXPECTATION_FAILED {
+        Err(ExpectationFailed)
+    } else {
+        Ok(())
+    }
+}
+
+/// Sets the [`RequestAttemptHeader`] on the request of the current attempt, if one is configured.
+pub(crate) fn set_request_attempt_header(
+    ctx: &mut InterceptorContext,
+    cfg: &ConfigBag,
+) -> Result<(), BoxError> {
+    let (header, attempt) = match (
+        cfg.load::<RequestAttemptHeader>(),
+        cfg.load::<RequestAttempt>().copied(),
+    ) {
+        (Some(header), Some(attempt)) => (header, attempt.attempt()),
+        _ => return Ok(()),
+    };
+    let max_attempts = cfg
+        .get::<RetryConfig>()
+        .map(|retry_config| retry_config.max_attempts());
+    let value = http::HeaderValue::try_from(header.value(attempt, max_attempts))?;
+    // Replaces the value set by the previous attempt
+    ctx.request_mut()?
+        .headers_mut()
+        .insert(header.name().clone(), value);
+    Ok(())
+}
+
+/// Replaces the body of the request with a new one from the configured
+/// [`ReplayableBody`](aws_smithy_runtime_api::client::orchestrator::ReplayableBody), if any.
+pub(crate) fn make_replayable_body(
+    ctx: &mut InterceptorContext,
+    cfg: &ConfigBag,
+) -> Result<(), BoxError> {
+    if let Some(replayable_body) = cfg.load::<ReplayableBody>() {
+        *ctx.request_mut()?.body_mut() = replayable_body.make_body();
+    }
+    Ok(())
+}
+
+/// Returns true if the response's `Content-Length` is below the configured
+/// [`BufferedResponseThreshold`], in which case it should be buffered rather than streamed.
+pub(crate) fn should_buffer(response: &HttpResponse, cfg: &ConfigBag) -> bool {
+    let content_length = response
+        .headers()
+        .get(http::header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    match (cfg.load::<BufferedResponseThreshold>(), content_length) {
+        (Some(threshold), Some(content_length)) => content_length < threshold.threshold(),
+        _ => false,
+    }
+}
+
+/// Records the size of the request body on the current span's `request_body_size` field.
+///
+/// The size is taken from the request's `Content-Length` header, or from the body itself when it
+/// is held in memory. Streaming bodies of unknown length are recorded as `"unknown"`.
+pub(crate) fn record_request_body_size(request: &HttpRequest) {
+    let size = request
+        .headers()
+        .get(http::header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .or_else(|| request.body().content_length());
+    let span = tracing::Span::current();
+    match size {
+        Some(size) => span.record("request_body_size", size),
+        None => span.record("request_body_size", "unknown"),
+    };
+}
+
+/// Records the method and path of the request on the current span, as `http.method` and
+/// `http.path`. The query string is redacted unless [`LogQueryString`] is enabled.
+pub(crate) fn record_request_line(request: &HttpRequest, cfg: &ConfigBag) {
+    let uri = request.uri();
+    let log_query_string = cfg
+        .load::<LogQueryString>()
+        .map(LogQueryString::enabled)
+        .unwrap_or_default();
+    let path = match uri.query() {
+        Some(query) if log_query_string => format!("{}?{}", uri.path(), query),
+        Some(_) => format!("{}?** redacted **", uri.path()),
+        None => uri.path().to_string(),
+    };
+    let span = tracing::Span::current();
+    span.record("http.method", request.method().as_str());
+    span.record("http.path", path.as_str());
+}
+
+/// The headers that services return their request id in, which differ between protocols.
+const REQUEST_ID_HEADERS: &[&str] = &["x-amzn-requestid", "x-amzn-request-id", "x-amz-request-id"];
+/// The header that S3 returns its extended request id (also known as host id) in.
+const EXTENDED_REQUEST_ID_HEADER: &str = "x-amz-id-2";
+
+/// Records the request id that the service returned in `response` on the current span, as
+/// `request_id`, so that the attempt can be correlated with the service's logs. The extended
+/// request id that S3 returns is recorded as `extended_request_id`.
+pub(crate) fn record_request_ids(response: &HttpResponse) {
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    };
+    let span = tracing::Span::current();
+    if let Some(request_id) = REQUEST_ID_HEADERS.iter().find_map(|name| header(name)) {
+        span.record("request_id", request_id);
+    }
+    if let Some(extended_request_id) = header(EXTENDED_REQUEST_ID_HEADER) {
+        span.record("extended_request_id", extended_request_id);
+    }
+}
+
+/// Which of the [`ByteCounts`] the bytes of a body are added to.
+#[derive(Copy, Clone, Debug)]
+enum Direction {
+    Sent,
+    Received,
+}
+
+impl Direction {
+    fn record(self, byte_counts: &ByteCounts, bytes: u64) {
+        match self {
+            Direction::Sent => byte_counts.record_sent(bytes),
+            Direction::Received => byte_counts.record_received(bytes),
+        }
+    }
+}
+
+pin_project! {
+    /// A body that adds the size of its data to a [`ByteCounts`] as it's polled.
+    struct CountingBody {
+        #[pin]
+        inner: SdkBody,
+        byte_counts: ByteCounts,
+        direction: Direction,
+    }
+}
+
+impl Body for CountingBody {
+    type Data = Bytes;
+    type Error = <SdkBody as Body>::Error;
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.project();
+        let poll = this.inner.poll_data(cx);
+        if let Poll::Ready(Some(Ok(data))) = &poll {
+            this.direction.record(this.byte_counts, data.len() as u64);
+        }
+        poll
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+        self.project().inner.poll_trailers(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+/// Adds the size of `body` to the operation's [`ByteCounts`]. In-memory bodies are counted right
+/// away; streaming bodies are wrapped so that they're counted as they flow.
+fn count_body(body: &mut SdkBody, cfg: &ConfigBag, direction: Direction) {
+    let byte_counts = match cfg.load::<ByteCounts>() {
+        Some(byte_counts) => byte_counts.clone(),
+        None => return,
+    };
+    if let Some(bytes) = body.bytes() {
+        direction.record(&byte_counts, bytes.len() as u64);
+        return;
+    }
+    let inner = std::mem::replace(body, SdkBody::taken());
+    *body = SdkBody::from_dyn(BoxBody::new(CountingBody {
+        inner,
+        byte_counts,
+        direction,
+    }));
+}
+
+/// Counts the bytes of the request body as it's sent. See [`ByteCounts`].
+pub(crate) fn count_request_body(request: &mut HttpRequest, cfg: &ConfigBag) {
+    count_body(request.body_mut(), cfg, Direction::Sent)
+}
+
+/// Counts the bytes of the response body as it's read. See [`ByteCounts`].
+pub(crate) fn count_response_body(response: &mut HttpResponse, cfg: &ConfigBag) {
+    count_body(response.body_mut(), cfg, Direction::Received)
+}
+
+/// Compresses the body of the request according to the configured [`RequestCompression`], if any,
+/// and sets its `Content-Encoding`.
+pub(crate) fn compress_request_body(
+    ctx: &mut InterceptorContext,
+    cfg: &ConfigBag,
+) -> Result<(), BoxError> {
+    let compression = match cfg.load::<RequestCompression>() {
+        Some(compression) => compression,
+        None => return Ok(()),
+    };
+    let request = ctx.request_mut()?;
+    if request
+        .headers()
+        .contains_key(http::header::CONTENT_ENCODING)
+    {
+        return Ok(());
+    }
+    // Streaming bodies would have to be compressed as they're sent, which isn't supported
+    let body = match request.body().bytes() {
+        Some(body) if body.len() as u64 >= compression.min_size() => body,
+        _ => return Ok(()),
+    };
+    let compressed = match compression.algorithm() {
+        ContentEncoding::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(body)?;
+            encoder.finish()?
+        }
+        ContentEncoding::Deflate => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(body)?;
+            encoder.finish()?
+        }
+        // The encoding is defined in another crate, so it may gain variants that aren't handled yet
+        algorithm => return Err(UnsupportedCompressionAlgorithm(algorithm).into()),
+    };
+    let headers = request.headers_mut();
+    headers.insert(
+        http::header::CONTENT_ENCODING,
+        http::HeaderValue::from_static(compression.algorithm().as_str()),
+    );
+    if headers.contains_key(http::header::CONTENT_LENGTH) {
+        headers.insert(http::header::CONTENT_LENGTH, compressed.len().into());
+    }
+    *request.body_mut() = SdkBody::from(compressed);
+    Ok(())
+}
+
+/// Sets the checksum of the request body, if a
+/// [request checksum algorithm](RequestChecksumAlgorithm) is set.
+///
+/// The checksum of a body held in memory is sent in a header. A streaming body is wrapped so that
+/// its checksum is computed as it's sent, and sent in a trailer of an `aws-chunked` body. That
+/// needs the length of the body to be known up front, so other streaming bodies fail.
+pub(crate) fn add_request_checksum(
+    ctx: &mut InterceptorContext,
+    cfg: &ConfigBag,
+) -> Result<(), BoxError> {
+    let algorithm = match cfg.load::<RequestChecksumAlgorithm>().copied() {
+        Some(algorithm) => algorithm,
+        None => return Ok(()),
+    };
+    let algorithm = algorithm.as_str().parse::<ChecksumAlgorithm>()?;
+    let header_name = http::HeaderName::from(algorithm);
+    let request = ctx.request_mut()?;
+    if request.headers().contains_key(&header_name) {
+        return Ok(());
+    }
+    match request.body().bytes() {
+        Some(body) => {
+            let mut checksum = algorithm.into_impl();
+            checksum.update(body);
+            let header_value = checksum.header_value();
+            request.headers_mut().insert(header_name, header_value);
+        }
+        None => {
+            let length = request.body().size_hint().exact().ok_or(
+                "the checksum of a streaming request body is sent in an `aws-chunked` trailer, \
+                which needs the length of the body to be known up front",
+            )?;
+            let body = std::mem::replace(request.body_mut(), SdkBody::taken());
+            *request.body_mut() = body.map(move |body| {
+                let checksum = algorithm.into_impl();
+                let options =
+                    AwsChunkedBodyOptions::new(length, vec![HttpChecksum::size(checksum.as_ref())]);
+                let body = calculate::ChecksumBody::new(body, checksum);
+                SdkBody::from_dyn(BoxBody::new(AwsChunkedBody::new(body, options)))
+            });
+            let encoded_length = request
+                .body()
+                .size_hint()
+                .exact()
+                .expect("an `aws-chunked` body has an exact size");
+            let headers = request.headers_mut();
+            headers.insert(http::header::CONTENT_LENGTH, encoded_length.into());
+            headers.append(
+                http::header::CONTENT_ENCODING,
+                http::HeaderValue::from_static(AWS_CHUNKED),
+            );
+            headers.insert("x-amz-decoded-content-length", length.into());
+            headers.insert("x-amz-trailer", http::HeaderValue::from(header_name));
+        }
+    }
+    Ok(())
+}
+
+/// A buffer acquired from a [`BufferPool`] that a response body is being read into. If reading
+/// the body fails, or is cancelled, the buffer is released when this is dropped.
+struct AcquiredBuffer {
+    buffer: Vec<u8>,
+    buffer_pool: Arc<dyn BufferPool>,
+}
+
+impl Drop for AcquiredBuffer {
+    fn drop(&mut self) {
+        // Once the body has been read, the buffer is taken, leaving nothing to release
+        if self.buffer.capacity() > 0 {
+            self.buffer_pool.release(std::mem::take(&mut self.buffer));
+        }
+    }
+}
+
+/// A handle to the pooled buffer that a response body was read into. It's stored in the
+/// response's extensions, and releases the buffer when it's dropped, unless something else, such
+/// as the response body, still refers to it.
+struct PooledBuffer {
+    bytes: Bytes,
+    buffer_pool: Arc<dyn BufferPool>,
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        let bytes = std::mem::take(&mut self.bytes);
+        if bytes.is_unique() {
+            self.buffer_pool.release(Vec::from(bytes));
+        }
+    }
+}
+
+/// Reads the body into `output`, followed by its trailers.
+async fn body_to_bytes(
+    body: SdkBody,
+    output: &mut Vec<u8>,
+) -> Result<Option<HeaderMap>, <SdkBody as Body>::Error> {
+    output.clear();
     pin_mut!(body);
     while let Some(buf) = body.data().await {
         let mut buf = buf?;
@@ -19,17 +548,181 @@ async fn body_to_bytes(body: SdkBody) -> Result<Bytes, <SdkBody as Body>::Error>
             buf.advance(buf.chunk().len())
         }
     }
+    body.trailers().await
+}
 
-    Ok(Bytes::from(output))
+/// If [response checksum validation](ValidateResponseChecksums) is enabled,
+/// wraps the body of the `response` so that reading it fails if it doesn't match the checksum in
+/// the response's headers.
+///
+/// The first `x-amz-checksum-*` header in [`CHECKSUM_ALGORITHMS_IN_PRIORITY_ORDER`] is used,
+/// falling back to `Content-MD5`.
+pub(crate) fn validate_response_checksum(
+    response: &mut HttpResponse,
+    cfg: &ConfigBag,
+) -> Result<(), UnknownChecksumAlgorithmError> {
+    if !cfg
+        .load::<ValidateResponseChecksums>()
+        .map_or(false, ValidateResponseChecksums::enabled)
+    {
+        return Ok(());
+    }
+    let mut algorithms = CHECKSUM_ALGORITHMS_IN_PRIORITY_ORDER
+        .iter()
+        .map(|name| name.parse::<ChecksumAlgorithm>())
+        .collect::<Result<Vec<_>, _>>()?;
+    algorithms.push(ChecksumAlgorithm::Md5);
+    let found = algorithms
+        .into_iter()
+        .map(ChecksumAlgorithm::into_impl)
+        .find_map(|checksum| {
+            let expected = response
+                .headers()
+                .get(checksum.header_name())?
+                .to_str()
+                .ok()?;
+            Some((checksum, expected.to_string()))
+        });
+    let (checksum, expected) = match found {
+        Some(found) => found,
+        None => return Ok(()),
+    };
+    // Checksums of multipart uploads are checksums of the parts' checksums, such as
+    // `ZXhhbXBsZQ==-3`, and can't be validated against the body
+    let expected = match base64::decode(&expected) {
+        Ok(expected) => expected,
+        Err(_) => {
+            tracing::debug!(
+                checksum = %expected,
+                "not validating the response body against a checksum that isn't base64"
+            );
+            return Ok(());
+        }
+    };
+    let body = std::mem::replace(response.body_mut(), SdkBody::taken());
+    *response.body_mut() = SdkBody::from_dyn(BoxBody::new(validate::ChecksumBody::new(
+        body,
+        checksum,
+        expected.into(),
+    )));
+    Ok(())
 }
 
-pub(crate) async fn read_body(response: &mut HttpResponse) -> Result<(), <SdkBody as Body>::Error> {
+pub(crate) async fn read_body(
+    response: &mut HttpResponse,
+    buffer_pool: Option<Arc<dyn BufferPool>>,
+) -> Result<(), <SdkBody as Body>::Error> {
     let mut body = SdkBody::taken();
     std::mem::swap(&mut body, response.body_mut());
 
-    let bytes = body_to_bytes(body).await?;
+    let (bytes, trailers) = match buffer_pool {
+        Some(buffer_pool) => {
+            let mut acquired = AcquiredBuffer {
+                buffer: buffer_pool.acquire(),
+                buffer_pool,
+            };
+            let trailers = body_to_bytes(body, &mut acquired.buffer).await?;
+            let bytes = Bytes::from(std::mem::take(&mut acquired.buffer));
+            response.extensions_mut().insert(PooledBuffer {
+                bytes: bytes.clone(),
+                buffer_pool: acquired.buffer_pool.clone(),
+            });
+            (bytes, trailers)
+        }
+        None => {
+            let mut buffer = Vec::new();
+            let trailers = body_to_bytes(body, &mut buffer).await?;
+            (Bytes::from(buffer), trailers)
+        }
+    };
+    if let Some(trailers) = trailers {
+        response
+            .extensions_mut()
+            .insert(ResponseTrailers::new(trailers));
+    }
     let mut body = SdkBody::from(bytes);
     std::mem::swap(&mut body, response.body_mut());
 
     Ok(())
 }
+
+/// Decompresses the buffered body of the response according to its `Content-Encoding`, if
+/// [`ResponseDecompression`] is enabled.
+///
+/// Decompressed responses have their `Content-Encoding` and `Content-Length` headers removed, since
+/// they no longer describe the body. Responses that decompress to more than the
+/// [`max_decompressed_size`](ResponseDecompression::max_decompressed_size) fail.
+pub(crate) fn decompress_body(
+    response: &mut HttpResponse,
+    cfg: &ConfigBag,
+) -> Result<(), BoxError> {
+    let decompression = match cfg.load::<ResponseDecompression>() {
+        Some(decompression) if !decompression.encodings().is_empty() => decompression,
+        _ => return Ok(()),
+    };
+    let encoding = match response.headers().get(http::header::CONTENT_ENCODING) {
+        Some(value) => String::from_utf8_lossy(value.as_bytes())
+            .trim()
+            .to_ascii_lowercase(),
+        None => return Ok(()),
+    };
+    if encoding == "identity" {
+        return Ok(());
+    }
+    let encoding = decompression
+        .encodings()
+        .iter()
+        .find(|allowed| allowed.as_str() == encoding)
+        .ok_or(UnsupportedContentEncoding(encoding))?;
+
+    let body = response.body().bytes().expect("body was read");
+    let max_size = decompression.max_decompressed_size();
+    // One byte more than the maximum is read, to tell a body of exactly the maximum size apart
+    // from a larger one
+    let limit = max_size.saturating_add(1);
+    let mut decompressed = Vec::new();
+    match encoding {
+        ContentEncoding::Gzip => GzDecoder::new(body)
+            .take(limit)
+            .read_to_end(&mut decompressed)?,
+        ContentEncoding::Deflate => ZlibDecoder::new(body)
+            .take(limit)
+            .read_to_end(&mut decompressed)?,
+        // The encoding is defined in another crate, so it may gain variants that aren't handled yet
+        encoding => return Err(UnsupportedContentEncoding(encoding.as_str().to_owned()).into()),
+    };
+    if decompressed.len() as u64 > max_size {
+        return Err(DecompressedResponseTooLarge { max_size }.into());
+    }
+    *response.body_mut() = SdkBody::from(decompressed);
+    response
+        .headers_mut()
+        .remove(http::header::CONTENT_ENCODING);
+    response.headers_mut().remove(http::header::CONTENT_LENGTH);
+    Ok(())
+}
+
+/// Releases the buffer that the response body was read into back to the [`BufferPool`], once the
+/// orchestrator is done with the response.
+///
+/// The body of a successfully deserialized response is left empty. The response of a failed
+/// request is returned to the caller as part of the error, so it's left with a copy of the body.
+pub(crate) fn release_body(ctx: &mut InterceptorContext) {
+    let succeeded = matches!(ctx.output_or_error(), Ok(Ok(_)));
+    let response = match ctx.response_mut() {
+        Ok(response) => response,
+        Err(_) => return,
+    };
+    let pooled_buffer = match response.extensions_mut().remove::<PooledBuffer>() {
+        Some(pooled_buffer) => pooled_buffer,
+        None => return,
+    };
+    // The body's references to the buffer are dropped first, so that it can be reclaimed
+    let body = std::mem::replace(response.body_mut(), SdkBody::taken());
+    if succeeded {
+        drop(body);
+    } else {
+        *response.body_mut() = body.bytes().map(SdkBody::from).unwrap_or(body);
+    }
+    drop(pooled_buffer);
+}
@@ -6,7 +6,8 @@
 use aws_smithy_http::result::{ConnectorError, SdkError};
 use aws_smithy_runtime_api::client::interceptors::context::{Error, Output};
 use aws_smithy_runtime_api::client::interceptors::InterceptorContext;
-use aws_smithy_runtime_api::client::orchestrator::{BoxError, HttpResponse};
+use aws_smithy_runtime_api::client::orchestrator::{BoxError, HttpResponse, PhaseObserver};
+use aws_smithy_runtime_api::config_bag::ConfigBag;
 
 #[derive(Copy, Clone, Eq, PartialEq)]
 enum OrchestrationPhase {
@@ -15,6 +16,16 @@ enum OrchestrationPhase {
     ResponseHandling,
 }
 
+impl OrchestrationPhase {
+    fn name(self) -> &'static str {
+        match self {
+            OrchestrationPhase::Construction => "construction",
+            OrchestrationPhase::Dispatch => "dispatch",
+            OrchestrationPhase::ResponseHandling => "response_handling",
+        }
+    }
+}
+
 pub(super) struct Phase {
     phase: OrchestrationPhase,
     context: InterceptorContext,
@@ -35,11 +46,22 @@ impl Phase {
         match phase {
             OrchestrationPhase::Construction => {}
             OrchestrationPhase::Dispatch => {}
-            OrchestrationPhase::ResponseHandling => debug_assert!(context.response().is_ok()),
+            // Failed dispatch attempts have no response, but they record their connector error
+            OrchestrationPhase::ResponseHandling => {
+                debug_assert!(context.response().is_ok() || context.output_or_error().is_ok())
+            }
         }
         Self { phase, context }
     }
 
+    /// Reports a snapshot of the context to the [`PhaseObserver`], if there is one.
+    pub(crate) fn observe(self, cfg: &ConfigBag) -> Self {
+        if let Some(phase_observer) = cfg.load::<Box<dyn PhaseObserver>>() {
+            phase_observer.observe(self.phase.name(), self.context.snapshot());
+        }
+        self
+    }
+
     pub(crate) fn include_mut<E: Into<BoxError>>(
         mut self,
         c: impl FnOnce(&mut InterceptorContext) -> Result<(), E>,
@@ -70,10 +92,11 @@ impl Phase {
         match output_or_error {
             Some(output_or_error) => match output_or_error {
                 Ok(output) => Ok(output),
-                Err(error) => Err(SdkError::service_error(
-                    error,
-                    response.expect("response must be set by this point"),
-                )),
+                Err(error) => match response {
+                    Some(response) => Err(SdkError::service_error(error, response)),
+                    // Without a response, the error is the connector error of the last attempt
+                    None => Err(dispatch_failure(error)),
+                },
             },
             None => unreachable!("phase can't get this far without bubbling up a failure"),
         }
@@ -103,7 +126,8 @@ impl Phase {
             OrchestrationPhase::ResponseHandling => match (response, output_or_error) {
                 (Some(response), Some(Err(error))) => SdkError::service_error(error, response),
                 (Some(response), _) => SdkError::response_error(e, response),
-                _ => unreachable!("response handling phase at least has a response"),
+                (None, Some(Err(error))) => dispatch_failure(error),
+                _ => unreachable!("response handling phase at least has a response or an error"),
             },
         }
     }
@@ -112,3 +136,15 @@ impl Phase {
         self.context
     }
 }
+
+fn dispatch_failure(error: Error) -> SdkError<Error, HttpResponse> {
+    let connector_error = match error.downcast::<ConnectorError>() {
+        Ok(connector_error) => *connector_error,
+        // Keep the source chain of boxed errors intact rather than flattening them into a message
+        Err(error) => match error.downcast::<BoxError>() {
+            Ok(error) => ConnectorError::other(*error, None),
+            Err(error) => ConnectorError::other(format!("{error:?}").into(), None),
+        },
+    };
+    SdkError::dispatch_failure(connector_error)
+}
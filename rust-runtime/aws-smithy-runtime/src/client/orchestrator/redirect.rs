@@ -0,0 +1,379 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::auth::sign_request;
+use super::call_connection;
+use super::http::count_request_body;
+use aws_smithy_http::body::SdkBody;
+use aws_smithy_runtime_api::client::interceptors::InterceptorContext;
+use aws_smithy_runtime_api::client::orchestrator::{
+    BoxError, HttpRequest, HttpResponse, RedirectPolicy,
+};
+use aws_smithy_runtime_api::config_bag::{ConfigBag, Storable, StoreReplace};
+use http::header::{
+    AUTHORIZATION, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, EXPECT, HOST, LOCATION,
+    PROXY_AUTHORIZATION, TRANSFER_ENCODING,
+};
+use http::uri::Scheme;
+use http::{HeaderMap, Method, StatusCode, Uri, Version};
+use std::fmt;
+
+/// The request of the current attempt as it was before it was signed, so that it can be signed
+/// again for the location that a response redirects it to.
+#[derive(Debug)]
+pub(super) struct UnsignedRequest {
+    method: Method,
+    version: Version,
+    headers: HeaderMap,
+    // `None` if the body can't be sent more than once
+    body: Option<SdkBody>,
+}
+
+impl Storable for UnsignedRequest {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Keeps a copy of the request before it's signed, if redirects are followed.
+pub(super) fn save_unsigned_request(
+    ctx: &InterceptorContext,
+    cfg: &mut ConfigBag,
+) -> Result<(), BoxError> {
+    if cfg.load::<RedirectPolicy>().is_none() {
+        return Ok(());
+    }
+    let request = ctx.request()?;
+    cfg.store_put(UnsignedRequest {
+        method: request.method().clone(),
+        version: request.version(),
+        headers: request.headers().clone(),
+        body: request.body().try_clone(),
+    });
+    Ok(())
+}
+
+#[derive(Debug)]
+enum RedirectError {
+    TooManyRedirects { max_redirects: u32 },
+    Loop { location: Uri },
+    HttpsDowngrade { location: Uri },
+    CrossHost { location: Uri },
+    InvalidLocation { location: String },
+    BodyNotReplayable,
+}
+
+impl fmt::Display for RedirectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RedirectError::TooManyRedirects { max_redirects } => {
+                write!(
+                    f,
+                    "the request was redirected more than {max_redirects} times"
+                )
+            }
+            RedirectError::Loop { location } => {
+                write!(
+                    f,
+                    "the request was redirected back to `{location}` in a loop"
+                )
+            }
+            RedirectError::HttpsDowngrade { location } => write!(
+                f,
+                "refused to follow a redirect from https to `{location}`, since downgrades \
+                to http aren't allowed"
+            ),
+            RedirectError::CrossHost { location } => write!(
+                f,
+                "refused to follow a redirect to `{location}`, since redirects to another host \
+                aren't allowed"
+            ),
+            RedirectError::InvalidLocation { location } => {
+                write!(f, "the redirect location `{location}` is invalid")
+            }
+            RedirectError::BodyNotReplayable => write!(
+                f,
+                "the request can't be redirected because its body can't be sent again; use a \
+                body that can be replayed (e.g. `SdkBody::retryable`) to follow redirects"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RedirectError {}
+
+/// Returns the location that `response` redirects the request for `uri` to, if it's a redirect.
+fn redirect_location(response: &HttpResponse, uri: &Uri) -> Result<Option<Uri>, RedirectError> {
+    let redirects = matches!(
+        response.status(),
+        StatusCode::MOVED_PERMANENTLY
+            | StatusCode::FOUND
+            | StatusCode::SEE_OTHER
+            | StatusCode::TEMPORARY_REDIRECT
+            | StatusCode::PERMANENT_REDIRECT
+    );
+    let location = match response.headers().get(LOCATION) {
+        Some(location) if redirects => location,
+        _ => return Ok(None),
+    };
+    let invalid = || RedirectError::InvalidLocation {
+        location: String::from_utf8_lossy(location.as_bytes()).into_owned(),
+    };
+    let location: Uri = location
+        .to_str()
+        .ok()
+        .and_then(|location| location.parse().ok())
+        .ok_or_else(invalid)?;
+    if location.scheme().is_some() && location.authority().is_some() {
+        return Ok(Some(location));
+    }
+    // A relative location is on the same host as the request that was redirected
+    let path_and_query = match location.path_and_query() {
+        Some(path_and_query) if path_and_query.as_str().starts_with('/') => path_and_query.clone(),
+        _ => return Err(invalid()),
+    };
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(path_and_query);
+    Uri::from_parts(parts).map(Some).map_err(|_| invalid())
+}
+
+/// Returns true if `location` is on a different host than `uri`.
+fn is_cross_host(uri: &Uri, location: &Uri) -> bool {
+    let host = |uri: &Uri| uri.host().map(str::to_ascii_lowercase);
+    host(uri) != host(location)
+}
+
+/// Builds the request for `location` that `status` redirected a `method` request to, from the
+/// unsigned request of the attempt. The credentials of the unsigned request aren't copied to a
+/// request for another host.
+fn redirected_request(
+    unsigned: &UnsignedRequest,
+    method: &Method,
+    status: StatusCode,
+    location: &Uri,
+    cross_host: bool,
+) -> Result<HttpRequest, RedirectError> {
+    // Like browsers do, `301` and `302` responses to `POST` requests are followed with a `GET`.
+    // Once a redirect changed the method, the body is no longer sent either.
+    let use_get = (status == StatusCode::SEE_OTHER && method != Method::HEAD)
+        || (matches!(status, StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND)
+            && method == Method::POST)
+        || method != unsigned.method;
+    let mut headers = unsigned.headers.clone();
+    // The host header, if any, is for the original location
+    headers.remove(HOST);
+    if cross_host {
+        for header in [AUTHORIZATION, PROXY_AUTHORIZATION, COOKIE] {
+            headers.remove(header);
+        }
+    }
+    let (method, body) = if use_get {
+        for header in [
+            CONTENT_ENCODING,
+            CONTENT_LENGTH,
+            CONTENT_TYPE,
+            EXPECT,
+            TRANSFER_ENCODING,
+        ] {
+            headers.remove(header);
+        }
+        (Method::GET, SdkBody::empty())
+    } else {
+        let body = unsigned
+            .body
+            .as_ref()
+            .and_then(SdkBody::try_clone)
+            .ok_or(RedirectError::BodyNotReplayable)?;
+        (unsigned.method.clone(), body)
+    };
+
+    let mut request = http::Request::builder()
+        .method(method)
+        .uri(location.clone())
+        .version(unsigned.version)
+        .body(body)
+        .expect("the parts of a valid request are valid");
+    *request.headers_mut() = headers;
+    Ok(request)
+}
+
+/// Follows the redirects of `response`, which is the response to a request for `uri`, according
+/// to the [`RedirectPolicy`](aws_smithy_runtime_api::client::orchestrator::RedirectPolicy), and
+/// returns the response that isn't a redirect.
+///
+/// Auth is resolved again for every redirected request, which is then signed, since the
+/// signature covers its location.
+pub(super) async fn follow_redirects(
+    mut response: HttpResponse,
+    mut uri: Uri,
+    fresh_connection: bool,
+    cfg: &ConfigBag,
+) -> Result<HttpResponse, BoxError> {
+    // The unsigned request isn't saved when requests are replayed without being signed
+    let (policy, unsigned) = match (
+        cfg.load::<RedirectPolicy>().copied(),
+        cfg.load::<UnsignedRequest>(),
+    ) {
+        (Some(policy), Some(unsigned)) if policy.max_redirects() > 0 => (policy, unsigned),
+        _ => return Ok(response),
+    };
+    let mut method = unsigned.method.clone();
+    // A `303` can redirect a request to the location it was sent to, but with another method,
+    // so only the same request for the same location is a loop
+    let mut visited = vec![(method.clone(), uri.clone())];
+    while let Some(location) = redirect_location(&response, &uri)? {
+        if visited.len() > policy.max_redirects() as usize {
+            return Err(RedirectError::TooManyRedirects {
+                max_redirects: policy.max_redirects(),
+            }
+            .into());
+        }
+        if uri.scheme() == Some(&Scheme::HTTPS)
+            && location.scheme() == Some(&Scheme::HTTP)
+            && !policy.allow_https_downgrade()
+        {
+            return Err(RedirectError::HttpsDowngrade { location }.into());
+        }
+        let cross_host = is_cross_host(&uri, &location);
+        if cross_host && !policy.allow_cross_host() {
+            return Err(RedirectError::CrossHost { location }.into());
+        }
+
+        let mut request =
+            redirected_request(unsigned, &method, response.status(), &location, cross_host)?;
+        if visited.contains(&(request.method().clone(), location.clone())) {
+            return Err(RedirectError::Loop { location }.into());
+        }
+        sign_request(&mut request, cfg).await?;
+        count_request_body(&mut request, cfg);
+        tracing::debug!(
+            status = response.status().as_u16(),
+            location = %location,
+            "following a redirect"
+        );
+        method = request.method().clone();
+        visited.push((method.clone(), location.clone()));
+        response = call_connection(request, fresh_connection, cfg).await?;
+        uri = location;
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redirect(status: u16, location: &str) -> HttpResponse {
+        http::Response::builder()
+            .status(status)
+            .header(LOCATION, location)
+            .body(SdkBody::empty())
+            .unwrap()
+    }
+
+    #[test]
+    fn relative_locations_are_resolved_against_the_request_uri() {
+        let uri: Uri = "https://example.com/a/b?c=d".parse().unwrap();
+        assert_eq!(
+            Some("https://example.com/moved?to=here".parse::<Uri>().unwrap()),
+            redirect_location(&redirect(307, "/moved?to=here"), &uri).unwrap()
+        );
+        assert_eq!(
+            Some("http://other.example.com/x".parse::<Uri>().unwrap()),
+            redirect_location(&redirect(301, "http://other.example.com/x"), &uri).unwrap()
+        );
+        // Not a redirect
+        assert_eq!(
+            None,
+            redirect_location(&redirect(200, "/moved"), &uri).unwrap()
+        );
+        assert!(redirect_location(&redirect(302, "moved"), &uri).is_err());
+    }
+
+    #[test]
+    fn see_other_redirects_are_followed_with_get() {
+        let unsigned = UnsignedRequest {
+            method: Method::POST,
+            version: Version::HTTP_11,
+            headers: [(CONTENT_LENGTH, "5".parse().unwrap())]
+                .into_iter()
+                .collect(),
+            body: None,
+        };
+        let location: Uri = "https://example.com/result".parse().unwrap();
+
+        let request = redirected_request(
+            &unsigned,
+            &Method::POST,
+            StatusCode::SEE_OTHER,
+            &location,
+            false,
+        )
+        .unwrap();
+        assert_eq!(Method::GET, request.method());
+        assert_eq!(&location, request.uri());
+        assert!(request.headers().get(CONTENT_LENGTH).is_none());
+
+        // The body would have to be sent again, but it can't be
+        let err = redirected_request(
+            &unsigned,
+            &Method::POST,
+            StatusCode::TEMPORARY_REDIRECT,
+            &location,
+            false,
+        )
+        .expect_err("the body can't be replayed");
+        assert!(matches!(err, RedirectError::BodyNotReplayable), "{err}");
+
+        // After being redirected with a `GET`, further redirects are followed with a `GET` too
+        let request = redirected_request(
+            &unsigned,
+            &Method::GET,
+            StatusCode::TEMPORARY_REDIRECT,
+            &location,
+            false,
+        )
+        .unwrap();
+        assert_eq!(Method::GET, request.method());
+    }
+
+    #[test]
+    fn credentials_are_not_sent_to_another_host() {
+        let unsigned = UnsignedRequest {
+            method: Method::GET,
+            version: Version::HTTP_11,
+            headers: [
+                (AUTHORIZATION, "Bearer secret".parse().unwrap()),
+                (COOKIE, "session=secret".parse().unwrap()),
+                (CONTENT_TYPE, "text/plain".parse().unwrap()),
+            ]
+            .into_iter()
+            .collect(),
+            body: Some(SdkBody::empty()),
+        };
+        let uri: Uri = "https://example.com/a".parse().unwrap();
+        let same_host: Uri = "https://EXAMPLE.com/b".parse().unwrap();
+        let other_host: Uri = "https://other.example.com/b".parse().unwrap();
+        assert!(!is_cross_host(&uri, &same_host));
+        assert!(is_cross_host(&uri, &other_host));
+
+        let redirect = |location: &Uri, cross_host| {
+            redirected_request(
+                &unsigned,
+                &Method::GET,
+                StatusCode::TEMPORARY_REDIRECT,
+                location,
+                cross_host,
+            )
+            .unwrap()
+        };
+        let request = redirect(&same_host, false);
+        assert!(request.headers().contains_key(AUTHORIZATION));
+        assert!(request.headers().contains_key(COOKIE));
+
+        let request = redirect(&other_host, true);
+        assert!(request.headers().get(AUTHORIZATION).is_none());
+        assert!(request.headers().get(COOKIE).is_none());
+        assert_eq!("text/plain", request.headers()[CONTENT_TYPE]);
+    }
+}
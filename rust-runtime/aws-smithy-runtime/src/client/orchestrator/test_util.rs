@@ -0,0 +1,41 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::{apply_configuration, checkpoint_request, prepare_attempt, serialize_input};
+use aws_smithy_http::result::SdkError;
+use aws_smithy_runtime_api::client::interceptors::context::{Error, Input};
+use aws_smithy_runtime_api::client::interceptors::{InterceptorContext, Interceptors};
+use aws_smithy_runtime_api::client::orchestrator::HttpResponse;
+use aws_smithy_runtime_api::client::runtime_plugin::RuntimePlugins;
+use aws_smithy_runtime_api::config_bag::ConfigBag;
+
+/// Runs the orchestrator for the given `input` up to the point where the request would be handed
+/// to the connection, and returns the resulting [`InterceptorContext`].
+///
+/// Every interceptor hook from `read_before_execution` through `read_before_transmit` is run,
+/// along with serialization and the steps of the first attempt, such as endpoint resolution and
+/// signing, but no request is sent. This makes it possible to unit test interceptors by inspecting
+/// the request they produced, without having to set up a connection.
+///
+/// Since only the first attempt is prepared, the retry strategy isn't asked whether to make it,
+/// and timeouts are not applied.
+pub async fn invoke_until_transmit(
+    input: Input,
+    runtime_plugins: &RuntimePlugins,
+) -> Result<InterceptorContext, SdkError<Error, HttpResponse>> {
+    let mut cfg = ConfigBag::base();
+    let cfg = &mut cfg;
+
+    let mut interceptors = Interceptors::new();
+    let context = apply_configuration(
+        InterceptorContext::new(input),
+        cfg,
+        &mut interceptors,
+        runtime_plugins,
+    )?;
+    let mut context = serialize_input(context, cfg, &interceptors)?;
+    checkpoint_request(&mut context, cfg);
+    prepare_attempt(1, context, cfg, &interceptors).await
+}
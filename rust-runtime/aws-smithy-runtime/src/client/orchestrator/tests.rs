@@ -0,0 +1,467 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::*;
+use crate::client::orchestrator::endpoints::StaticUriEndpointResolver;
+use crate::client::retries::strategy::NeverRetryStrategy;
+// Shadows the private `http` module that is glob imported from `super`
+use ::http;
+use aws_smithy_http::body::SdkBody;
+use aws_smithy_runtime_api::client::auth::option_resolver::{
+    StaticAuthOptionResolver, StaticAuthOptionResolverParams,
+};
+use aws_smithy_runtime_api::client::auth::{
+    AuthOptionResolverParams, AuthSchemeId, HttpAuthScheme, HttpAuthSchemes, HttpRequestSigner,
+};
+use aws_smithy_runtime_api::client::identity::{
+    AnonymousIdentityResolver, Identity, IdentityResolver, IdentityResolvers,
+};
+use aws_smithy_runtime_api::client::interceptors::context::OutputOrError;
+use aws_smithy_runtime_api::client::orchestrator::{
+    AsyncEndpointResolver, BoxFuture, EndpointResolverParams, RequestSerializer,
+    ResponseDeserializer, TimeSource, TraceProbe,
+};
+use aws_smithy_runtime_api::client::retries::{BackoffStrategy, RetryStrategy};
+use aws_smithy_runtime_api::client::runtime_plugin::RuntimePlugin;
+use aws_smithy_types::endpoint::Endpoint;
+use aws_smithy_types::error::display::DisplayErrorContext;
+use aws_smithy_types::retry::ProvideErrorKind;
+use bytes::Bytes;
+use http_body::Body;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+mod auth;
+mod caching;
+mod checksums;
+mod clock_skew;
+mod compression;
+mod connections;
+mod deserialization;
+mod endpoints;
+mod errors;
+mod event_streams;
+mod fault_injection;
+mod invoke;
+mod observability;
+mod redirects;
+mod retries;
+mod timeouts;
+
+const NO_AUTH_SCHEME_ID: AuthSchemeId = AuthSchemeId::new("no_auth");
+
+#[derive(Debug)]
+struct NoAuthSigner;
+
+impl HttpRequestSigner for NoAuthSigner {
+    fn sign_request(
+        &self,
+        _request: &mut HttpRequest,
+        _identity: &Identity,
+        _config_bag: &ConfigBag,
+    ) -> Result<(), BoxError> {
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct NoAuthScheme {
+    signer: NoAuthSigner,
+}
+
+impl HttpAuthScheme for NoAuthScheme {
+    fn scheme_id(&self) -> AuthSchemeId {
+        NO_AUTH_SCHEME_ID
+    }
+
+    fn identity_resolver<'a>(
+        &self,
+        identity_resolvers: &'a IdentityResolvers,
+    ) -> Option<&'a dyn IdentityResolver> {
+        identity_resolvers.identity_resolver(self.scheme_id())
+    }
+
+    fn request_signer(&self) -> &dyn HttpRequestSigner {
+        &self.signer
+    }
+}
+
+#[derive(Debug)]
+struct NoOpTraceProbe;
+
+impl TraceProbe for NoOpTraceProbe {
+    fn dispatch_events(&self) {}
+}
+
+/// Serializes a `String` input into the body of a `POST` request.
+#[derive(Debug)]
+struct StringSerializer;
+
+impl RequestSerializer for StringSerializer {
+    fn serialize_input(&self, input: Input) -> Result<HttpRequest, BoxError> {
+        let body = TypedBox::<String>::assume_from(input)
+            .expect("test input is a string")
+            .unwrap();
+        Ok(http::Request::builder()
+            .method("POST")
+            .uri("/")
+            .body(SdkBody::from(body))
+            .expect("valid request"))
+    }
+}
+
+/// A serializer that builds the request with the given closure.
+struct FnSerializer<F>(F);
+
+impl<F> fmt::Debug for FnSerializer<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FnSerializer")
+    }
+}
+
+impl<F> RequestSerializer for FnSerializer<F>
+where
+    F: Fn(Input) -> Result<HttpRequest, BoxError> + Send + Sync,
+{
+    fn serialize_input(&self, input: Input) -> Result<HttpRequest, BoxError> {
+        (self.0)(input)
+    }
+}
+
+/// The error returned by [`StatusDeserializer`] for non-2xx responses.
+#[derive(Debug, Eq, PartialEq)]
+struct TestError(u16);
+
+// Models 429s as throttling errors and 503s as transient errors
+impl ProvideErrorKind for TestError {
+    fn retryable_error_kind(&self) -> Option<ErrorKind> {
+        match self.0 {
+            429 => Some(ErrorKind::ThrottlingError),
+            503 => Some(ErrorKind::TransientError),
+            _ => None,
+        }
+    }
+
+    fn code(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Deserializes the body of 2xx responses into a `String` output, and
+/// all other responses into a [`TestError`] carrying the status code.
+#[derive(Debug)]
+struct StatusDeserializer;
+
+impl ResponseDeserializer for StatusDeserializer {
+    fn deserialize_nonstreaming(&self, response: &HttpResponse) -> OutputOrError {
+        if response.status().is_success() {
+            let body = response.body().bytes().expect("body was read");
+            Ok(TypedBox::new(String::from_utf8_lossy(body).to_string()).erase())
+        } else {
+            Err(TypedBox::new(TestError(response.status().as_u16())).erase())
+        }
+    }
+}
+
+/// A connection that replies with canned responses in order, and records the requests it received.
+#[derive(Clone, Debug, Default)]
+struct CannedConnection {
+    responses: Arc<Mutex<Vec<Result<HttpResponse, ConnectorError>>>>,
+    requests: Arc<Mutex<Vec<HttpRequest>>>,
+}
+
+impl CannedConnection {
+    fn new(mut responses: Vec<Result<HttpResponse, ConnectorError>>) -> Self {
+        responses.reverse();
+        Self {
+            responses: Arc::new(Mutex::new(responses)),
+            requests: Default::default(),
+        }
+    }
+
+    fn requests(&self) -> std::sync::MutexGuard<'_, Vec<HttpRequest>> {
+        self.requests.lock().unwrap()
+    }
+}
+
+impl Connection for CannedConnection {
+    fn call(&self, request: HttpRequest) -> BoxFuture<HttpResponse> {
+        self.requests.lock().unwrap().push(request);
+        let response = self
+            .responses
+            .lock()
+            .unwrap()
+            .pop()
+            .expect("a response was set for every request");
+        Box::pin(async move { response.map_err(BoxError::from) })
+    }
+}
+
+fn response(status: u16, body: &'static str) -> Result<HttpResponse, ConnectorError> {
+    Ok(http::Response::builder()
+        .status(status)
+        .body(SdkBody::from(body))
+        .expect("valid response"))
+}
+
+/// Retries every error response until `max_attempts` have been made.
+#[derive(Debug)]
+struct FixedAttemptsRetryStrategy {
+    max_attempts: usize,
+    attempts: AtomicUsize,
+}
+
+impl FixedAttemptsRetryStrategy {
+    fn new(max_attempts: usize) -> Self {
+        Self {
+            max_attempts,
+            attempts: AtomicUsize::new(1),
+        }
+    }
+}
+
+impl RetryStrategy for FixedAttemptsRetryStrategy {
+    fn should_attempt_initial_request(&self, _cfg: &ConfigBag) -> Result<ShouldAttempt, BoxError> {
+        Ok(ShouldAttempt::Yes)
+    }
+
+    fn should_attempt_retry(
+        &self,
+        context: &InterceptorContext,
+        _cfg: &ConfigBag,
+    ) -> Result<ShouldAttempt, BoxError> {
+        let failed = matches!(context.output_or_error(), Ok(Err(_)));
+        if failed && self.attempts.fetch_add(1, Ordering::SeqCst) < self.max_attempts {
+            Ok(ShouldAttempt::Yes)
+        } else {
+            Ok(ShouldAttempt::No)
+        }
+    }
+
+    fn max_attempts(&self) -> Option<u32> {
+        Some(self.max_attempts as u32)
+    }
+}
+
+/// Configures everything an operation needs except for the connection.
+struct TestOperationPlugin;
+
+impl RuntimePlugin for TestOperationPlugin {
+    fn configure(
+        &self,
+        cfg: &mut ConfigBag,
+        _interceptors: &mut Interceptors,
+    ) -> Result<(), BoxError> {
+        cfg.set_request_serializer(StringSerializer);
+        cfg.set_response_deserializer(StatusDeserializer);
+        cfg.set_endpoint_resolver(StaticUriEndpointResolver::http_localhost(8080));
+        cfg.set_endpoint_resolver_params(EndpointResolverParams::new(()));
+        cfg.set_auth_option_resolver_params(AuthOptionResolverParams::new(
+            StaticAuthOptionResolverParams::new(),
+        ));
+        cfg.set_auth_option_resolver(StaticAuthOptionResolver::new(vec![NO_AUTH_SCHEME_ID]));
+        cfg.set_identity_resolvers(
+            IdentityResolvers::builder()
+                .identity_resolver(NO_AUTH_SCHEME_ID, AnonymousIdentityResolver::new())
+                .build(),
+        );
+        cfg.set_http_auth_schemes(
+            HttpAuthSchemes::builder()
+                .auth_scheme(
+                    NO_AUTH_SCHEME_ID,
+                    NoAuthScheme {
+                        signer: NoAuthSigner,
+                    },
+                )
+                .build(),
+        );
+        cfg.set_retry_strategy(NeverRetryStrategy::new());
+        cfg.set_trace_probe(NoOpTraceProbe);
+        Ok(())
+    }
+}
+
+/// A runtime plugin that runs the given closure, used by tests to override the defaults
+/// set by [`TestOperationPlugin`].
+struct FnPlugin<F>(F);
+
+impl<F> RuntimePlugin for FnPlugin<F>
+where
+    F: Fn(&mut ConfigBag, &mut Interceptors),
+{
+    fn configure(
+        &self,
+        cfg: &mut ConfigBag,
+        interceptors: &mut Interceptors,
+    ) -> Result<(), BoxError> {
+        (self.0)(cfg, interceptors);
+        Ok(())
+    }
+}
+
+fn test_plugins(configure: impl Fn(&mut ConfigBag, &mut Interceptors) + 'static) -> RuntimePlugins {
+    RuntimePlugins::new()
+        .with_client_plugin(TestOperationPlugin)
+        .with_operation_plugin(FnPlugin(configure))
+}
+
+fn test_input(body: &str) -> Input {
+    TypedBox::new(body.to_string()).erase()
+}
+
+/// Formats the full source chain of an error returned by [`invoke`].
+fn display_error(err: SdkError<Error, HttpResponse>) -> String {
+    #[derive(Debug)]
+    struct ErasedError(String);
+    impl fmt::Display for ErasedError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(&self.0)
+        }
+    }
+    impl std::error::Error for ErasedError {}
+
+    let err = err.map_service_error(|err| ErasedError(format!("{err:?}")));
+    format!("{}", DisplayErrorContext(&err))
+}
+
+fn output_string(output: Output) -> String {
+    TypedBox::<String>::assume_from(output)
+        .expect("test output is a string")
+        .unwrap()
+}
+
+/// Doubles the delay after every failed attempt, starting at `base`.
+#[derive(Debug)]
+struct ExponentialBackoff {
+    base: Duration,
+}
+
+impl BackoffStrategy for ExponentialBackoff {
+    fn backoff(&self, attempt: u32, _cfg: &ConfigBag) -> Duration {
+        self.base * 2u32.pow(attempt - 1)
+    }
+}
+
+/// A [`TimeSource`] that follows Tokio's clock, so that it advances with paused time.
+#[derive(Debug)]
+struct TokioTimeSource {
+    start: SystemTime,
+    started_at: tokio::time::Instant,
+}
+
+impl TokioTimeSource {
+    fn new() -> Self {
+        Self {
+            start: SystemTime::UNIX_EPOCH,
+            started_at: tokio::time::Instant::now(),
+        }
+    }
+}
+
+impl TimeSource for TokioTimeSource {
+    fn now(&self) -> SystemTime {
+        self.start + self.started_at.elapsed()
+    }
+}
+
+/// An endpoint resolver that looks the endpoint up after a simulated delay.
+#[derive(Debug)]
+struct DiscoveryEndpointResolver {
+    delay: Duration,
+}
+
+impl AsyncEndpointResolver for DiscoveryEndpointResolver {
+    fn resolve_endpoint(
+        &self,
+        _params: &EndpointResolverParams,
+    ) -> aws_smithy_runtime_api::client::orchestrator::Future<Endpoint> {
+        let delay = self.delay;
+        aws_smithy_runtime_api::client::orchestrator::Future::new(Box::pin(async move {
+            tokio::time::sleep(delay).await;
+            Ok(Endpoint::builder()
+                .url("https://discovered.example.com")
+                .build())
+        }))
+    }
+}
+
+/// A [`TimeSource`] that only moves when it's told to.
+#[derive(Clone, Debug)]
+struct ManualTimeSource(Arc<Mutex<SystemTime>>);
+
+impl ManualTimeSource {
+    fn new() -> Self {
+        Self(Arc::new(Mutex::new(SystemTime::UNIX_EPOCH)))
+    }
+
+    fn advance(&self, duration: Duration) {
+        *self.0.lock().unwrap() += duration;
+    }
+}
+
+impl TimeSource for ManualTimeSource {
+    fn now(&self) -> SystemTime {
+        *self.0.lock().unwrap()
+    }
+}
+
+/// A body followed by a `checksum` trailer.
+struct ChecksummedBody {
+    data: Option<Bytes>,
+    checksum: Option<&'static str>,
+}
+
+impl Body for ChecksummedBody {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_data(
+        mut self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Result<Self::Data, Self::Error>>> {
+        std::task::Poll::Ready(self.data.take().map(Ok))
+    }
+
+    fn poll_trailers(
+        mut self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        let trailers = self.checksum.take().map(|checksum| {
+            let mut trailers = http::HeaderMap::new();
+            trailers.insert("checksum", http::HeaderValue::from_static(checksum));
+            trailers
+        });
+        std::task::Poll::Ready(Ok(trailers))
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        let length = self.data.as_ref().map(Bytes::len).unwrap_or_default();
+        http_body::SizeHint::with_exact(length as u64)
+    }
+}
+
+/// A body fed by a channel, standing in for an event stream that's written to over time.
+struct ChannelBody(tokio::sync::mpsc::UnboundedReceiver<Bytes>);
+
+impl Body for ChannelBody {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_data(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Result<Self::Data, Self::Error>>> {
+        self.0.poll_recv(cx).map(|chunk| chunk.map(Ok))
+    }
+
+    fn poll_trailers(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        std::task::Poll::Ready(Ok(None))
+    }
+}
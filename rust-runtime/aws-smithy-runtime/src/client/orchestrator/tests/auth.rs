@@ -0,0 +1,493 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::*;
+use aws_smithy_async::rt::sleep::TokioSleep;
+use aws_smithy_runtime_api::client::interceptors::Interceptor;
+use aws_smithy_runtime_api::client::orchestrator::SigningRetryConfig;
+
+#[derive(Debug)]
+struct TestSigner;
+
+impl HttpRequestSigner for TestSigner {
+    fn sign_request(
+        &self,
+        request: &mut HttpRequest,
+        _identity: &Identity,
+        _config_bag: &ConfigBag,
+    ) -> Result<(), BoxError> {
+        request.headers_mut().insert(
+            "authorization",
+            http::HeaderValue::from_static("TestSignature abc123"),
+        );
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct TestSigningScheme {
+    signer: TestSigner,
+}
+
+impl HttpAuthScheme for TestSigningScheme {
+    fn scheme_id(&self) -> AuthSchemeId {
+        NO_AUTH_SCHEME_ID
+    }
+
+    fn identity_resolver<'a>(
+        &self,
+        identity_resolvers: &'a IdentityResolvers,
+    ) -> Option<&'a dyn IdentityResolver> {
+        identity_resolvers.identity_resolver(self.scheme_id())
+    }
+
+    fn request_signer(&self) -> &dyn HttpRequestSigner {
+        &self.signer
+    }
+}
+
+/// Captures the serialized request right before it's transmitted.
+#[derive(Debug, Default)]
+struct CaptureRequestBytes {
+    captured: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Interceptor for CaptureRequestBytes {
+    fn read_before_transmit(
+        &self,
+        context: &InterceptorContext,
+        _cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        *self.captured.lock().unwrap() = context.request_bytes(true)?;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn signed_request_bytes_can_be_captured_before_transmit() {
+    let connection = CannedConnection::new(vec![response(200, "done")]);
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let runtime_plugins = test_plugins({
+        let (connection, captured) = (connection.clone(), captured.clone());
+        move |cfg, interceptors| {
+            cfg.set_connection(connection.clone());
+            cfg.set_http_auth_schemes(
+                HttpAuthSchemes::builder()
+                    .auth_scheme(NO_AUTH_SCHEME_ID, TestSigningScheme { signer: TestSigner })
+                    .build(),
+            );
+            interceptors.register_operation_interceptor(Arc::new(CaptureRequestBytes {
+                captured: captured.clone(),
+            }));
+        }
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    let captured = String::from_utf8(captured.lock().unwrap().clone()).unwrap();
+    assert!(
+        captured.starts_with("POST / HTTP/1.1\r\nhost: localhost:8080\r\n"),
+        "{captured}"
+    );
+    assert!(
+        captured.contains("\r\nauthorization: TestSignature abc123\r\n"),
+        "{captured}"
+    );
+    assert!(captured.ends_with("\r\n\r\nhello"), "{captured}");
+}
+
+/// A signer that signs the request's query string by copying it into a header.
+#[derive(Debug)]
+struct QuerySigner;
+
+impl HttpRequestSigner for QuerySigner {
+    fn sign_request(
+        &self,
+        request: &mut HttpRequest,
+        _identity: &Identity,
+        _config_bag: &ConfigBag,
+    ) -> Result<(), BoxError> {
+        let signed_query = request.uri().query().unwrap_or_default().to_string();
+        request
+            .headers_mut()
+            .insert("x-signed-query", signed_query.parse()?);
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct QuerySigningScheme {
+    signer: QuerySigner,
+}
+
+impl HttpAuthScheme for QuerySigningScheme {
+    fn scheme_id(&self) -> AuthSchemeId {
+        NO_AUTH_SCHEME_ID
+    }
+
+    fn identity_resolver<'a>(
+        &self,
+        identity_resolvers: &'a IdentityResolvers,
+    ) -> Option<&'a dyn IdentityResolver> {
+        identity_resolvers.identity_resolver(self.scheme_id())
+    }
+
+    fn request_signer(&self) -> &dyn HttpRequestSigner {
+        &self.signer
+    }
+}
+
+/// Adds an `api-version` query parameter to every request.
+#[derive(Debug)]
+struct ApiVersionInterceptor;
+
+impl Interceptor for ApiVersionInterceptor {
+    fn modify_before_signing(
+        &self,
+        context: &mut InterceptorContext,
+        _cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let request = context.request_mut()?;
+        let uri = match request.uri().query() {
+            Some(query) => format!("{}?{query}&api-version=2023-01-01", request.uri().path()),
+            None => format!("{}?api-version=2023-01-01", request.uri().path()),
+        };
+        let mut parts = request.uri().clone().into_parts();
+        parts.path_and_query = Some(uri.parse()?);
+        *request.uri_mut() = http::Uri::from_parts(parts)?;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn query_params_added_before_signing_are_signed() {
+    let connection = CannedConnection::new(vec![response(200, "done")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, interceptors| {
+            cfg.set_connection(connection.clone());
+            cfg.set_http_auth_schemes(
+                HttpAuthSchemes::builder()
+                    .auth_scheme(
+                        NO_AUTH_SCHEME_ID,
+                        QuerySigningScheme {
+                            signer: QuerySigner,
+                        },
+                    )
+                    .build(),
+            );
+            interceptors.register_operation_interceptor(Arc::new(ApiVersionInterceptor));
+        }
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    let requests = connection.requests();
+    assert_eq!(Some("api-version=2023-01-01"), requests[0].uri().query());
+    assert_eq!(
+        "api-version=2023-01-01",
+        requests[0].headers()["x-signed-query"]
+    );
+}
+
+/// Resolves credentials named `creds-1`, `creds-2`, etc., caching each until it's invalidated.
+#[derive(Debug, Default)]
+struct CachingIdentityResolver {
+    cached: Mutex<Option<Identity>>,
+    resolutions: AtomicUsize,
+}
+
+impl IdentityResolver for CachingIdentityResolver {
+    fn resolve_identity(
+        &self,
+        _config_bag: &ConfigBag,
+    ) -> aws_smithy_runtime_api::client::orchestrator::Future<Identity> {
+        let identity = self
+            .cached
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| {
+                let resolution = self.resolutions.fetch_add(1, Ordering::SeqCst) + 1;
+                Identity::new(format!("creds-{resolution}"), None)
+            })
+            .clone();
+        aws_smithy_runtime_api::client::orchestrator::Future::ready(Ok(identity))
+    }
+
+    fn invalidate_cached_identity(&self) {
+        self.cached.lock().unwrap().take();
+    }
+}
+
+/// Signs requests by putting the identity's credentials in the `authorization` header.
+#[derive(Debug)]
+struct CredentialsSigner;
+
+impl HttpRequestSigner for CredentialsSigner {
+    fn sign_request(
+        &self,
+        request: &mut HttpRequest,
+        identity: &Identity,
+        _config_bag: &ConfigBag,
+    ) -> Result<(), BoxError> {
+        let credentials = identity.data::<String>().expect("credentials are a string");
+        request
+            .headers_mut()
+            .insert("authorization", credentials.parse()?);
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct CredentialsSigningScheme {
+    signer: CredentialsSigner,
+}
+
+impl HttpAuthScheme for CredentialsSigningScheme {
+    fn scheme_id(&self) -> AuthSchemeId {
+        NO_AUTH_SCHEME_ID
+    }
+
+    fn identity_resolver<'a>(
+        &self,
+        identity_resolvers: &'a IdentityResolvers,
+    ) -> Option<&'a dyn IdentityResolver> {
+        identity_resolvers.identity_resolver(self.scheme_id())
+    }
+
+    fn request_signer(&self) -> &dyn HttpRequestSigner {
+        &self.signer
+    }
+}
+
+#[tokio::test]
+async fn retries_after_auth_errors_use_refreshed_credentials() {
+    let connection = CannedConnection::new(vec![response(401, ""), response(200, "done")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(2));
+            cfg.set_identity_resolvers(
+                IdentityResolvers::builder()
+                    .identity_resolver(NO_AUTH_SCHEME_ID, CachingIdentityResolver::default())
+                    .build(),
+            );
+            cfg.set_http_auth_schemes(
+                HttpAuthSchemes::builder()
+                    .auth_scheme(
+                        NO_AUTH_SCHEME_ID,
+                        CredentialsSigningScheme {
+                            signer: CredentialsSigner,
+                        },
+                    )
+                    .build(),
+            );
+        }
+    });
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("the retry succeeds with refreshed credentials");
+    assert_eq!("done", output_string(output));
+    let authorizations: Vec<_> = connection
+        .requests()
+        .iter()
+        .map(|request| request.headers()["authorization"].clone())
+        .collect();
+    assert_eq!(vec!["creds-1", "creds-2"], authorizations);
+}
+
+/// Records the signing time that's visible after signing.
+#[derive(Debug, Default)]
+struct RecordSigningTime(Arc<Mutex<Vec<Option<SigningTime>>>>);
+
+impl Interceptor for RecordSigningTime {
+    fn read_after_signing(
+        &self,
+        _context: &InterceptorContext,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        self.0
+            .lock()
+            .unwrap()
+            .push(cfg.load::<SigningTime>().copied());
+        Ok(())
+    }
+}
+
+fn signing_time_plugins(
+    time_source: &ManualTimeSource,
+    clock_skew: Option<ClockSkew>,
+    signing_times: &Arc<Mutex<Vec<Option<SigningTime>>>>,
+) -> RuntimePlugins {
+    let (time_source, signing_times) = (time_source.clone(), signing_times.clone());
+    test_plugins(move |cfg, interceptors| {
+        cfg.set_connection(CannedConnection::new(vec![response(200, "done")]));
+        cfg.store_put::<Box<dyn TimeSource>>(Box::new(time_source.clone()));
+        if let Some(clock_skew) = clock_skew {
+            cfg.store_put(clock_skew);
+        }
+        interceptors
+            .register_operation_interceptor(Arc::new(RecordSigningTime(signing_times.clone())));
+    })
+}
+
+#[tokio::test]
+async fn signing_time_is_read_from_the_time_source() {
+    let time_source = ManualTimeSource::new();
+    time_source.advance(Duration::from_secs(1_000_000));
+    let signing_times = Arc::new(Mutex::new(Vec::new()));
+    let runtime_plugins = signing_time_plugins(&time_source, None, &signing_times);
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    let signing_time = signing_times.lock().unwrap()[0].expect("signing time was recorded");
+    let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+    assert_eq!(now, signing_time.raw());
+    assert_eq!(None, signing_time.corrected());
+    assert_eq!(now, signing_time.time());
+}
+
+#[tokio::test]
+async fn signing_time_records_the_clock_skew_correction() {
+    let time_source = ManualTimeSource::new();
+    time_source.advance(Duration::from_secs(1_000_000));
+    let signing_times = Arc::new(Mutex::new(Vec::new()));
+    let clock_skew = ClockSkew::Ahead(Duration::from_secs(300));
+    let runtime_plugins = signing_time_plugins(&time_source, Some(clock_skew), &signing_times);
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    let signing_time = signing_times.lock().unwrap()[0].expect("signing time was recorded");
+    let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+    assert_eq!(now, signing_time.raw());
+    assert_eq!(
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(999_700)),
+        signing_time.corrected()
+    );
+    assert_eq!(signing_time.corrected(), Some(signing_time.time()));
+}
+
+/// An identity resolver that fails the first `failures` times it's called.
+#[derive(Clone, Debug)]
+struct FlakyIdentityResolver {
+    failures: usize,
+    transient: bool,
+    calls: Arc<AtomicUsize>,
+}
+
+impl IdentityResolver for FlakyIdentityResolver {
+    fn resolve_identity(
+        &self,
+        _config_bag: &ConfigBag,
+    ) -> aws_smithy_runtime_api::client::orchestrator::Future<Identity> {
+        let result = if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+            Err(match self.transient {
+                true => std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "the credential provider timed out",
+                )
+                .into(),
+                false => "the credential provider is unavailable".into(),
+            })
+        } else {
+            Ok(Identity::new("credentials", None))
+        };
+        aws_smithy_runtime_api::client::orchestrator::Future::ready(result)
+    }
+}
+
+fn flaky_identity_plugins(
+    connection: &CannedConnection,
+    identity_resolver: &FlakyIdentityResolver,
+    signing_retry_config: Option<SigningRetryConfig>,
+) -> RuntimePlugins {
+    let (connection, identity_resolver) = (connection.clone(), identity_resolver.clone());
+    test_plugins(move |cfg, _| {
+        cfg.set_connection(connection.clone());
+        cfg.set_identity_resolvers(
+            IdentityResolvers::builder()
+                .identity_resolver(NO_AUTH_SCHEME_ID, identity_resolver.clone())
+                .build(),
+        );
+        cfg.set_sleep_impl(Some(Arc::new(TokioSleep::new())));
+        if let Some(signing_retry_config) = signing_retry_config {
+            cfg.store_put(signing_retry_config);
+        }
+    })
+}
+
+#[tokio::test]
+async fn identity_resolution_is_retried_before_signing() {
+    tokio::time::pause();
+    let connection = CannedConnection::new(vec![response(200, "done")]);
+    let identity_resolver = FlakyIdentityResolver {
+        failures: 1,
+        transient: true,
+        calls: Default::default(),
+    };
+    let runtime_plugins = flaky_identity_plugins(
+        &connection,
+        &identity_resolver,
+        Some(SigningRetryConfig::new(3)),
+    );
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("done", output_string(output));
+    assert_eq!(2, identity_resolver.calls.load(Ordering::SeqCst));
+    assert_eq!(1, connection.requests().len());
+}
+
+#[tokio::test]
+async fn identity_resolution_is_not_retried_by_default() {
+    let connection = CannedConnection::new(vec![response(200, "done")]);
+    let identity_resolver = FlakyIdentityResolver {
+        failures: 1,
+        transient: true,
+        calls: Default::default(),
+    };
+    let runtime_plugins = flaky_identity_plugins(&connection, &identity_resolver, None);
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("the identity couldn't be resolved");
+    let err = display_error(err);
+    assert!(err.contains("the credential provider timed out"), "{err}");
+    assert_eq!(1, identity_resolver.calls.load(Ordering::SeqCst));
+    assert_eq!(0, connection.requests().len());
+}
+
+#[tokio::test]
+async fn only_transient_identity_resolution_failures_are_retried() {
+    let connection = CannedConnection::new(vec![response(200, "done")]);
+    let identity_resolver = FlakyIdentityResolver {
+        failures: 1,
+        transient: false,
+        calls: Default::default(),
+    };
+    let runtime_plugins = flaky_identity_plugins(
+        &connection,
+        &identity_resolver,
+        Some(SigningRetryConfig::new(3)),
+    );
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("the identity couldn't be resolved");
+    let err = display_error(err);
+    assert!(
+        err.contains("the credential provider is unavailable"),
+        "{err}"
+    );
+    assert_eq!(1, identity_resolver.calls.load(Ordering::SeqCst));
+    assert_eq!(0, connection.requests().len());
+}
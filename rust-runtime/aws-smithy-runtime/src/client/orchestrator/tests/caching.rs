@@ -0,0 +1,105 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::*;
+use crate::client::response_cache::InMemoryResponseCache;
+use aws_smithy_runtime_api::client::orchestrator::ResponseCache;
+
+/// Caches outputs for a minute, keyed by the input string.
+fn response_caching_plugins(
+    connection: &CannedConnection,
+    cache: &Arc<InMemoryResponseCache<String>>,
+    time_source: &ManualTimeSource,
+) -> RuntimePlugins {
+    let (connection, cache, time_source) = (connection.clone(), cache.clone(), time_source.clone());
+    test_plugins(move |cfg, _| {
+        cfg.set_connection(connection.clone());
+        cfg.store_put::<Box<dyn TimeSource>>(Box::new(time_source.clone()));
+        cfg.store_put(ResponseCaching::new(cache.clone(), |input| {
+            input.downcast_ref::<String>().cloned()
+        }));
+    })
+}
+
+fn response_cache() -> Arc<InMemoryResponseCache<String>> {
+    Arc::new(InMemoryResponseCache::new(Duration::from_secs(60)))
+}
+
+#[tokio::test]
+async fn cache_hits_are_returned_without_dispatching() {
+    let connection = CannedConnection::new(vec![]);
+    let cache = response_cache();
+    let time_source = ManualTimeSource::new();
+    cache.put(
+        "hello".to_string(),
+        &TypedBox::new("cached".to_string()).erase(),
+        time_source.now(),
+    );
+    let runtime_plugins = response_caching_plugins(&connection, &cache, &time_source);
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("cache hit");
+    assert_eq!("cached", output_string(output));
+    assert!(connection.requests().is_empty());
+}
+
+#[tokio::test]
+async fn cache_misses_populate_the_cache() {
+    let connection = CannedConnection::new(vec![response(200, "fresh")]);
+    let cache = response_cache();
+    let time_source = ManualTimeSource::new();
+    let runtime_plugins = response_caching_plugins(&connection, &cache, &time_source);
+
+    for _ in 0..2 {
+        let output = invoke(test_input("hello"), &runtime_plugins)
+            .await
+            .expect("success");
+        assert_eq!("fresh", output_string(output));
+    }
+    assert_eq!(1, connection.requests().len());
+    let cached = cache
+        .get("hello", time_source.now())
+        .expect("the output was cached");
+    assert_eq!("fresh", output_string(cached));
+}
+
+#[tokio::test]
+async fn failed_responses_are_not_cached() {
+    let connection = CannedConnection::new(vec![response(500, ""), response(200, "fresh")]);
+    let cache = response_cache();
+    let time_source = ManualTimeSource::new();
+    let runtime_plugins = response_caching_plugins(&connection, &cache, &time_source);
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("service error");
+    assert!(cache.get("hello", time_source.now()).is_none());
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("fresh", output_string(output));
+    assert_eq!(2, connection.requests().len());
+}
+
+#[tokio::test]
+async fn expired_entries_are_fetched_again() {
+    let connection = CannedConnection::new(vec![response(200, "first"), response(200, "second")]);
+    let cache = response_cache();
+    let time_source = ManualTimeSource::new();
+    let runtime_plugins = response_caching_plugins(&connection, &cache, &time_source);
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("first", output_string(output));
+
+    time_source.advance(Duration::from_secs(60));
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("second", output_string(output));
+    assert_eq!(2, connection.requests().len());
+}
@@ -0,0 +1,203 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::*;
+use aws_smithy_checksums::ChecksumAlgorithm;
+use aws_smithy_http::body::BoxBody;
+use aws_smithy_runtime_api::client::interceptors::Interceptor;
+use aws_smithy_runtime_api::client::orchestrator::{
+    ContentEncoding, RequestChecksumAlgorithm, RequestCompression, ResponseTrailers,
+    ValidateResponseChecksums,
+};
+
+async fn invoke_with_request_checksum(
+    configure: impl Fn(&mut ConfigBag) + Send + Sync + 'static,
+) -> HttpRequest {
+    let connection = CannedConnection::new(vec![response(200, "done")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.store_put(RequestChecksumAlgorithm::Crc32);
+            configure(cfg);
+        }
+    });
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    let request = connection.requests().pop().expect("one request was sent");
+    request
+}
+
+#[tokio::test]
+async fn request_checksums_are_sent_in_a_header() {
+    let request = invoke_with_request_checksum(|_| {}).await;
+    assert_eq!("NhCmhg==", request.headers()["x-amz-checksum-crc32"]);
+    assert!(request.headers().get("x-amz-trailer").is_none());
+}
+
+#[tokio::test]
+async fn request_checksums_cover_the_compressed_body() {
+    let request = invoke_with_request_checksum(|cfg| {
+        cfg.store_put(RequestCompression::new(ContentEncoding::Gzip, 0));
+    })
+    .await;
+    let mut checksum = ChecksumAlgorithm::Crc32.into_impl();
+    checksum.update(request.body().bytes().expect("in-memory body"));
+    assert_eq!(
+        aws_smithy_types::base64::encode(checksum.finalize()),
+        request.headers()["x-amz-checksum-crc32"]
+    );
+}
+
+#[tokio::test]
+async fn request_checksums_of_streaming_bodies_are_sent_in_an_aws_chunked_trailer() {
+    let mut request = invoke_with_request_checksum(|cfg| {
+        cfg.store_put(ReplayableBody::new(|| {
+            SdkBody::from_dyn(BoxBody::new(ChecksummedBody {
+                data: Some(Bytes::from_static(b"hello")),
+                checksum: None,
+            }))
+        }));
+    })
+    .await;
+    let expected_body = "5\r\nhello\r\n0\r\nx-amz-checksum-crc32:NhCmhg==\r\n\r\n";
+    let headers = request.headers();
+    assert!(headers.get("x-amz-checksum-crc32").is_none());
+    assert_eq!("x-amz-checksum-crc32", headers["x-amz-trailer"]);
+    assert_eq!("aws-chunked", headers["content-encoding"]);
+    assert_eq!("5", headers["x-amz-decoded-content-length"]);
+    assert_eq!(
+        expected_body.len().to_string(),
+        headers["content-length"].to_str().unwrap()
+    );
+
+    let body = request.body_mut();
+    let mut sent = Vec::new();
+    while let Some(chunk) = body.data().await {
+        sent.extend_from_slice(&chunk.unwrap());
+    }
+    assert_eq!(expected_body, String::from_utf8(sent).unwrap());
+    assert!(body.trailers().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn request_checksums_of_streaming_bodies_need_a_known_length() {
+    let connection = CannedConnection::new(vec![response(200, "done")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.store_put(RequestChecksumAlgorithm::Crc32);
+            cfg.store_put(ReplayableBody::new(|| {
+                let (_sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+                SdkBody::from_dyn(BoxBody::new(ChannelBody(receiver)))
+            }));
+        }
+    });
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("the length of the body isn't known");
+    assert!(
+        display_error(err).contains("needs the length of the body to be known up front"),
+        "the error explains why the request wasn't sent"
+    );
+    assert!(connection.requests().is_empty());
+}
+
+/// Deserializes the body, followed by the value of its `checksum` trailer.
+#[derive(Debug)]
+struct ChecksumDeserializer;
+
+impl ResponseDeserializer for ChecksumDeserializer {
+    fn deserialize_nonstreaming(&self, response: &HttpResponse) -> OutputOrError {
+        let body = response.body().bytes().expect("body was read");
+        let checksum = response
+            .extensions()
+            .get::<ResponseTrailers>()
+            .and_then(|trailers| trailers.headers().get("checksum"))
+            .map(|checksum| checksum.to_str().unwrap().to_string());
+        let output = format!("{} {:?}", String::from_utf8_lossy(body), checksum);
+        Ok(TypedBox::new(output).erase())
+    }
+}
+
+/// Records the `checksum` trailer as seen by `read_after_deserialization`.
+#[derive(Debug)]
+struct RecordChecksumTrailer(Arc<Mutex<Option<String>>>);
+
+impl Interceptor for RecordChecksumTrailer {
+    fn read_after_deserialization(
+        &self,
+        context: &InterceptorContext,
+        _cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        *self.0.lock().unwrap() = context
+            .response_trailers()
+            .and_then(|trailers| trailers.get("checksum"))
+            .map(|checksum| checksum.to_str().unwrap().to_string());
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn response_trailers_are_available_after_the_body_is_read() {
+    let seen = Arc::new(Mutex::new(None));
+    let runtime_plugins = test_plugins({
+        let seen = seen.clone();
+        move |cfg, interceptors| {
+            let body = ChecksummedBody {
+                data: Some(Bytes::from_static(b"hello")),
+                checksum: Some("abc123"),
+            };
+            cfg.set_connection(CannedConnection::new(vec![Ok(http::Response::builder()
+                .status(200)
+                .body(SdkBody::from_dyn(BoxBody::new(body)))
+                .unwrap())]));
+            cfg.set_response_deserializer(ChecksumDeserializer);
+            interceptors
+                .register_operation_interceptor(Arc::new(RecordChecksumTrailer(seen.clone())));
+        }
+    });
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!(r#"hello Some("abc123")"#, output_string(output));
+    assert_eq!(Some("abc123".to_string()), seen.lock().unwrap().take());
+}
+
+/// Invokes an operation whose response to `hello` is `body`, with a CRC32 checksum of `done`.
+async fn invoke_with_checksum(body: &'static str) -> Result<Output, SdkError<Error, HttpResponse>> {
+    let mut checksum = ChecksumAlgorithm::Crc32.into_impl();
+    checksum.update(b"done");
+    let checksum = aws_smithy_types::base64::encode(checksum.finalize());
+    let runtime_plugins = test_plugins(move |cfg, _| {
+        let response = http::Response::builder()
+            .status(200)
+            .header("x-amz-checksum-crc32", checksum.as_str())
+            .body(SdkBody::from(body))
+            .unwrap();
+        cfg.set_connection(CannedConnection::new(vec![Ok(response)]));
+        cfg.store_put(ValidateResponseChecksums::new(true));
+    });
+    invoke(test_input("hello"), &runtime_plugins).await
+}
+
+#[tokio::test]
+async fn response_bodies_matching_their_checksum_are_read() {
+    let output = invoke_with_checksum("done").await.expect("success");
+    assert_eq!("done", output_string(output));
+}
+
+#[tokio::test]
+async fn response_bodies_not_matching_their_checksum_fail() {
+    let err = invoke_with_checksum("dome")
+        .await
+        .expect_err("the body was corrupted");
+    let message = display_error(err);
+    assert!(message.contains("body checksum mismatch"), "{message}");
+}
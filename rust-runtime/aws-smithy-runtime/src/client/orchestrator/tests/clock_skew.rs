@@ -0,0 +1,207 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::*;
+
+/// A signer that signs the request by writing the signing time into a header.
+#[derive(Debug)]
+struct SigningTimeSigner;
+
+impl HttpRequestSigner for SigningTimeSigner {
+    fn sign_request(
+        &self,
+        request: &mut HttpRequest,
+        _identity: &Identity,
+        config_bag: &ConfigBag,
+    ) -> Result<(), BoxError> {
+        let signed_at = config_bag
+            .load::<SigningTime>()
+            .copied()
+            .ok_or("the signing time is recorded before signing")?
+            .time()
+            .duration_since(SystemTime::UNIX_EPOCH)?
+            .as_secs();
+        request
+            .headers_mut()
+            .insert("x-signed-at", signed_at.to_string().parse()?);
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct SigningTimeSigningScheme {
+    signer: SigningTimeSigner,
+}
+
+impl HttpAuthScheme for SigningTimeSigningScheme {
+    fn scheme_id(&self) -> AuthSchemeId {
+        NO_AUTH_SCHEME_ID
+    }
+
+    fn identity_resolver<'a>(
+        &self,
+        identity_resolvers: &'a IdentityResolvers,
+    ) -> Option<&'a dyn IdentityResolver> {
+        identity_resolvers.identity_resolver(self.scheme_id())
+    }
+
+    fn request_signer(&self) -> &dyn HttpRequestSigner {
+        &self.signer
+    }
+}
+
+/// Classifies 403s as clock skew rejections, as a protocol would by their error code.
+#[derive(Debug)]
+struct ForbiddenIsClockSkew;
+
+impl ClassifyClockSkew for ForbiddenIsClockSkew {
+    fn is_clock_skew_error(&self, error: &Error) -> bool {
+        error.downcast_ref::<TestError>() == Some(&TestError(403))
+    }
+}
+
+fn clock_skew_plugins(
+    connection: &CannedConnection,
+    correct_clock_skew: bool,
+    max_attempts: usize,
+) -> RuntimePlugins {
+    let connection = connection.clone();
+    test_plugins(move |cfg, _| {
+        cfg.set_connection(connection.clone());
+        let time_source = ManualTimeSource::new();
+        time_source.advance(Duration::from_secs(1_000_000));
+        cfg.store_put::<Box<dyn TimeSource>>(Box::new(time_source));
+        cfg.set_http_auth_schemes(
+            HttpAuthSchemes::builder()
+                .auth_scheme(
+                    NO_AUTH_SCHEME_ID,
+                    SigningTimeSigningScheme {
+                        signer: SigningTimeSigner,
+                    },
+                )
+                .build(),
+        );
+        cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(max_attempts));
+        cfg.store_put(CorrectClockSkew::new(correct_clock_skew));
+        cfg.store_put::<Box<dyn ClassifyClockSkew>>(Box::new(ForbiddenIsClockSkew));
+    })
+}
+
+/// A rejection from a service whose clock is five minutes ahead of the local clock.
+fn clock_skew_rejection() -> Result<HttpResponse, ConnectorError> {
+    // 1,000,300 seconds after the epoch
+    rejection_at(
+        "Mon, 12 Jan 1970 13:51:40 GMT",
+        "<Error><Code>RequestTimeTooSkewed</Code></Error>",
+    )
+}
+
+fn rejection_at(date: &str, body: &str) -> Result<HttpResponse, ConnectorError> {
+    Ok(http::Response::builder()
+        .status(403)
+        .header("date", date)
+        .body(SdkBody::from(body))
+        .expect("valid response"))
+}
+
+/// Invokes an operation whose first attempt gets `rejection`, and returns the times its
+/// attempts were signed at.
+async fn signing_times_after(
+    rejection: Result<HttpResponse, ConnectorError>,
+    correct_clock_skew: bool,
+) -> Vec<String> {
+    let connection = CannedConnection::new(vec![rejection, response(200, "done")]);
+    let runtime_plugins = clock_skew_plugins(&connection, correct_clock_skew, 2);
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("the rejection is retried");
+    let requests = connection.requests();
+    requests
+        .iter()
+        .map(|request| {
+            request.headers()["x-signed-at"]
+                .to_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn requests_rejected_for_clock_skew_are_retried_at_the_corrected_time() {
+    let signing_times = signing_times_after(clock_skew_rejection(), true).await;
+    assert_eq!(vec!["1000000", "1000300"], signing_times);
+}
+
+#[tokio::test]
+async fn clock_skew_is_only_corrected_when_enabled() {
+    let signing_times = signing_times_after(clock_skew_rejection(), false).await;
+    assert_eq!(vec!["1000000", "1000000"], signing_times);
+}
+
+#[tokio::test]
+async fn clock_skew_rejections_are_only_retried_if_the_retry_strategy_allows_it() {
+    let connection = CannedConnection::new(vec![clock_skew_rejection()]);
+    let runtime_plugins = clock_skew_plugins(&connection, true, 1);
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("the rejection isn't retried");
+    assert_eq!(1, connection.requests().len());
+}
+
+#[tokio::test]
+async fn clock_skew_is_only_corrected_for_errors_classified_as_clock_skew() {
+    let rejection = Ok(http::Response::builder()
+        .status(401)
+        .header("date", "Mon, 12 Jan 1970 13:51:40 GMT")
+        .body(SdkBody::from(
+            "<Error><Code>RequestTimeTooSkewed</Code></Error>",
+        ))
+        .expect("valid response"));
+    assert_eq!(
+        vec!["1000000", "1000000"],
+        signing_times_after(rejection, true).await
+    );
+}
+
+#[tokio::test]
+async fn clock_skew_is_not_corrected_without_a_classifier() {
+    let connection = CannedConnection::new(vec![clock_skew_rejection(), response(200, "done")]);
+    let runtime_plugins = clock_skew_plugins(&connection, true, 2).with_operation_plugin(FnPlugin(
+        |cfg: &mut ConfigBag, _: &mut Interceptors| {
+            cfg.unset::<Box<dyn ClassifyClockSkew>>();
+        },
+    ));
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("the rejection is retried");
+    let signing_times: Vec<_> = connection
+        .requests()
+        .iter()
+        .map(|request| {
+            request.headers()["x-signed-at"]
+                .to_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    assert_eq!(vec!["1000000", "1000000"], signing_times);
+}
+
+#[tokio::test]
+async fn clock_skew_within_the_threshold_is_not_corrected() {
+    // 1,000,100 seconds after the epoch
+    let rejection = rejection_at(
+        "Mon, 12 Jan 1970 13:48:20 GMT",
+        "<Error><Code>RequestTimeTooSkewed</Code></Error>",
+    );
+    assert_eq!(
+        vec!["1000000", "1000000"],
+        signing_times_after(rejection, true).await
+    );
+}
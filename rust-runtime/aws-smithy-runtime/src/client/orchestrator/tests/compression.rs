@@ -0,0 +1,124 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::*;
+use aws_smithy_runtime_api::client::orchestrator::{
+    ContentEncoding, RequestCompression, ResponseDecompression,
+};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+
+async fn invoke_with_encoded_response(
+    content_encoding: &'static str,
+    body: Vec<u8>,
+) -> Result<Output, SdkError<Error, HttpResponse>> {
+    let runtime_plugins = test_plugins(move |cfg, _| {
+        let response = http::Response::builder()
+            .header("content-encoding", content_encoding)
+            .body(SdkBody::from(body.clone()))
+            .expect("valid response");
+        cfg.set_connection(CannedConnection::new(vec![Ok(response)]));
+        cfg.store_put(ResponseDecompression::new(vec![ContentEncoding::Gzip]));
+    });
+    invoke(test_input("hello"), &runtime_plugins).await
+}
+
+#[tokio::test]
+async fn gzip_responses_are_decompressed_before_deserialization() {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(b"decompressed").unwrap();
+    let body = encoder.finish().unwrap();
+
+    let output = invoke_with_encoded_response("gzip", body)
+        .await
+        .expect("success");
+    assert_eq!("decompressed", output_string(output));
+}
+
+#[tokio::test]
+async fn responses_that_decompress_to_more_than_the_maximum_size_fail() {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&[0; 1024]).unwrap();
+    let body = encoder.finish().unwrap();
+
+    for (max_size, succeeds) in [(1024, true), (1023, false)] {
+        let runtime_plugins = test_plugins({
+            let body = body.clone();
+            move |cfg, _| {
+                let response = http::Response::builder()
+                    .header("content-encoding", "gzip")
+                    .body(SdkBody::from(body.clone()))
+                    .expect("valid response");
+                cfg.set_connection(CannedConnection::new(vec![Ok(response)]));
+                cfg.store_put(
+                    ResponseDecompression::new(vec![ContentEncoding::Gzip])
+                        .with_max_decompressed_size(max_size),
+                );
+            }
+        });
+        let result = invoke(test_input("hello"), &runtime_plugins).await;
+        match (result, succeeds) {
+            (Ok(output), true) => assert_eq!(1024, output_string(output).len()),
+            (Err(err), false) => {
+                let message = display_error(err);
+                assert!(
+                    message.contains("decompresses to more than the maximum of 1023 bytes"),
+                    "{message}"
+                );
+            }
+            (result, _) => panic!("unexpected result with a maximum of {max_size}: {result:?}"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn unsupported_encodings_fail_to_deserialize() {
+    let err = invoke_with_encoded_response("br", b"compressed".to_vec())
+        .await
+        .expect_err("brotli isn't in the allowlist");
+    let message = display_error(err);
+    assert!(
+        message.contains("unsupported `Content-Encoding` of `br`"),
+        "{message}"
+    );
+}
+
+async fn invoke_with_request_compression(body: &str) -> HttpRequest {
+    let connection = CannedConnection::new(vec![response(200, "done")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.store_put(RequestCompression::new(ContentEncoding::Gzip, 64));
+        }
+    });
+    invoke(test_input(body), &runtime_plugins)
+        .await
+        .expect("success");
+    let request = connection.requests().pop().expect("one request was sent");
+    request
+}
+
+#[tokio::test]
+async fn large_request_bodies_are_compressed() {
+    let body = "a".repeat(1024);
+    let request = invoke_with_request_compression(&body).await;
+    assert_eq!("gzip", request.headers()["content-encoding"]);
+
+    let mut decompressed = String::new();
+    GzDecoder::new(request.body().bytes().expect("in-memory body"))
+        .read_to_string(&mut decompressed)
+        .unwrap();
+    assert_eq!(body, decompressed);
+}
+
+#[tokio::test]
+async fn small_request_bodies_are_left_alone() {
+    let request = invoke_with_request_compression("hello").await;
+    assert!(request.headers().get("content-encoding").is_none());
+    assert_eq!(Some(&b"hello"[..]), request.body().bytes());
+}
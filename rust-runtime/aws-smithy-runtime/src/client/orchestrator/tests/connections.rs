@@ -0,0 +1,295 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::*;
+use aws_smithy_runtime_api::client::interceptors::Interceptor;
+
+fn expect_continue_plugins(connection: &CannedConnection) -> RuntimePlugins {
+    test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(2));
+            cfg.set_request_serializer(FnSerializer(|_input: Input| {
+                Ok(http::Request::builder()
+                    .uri("/")
+                    .header("expect", "100-continue")
+                    .body(SdkBody::from("a large upload"))
+                    .expect("valid request"))
+            }));
+        }
+    })
+}
+
+#[tokio::test]
+async fn rejected_expect_continue_fails_early() {
+    let connection = CannedConnection::new(vec![response(417, "")]);
+    let runtime_plugins = expect_continue_plugins(&connection);
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("the server rejected the expectation");
+    let response_error = match err {
+        SdkError::ResponseError(response_error) => response_error,
+        err => panic!("expected a response error, got {err:?}"),
+    };
+    assert_eq!(417, response_error.raw().status().as_u16());
+    let message = display_error(SdkError::ResponseError(response_error));
+    assert!(message.contains("Expectation Failed"), "{message}");
+    // The rejection isn't retried
+    assert_eq!(1, connection.requests().len());
+}
+
+#[tokio::test]
+async fn accepted_expect_continue_is_deserialized() {
+    let connection = CannedConnection::new(vec![response(200, "uploaded")]);
+    let runtime_plugins = expect_continue_plugins(&connection);
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("uploaded", output_string(output));
+}
+
+#[tokio::test]
+async fn expectation_failed_without_expect_continue_is_a_service_error() {
+    let connection = CannedConnection::new(vec![response(417, "")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| cfg.set_connection(connection.clone())
+    });
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("417 is an error status");
+    assert!(matches!(err, SdkError::ServiceError(_)), "{err:?}");
+}
+
+#[tokio::test]
+async fn concurrency_hint_is_attached_to_requests() {
+    let connection = CannedConnection::new(vec![response(200, "done")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.store_put(ConcurrencyHint::new(64));
+        }
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    let hint = connection.requests()[0]
+        .extensions()
+        .get::<ConcurrencyHint>()
+        .copied();
+    assert_eq!(Some(ConcurrencyHint::new(64)), hint);
+}
+
+#[tokio::test]
+async fn concurrency_hint_is_absent_by_default() {
+    let connection = CannedConnection::new(vec![response(200, "done")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| cfg.set_connection(connection.clone())
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert!(connection.requests()[0]
+        .extensions()
+        .get::<ConcurrencyHint>()
+        .is_none());
+}
+
+/// A connection that reports a simulated DNS resolution duration for every request, and then
+/// replies like a [`CannedConnection`].
+#[derive(Debug)]
+struct DnsReportingConnection {
+    inner: CannedConnection,
+    dns_durations: Mutex<Vec<Duration>>,
+}
+
+impl Connection for DnsReportingConnection {
+    fn call(&self, request: HttpRequest) -> BoxFuture<HttpResponse> {
+        let duration = self.dns_durations.lock().unwrap().remove(0);
+        request
+            .extensions()
+            .get::<DnsTiming>()
+            .expect("the orchestrator attaches a DNS timing")
+            .record(duration);
+        self.inner.call(request)
+    }
+}
+
+/// Records the DNS resolution duration of every attempt.
+#[derive(Debug)]
+struct RecordDnsResolutionDuration(Arc<Mutex<Vec<Option<Duration>>>>);
+
+impl Interceptor for RecordDnsResolutionDuration {
+    fn read_after_attempt(
+        &self,
+        _context: &InterceptorContext,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let duration = cfg.load::<DnsTiming>().and_then(DnsTiming::duration);
+        self.0.lock().unwrap().push(duration);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn dns_resolution_duration_is_reported_on_success_and_failure() {
+    let durations = Arc::new(Mutex::new(Vec::new()));
+    let runtime_plugins = test_plugins({
+        let durations = durations.clone();
+        move |cfg, interceptors| {
+            cfg.set_connection(DnsReportingConnection {
+                inner: CannedConnection::new(vec![
+                    Err(ConnectorError::io("connection refused".into())),
+                    response(200, "done"),
+                ]),
+                dns_durations: Mutex::new(vec![Duration::from_millis(5), Duration::from_millis(3)]),
+            });
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(2));
+            interceptors.register_operation_interceptor(Arc::new(RecordDnsResolutionDuration(
+                durations.clone(),
+            )));
+        }
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!(
+        vec![
+            Some(Duration::from_millis(5)),
+            Some(Duration::from_millis(3))
+        ],
+        *durations.lock().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn dns_resolution_duration_is_none_when_not_reported() {
+    let durations = Arc::new(Mutex::new(Vec::new()));
+    let runtime_plugins = test_plugins({
+        let durations = durations.clone();
+        move |cfg, interceptors| {
+            cfg.set_connection(CannedConnection::new(vec![response(200, "done")]));
+            interceptors.register_operation_interceptor(Arc::new(RecordDnsResolutionDuration(
+                durations.clone(),
+            )));
+        }
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!(vec![None], *durations.lock().unwrap());
+}
+
+/// A connection that records whether it was prewarmed or called, in order.
+#[derive(Clone, Debug)]
+struct PrewarmRecordingConnection {
+    inner: CannedConnection,
+    events: Arc<Mutex<Vec<String>>>,
+}
+
+impl Connection for PrewarmRecordingConnection {
+    fn call(&self, request: HttpRequest) -> BoxFuture<HttpResponse> {
+        self.events.lock().unwrap().push("call".into());
+        self.inner.call(request)
+    }
+
+    fn prewarm(&self, uri: &http::Uri) -> BoxFuture<()> {
+        self.events.lock().unwrap().push(format!("prewarm {uri}"));
+        Box::pin(async { Err("the host is unreachable".into()) })
+    }
+}
+
+async fn invoke_recording_prewarm(prewarm: bool) -> Vec<String> {
+    let connection = PrewarmRecordingConnection {
+        inner: CannedConnection::new(vec![response(200, "done")]),
+        events: Default::default(),
+    };
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.store_put(ConnectionConfig::new().with_prewarm(prewarm));
+        }
+    });
+    // Prewarm failures don't fail the operation
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    let events = connection.events.lock().unwrap().clone();
+    events
+}
+
+#[tokio::test]
+async fn prewarm_precedes_the_first_attempt_when_enabled() {
+    assert_eq!(
+        vec!["prewarm http://localhost:8080/", "call"],
+        invoke_recording_prewarm(true).await
+    );
+}
+
+#[tokio::test]
+async fn connections_are_not_prewarmed_by_default() {
+    assert_eq!(vec!["call"], invoke_recording_prewarm(false).await);
+}
+
+/// A keepalive-aware connection, which reports the HTTP/2 ping interval it was asked to use.
+#[derive(Clone, Debug, Default)]
+struct KeepAliveConnection(Arc<Mutex<Vec<Option<Duration>>>>);
+
+impl Connection for KeepAliveConnection {
+    fn call(&self, request: HttpRequest) -> BoxFuture<HttpResponse> {
+        let ping_interval = request
+            .extensions()
+            .get::<KeepAliveConfig>()
+            .and_then(|keep_alive_config| keep_alive_config.http2_ping_interval());
+        self.0.lock().unwrap().push(ping_interval);
+        Box::pin(async { response(200, "done").map_err(BoxError::from) })
+    }
+}
+
+async fn ping_intervals(keep_alive_config: Option<KeepAliveConfig>) -> Vec<Option<Duration>> {
+    let connection = KeepAliveConnection::default();
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            if let Some(keep_alive_config) = keep_alive_config {
+                cfg.store_put(keep_alive_config);
+            }
+        }
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    let ping_intervals = connection.0.lock().unwrap().clone();
+    ping_intervals
+}
+
+#[tokio::test]
+async fn keep_alive_config_is_passed_to_the_connection() {
+    let keep_alive_config = KeepAliveConfig::new()
+        .with_tcp_keepalive(Duration::from_secs(60))
+        .with_http2_ping_interval(Duration::from_secs(20));
+    assert_eq!(
+        vec![Some(Duration::from_secs(20))],
+        ping_intervals(Some(keep_alive_config)).await
+    );
+}
+
+#[tokio::test]
+async fn keep_alive_config_is_absent_by_default() {
+    assert_eq!(vec![None], ping_intervals(None).await);
+}
@@ -0,0 +1,238 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::*;
+use aws_smithy_runtime_api::client::orchestrator::{BufferedResponseThreshold, ExpectedOutputType};
+
+/// A pool that keeps released buffers for reuse, and counts how they were used.
+#[derive(Clone, Debug, Default)]
+struct InstrumentedBufferPool {
+    free: Arc<Mutex<Vec<Vec<u8>>>>,
+    acquired: Arc<AtomicUsize>,
+    reused: Arc<AtomicUsize>,
+    released: Arc<AtomicUsize>,
+}
+
+impl BufferPool for InstrumentedBufferPool {
+    fn acquire(&self) -> Vec<u8> {
+        self.acquired.fetch_add(1, Ordering::SeqCst);
+        match self.free.lock().unwrap().pop() {
+            Some(buffer) => {
+                self.reused.fetch_add(1, Ordering::SeqCst);
+                buffer
+            }
+            None => Vec::new(),
+        }
+    }
+
+    fn release(&self, buffer: Vec<u8>) {
+        self.released.fetch_add(1, Ordering::SeqCst);
+        self.free.lock().unwrap().push(buffer);
+    }
+}
+
+#[tokio::test]
+async fn response_bodies_are_read_into_pooled_buffers() {
+    let connection = CannedConnection::new(vec![response(200, "one"), response(200, "two")]);
+    let pool = InstrumentedBufferPool::default();
+    let runtime_plugins = test_plugins({
+        let (connection, pool) = (connection.clone(), pool.clone());
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.store_put::<Arc<dyn BufferPool>>(Arc::new(pool.clone()));
+        }
+    });
+
+    for expected in ["one", "two"] {
+        let output = invoke(test_input("hello"), &runtime_plugins)
+            .await
+            .expect("success");
+        assert_eq!(expected, output_string(output));
+    }
+    assert_eq!(2, pool.acquired.load(Ordering::SeqCst));
+    assert_eq!(2, pool.released.load(Ordering::SeqCst));
+    // The buffer released by the first invocation was reused by the second
+    assert_eq!(1, pool.reused.load(Ordering::SeqCst));
+    assert_eq!(1, pool.free.lock().unwrap().len());
+}
+
+#[tokio::test]
+async fn pooled_buffers_are_released_when_attempts_fail() {
+    let connection = CannedConnection::new(vec![response(500, "retried"), response(500, "failed")]);
+    let pool = InstrumentedBufferPool::default();
+    let runtime_plugins = test_plugins({
+        let (connection, pool) = (connection.clone(), pool.clone());
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(2));
+            cfg.store_put::<Arc<dyn BufferPool>>(Arc::new(pool.clone()));
+        }
+    });
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("both attempts fail");
+    assert_eq!(2, pool.acquired.load(Ordering::SeqCst));
+    assert_eq!(2, pool.released.load(Ordering::SeqCst));
+    // The buffer of the retried attempt was reused by the next one
+    assert_eq!(1, pool.reused.load(Ordering::SeqCst));
+    // The response returned with the error keeps a copy of its body
+    let response = err.raw_response().expect("the response is returned");
+    assert_eq!(Some(&b"failed"[..]), response.body().bytes());
+}
+
+#[tokio::test]
+async fn pooled_buffers_are_released_when_reading_the_body_fails() {
+    // Polling a taken body fails
+    let connection = CannedConnection::new(vec![Ok(http::Response::builder()
+        .status(200)
+        .body(SdkBody::taken())
+        .expect("valid response"))]);
+    let pool = InstrumentedBufferPool::default();
+    pool.free.lock().unwrap().push(Vec::with_capacity(64));
+    let runtime_plugins = test_plugins({
+        let (connection, pool) = (connection.clone(), pool.clone());
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.store_put::<Arc<dyn BufferPool>>(Arc::new(pool.clone()));
+        }
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("the body can't be read");
+    assert_eq!(1, pool.acquired.load(Ordering::SeqCst));
+    assert_eq!(1, pool.released.load(Ordering::SeqCst));
+    assert!(pool.free.lock().unwrap()[0].capacity() >= 64);
+}
+
+/// Streams every success response as `"streamed"`, or buffers it into a `String` output when
+/// it isn't streamed.
+#[derive(Debug)]
+struct StreamingDeserializer;
+
+impl ResponseDeserializer for StreamingDeserializer {
+    fn deserialize_streaming(&self, response: &mut HttpResponse) -> Option<OutputOrError> {
+        response
+            .status()
+            .is_success()
+            .then(|| Ok(TypedBox::new("streamed".to_string()).erase()))
+    }
+
+    fn deserialize_nonstreaming(&self, response: &HttpResponse) -> OutputOrError {
+        StatusDeserializer.deserialize_nonstreaming(response)
+    }
+}
+
+async fn invoke_with_content_length(content_length: usize, threshold: Option<u64>) -> String {
+    let runtime_plugins = test_plugins(move |cfg, _| {
+        let response = http::Response::builder()
+            .header("content-length", content_length)
+            .body(SdkBody::from("a".repeat(content_length)))
+            .expect("valid response");
+        cfg.set_connection(CannedConnection::new(vec![Ok(response)]));
+        cfg.set_response_deserializer(StreamingDeserializer);
+        if let Some(threshold) = threshold {
+            cfg.store_put(BufferedResponseThreshold::new(threshold));
+        }
+    });
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    output_string(output)
+}
+
+#[tokio::test]
+async fn small_responses_are_buffered_below_the_threshold() {
+    assert_eq!("aaaaa", invoke_with_content_length(5, Some(1024)).await);
+}
+
+#[tokio::test]
+async fn large_responses_are_streamed_above_the_threshold() {
+    assert_eq!(
+        "streamed",
+        invoke_with_content_length(2048, Some(1024)).await
+    );
+}
+
+#[tokio::test]
+async fn responses_are_streamed_without_a_threshold() {
+    assert_eq!("streamed", invoke_with_content_length(5, None).await);
+}
+
+/// A deserializer that returns a `u32` where a `String` output is expected.
+#[derive(Debug)]
+struct MismatchedDeserializer;
+
+impl ResponseDeserializer for MismatchedDeserializer {
+    fn deserialize_nonstreaming(&self, _response: &HttpResponse) -> OutputOrError {
+        Ok(TypedBox::new(42u32).erase())
+    }
+}
+
+#[tokio::test]
+async fn mismatched_output_types_fail_with_both_type_names() {
+    let runtime_plugins = test_plugins(|cfg, _| {
+        cfg.set_connection(CannedConnection::new(vec![response(200, "done")]));
+        cfg.set_response_deserializer(MismatchedDeserializer);
+        cfg.store_put(ExpectedOutputType::of::<String>());
+    });
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("the output isn't a string");
+    let message = display_error(err);
+    assert!(message.contains("of type `u32`"), "{message}");
+    assert!(
+        message.contains("but `alloc::string::String` was expected"),
+        "{message}"
+    );
+}
+
+#[tokio::test]
+async fn matching_output_types_succeed() {
+    let runtime_plugins = test_plugins(|cfg, _| {
+        cfg.set_connection(CannedConnection::new(vec![response(200, "done")]));
+        cfg.store_put(ExpectedOutputType::of::<String>());
+    });
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("done", output_string(output));
+}
+
+/// Deserializes the error responses of a service whose errors don't look like its outputs.
+#[derive(Debug)]
+struct NotFoundDeserializer;
+
+impl ResponseDeserializer for NotFoundDeserializer {
+    fn deserialize_nonstreaming(&self, _response: &HttpResponse) -> OutputOrError {
+        Err(TypedBox::new("not found".to_string()).erase())
+    }
+}
+
+async fn invoke_with_status_deserializers(status: u16, body: &'static str) -> String {
+    let runtime_plugins = test_plugins(move |cfg, _| {
+        cfg.set_connection(CannedConnection::new(vec![response(status, body)]));
+        cfg.store_put(StatusDeserializers::new().with_deserializer(404, NotFoundDeserializer));
+    });
+
+    match invoke(test_input("hello"), &runtime_plugins).await {
+        Ok(output) => output_string(output),
+        Err(err) => display_error(err),
+    }
+}
+
+#[tokio::test]
+async fn status_deserializers_handle_their_status() {
+    let message = invoke_with_status_deserializers(404, "<html>").await;
+    assert!(message.contains("not found"), "{message}");
+}
+
+#[tokio::test]
+async fn other_statuses_fall_back_to_the_default_deserializer() {
+    assert_eq!("done", invoke_with_status_deserializers(200, "done").await);
+    let message = invoke_with_status_deserializers(500, "").await;
+    assert!(message.contains("TestError(500)"), "{message}");
+}
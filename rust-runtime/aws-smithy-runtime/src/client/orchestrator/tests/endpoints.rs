@@ -0,0 +1,127 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::*;
+use aws_smithy_http::endpoint::EndpointPrefix;
+use aws_smithy_runtime_api::client::orchestrator::{
+    EndpointFailover, EndpointResolver, PreresolvedEndpoint,
+};
+
+#[tokio::test]
+async fn async_endpoint_resolvers_are_awaited() {
+    tokio::time::pause();
+    let connection = CannedConnection::new(vec![response(200, "done")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.store_put::<Box<dyn AsyncEndpointResolver>>(Box::new(DiscoveryEndpointResolver {
+                delay: Duration::from_millis(100),
+            }));
+        }
+    });
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("done", output_string(output));
+    let requests = connection.requests();
+    assert_eq!(Some("discovered.example.com"), requests[0].uri().host());
+}
+
+/// An endpoint resolver that counts how many times it's called, and fails.
+#[derive(Debug, Default)]
+struct CountingEndpointResolver {
+    calls: Arc<AtomicUsize>,
+}
+
+impl EndpointResolver for CountingEndpointResolver {
+    fn resolve_and_apply_endpoint(
+        &self,
+        _params: &EndpointResolverParams,
+        _endpoint_prefix: Option<&EndpointPrefix>,
+        _request: &mut HttpRequest,
+    ) -> Result<(), BoxError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Err("the endpoint resolver must not be called".into())
+    }
+}
+
+#[tokio::test]
+async fn preresolved_endpoints_bypass_endpoint_resolution() {
+    let connection = CannedConnection::new(vec![response(200, "done")]);
+    let calls = Arc::new(AtomicUsize::new(0));
+    let runtime_plugins = test_plugins({
+        let (connection, calls) = (connection.clone(), calls.clone());
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_endpoint_resolver(CountingEndpointResolver {
+                calls: calls.clone(),
+            });
+            cfg.store_put::<Box<dyn AsyncEndpointResolver>>(Box::new(DiscoveryEndpointResolver {
+                delay: Duration::ZERO,
+            }));
+            cfg.store_put(PreresolvedEndpoint::new(
+                Endpoint::builder()
+                    .url("https://preresolved.example.com")
+                    .header("x-endpoint", "preresolved")
+                    .build(),
+            ));
+        }
+    });
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("done", output_string(output));
+    assert_eq!(0, calls.load(Ordering::SeqCst));
+    let requests = connection.requests();
+    assert_eq!(Some("preresolved.example.com"), requests[0].uri().host());
+    assert_eq!("preresolved", requests[0].headers()["x-endpoint"]);
+}
+
+#[tokio::test]
+async fn attempts_fail_over_to_the_next_host_after_a_connection_failure() {
+    let connection = CannedConnection::new(vec![
+        Err(ConnectorError::io("connection refused".into())),
+        response(200, "done"),
+    ]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(3));
+            cfg.store_put(EndpointFailover::new(vec![
+                "https://secondary.example.com".parse().unwrap(),
+                "https://tertiary.example.com".parse().unwrap(),
+            ]));
+        }
+    });
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("done", output_string(output));
+    let uris: Vec<_> = connection
+        .requests()
+        .iter()
+        .map(|request| request.uri().to_string())
+        .collect();
+    assert_eq!(
+        vec!["http://localhost:8080/", "https://secondary.example.com/"],
+        uris
+    );
+}
+
+#[test]
+fn endpoint_failover_starts_over_once_every_host_was_tried() {
+    let failover = EndpointFailover::new(vec!["https://secondary.example.com".parse().unwrap()]);
+    assert_eq!(None, failover.host(0));
+    assert_eq!(
+        Some("secondary.example.com"),
+        failover.host(1).and_then(|host| host.host())
+    );
+    assert_eq!(None, failover.host(2));
+}
@@ -0,0 +1,209 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::*;
+use aws_smithy_async::rt::sleep::TokioSleep;
+use aws_smithy_types::timeout::TimeoutConfig;
+
+#[derive(Debug)]
+struct DnsError(std::io::Error);
+
+impl fmt::Display for DnsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to resolve example.com")
+    }
+}
+
+impl std::error::Error for DnsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+/// A connection that always fails with a [`DnsError`] that isn't wrapped in a [`ConnectorError`].
+#[derive(Debug)]
+struct UnresolvableConnection;
+
+impl Connection for UnresolvableConnection {
+    fn call(&self, _request: HttpRequest) -> BoxFuture<HttpResponse> {
+        let err = DnsError(std::io::Error::new(
+            std::io::ErrorKind::ConnectionRefused,
+            "connection refused",
+        ));
+        Box::pin(async move { Err::<HttpResponse, _>(BoxError::from(err)) })
+    }
+}
+
+#[tokio::test]
+async fn dispatch_failure_preserves_the_connector_error_source_chain() {
+    let runtime_plugins = test_plugins(|cfg, _| {
+        cfg.set_connection(UnresolvableConnection);
+    });
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("the connection always fails");
+    let failure = match err {
+        SdkError::DispatchFailure(failure) => failure,
+        err => panic!("expected a dispatch failure, got {err:?}"),
+    };
+    let chain: Vec<String> = failure.source_chain().map(|err| err.to_string()).collect();
+    assert_eq!(
+        vec!["failed to resolve example.com", "connection refused"],
+        chain
+    );
+    assert!(failure
+        .source_chain()
+        .last()
+        .expect("chain is non-empty")
+        .downcast_ref::<std::io::Error>()
+        .is_some());
+}
+
+/// A modeled error, which generated code would downcast the type-erased service error to.
+#[derive(Debug)]
+struct ServiceError(String);
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ServiceError {}
+
+/// An application's error type, which the errors of every operation are converted into.
+#[derive(Debug)]
+enum AppError {
+    TimedOut(SdkError<ServiceError, HttpResponse>),
+    Unavailable(SdkError<ServiceError, HttpResponse>),
+    Other(SdkError<ServiceError, HttpResponse>),
+}
+
+impl AppError {
+    fn from_sdk_error(err: SdkError<Error, HttpResponse>) -> Self {
+        let err = err.map_service_error(|err| ServiceError(format!("{err:?}")));
+        match err {
+            SdkError::TimeoutError(_) => AppError::TimedOut(err),
+            SdkError::DispatchFailure(_) => AppError::Unavailable(err),
+            _ => AppError::Other(err),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::TimedOut(_) => write!(f, "the request timed out"),
+            AppError::Unavailable(_) => write!(f, "the service is unavailable"),
+            AppError::Other(_) => write!(f, "the request failed"),
+        }
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::TimedOut(err) | AppError::Unavailable(err) | AppError::Other(err) => {
+                Some(err)
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn errors_are_mapped_to_the_application_error_type() {
+    tokio::time::pause();
+    let timeout_plugins = test_plugins(|cfg, _| {
+        cfg.set_connection(CannedConnection::new(vec![response(200, "done")]));
+        cfg.store_put::<Box<dyn AsyncEndpointResolver>>(Box::new(DiscoveryEndpointResolver {
+            delay: Duration::from_secs(10),
+        }));
+        cfg.put(
+            TimeoutConfig::builder()
+                .operation_attempt_timeout(Duration::from_secs(1))
+                .build(),
+        );
+        cfg.set_sleep_impl(Some(Arc::new(TokioSleep::new())));
+    });
+    let err = invoke_with_error_map(
+        test_input("hello"),
+        &timeout_plugins,
+        AppError::from_sdk_error,
+    )
+    .await
+    .expect_err("endpoint resolution outlasts the attempt timeout");
+    assert!(matches!(err, AppError::TimedOut(_)), "{err:?}");
+    let message = format!("{}", DisplayErrorContext(&err));
+    assert!(message.starts_with("the request timed out"), "{message}");
+    assert!(message.contains("operation attempt timeout"), "{message}");
+
+    let dispatch_plugins = test_plugins(|cfg, _| {
+        cfg.set_connection(UnresolvableConnection);
+    });
+    let err = invoke_with_error_map(
+        test_input("hello"),
+        &dispatch_plugins,
+        AppError::from_sdk_error,
+    )
+    .await
+    .expect_err("the connection always fails");
+    assert!(matches!(err, AppError::Unavailable(_)), "{err:?}");
+    // The whole source chain is preserved, down to the connector's IO error
+    let mut sources = Vec::new();
+    let mut source = std::error::Error::source(&err);
+    while let Some(err) = source {
+        sources.push(err);
+        source = err.source();
+    }
+    assert!(sources[0]
+        .downcast_ref::<SdkError<ServiceError, HttpResponse>>()
+        .is_some());
+    assert!(sources
+        .last()
+        .expect("the chain is non-empty")
+        .downcast_ref::<std::io::Error>()
+        .is_some());
+}
+
+#[tokio::test]
+async fn errors_of_retried_attempts_are_attached_to_the_final_error() {
+    let connection = CannedConnection::new(vec![
+        response(500, ""),
+        response(502, ""),
+        response(503, ""),
+    ]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(3));
+        }
+    });
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("every attempt fails");
+
+    let attempt_errors: Vec<_> = err
+        .attempt_errors()
+        .iter()
+        .map(|attempt_error| (attempt_error.attempt(), attempt_error.status()))
+        .collect();
+    assert_eq!(vec![(1, Some(500)), (2, Some(502))], attempt_errors);
+    assert!(DisplayErrorContext(&err.attempt_errors()[0])
+        .to_string()
+        .contains("TestError(500)"));
+
+    // The returned error is still the error of the last attempt
+    assert_eq!(
+        503,
+        err.raw_response()
+            .expect("the service responded")
+            .status()
+            .as_u16()
+    );
+    assert!(matches!(err, SdkError::ServiceError(_)), "{err:?}");
+}
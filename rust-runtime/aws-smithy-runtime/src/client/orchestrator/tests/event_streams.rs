@@ -0,0 +1,175 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::*;
+use aws_smithy_eventstream::error::Error as EventStreamError;
+use aws_smithy_eventstream::frame::{
+    DecodedFrame, Message, MessageFrameDecoder, UnmarshallMessage, UnmarshalledMessage,
+};
+use aws_smithy_http::body::BoxBody;
+use aws_smithy_http::event_stream::Receiver;
+
+/// Creates the input of the test event stream operation, which is the body of its input event
+/// stream, along with the sender that events are written to.
+fn event_stream_input() -> (tokio::sync::mpsc::UnboundedSender<Bytes>, Input) {
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+    let body = SdkBody::from_dyn(BoxBody::new(ChannelBody(receiver)));
+    (sender, TypedBox::new(body).erase())
+}
+
+fn send_event(sender: &tokio::sync::mpsc::UnboundedSender<Bytes>, payload: &'static str) {
+    let mut frame = Vec::new();
+    Message::new(payload)
+        .write_to(&mut frame)
+        .expect("valid message");
+    sender.send(frame.into()).expect("the stream is open");
+}
+
+/// Sends the input event stream as the request body.
+#[derive(Debug)]
+struct EventStreamSerializer;
+
+impl RequestSerializer for EventStreamSerializer {
+    fn serialize_input(&self, input: Input) -> Result<HttpRequest, BoxError> {
+        let body = input
+            .downcast::<SdkBody>()
+            .expect("test input is an event stream body");
+        Ok(http::Request::builder()
+            .method("POST")
+            .uri("/")
+            .body(*body)
+            .expect("valid request"))
+    }
+}
+
+/// Unmarshalls every event as its payload.
+#[derive(Debug)]
+struct StringUnmarshaller;
+
+impl UnmarshallMessage for StringUnmarshaller {
+    type Output = String;
+    type Error = TestError;
+
+    fn unmarshall(
+        &self,
+        message: &Message,
+    ) -> Result<UnmarshalledMessage<Self::Output, Self::Error>, EventStreamError> {
+        let payload = String::from_utf8_lossy(message.payload()).into_owned();
+        Ok(UnmarshalledMessage::Event(payload))
+    }
+}
+
+type TestEventReceiver = Receiver<String, TestError>;
+
+/// Hands the response body to the output event stream.
+#[derive(Debug)]
+struct EventStreamDeserializer;
+
+impl ResponseDeserializer for EventStreamDeserializer {
+    fn deserialize_streaming(&self, response: &mut HttpResponse) -> Option<OutputOrError> {
+        let body = std::mem::replace(response.body_mut(), SdkBody::taken());
+        let receiver: TestEventReceiver = Receiver::new(StringUnmarshaller, body);
+        Some(Ok(TypedBox::new(receiver).erase()))
+    }
+
+    fn deserialize_nonstreaming(&self, response: &HttpResponse) -> OutputOrError {
+        StatusDeserializer.deserialize_nonstreaming(response)
+    }
+}
+
+/// An event stream connection that responds right away, then echoes every event of the
+/// request body back in the response body for as long as the request body is open.
+#[derive(Clone, Debug, Default)]
+struct EchoEventStreamConnection {
+    calls: Arc<AtomicUsize>,
+}
+
+impl Connection for EchoEventStreamConnection {
+    fn call(&self, request: HttpRequest) -> BoxFuture<HttpResponse> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut body = request.into_body();
+            let mut decoder = MessageFrameDecoder::new();
+            let mut buffer = bytes::BytesMut::new();
+            while let Some(chunk) = body.data().await {
+                buffer.extend_from_slice(&chunk.expect("the request body is readable"));
+                while let DecodedFrame::Complete(message) =
+                    decoder.decode_frame(&mut buffer).expect("valid frame")
+                {
+                    let echo = format!("echo: {}", String::from_utf8_lossy(message.payload()));
+                    let mut frame = Vec::new();
+                    Message::new(echo).write_to(&mut frame).unwrap();
+                    if sender.send(Bytes::from(frame)).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Box::pin(async move {
+            Ok(http::Response::builder()
+                .status(200)
+                .body(SdkBody::from_dyn(BoxBody::new(ChannelBody(receiver))))
+                .expect("valid response"))
+        })
+    }
+}
+
+#[tokio::test]
+async fn event_stream_operations_exchange_events_over_the_connection() {
+    let connection = EchoEventStreamConnection::default();
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_request_serializer(EventStreamSerializer);
+            cfg.set_response_deserializer(EventStreamDeserializer);
+            cfg.store_put(EventStreamOperation::new(true));
+        }
+    });
+    let (input_events, input) = event_stream_input();
+
+    // The operation completes with the initial response, before any events are exchanged
+    let output = invoke(input, &runtime_plugins).await.expect("success");
+    let mut output_events = output
+        .downcast::<TestEventReceiver>()
+        .expect("the output is the output event stream");
+
+    send_event(&input_events, "hello");
+    assert_eq!(
+        Some("echo: hello".to_string()),
+        output_events.recv().await.expect("valid event")
+    );
+    send_event(&input_events, "goodbye");
+    assert_eq!(
+        Some("echo: goodbye".to_string()),
+        output_events.recv().await.expect("valid event")
+    );
+
+    // Closing the input event stream ends the exchange
+    drop(input_events);
+    assert_eq!(None, output_events.recv().await.expect("valid event"));
+    assert_eq!(1, connection.calls.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn event_stream_operations_are_never_retried() {
+    let connection = CannedConnection::new(vec![response(500, ""), response(200, "done")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(2));
+            cfg.store_put(EventStreamOperation::new(true));
+        }
+    });
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("the initial response is an error");
+    // Error responses aren't event streams, so they're deserialized as usual
+    assert!(matches!(err, SdkError::ServiceError(_)), "{err:?}");
+    assert_eq!(1, connection.requests().len());
+}
@@ -0,0 +1,86 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::*;
+use aws_smithy_async::rt::sleep::TokioSleep;
+use aws_smithy_types::timeout::TimeoutConfig;
+
+#[tokio::test]
+async fn injected_faults_are_retried() {
+    use crate::client::connections::fault_injection::{Fault, FaultInjector};
+
+    let connection = CannedConnection::new(vec![response(200, "done")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(FaultInjector::for_attempts(
+                connection.clone(),
+                Fault::Drop,
+                [1],
+            ));
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(2));
+        }
+    });
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("the second attempt succeeds");
+    assert_eq!("done", output_string(output));
+    // The dropped attempt never reached the connection
+    assert_eq!(1, connection.requests().len());
+}
+
+#[tokio::test(start_paused = true)]
+async fn synthetic_latency_is_added_to_every_attempt() {
+    use crate::client::connections::fault_injection::SyntheticLatency;
+
+    let connection = CannedConnection::new(vec![response(500, ""), response(200, "done")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(
+                SyntheticLatency::new(connection.clone(), Duration::from_millis(250))
+                    .sleep_impl(Arc::new(TokioSleep::new())),
+            );
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(2));
+        }
+    });
+
+    let started_at = tokio::time::Instant::now();
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("the second attempt succeeds");
+    assert_eq!(Duration::from_millis(500), started_at.elapsed());
+    assert_eq!(2, connection.requests().len());
+}
+
+#[tokio::test]
+async fn synthetic_latency_counts_towards_the_attempt_timeout() {
+    use crate::client::connections::fault_injection::SyntheticLatency;
+
+    tokio::time::pause();
+    let connection = CannedConnection::new(vec![response(200, "done")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(
+                SyntheticLatency::new(connection.clone(), Duration::from_secs(10))
+                    .sleep_impl(Arc::new(TokioSleep::new())),
+            );
+            cfg.set_sleep_impl(Some(Arc::new(TokioSleep::new())));
+            cfg.put(
+                TimeoutConfig::builder()
+                    .operation_attempt_timeout(Duration::from_secs(1))
+                    .build(),
+            );
+        }
+    });
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("the latency outlasts the attempt timeout");
+    assert!(matches!(err, SdkError::TimeoutError(_)), "{err:?}");
+    assert_eq!(0, connection.requests().len());
+}
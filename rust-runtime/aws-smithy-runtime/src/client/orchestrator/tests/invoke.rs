@@ -0,0 +1,282 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::*;
+use aws_smithy_runtime_api::client::interceptors::Interceptor;
+use aws_smithy_runtime_api::client::orchestrator::{
+    MaxSerializedRequestSize, RequestAttemptHeader,
+};
+use aws_smithy_types::retry::RetryConfig;
+
+#[derive(Debug)]
+struct HeaderInjectingInterceptor;
+
+impl Interceptor for HeaderInjectingInterceptor {
+    fn modify_before_transmit(
+        &self,
+        context: &mut InterceptorContext,
+        _cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        context
+            .request_mut()?
+            .headers_mut()
+            .insert("x-test-header", http::HeaderValue::from_static("injected"));
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn invoke_until_transmit_runs_interceptors_without_dispatching() {
+    let runtime_plugins = test_plugins(|_, interceptors| {
+        interceptors.register_operation_interceptor(Arc::new(HeaderInjectingInterceptor));
+    });
+
+    let context = test_util::invoke_until_transmit(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    let request = context.request().expect("request is set");
+    assert_eq!("injected", request.headers()["x-test-header"]);
+    assert_eq!(Some(&b"hello"[..]), request.body().bytes());
+    assert!(context.response().is_err());
+}
+
+#[tokio::test]
+async fn invoke_until_transmit_prepares_the_first_attempt() {
+    let runtime_plugins = test_plugins(|cfg, _| {
+        cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(3));
+        cfg.put(RetryConfig::standard().with_max_attempts(3));
+        cfg.store_put(RequestAttemptHeader::amz_sdk_request());
+    });
+
+    let context = test_util::invoke_until_transmit(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    let request = context.request().expect("request is set");
+    assert_eq!("attempt=1; max=3", request.headers()["amz-sdk-request"]);
+}
+
+#[tokio::test]
+async fn replay_sends_the_captured_request_as_is() {
+    let connection = CannedConnection::new(vec![response(200, "replayed")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, interceptors| {
+            cfg.set_connection(connection.clone());
+            interceptors.register_operation_interceptor(Arc::new(HeaderInjectingInterceptor));
+        }
+    });
+    let captured = http::Request::builder()
+        .method("PUT")
+        .uri("https://example.com/captured")
+        .header("authorization", "TestSignature abc123")
+        .body(SdkBody::from("captured body"))
+        .unwrap();
+
+    let output = replay(captured, &runtime_plugins).await.expect("success");
+    assert_eq!("replayed", output_string(output));
+
+    let requests = connection.requests();
+    assert_eq!(1, requests.len());
+    let request = &requests[0];
+    // Neither the endpoint nor the signature were replaced...
+    assert_eq!("https://example.com/captured", request.uri().to_string());
+    assert_eq!("TestSignature abc123", request.headers()["authorization"]);
+    assert_eq!(Some(&b"captured body"[..]), request.body().bytes());
+    // ...but the transmit interceptors still ran
+    assert_eq!("injected", request.headers()["x-test-header"]);
+}
+
+#[tokio::test]
+async fn oversized_requests_fail_before_being_sent() {
+    let connection = CannedConnection::new(vec![response(200, "done")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.store_put(MaxSerializedRequestSize::new(4));
+        }
+    });
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("the request is larger than the maximum");
+    assert!(matches!(err, SdkError::ConstructionFailure(_)), "{err:?}");
+    let message = display_error(err);
+    assert!(
+        message.contains(
+            "the serialized request body is 5 bytes, which is larger than the maximum of 4 bytes"
+        ),
+        "{message}"
+    );
+    assert!(connection.requests().is_empty());
+}
+
+#[tokio::test]
+async fn requests_at_the_maximum_size_are_sent() {
+    let runtime_plugins = test_plugins(|cfg, _| {
+        cfg.set_connection(CannedConnection::new(vec![response(200, "done")]));
+        cfg.store_put(MaxSerializedRequestSize::new(5));
+    });
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("the request isn't larger than the maximum");
+    assert_eq!("done", output_string(output));
+}
+
+/// Records the original input as seen by `modify_before_completion`.
+#[derive(Debug)]
+struct RecordOriginalInput(Arc<Mutex<Option<String>>>);
+
+impl Interceptor for RecordOriginalInput {
+    fn modify_before_completion(
+        &self,
+        context: &mut InterceptorContext,
+        _cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        *self.0.lock().unwrap() = context
+            .original_input()
+            .and_then(|input| input.downcast_ref::<String>())
+            .cloned();
+        Ok(())
+    }
+}
+
+async fn original_input_seen_on_completion(retain_input: bool) -> Option<String> {
+    let seen = Arc::new(Mutex::new(None));
+    let runtime_plugins = test_plugins({
+        let seen = seen.clone();
+        move |cfg, interceptors| {
+            cfg.set_connection(CannedConnection::new(vec![response(200, "done")]));
+            if retain_input {
+                cfg.store_put(RetainInput::of::<String>());
+            }
+            interceptors
+                .register_operation_interceptor(Arc::new(RecordOriginalInput(seen.clone())));
+        }
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    let seen = seen.lock().unwrap().take();
+    seen
+}
+
+#[tokio::test]
+async fn late_interceptors_can_read_the_original_input_when_it_is_retained() {
+    assert_eq!(
+        Some("hello".to_string()),
+        original_input_seen_on_completion(true).await
+    );
+}
+
+#[tokio::test]
+async fn the_original_input_is_not_retained_by_default() {
+    assert_eq!(None, original_input_seen_on_completion(false).await);
+}
+
+/// A connection that tracks how many requests are in flight at once. Requests whose body is
+/// `"fail"` are rejected.
+#[derive(Clone, Debug, Default)]
+struct ConcurrencyTrackingConnection {
+    in_flight: Arc<AtomicUsize>,
+    max_in_flight: Arc<AtomicUsize>,
+}
+
+impl Connection for ConcurrencyTrackingConnection {
+    fn call(&self, request: HttpRequest) -> BoxFuture<HttpResponse> {
+        let in_flight = self.in_flight.clone();
+        let now_in_flight = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight
+            .fetch_max(now_in_flight, Ordering::SeqCst);
+        let status = match request.body().bytes() {
+            Some(b"fail") => 500,
+            _ => 200,
+        };
+        Box::pin(async move {
+            // Give the other operations a chance to send their requests
+            for _ in 0..5 {
+                tokio::task::yield_now().await;
+            }
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(http::Response::builder()
+                .status(status)
+                .body(SdkBody::from("done"))
+                .expect("valid response"))
+        })
+    }
+}
+
+#[tokio::test]
+async fn invoke_all_bounds_concurrency_and_collects_every_result() {
+    let connection = ConcurrencyTrackingConnection::default();
+    let client_configurations = Arc::new(AtomicUsize::new(0));
+    let runtime_plugins = RuntimePlugins::new()
+        .with_client_plugin(TestOperationPlugin)
+        .with_client_plugin(FnPlugin({
+            let client_configurations = client_configurations.clone();
+            move |_: &mut ConfigBag, _: &mut Interceptors| {
+                client_configurations.fetch_add(1, Ordering::SeqCst);
+            }
+        }))
+        .with_operation_plugin(FnPlugin({
+            let connection = connection.clone();
+            move |cfg: &mut ConfigBag, _: &mut Interceptors| cfg.set_connection(connection.clone())
+        }));
+
+    let inputs = (0..10).map(|i| test_input(if i == 4 { "fail" } else { "hello" }));
+    let results: Vec<_> = invoke_all(inputs, &runtime_plugins, 3)
+        .expect("valid client configuration")
+        .collect()
+        .await;
+
+    assert_eq!(10, results.len());
+    for (i, result) in results.into_iter().enumerate() {
+        match result {
+            Err(SdkError::ServiceError(_)) if i == 4 => {}
+            Ok(output) if i != 4 => assert_eq!("done", output_string(output)),
+            other => panic!("unexpected result for operation {i}: {other:?}"),
+        }
+    }
+    assert_eq!(3, connection.max_in_flight.load(Ordering::SeqCst));
+    assert_eq!(0, connection.in_flight.load(Ordering::SeqCst));
+    assert_eq!(1, client_configurations.load(Ordering::SeqCst));
+}
+
+/// Fails to apply its configuration.
+#[derive(Debug)]
+struct FailingPlugin;
+
+impl RuntimePlugin for FailingPlugin {
+    fn configure(
+        &self,
+        _cfg: &mut ConfigBag,
+        _interceptors: &mut Interceptors,
+    ) -> Result<(), BoxError> {
+        Err("invalid configuration".into())
+    }
+}
+
+#[test]
+fn invoke_all_fails_up_front_without_a_usable_configuration() {
+    let connection = CannedConnection::new(vec![]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| cfg.set_connection(connection.clone())
+    });
+    let err = invoke_all([test_input("hello")], &runtime_plugins, 0)
+        .err()
+        .expect("a concurrency limit of zero is rejected");
+    assert!(matches!(err, SdkError::ConstructionFailure(_)), "{err:?}");
+
+    let runtime_plugins = runtime_plugins.with_client_plugin(FailingPlugin);
+    let err = invoke_all([test_input("hello")], &runtime_plugins, 1)
+        .err()
+        .expect("the client configuration can't be applied");
+    assert!(matches!(err, SdkError::ConstructionFailure(_)), "{err:?}");
+    assert!(format!("{err:?}").contains("invalid configuration"));
+    assert!(connection.requests().is_empty());
+}
@@ -0,0 +1,352 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::*;
+use aws_smithy_http::body::BoxBody;
+use aws_smithy_runtime_api::client::interceptors::context::ContextSnapshot;
+use aws_smithy_runtime_api::client::interceptors::{Interceptor, TraceInterceptorHooks};
+use aws_smithy_runtime_api::client::orchestrator::{LogQueryString, PhaseObserver};
+use tracing_test::traced_test;
+
+#[tokio::test]
+async fn byte_counts_are_summed_across_attempts() {
+    let connection = CannedConnection::new(vec![response(500, "oops"), response(200, "done")]);
+    let byte_counts = ByteCounts::new();
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        let byte_counts = byte_counts.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(2));
+            cfg.store_put(byte_counts.clone());
+        }
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+
+    assert_eq!(2, connection.requests().len());
+    assert_eq!(10, byte_counts.bytes_sent());
+    assert_eq!(8, byte_counts.bytes_received());
+}
+
+#[tokio::test]
+async fn streaming_bodies_are_counted_as_they_flow() {
+    fn streaming_body(data: &'static str) -> SdkBody {
+        SdkBody::from_dyn(BoxBody::new(ChecksummedBody {
+            data: Some(Bytes::from_static(data.as_bytes())),
+            checksum: None,
+        }))
+    }
+
+    let connection = CannedConnection::new(vec![Ok(http::Response::builder()
+        .status(200)
+        .body(streaming_body("done"))
+        .expect("valid response"))]);
+    let byte_counts = ByteCounts::new();
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        let byte_counts = byte_counts.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.store_put(byte_counts.clone());
+            cfg.store_put(ReplayableBody::new(|| streaming_body("hello")));
+        }
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!(4, byte_counts.bytes_received());
+    // The connection didn't read the request body, so none of it was sent yet
+    assert_eq!(0, byte_counts.bytes_sent());
+
+    let mut request = connection.requests().pop().expect("one request was sent");
+    while let Some(chunk) = request.body_mut().data().await {
+        chunk.unwrap();
+    }
+    assert_eq!(5, byte_counts.bytes_sent());
+}
+
+/// A connection that logs an event from within the `make_an_attempt` span, so that the
+/// span's fields are captured alongside it.
+#[derive(Debug)]
+struct LoggingConnection(CannedConnection);
+
+impl Connection for LoggingConnection {
+    fn call(&self, request: HttpRequest) -> BoxFuture<HttpResponse> {
+        tracing::debug!("dispatching the request");
+        self.0.call(request)
+    }
+}
+
+#[tokio::test]
+#[traced_test]
+async fn request_body_size_is_recorded_on_the_attempt_span() {
+    let runtime_plugins = test_plugins(|cfg, _| {
+        cfg.set_connection(LoggingConnection(CannedConnection::new(vec![response(
+            200, "done",
+        )])));
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert!(logs_contain(
+        "make_an_attempt{request_body_size=5 http.method=\"POST\" http.path=\"/\"}"
+    ));
+}
+
+/// An interceptor that logs an event from within the `make_an_attempt` span once the
+/// response has been received.
+#[derive(Debug)]
+struct LogAfterTransmit;
+
+impl Interceptor for LogAfterTransmit {
+    fn read_after_transmit(
+        &self,
+        _context: &InterceptorContext,
+        _cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        tracing::debug!("received the response");
+        Ok(())
+    }
+}
+
+#[tokio::test]
+#[traced_test]
+async fn request_ids_are_recorded_on_the_attempt_span() {
+    for header in ["x-amzn-requestid", "x-amzn-request-id", "x-amz-request-id"] {
+        let runtime_plugins = test_plugins(move |cfg, interceptors| {
+            let response = http::Response::builder()
+                .status(200)
+                .header(header, format!("id-from-{header}"))
+                .header("x-amz-id-2", "extended-id")
+                .body(SdkBody::from("done"))
+                .expect("valid response");
+            cfg.set_connection(CannedConnection::new(vec![Ok(response)]));
+            interceptors.register_operation_interceptor(Arc::new(LogAfterTransmit));
+        });
+
+        invoke(test_input("hello"), &runtime_plugins)
+            .await
+            .expect("success");
+        assert!(
+            logs_contain(&format!(
+                "request_id=\"id-from-{header}\" extended_request_id=\"extended-id\""
+            )),
+            "the request id in `{header}` wasn't recorded"
+        );
+    }
+}
+
+fn get_with_query_plugins(log_query_string: bool) -> RuntimePlugins {
+    test_plugins(move |cfg, _| {
+        cfg.set_connection(LoggingConnection(CannedConnection::new(vec![response(
+            200, "done",
+        )])));
+        cfg.set_request_serializer(FnSerializer(|_input: Input| {
+            Ok(http::Request::builder()
+                .method("GET")
+                .uri("/bucket/key?versionId=secret")
+                .body(SdkBody::empty())
+                .expect("valid request"))
+        }));
+        cfg.store_put(LogQueryString::new(log_query_string));
+    })
+}
+
+#[tokio::test]
+#[traced_test]
+async fn request_line_is_recorded_on_the_attempt_span_with_the_query_redacted() {
+    invoke(test_input("hello"), &get_with_query_plugins(false))
+        .await
+        .expect("success");
+    assert!(logs_contain(
+        "http.method=\"GET\" http.path=\"/bucket/key?** redacted **\""
+    ));
+    assert!(!logs_contain("secret"));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn query_string_is_recorded_when_enabled() {
+    invoke(test_input("hello"), &get_with_query_plugins(true))
+        .await
+        .expect("success");
+    assert!(logs_contain(
+        "http.method=\"GET\" http.path=\"/bucket/key?versionId=secret\""
+    ));
+}
+
+/// Records the phases that were entered, along with a snapshot of the context.
+#[derive(Clone, Debug, Default)]
+struct RecordPhases(Arc<Mutex<Vec<(&'static str, ContextSnapshot)>>>);
+
+impl PhaseObserver for RecordPhases {
+    fn observe(&self, phase: &'static str, snapshot: ContextSnapshot) {
+        self.0.lock().unwrap().push((phase, snapshot));
+    }
+}
+
+#[tokio::test]
+async fn the_context_is_snapshotted_as_each_phase_is_entered() {
+    let phases = RecordPhases::default();
+    let runtime_plugins = test_plugins({
+        let phases = phases.clone();
+        move |cfg, _| {
+            cfg.set_connection(CannedConnection::new(vec![
+                response(500, ""),
+                response(200, "done"),
+            ]));
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(2));
+            cfg.store_put::<Box<dyn PhaseObserver>>(Box::new(phases.clone()));
+        }
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success on the second attempt");
+
+    let dispatch = ContextSnapshot {
+        request: Some("POST /".to_string()),
+        ..Default::default()
+    };
+    let response_handling = |status| ContextSnapshot {
+        response: Some(status),
+        ..Default::default()
+    };
+    assert_eq!(
+        vec![
+            (
+                "construction",
+                ContextSnapshot {
+                    input: Some(r#"TypeErasedBox:"hello""#.to_string()),
+                    ..Default::default()
+                }
+            ),
+            ("dispatch", dispatch.clone()),
+            ("response_handling", response_handling(500)),
+            ("dispatch", dispatch),
+            ("response_handling", response_handling(200)),
+        ],
+        *phases.0.lock().unwrap()
+    );
+}
+
+/// A subscriber that records the interceptor hook events, as `entering <hook>` or
+/// `exiting <hook>`.
+#[derive(Clone, Default)]
+struct RecordHookEvents(Arc<Mutex<Vec<String>>>);
+
+impl tracing::Subscriber for RecordHookEvents {
+    fn enabled(&self, _metadata: &tracing::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+        tracing::span::Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &tracing::span::Id, _values: &tracing::span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &tracing::span::Id, _follows: &tracing::span::Id) {}
+
+    fn event(&self, event: &tracing::Event<'_>) {
+        #[derive(Default)]
+        struct Visitor {
+            hook: Option<String>,
+            message: Option<String>,
+        }
+
+        impl tracing::field::Visit for Visitor {
+            fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+                if field.name() == "hook" {
+                    self.hook = Some(value.to_string());
+                }
+            }
+
+            fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn fmt::Debug) {
+                if field.name() == "message" {
+                    self.message = Some(format!("{value:?}"));
+                }
+            }
+        }
+
+        let mut visitor = Visitor::default();
+        event.record(&mut visitor);
+        let direction = match visitor.message.as_deref() {
+            Some("entering interceptor hook") => "entering",
+            Some("exiting interceptor hook") => "exiting",
+            _ => return,
+        };
+        if let Some(hook) = visitor.hook {
+            self.0.lock().unwrap().push(format!("{direction} {hook}"));
+        }
+    }
+
+    fn enter(&self, _span: &tracing::span::Id) {}
+
+    fn exit(&self, _span: &tracing::span::Id) {}
+}
+
+#[derive(Debug)]
+struct NoOpInterceptor;
+
+impl Interceptor for NoOpInterceptor {}
+
+async fn hook_events(trace_interceptor_hooks: bool) -> Vec<String> {
+    let events = RecordHookEvents::default();
+    let _guard = tracing::subscriber::set_default(events.clone());
+    let runtime_plugins = test_plugins(move |cfg, interceptors| {
+        cfg.set_connection(CannedConnection::new(vec![response(200, "done")]));
+        cfg.store_put(TraceInterceptorHooks::new(trace_interceptor_hooks));
+        // Hooks are only traced while there are interceptors to run
+        interceptors.register_operation_interceptor(Arc::new(NoOpInterceptor));
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    let events = events.0.lock().unwrap().clone();
+    events
+}
+
+#[tokio::test]
+async fn interceptor_hooks_are_traced_in_order_when_enabled() {
+    // The setting is made by an operation runtime plugin, so the client hook is already done
+    let hooks = [
+        "operation_read_before_execution",
+        "read_before_serialization",
+        "modify_before_serialization",
+        "read_after_serialization",
+        "modify_before_retry_loop",
+        "read_before_attempt",
+        "modify_before_signing",
+        "read_before_signing",
+        "read_after_signing",
+        "modify_before_transmit",
+        "read_before_transmit",
+        "read_after_transmit",
+        "modify_before_deserialization",
+        "read_before_deserialization",
+        "read_after_deserialization",
+        "read_after_attempt",
+        "modify_before_attempt_completion",
+        "modify_before_completion",
+        "read_after_execution",
+    ];
+    let expected: Vec<_> = hooks
+        .iter()
+        .flat_map(|hook| [format!("entering {hook}"), format!("exiting {hook}")])
+        .collect();
+    assert_eq!(expected, hook_events(true).await);
+}
+
+#[tokio::test]
+async fn interceptor_hooks_are_not_traced_by_default() {
+    assert!(hook_events(false).await.is_empty());
+}
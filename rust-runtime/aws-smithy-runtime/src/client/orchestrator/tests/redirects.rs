@@ -0,0 +1,171 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::*;
+use aws_smithy_runtime_api::client::orchestrator::RedirectPolicy;
+
+fn redirect(status: u16, location: &'static str) -> Result<HttpResponse, ConnectorError> {
+    Ok(http::Response::builder()
+        .status(status)
+        .header(http::header::LOCATION, location)
+        .body(SdkBody::empty())
+        .expect("valid response"))
+}
+
+fn redirect_plugins(
+    connection: &CannedConnection,
+    redirect_policy: RedirectPolicy,
+) -> RuntimePlugins {
+    let connection = connection.clone();
+    test_plugins(move |cfg, _| {
+        cfg.set_connection(connection.clone());
+        cfg.set_endpoint_resolver(StaticUriEndpointResolver::uri(
+            "https://example.com/".parse().unwrap(),
+        ));
+        cfg.store_put(redirect_policy);
+    })
+}
+
+#[tokio::test]
+async fn redirects_are_followed_and_signed_again() {
+    let connection = CannedConnection::new(vec![
+        redirect(307, "https://example.com/moved"),
+        response(200, "done"),
+    ]);
+    let runtime_plugins = redirect_plugins(&connection, RedirectPolicy::new(3));
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("done", output_string(output));
+
+    let requests = connection.requests();
+    assert_eq!(2, requests.len());
+    assert_eq!("https://example.com/moved", requests[1].uri().to_string());
+    assert_eq!(requests[0].method(), requests[1].method());
+    assert_eq!(Some(&b"hello"[..]), requests[1].body().bytes());
+}
+
+#[tokio::test]
+async fn redirects_are_returned_as_is_when_none_are_allowed() {
+    let connection = CannedConnection::new(vec![redirect(307, "/moved")]);
+    let runtime_plugins = redirect_plugins(&connection, RedirectPolicy::new(0));
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("the redirect is an error response");
+    assert_eq!(
+        Some(307),
+        err.raw_response()
+            .map(|response| response.status().as_u16())
+    );
+    assert_eq!(1, connection.requests().len());
+}
+
+#[tokio::test]
+async fn redirects_to_another_host_are_refused_unless_allowed() {
+    let connection =
+        CannedConnection::new(vec![redirect(307, "https://attacker.example.net/moved")]);
+    let runtime_plugins = redirect_plugins(&connection, RedirectPolicy::new(3));
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("cross-host redirect");
+    let err = display_error(err);
+    assert!(err.contains("redirects to another host"), "{err}");
+    assert_eq!(1, connection.requests().len());
+
+    let connection = CannedConnection::new(vec![
+        redirect(307, "https://other.example.com/moved"),
+        response(200, "done"),
+    ]);
+    let runtime_plugins =
+        redirect_plugins(&connection, RedirectPolicy::new(3).with_cross_host(true));
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("done", output_string(output));
+    assert_eq!(
+        "https://other.example.com/moved",
+        connection.requests()[1].uri().to_string()
+    );
+}
+
+#[tokio::test]
+async fn redirects_are_not_followed_without_a_policy() {
+    let connection = CannedConnection::new(vec![redirect(307, "/moved")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| cfg.set_connection(connection.clone())
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("the redirect is an error response");
+    assert_eq!(1, connection.requests().len());
+}
+
+#[tokio::test]
+async fn too_many_redirects_fail_the_request() {
+    let connection = CannedConnection::new(vec![
+        redirect(302, "/one"),
+        redirect(302, "/two"),
+        redirect(302, "/three"),
+    ]);
+    let runtime_plugins = redirect_plugins(&connection, RedirectPolicy::new(2));
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("too many redirects");
+    let err = display_error(err);
+    assert!(err.contains("redirected more than 2 times"), "{err}");
+    assert_eq!(3, connection.requests().len());
+}
+
+#[tokio::test]
+async fn redirect_loops_fail_the_request() {
+    let connection = CannedConnection::new(vec![redirect(307, "/other"), redirect(307, "/")]);
+    let runtime_plugins = redirect_plugins(&connection, RedirectPolicy::new(10));
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("redirect loop");
+    let err = display_error(err);
+    assert!(err.contains("in a loop"), "{err}");
+    assert_eq!(2, connection.requests().len());
+}
+
+#[tokio::test]
+async fn https_downgrades_are_refused_unless_allowed() {
+    let connection = CannedConnection::new(vec![redirect(301, "http://example.com/")]);
+    let runtime_plugins = redirect_plugins(&connection, RedirectPolicy::new(3));
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("downgrade");
+    let err = display_error(err);
+    assert!(
+        err.contains("refused to follow a redirect from https"),
+        "{err}"
+    );
+    assert_eq!(1, connection.requests().len());
+
+    let connection = CannedConnection::new(vec![
+        redirect(301, "http://example.com/"),
+        response(200, "done"),
+    ]);
+    let runtime_plugins = redirect_plugins(
+        &connection,
+        RedirectPolicy::new(3).with_https_downgrade(true),
+    );
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("done", output_string(output));
+    assert_eq!(
+        "http://example.com/",
+        connection.requests()[1].uri().to_string()
+    );
+}
@@ -0,0 +1,988 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::*;
+use crate::client::retries::classifier::{ModeledErrorClassifier, TransportIoErrorClassifier};
+use aws_smithy_async::rt::sleep::{AsyncSleep, Sleep, TokioSleep};
+use aws_smithy_http::body::BoxBody;
+use aws_smithy_runtime_api::client::interceptors::Interceptor;
+use aws_smithy_runtime_api::client::orchestrator::RequestAttemptHeader;
+use aws_smithy_runtime_api::client::retries::rate_limiting::token_bucket::Standard as StandardTokenBucket;
+use aws_smithy_runtime_api::client::retries::{ClassifyRetry, RetryClassifiers, RetryRequested};
+use aws_smithy_types::retry::RetryConfig;
+use std::sync::atomic::AtomicBool;
+
+#[tokio::test]
+async fn retry_resends_the_checkpointed_request() {
+    let connection = CannedConnection::new(vec![response(500, ""), response(200, "done")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(2));
+        }
+    });
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("done", output_string(output));
+
+    let requests = connection.requests();
+    assert_eq!(2, requests.len());
+    for request in requests.iter() {
+        assert_eq!(Some(&b"hello"[..]), request.body().bytes());
+    }
+}
+
+#[tokio::test]
+async fn retry_with_a_one_shot_body_fails_with_a_descriptive_error() {
+    let connection = CannedConnection::new(vec![response(500, ""), response(200, "done")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(2));
+            cfg.set_request_serializer(FnSerializer(|_input: Input| {
+                let body: BoxBody = http_body::Full::new(Bytes::from_static(b"hello"))
+                    .map_err(|never| match never {})
+                    .boxed();
+                Ok(http::Request::builder()
+                    .uri("/")
+                    .body(SdkBody::from_dyn(body))
+                    .expect("valid request"))
+            }));
+        }
+    });
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("the second attempt can't replay the body");
+    // The response of the first attempt is kept
+    assert_eq!(
+        Some(500),
+        err.raw_response()
+            .map(|response| response.status().as_u16())
+    );
+    assert!(matches!(err, SdkError::ResponseError(_)), "{err:?}");
+    let message = display_error(err);
+    assert!(message.contains("one-shot stream"), "{message}");
+    // Only the first attempt was dispatched
+    assert_eq!(1, connection.requests().len());
+}
+
+/// Records the durations it was asked to sleep for, and returns immediately.
+#[derive(Clone, Debug, Default)]
+struct RecordingSleep {
+    sleeps: Arc<Mutex<Vec<Duration>>>,
+}
+
+impl AsyncSleep for RecordingSleep {
+    fn sleep(&self, duration: Duration) -> Sleep {
+        self.sleeps.lock().unwrap().push(duration);
+        Sleep::new(async {})
+    }
+}
+
+#[tokio::test]
+async fn retries_sleep_for_the_duration_given_by_the_backoff_strategy() {
+    let connection = CannedConnection::new(vec![
+        response(500, ""),
+        response(500, ""),
+        response(500, ""),
+        response(200, "done"),
+    ]);
+    let sleep = RecordingSleep::default();
+    let runtime_plugins = test_plugins({
+        let (connection, sleep) = (connection.clone(), sleep.clone());
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(4));
+            cfg.store_put::<Box<dyn BackoffStrategy>>(Box::new(ExponentialBackoff {
+                base: Duration::from_millis(100),
+            }));
+            cfg.set_sleep_impl(Some(Arc::new(sleep.clone())));
+        }
+    });
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("done", output_string(output));
+    assert_eq!(4, connection.requests().len());
+    assert_eq!(
+        vec![
+            Duration::from_millis(100),
+            Duration::from_millis(200),
+            Duration::from_millis(400)
+        ],
+        *sleep.sleeps.lock().unwrap()
+    );
+}
+
+/// A connection whose pooled connections are all broken, while fresh connections succeed.
+#[derive(Clone, Debug, Default)]
+struct StalePoolConnection {
+    calls: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Connection for StalePoolConnection {
+    fn call(&self, _request: HttpRequest) -> BoxFuture<HttpResponse> {
+        self.calls.lock().unwrap().push("pooled");
+        let err = ConnectorError::io("broken pipe".into());
+        Box::pin(async move { Err::<HttpResponse, _>(BoxError::from(err)) })
+    }
+
+    fn call_on_fresh_connection(&self, _request: HttpRequest) -> BoxFuture<HttpResponse> {
+        self.calls.lock().unwrap().push("fresh");
+        let response = response(200, "done");
+        Box::pin(async move { response.map_err(BoxError::from) })
+    }
+}
+
+#[tokio::test]
+async fn retry_after_dispatch_failure_uses_a_fresh_connection_when_enabled() {
+    let connection = StalePoolConnection::default();
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(2));
+            cfg.store_put(
+                ConnectionConfig::new().with_fresh_connection_after_dispatch_failure(true),
+            );
+        }
+    });
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("done", output_string(output));
+    assert_eq!(vec!["pooled", "fresh"], *connection.calls.lock().unwrap());
+}
+
+#[tokio::test]
+async fn retry_after_dispatch_failure_reuses_the_pool_by_default() {
+    let connection = StalePoolConnection::default();
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(2));
+        }
+    });
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("every pooled connection is broken");
+    assert!(matches!(err, SdkError::DispatchFailure(_)), "{err:?}");
+    assert_eq!(vec!["pooled", "pooled"], *connection.calls.lock().unwrap());
+}
+
+/// Records the [`RequestAttempt`] once the operation has completed.
+#[derive(Debug, Default)]
+struct RecordRequestAttempt(Arc<Mutex<Option<RequestAttempt>>>);
+
+impl Interceptor for RecordRequestAttempt {
+    fn read_after_execution(
+        &self,
+        _context: &InterceptorContext,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        *self.0.lock().unwrap() = cfg.load::<RequestAttempt>().copied();
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn request_attempt_identifies_the_successful_attempt() {
+    let request_attempt = Arc::new(Mutex::new(None));
+    let runtime_plugins = test_plugins({
+        let request_attempt = request_attempt.clone();
+        move |cfg, interceptors| {
+            cfg.set_connection(CannedConnection::new(vec![
+                response(500, ""),
+                response(200, "done"),
+            ]));
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(2));
+            interceptors.register_operation_interceptor(Arc::new(RecordRequestAttempt(
+                request_attempt.clone(),
+            )));
+        }
+    });
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("done", output_string(output));
+
+    let request_attempt = request_attempt
+        .lock()
+        .unwrap()
+        .expect("attempt was recorded");
+    assert_eq!(2, request_attempt.attempt());
+    assert!(request_attempt.is_retry());
+}
+
+#[tokio::test]
+async fn request_attempt_of_a_first_attempt_success_is_not_a_retry() {
+    let request_attempt = Arc::new(Mutex::new(None));
+    let runtime_plugins = test_plugins({
+        let request_attempt = request_attempt.clone();
+        move |cfg, interceptors| {
+            cfg.set_connection(CannedConnection::new(vec![response(200, "done")]));
+            interceptors.register_operation_interceptor(Arc::new(RecordRequestAttempt(
+                request_attempt.clone(),
+            )));
+        }
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+
+    let request_attempt = request_attempt
+        .lock()
+        .unwrap()
+        .expect("attempt was recorded");
+    assert_eq!(1, request_attempt.attempt());
+    assert!(!request_attempt.is_retry());
+}
+
+#[derive(Clone, Debug, Default)]
+struct TestCancellationSignal(Arc<AtomicBool>);
+
+impl CancellationSignal for TestCancellationSignal {
+    fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Cancels the operation once the given attempt has completed.
+#[derive(Debug)]
+struct CancelAfterAttempt {
+    attempt: u32,
+    signal: TestCancellationSignal,
+}
+
+impl Interceptor for CancelAfterAttempt {
+    fn read_after_attempt(
+        &self,
+        _context: &InterceptorContext,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let attempt = cfg
+            .load::<RequestAttempt>()
+            .expect("attempt is set")
+            .attempt();
+        if attempt == self.attempt {
+            self.signal.0.store(true, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn cancellation_stops_the_retry_loop() {
+    let connection = CannedConnection::new(vec![
+        response(500, ""),
+        response(500, ""),
+        response(500, ""),
+        response(200, "done"),
+    ]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, interceptors| {
+            let signal = TestCancellationSignal::default();
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(4));
+            cfg.store_put::<Box<dyn CancellationSignal>>(Box::new(signal.clone()));
+            interceptors.register_operation_interceptor(Arc::new(CancelAfterAttempt {
+                attempt: 2,
+                signal,
+            }));
+        }
+    });
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("the operation was cancelled");
+    let failure = match err {
+        SdkError::DispatchFailure(failure) => failure,
+        err => panic!("expected a dispatch failure, got {err:?}"),
+    };
+    assert!(failure
+        .source_chain()
+        .any(|err| err.downcast_ref::<OperationCancelled>().is_some()));
+    // No attempts are made after the cancellation
+    assert_eq!(2, connection.requests().len());
+}
+
+#[tokio::test]
+async fn request_attempt_header_is_updated_for_every_attempt() {
+    let connection = CannedConnection::new(vec![
+        response(500, ""),
+        response(500, ""),
+        response(200, "done"),
+    ]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(3));
+            cfg.put(RetryConfig::standard().with_max_attempts(3));
+            cfg.store_put(RequestAttemptHeader::amz_sdk_request());
+        }
+    });
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("done", output_string(output));
+
+    let headers: Vec<_> = connection
+        .requests()
+        .iter()
+        .map(|request| {
+            let values = request.headers().get_all("amz-sdk-request");
+            assert_eq!(1, values.iter().count(), "the header isn't repeated");
+            request.headers()["amz-sdk-request"]
+                .to_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    assert_eq!(
+        vec!["attempt=1; max=3", "attempt=2; max=3", "attempt=3; max=3"],
+        headers
+    );
+}
+
+#[tokio::test]
+async fn request_attempt_header_format_is_configurable() {
+    let connection = CannedConnection::new(vec![response(200, "done")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.store_put(RequestAttemptHeader::new(
+                http::HeaderName::from_static("x-attempt"),
+                |attempt, max_attempts| format!("{attempt}/{max_attempts:?}"),
+            ));
+        }
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("1/None", connection.requests()[0].headers()["x-attempt"]);
+}
+
+/// Replaces the retry strategy with `FixedAttemptsRetryStrategy::new(max_attempts)` when a
+/// throttling response is received.
+#[derive(Debug)]
+struct SwitchStrategyOnThrottle {
+    max_attempts: usize,
+}
+
+impl Interceptor for SwitchStrategyOnThrottle {
+    fn modify_before_attempt_completion(
+        &self,
+        context: &mut InterceptorContext,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        if context.response()?.status() == 429 {
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(self.max_attempts));
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn swapped_retry_strategy_governs_subsequent_attempts() {
+    let connection = CannedConnection::new(vec![
+        response(429, ""),
+        response(500, ""),
+        response(500, ""),
+        response(200, "done"),
+    ]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, interceptors| {
+            cfg.set_connection(connection.clone());
+            // Without the swap, the throttling response wouldn't be retried
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(1));
+            interceptors.register_operation_interceptor(Arc::new(SwitchStrategyOnThrottle {
+                max_attempts: 4,
+            }));
+        }
+    });
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("done", output_string(output));
+    assert_eq!(4, connection.requests().len());
+}
+
+#[tokio::test]
+async fn idempotency_token_is_reused_across_retries() {
+    let connection = CannedConnection::new(vec![response(500, ""), response(200, "done")]);
+    let tokens_generated = Arc::new(AtomicUsize::new(0));
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        let tokens_generated = tokens_generated.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(2));
+            let tokens_generated = tokens_generated.clone();
+            // Generates a new token every time it's called, like an idempotency token provider
+            cfg.set_request_serializer(FnSerializer(move |_input: Input| {
+                let token = tokens_generated.fetch_add(1, Ordering::SeqCst);
+                Ok(http::Request::builder()
+                    .uri("/")
+                    .header("x-idempotency-token", format!("token-{token}"))
+                    .body(SdkBody::from("hello"))
+                    .expect("valid request"))
+            }));
+        }
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!(1, tokens_generated.load(Ordering::SeqCst));
+    let requests = connection.requests();
+    assert_eq!(2, requests.len());
+    for request in requests.iter() {
+        assert_eq!("token-0", request.headers()["x-idempotency-token"]);
+    }
+}
+
+/// Retries up to `max_attempts` times when the configured retry classifiers classify the
+/// attempt's error as retryable.
+#[derive(Debug)]
+struct ClassifyingRetryStrategy {
+    max_attempts: u32,
+}
+
+impl RetryStrategy for ClassifyingRetryStrategy {
+    fn should_attempt_initial_request(&self, _cfg: &ConfigBag) -> Result<ShouldAttempt, BoxError> {
+        Ok(ShouldAttempt::Yes)
+    }
+
+    fn should_attempt_retry(
+        &self,
+        context: &InterceptorContext,
+        cfg: &ConfigBag,
+    ) -> Result<ShouldAttempt, BoxError> {
+        let attempt = cfg
+            .load::<RequestAttempt>()
+            .expect("attempt is set")
+            .attempt();
+        let retryable = match context.output_or_error() {
+            Ok(Err(error)) => cfg.retry_classifiers().classify_retry(error).is_some(),
+            _ => false,
+        };
+        if retryable && attempt < self.max_attempts {
+            Ok(ShouldAttempt::Yes)
+        } else {
+            Ok(ShouldAttempt::No)
+        }
+    }
+}
+
+#[tokio::test]
+async fn connection_reset_is_retried_for_idempotent_operations() {
+    let reset = std::io::Error::from(std::io::ErrorKind::ConnectionReset);
+    let connection = CannedConnection::new(vec![
+        Err(ConnectorError::other(reset.into(), None)),
+        response(200, "done"),
+    ]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(ClassifyingRetryStrategy { max_attempts: 2 });
+            cfg.set_retry_classifiers(
+                RetryClassifiers::new().with_classifier(TransportIoErrorClassifier::default()),
+            );
+        }
+    });
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("done", output_string(output));
+    assert_eq!(2, connection.requests().len());
+}
+
+/// Backs off for longer after throttling errors than after other errors.
+#[derive(Debug)]
+struct ThrottlingAwareBackoff;
+
+impl BackoffStrategy for ThrottlingAwareBackoff {
+    fn backoff(&self, _attempt: u32, cfg: &ConfigBag) -> Duration {
+        match cfg
+            .load::<AttemptRetryReason>()
+            .and_then(AttemptRetryReason::retry_reason)
+        {
+            Some(RetryReason::Error(ErrorKind::ThrottlingError)) => Duration::from_secs(1),
+            _ => Duration::from_millis(100),
+        }
+    }
+}
+
+fn modeled_retry_plugins(connection: CannedConnection, sleep: RecordingSleep) -> RuntimePlugins {
+    test_plugins(move |cfg, _| {
+        cfg.set_connection(connection.clone());
+        cfg.set_retry_strategy(ClassifyingRetryStrategy { max_attempts: 3 });
+        cfg.set_retry_classifiers(
+            RetryClassifiers::new().with_classifier(ModeledErrorClassifier::<TestError>::new()),
+        );
+        cfg.store_put::<Box<dyn BackoffStrategy>>(Box::new(ThrottlingAwareBackoff));
+        cfg.set_sleep_impl(Some(Arc::new(sleep.clone())));
+    })
+}
+
+#[tokio::test]
+async fn modeled_throttling_errors_are_retried_with_throttling_backoff() {
+    let connection = CannedConnection::new(vec![
+        response(429, ""),
+        response(503, ""),
+        response(200, "done"),
+    ]);
+    let sleep = RecordingSleep::default();
+    let runtime_plugins = modeled_retry_plugins(connection.clone(), sleep.clone());
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("done", output_string(output));
+    assert_eq!(3, connection.requests().len());
+    assert_eq!(
+        vec![Duration::from_secs(1), Duration::from_millis(100)],
+        *sleep.sleeps.lock().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn modeled_non_retryable_errors_are_not_retried() {
+    let connection = CannedConnection::new(vec![response(400, ""), response(200, "done")]);
+    let sleep = RecordingSleep::default();
+    let runtime_plugins = modeled_retry_plugins(connection.clone(), sleep.clone());
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("the error isn't retryable");
+    assert!(matches!(err, SdkError::ServiceError(_)));
+    assert_eq!(1, connection.requests().len());
+    assert!(sleep.sleeps.lock().unwrap().is_empty());
+}
+
+/// A connection that records when it was called, relative to `started_at`, and takes a second
+/// to reply like a [`CannedConnection`].
+#[derive(Clone, Debug)]
+struct TimedConnection {
+    inner: CannedConnection,
+    started_at: tokio::time::Instant,
+    calls: Arc<Mutex<Vec<Duration>>>,
+}
+
+impl Connection for TimedConnection {
+    fn call(&self, request: HttpRequest) -> BoxFuture<HttpResponse> {
+        self.calls.lock().unwrap().push(self.started_at.elapsed());
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            response.await
+        })
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn retries_queue_when_the_retry_concurrency_limit_is_reached() {
+    let limiter = RetryConcurrencyLimiter::new(1);
+    let started_at = tokio::time::Instant::now();
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let runtime_plugins = || {
+        let connection = TimedConnection {
+            inner: CannedConnection::new(vec![response(500, ""), response(200, "done")]),
+            started_at,
+            calls: calls.clone(),
+        };
+        let limiter = limiter.clone();
+        test_plugins(move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(2));
+            cfg.store_put::<Box<dyn BackoffStrategy>>(Box::new(ExponentialBackoff {
+                base: Duration::from_secs(1),
+            }));
+            cfg.set_sleep_impl(Some(Arc::new(TokioSleep::new())));
+            cfg.store_put(limiter.clone());
+        })
+    };
+    let (first, second) = (runtime_plugins(), runtime_plugins());
+
+    let (first, second) = tokio::join!(
+        invoke(test_input("hello"), &first),
+        invoke(test_input("hello"), &second)
+    );
+    first.expect("success");
+    second.expect("success");
+    // The second operation only starts waiting to retry once the first one's retry completed
+    assert_eq!(
+        vec![
+            Duration::from_secs(0),
+            Duration::from_secs(0),
+            Duration::from_secs(2),
+            Duration::from_secs(4),
+        ],
+        *calls.lock().unwrap()
+    );
+}
+
+fn retry_token_bucket_plugins(
+    connection: CannedConnection,
+    token_bucket: StandardTokenBucket,
+) -> RuntimePlugins {
+    test_plugins(move |cfg, _| {
+        cfg.set_connection(connection.clone());
+        cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(10));
+        cfg.store_put(token_bucket.clone());
+    })
+}
+
+#[tokio::test]
+async fn retries_stop_once_the_token_bucket_is_empty() {
+    let connection = CannedConnection::new((0..10).map(|_| response(500, "")).collect());
+    // Enough for two retries of server errors, which cost five tokens each
+    let token_bucket = StandardTokenBucket::builder()
+        .starting_tokens(10)
+        .retryable_error_cost(5)
+        .build();
+    let runtime_plugins = retry_token_bucket_plugins(connection.clone(), token_bucket.clone());
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("every attempt fails");
+    assert!(matches!(err, SdkError::ServiceError(_)), "{err:?}");
+    assert_eq!(3, connection.requests().len());
+    assert_eq!(0, token_bucket.available());
+}
+
+#[tokio::test]
+async fn successes_refill_the_token_bucket() {
+    let token_bucket = StandardTokenBucket::builder()
+        .starting_tokens(8)
+        .max_tokens(10)
+        .retryable_error_cost(5)
+        .build();
+
+    // A successful retry gets its tokens back
+    let connection = CannedConnection::new(vec![response(500, ""), response(200, "done")]);
+    let runtime_plugins = retry_token_bucket_plugins(connection, token_bucket.clone());
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("the retry succeeds");
+    assert_eq!(8, token_bucket.available());
+
+    // A success on the first try adds a token
+    let connection = CannedConnection::new(vec![response(200, "done")]);
+    let runtime_plugins = retry_token_bucket_plugins(connection, token_bucket.clone());
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!(9, token_bucket.available());
+}
+
+#[tokio::test]
+async fn replayable_bodies_are_generated_for_every_attempt() {
+    let factory_calls = Arc::new(AtomicUsize::new(0));
+    let connection = CannedConnection::new(vec![response(500, ""), response(200, "done")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        let factory_calls = factory_calls.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(2));
+            let factory_calls = factory_calls.clone();
+            cfg.store_put(ReplayableBody::new(move || {
+                factory_calls.fetch_add(1, Ordering::SeqCst);
+                SdkBody::from("generated")
+            }));
+        }
+    });
+
+    let output = invoke(test_input("serialized"), &runtime_plugins)
+        .await
+        .expect("the retry succeeds");
+    assert_eq!("done", output_string(output));
+    assert_eq!(2, factory_calls.load(Ordering::SeqCst));
+    let requests = connection.requests();
+    assert_eq!(2, requests.len());
+    for request in requests.iter() {
+        assert_eq!(Some(&b"generated"[..]), request.body().bytes());
+    }
+}
+
+/// Asks for responses with a `pending` body to be retried, and otherwise deserializes like
+/// [`StatusDeserializer`].
+#[derive(Debug)]
+struct PollingDeserializer;
+
+impl ResponseDeserializer for PollingDeserializer {
+    fn deserialize_nonstreaming(&self, response: &HttpResponse) -> OutputOrError {
+        if response.body().bytes() == Some(&b"pending"[..]) {
+            Err(TypedBox::new(RetryRequested::new("the result isn't ready yet")).erase())
+        } else {
+            StatusDeserializer.deserialize_nonstreaming(response)
+        }
+    }
+}
+
+#[tokio::test]
+async fn deserializers_can_ask_for_a_retry() {
+    let connection = CannedConnection::new(vec![response(200, "pending"), response(200, "done")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_response_deserializer(PollingDeserializer);
+            cfg.set_retry_classifiers(RetryClassifiers::new());
+            cfg.set_retry_strategy(ClassifyingRetryStrategy { max_attempts: 3 });
+        }
+    });
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("the retry succeeds");
+    assert_eq!("done", output_string(output));
+    assert_eq!(2, connection.requests().len());
+}
+
+async fn attempts_made_for(operation_id: &'static str) -> usize {
+    let connection = CannedConnection::new(vec![response(500, ""), response(200, "done")]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(2));
+            cfg.store_put(
+                NonRetryableOperations::new()
+                    .with_operation(OperationId::new("com.example#CreateWidget")),
+            );
+        }
+    })
+    .with_operation_id(OperationId::new(operation_id));
+
+    let _ = invoke(test_input("hello"), &runtime_plugins).await;
+    let attempts = connection.requests().len();
+    attempts
+}
+
+#[tokio::test]
+async fn non_retryable_operations_are_not_retried() {
+    assert_eq!(1, attempts_made_for("com.example#CreateWidget").await);
+}
+
+#[tokio::test]
+async fn other_operations_are_retried() {
+    assert_eq!(2, attempts_made_for("com.example#GetWidget").await);
+}
+
+#[tokio::test]
+async fn retries_stop_once_the_retry_duration_is_exhausted() {
+    tokio::time::pause();
+    let connection = CannedConnection::new((0..10).map(|_| response(500, "")).collect());
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(10));
+            // Attempts start after 0s, 1s, and 3s. A fourth would start after 7s.
+            cfg.store_put::<Box<dyn BackoffStrategy>>(Box::new(ExponentialBackoff {
+                base: Duration::from_secs(1),
+            }));
+            cfg.set_sleep_impl(Some(Arc::new(TokioSleep::new())));
+            cfg.store_put::<Box<dyn TimeSource>>(Box::new(TokioTimeSource::new()));
+            cfg.store_put(MaxRetryDuration::new(Duration::from_secs(5)));
+        }
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("every attempt fails");
+    assert_eq!(3, connection.requests().len());
+}
+
+/// Records the retries it's told about, and vetoes those past `max_attempts`.
+#[derive(Clone, Debug, Default)]
+struct VetoRetriesAfter {
+    max_attempts: u32,
+    retries: Arc<Mutex<Vec<(Duration, u32)>>>,
+}
+
+impl BeforeRetryCallback for VetoRetriesAfter {
+    fn before_retry(&self, delay: Duration, attempt: u32) -> bool {
+        self.retries.lock().unwrap().push((delay, attempt));
+        attempt <= self.max_attempts
+    }
+}
+
+#[tokio::test]
+async fn before_retry_callback_can_veto_a_retry() {
+    tokio::time::pause();
+    let connection = CannedConnection::new((0..5).map(|_| response(500, "")).collect());
+    let callback = VetoRetriesAfter {
+        max_attempts: 2,
+        ..Default::default()
+    };
+    let runtime_plugins = test_plugins({
+        let (connection, callback) = (connection.clone(), callback.clone());
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(5));
+            cfg.store_put::<Box<dyn BackoffStrategy>>(Box::new(ExponentialBackoff {
+                base: Duration::from_secs(1),
+            }));
+            cfg.set_sleep_impl(Some(Arc::new(TokioSleep::new())));
+            cfg.store_put::<Box<dyn BeforeRetryCallback>>(Box::new(callback.clone()));
+        }
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("every attempt fails");
+    // The second retry (the third attempt) was vetoed, so only two requests were sent
+    assert_eq!(2, connection.requests().len());
+    assert_eq!(
+        vec![(Duration::from_secs(1), 2), (Duration::from_secs(2), 3)],
+        *callback.retries.lock().unwrap()
+    );
+}
+
+/// Records whether each attempt was the final one, as seen by interceptors.
+#[derive(Debug, Default)]
+struct RecordFinalAttempt(Mutex<Vec<bool>>);
+
+impl Interceptor for RecordFinalAttempt {
+    fn read_before_attempt(
+        &self,
+        _context: &InterceptorContext,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        self.0.lock().unwrap().push(
+            cfg.load::<FinalAttempt>()
+                .map_or(false, FinalAttempt::is_final),
+        );
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn only_the_last_allowed_attempt_is_final() {
+    let recorded = Arc::new(RecordFinalAttempt::default());
+    let runtime_plugins = test_plugins({
+        let recorded = recorded.clone();
+        move |cfg, interceptors| {
+            cfg.set_connection(CannedConnection::new(vec![
+                response(500, ""),
+                response(500, ""),
+                response(500, ""),
+            ]));
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(3));
+            interceptors.register_operation_interceptor(recorded.clone());
+        }
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("every attempt failed");
+    assert_eq!(vec![false, false, true], *recorded.0.lock().unwrap());
+}
+
+/// Retries every error response, no matter how many attempts have been made.
+#[derive(Debug)]
+struct RetryUntilSuccess;
+
+impl RetryStrategy for RetryUntilSuccess {
+    fn should_attempt_initial_request(&self, _cfg: &ConfigBag) -> Result<ShouldAttempt, BoxError> {
+        Ok(ShouldAttempt::Yes)
+    }
+
+    fn should_attempt_retry(
+        &self,
+        context: &InterceptorContext,
+        _cfg: &ConfigBag,
+    ) -> Result<ShouldAttempt, BoxError> {
+        match context.output_or_error() {
+            Ok(Err(_)) => Ok(ShouldAttempt::Yes),
+            _ => Ok(ShouldAttempt::No),
+        }
+    }
+}
+
+#[tokio::test]
+async fn no_attempt_is_final_when_attempts_are_unbounded() {
+    let recorded = Arc::new(RecordFinalAttempt::default());
+    let runtime_plugins = test_plugins({
+        let recorded = recorded.clone();
+        move |cfg, interceptors| {
+            cfg.set_connection(CannedConnection::new(vec![
+                response(500, ""),
+                response(200, "done"),
+            ]));
+            cfg.set_retry_strategy(RetryUntilSuccess);
+            interceptors.register_operation_interceptor(recorded.clone());
+        }
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!(vec![false, false], *recorded.0.lock().unwrap());
+}
+
+/// Sends the first attempt over `first`, and every retry over `fallback`.
+#[derive(Debug)]
+struct FallbackOnRetry {
+    first: CannedConnection,
+    fallback: CannedConnection,
+}
+
+impl ConnectionSelector for FallbackOnRetry {
+    fn select(&self, attempt: u32) -> Option<&dyn Connection> {
+        match attempt {
+            1 => Some(&self.first),
+            _ => Some(&self.fallback),
+        }
+    }
+}
+
+#[tokio::test]
+async fn retries_can_be_sent_over_a_different_connection() {
+    let first = CannedConnection::new(vec![Err(ConnectorError::io("connection refused".into()))]);
+    let fallback = CannedConnection::new(vec![response(200, "done")]);
+    let runtime_plugins = test_plugins({
+        let (first, fallback) = (first.clone(), fallback.clone());
+        move |cfg, _| {
+            // Never used, since the selector picks a connection for every attempt
+            cfg.set_connection(CannedConnection::new(vec![]));
+            cfg.store_put::<Box<dyn ConnectionSelector>>(Box::new(FallbackOnRetry {
+                first: first.clone(),
+                fallback: fallback.clone(),
+            }));
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(2));
+        }
+    });
+
+    let output = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!("done", output_string(output));
+    assert_eq!(1, first.requests().len());
+    assert_eq!(1, fallback.requests().len());
+}
@@ -0,0 +1,235 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::*;
+use aws_smithy_async::future::never::Never;
+use aws_smithy_async::rt::sleep::TokioSleep;
+use aws_smithy_runtime_api::client::interceptors::Interceptor;
+use aws_smithy_types::timeout::TimeoutConfig;
+
+/// An identity resolver that never resolves, like a credentials provider that hangs.
+#[derive(Debug)]
+struct HangingIdentityResolver;
+
+impl IdentityResolver for HangingIdentityResolver {
+    fn resolve_identity(
+        &self,
+        _config_bag: &ConfigBag,
+    ) -> aws_smithy_runtime_api::client::orchestrator::Future<Identity> {
+        aws_smithy_runtime_api::client::orchestrator::Future::new(Box::pin(async {
+            Never::new().await;
+            Ok::<_, BoxError>(Identity::new((), None))
+        }))
+    }
+}
+
+#[tokio::test]
+async fn auth_timeout_fires_when_identity_resolution_hangs() {
+    tokio::time::pause();
+    let connection = CannedConnection::new(vec![]);
+    let runtime_plugins = test_plugins({
+        let connection = connection.clone();
+        move |cfg, _| {
+            cfg.set_connection(connection.clone());
+            cfg.set_identity_resolvers(
+                IdentityResolvers::builder()
+                    .identity_resolver(NO_AUTH_SCHEME_ID, HangingIdentityResolver)
+                    .build(),
+            );
+            cfg.put(
+                TimeoutConfig::builder()
+                    .auth_timeout(Duration::from_millis(100))
+                    .build(),
+            );
+            cfg.set_sleep_impl(Some(Arc::new(TokioSleep::new())));
+        }
+    });
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("identity resolution never completes");
+    assert!(matches!(err, SdkError::TimeoutError(_)), "{err:?}");
+    let message = display_error(err);
+    assert!(message.contains("auth timeout"), "{message}");
+    assert!(message.contains("100ms"), "{message}");
+    assert!(connection.requests().is_empty());
+}
+
+/// Records the remaining operation budget at the start of every attempt.
+#[derive(Debug, Default)]
+struct RecordRemainingBudget(Arc<Mutex<Vec<Option<Duration>>>>);
+
+impl Interceptor for RecordRemainingBudget {
+    fn read_before_attempt(
+        &self,
+        _context: &InterceptorContext,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let remaining_budget = cfg
+            .load::<OperationDeadline>()
+            .map(|deadline| deadline.remaining(time_source(cfg)));
+        self.0.lock().unwrap().push(remaining_budget);
+        Ok(())
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn remaining_operation_budget_decreases_across_attempts() {
+    let budgets = Arc::new(Mutex::new(Vec::new()));
+    let runtime_plugins = test_plugins({
+        let budgets = budgets.clone();
+        move |cfg, interceptors| {
+            cfg.set_connection(CannedConnection::new(vec![
+                response(500, ""),
+                response(500, ""),
+                response(200, "done"),
+            ]));
+            cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(3));
+            cfg.store_put::<Box<dyn BackoffStrategy>>(Box::new(ExponentialBackoff {
+                base: Duration::from_secs(1),
+            }));
+            cfg.set_sleep_impl(Some(Arc::new(TokioSleep::new())));
+            cfg.store_put::<Box<dyn TimeSource>>(Box::new(TokioTimeSource::new()));
+            cfg.put(
+                TimeoutConfig::builder()
+                    .operation_timeout(Duration::from_secs(10))
+                    .build(),
+            );
+            interceptors
+                .register_operation_interceptor(Arc::new(RecordRemainingBudget(budgets.clone())));
+        }
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!(
+        vec![
+            Some(Duration::from_secs(10)),
+            Some(Duration::from_secs(9)),
+            Some(Duration::from_secs(7)),
+        ],
+        *budgets.lock().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn remaining_operation_budget_is_none_without_an_operation_timeout() {
+    let budgets = Arc::new(Mutex::new(Vec::new()));
+    let runtime_plugins = test_plugins({
+        let budgets = budgets.clone();
+        move |cfg, interceptors| {
+            cfg.set_connection(CannedConnection::new(vec![response(200, "done")]));
+            interceptors
+                .register_operation_interceptor(Arc::new(RecordRemainingBudget(budgets.clone())));
+        }
+    });
+
+    invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect("success");
+    assert_eq!(vec![None], *budgets.lock().unwrap());
+}
+
+/// A connection that takes `delay` to reply like a [`CannedConnection`].
+#[derive(Debug)]
+struct SlowConnection {
+    inner: CannedConnection,
+    delay: Duration,
+}
+
+impl Connection for SlowConnection {
+    fn call(&self, request: HttpRequest) -> BoxFuture<HttpResponse> {
+        let response = self.inner.call(request);
+        let delay = self.delay;
+        Box::pin(async move {
+            tokio::time::sleep(delay).await;
+            response.await
+        })
+    }
+}
+
+#[tokio::test]
+async fn serialization_counts_against_the_operation_timeout() {
+    let runtime_plugins = test_plugins(|cfg, _| {
+        // Neither serialization nor the connection take long enough to time out on their own
+        cfg.set_request_serializer(FnSerializer(|_input: Input| {
+            std::thread::sleep(Duration::from_millis(150));
+            Ok(http::Request::builder()
+                .uri("/")
+                .body(SdkBody::from("hello"))
+                .expect("valid request"))
+        }));
+        cfg.set_connection(SlowConnection {
+            inner: CannedConnection::new(vec![response(200, "done")]),
+            delay: Duration::from_millis(100),
+        });
+        cfg.set_sleep_impl(Some(Arc::new(TokioSleep::new())));
+        cfg.put(
+            TimeoutConfig::builder()
+                .operation_timeout(Duration::from_millis(200))
+                .build(),
+        );
+    });
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("serialization and the attempt exceed the operation timeout together");
+    assert!(matches!(err, SdkError::TimeoutError(_)), "{err:?}");
+}
+
+#[tokio::test]
+async fn attempt_errors_are_attached_when_the_operation_times_out() {
+    let runtime_plugins = test_plugins(|cfg, _| {
+        cfg.set_connection(SlowConnection {
+            inner: CannedConnection::new(vec![
+                response(500, ""),
+                response(502, ""),
+                response(200, "done"),
+            ]),
+            delay: Duration::from_millis(100),
+        });
+        cfg.set_retry_strategy(FixedAttemptsRetryStrategy::new(3));
+        cfg.set_sleep_impl(Some(Arc::new(TokioSleep::new())));
+        cfg.put(
+            TimeoutConfig::builder()
+                .operation_timeout(Duration::from_millis(250))
+                .build(),
+        );
+    });
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("the third attempt exceeds the operation timeout");
+    assert!(matches!(err, SdkError::TimeoutError(_)), "{err:?}");
+    let attempt_errors: Vec<_> = err
+        .attempt_errors()
+        .iter()
+        .map(|attempt_error| (attempt_error.attempt(), attempt_error.status()))
+        .collect();
+    assert_eq!(vec![(1, Some(500)), (2, Some(502))], attempt_errors);
+}
+
+#[tokio::test]
+async fn async_endpoint_resolution_counts_towards_the_attempt_timeout() {
+    tokio::time::pause();
+    let runtime_plugins = test_plugins(|cfg, _| {
+        cfg.set_connection(CannedConnection::new(vec![response(200, "done")]));
+        cfg.store_put::<Box<dyn AsyncEndpointResolver>>(Box::new(DiscoveryEndpointResolver {
+            delay: Duration::from_secs(10),
+        }));
+        cfg.put(
+            TimeoutConfig::builder()
+                .operation_attempt_timeout(Duration::from_secs(1))
+                .build(),
+        );
+        cfg.set_sleep_impl(Some(Arc::new(TokioSleep::new())));
+    });
+
+    let err = invoke(test_input("hello"), &runtime_plugins)
+        .await
+        .expect_err("endpoint resolution outlasts the attempt timeout");
+    assert!(matches!(err, SdkError::TimeoutError(_)), "{err:?}");
+}
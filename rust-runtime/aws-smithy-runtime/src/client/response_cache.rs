@@ -0,0 +1,64 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use aws_smithy_runtime_api::client::interceptors::context::Output;
+use aws_smithy_runtime_api::client::orchestrator::ResponseCache;
+use aws_smithy_runtime_api::type_erasure::TypeErasedBox;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+/// A [`ResponseCache`] that keeps outputs of type `T` in memory for a fixed time to live.
+///
+/// Expired entries are removed when they're looked up.
+pub struct InMemoryResponseCache<T> {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (SystemTime, T)>>,
+}
+
+impl<T> InMemoryResponseCache<T> {
+    /// Create a new [`InMemoryResponseCache`] whose entries expire `ttl` after they're cached.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<T> fmt::Debug for InMemoryResponseCache<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InMemoryResponseCache")
+            .field("ttl", &self.ttl)
+            .field("entries", &self.entries.lock().unwrap().len())
+            .finish()
+    }
+}
+
+impl<T> ResponseCache for InMemoryResponseCache<T>
+where
+    T: Clone + fmt::Debug + Send + Sync + 'static,
+{
+    fn get(&self, key: &str, now: SystemTime) -> Option<Output> {
+        let mut entries = self.entries.lock().unwrap();
+        let (expires_at, output) = entries.get(key)?;
+        if now < *expires_at {
+            return Some(TypeErasedBox::new(output.clone()));
+        }
+        entries.remove(key);
+        None
+    }
+
+    fn put(&self, key: String, output: &Output, now: SystemTime) {
+        // Outputs of other types can't be cloned, so they aren't cached
+        if let Some(output) = output.downcast_ref::<T>() {
+            self.entries
+                .lock()
+                .unwrap()
+                .insert(key, (now + self.ttl, output.clone()));
+        }
+    }
+}
@@ -3,10 +3,14 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-use aws_smithy_http::result::SdkError;
-use aws_smithy_runtime_api::client::retries::RetryReason;
+use aws_smithy_http::result::{ConnectorError, SdkError};
+use aws_smithy_runtime_api::client::interceptors::context::Error;
+use aws_smithy_runtime_api::client::retries::{ClassifyRetry, RetryReason};
 use aws_smithy_types::retry::{ErrorKind, ProvideErrorKind};
 use std::borrow::Cow;
+use std::fmt;
+use std::io;
+use std::marker::PhantomData;
 
 /// A retry classifier for checking if an error is modeled as retryable.
 #[derive(Debug)]
@@ -27,6 +31,52 @@ impl ModeledAsRetryableClassifier {
     }
 }
 
+/// A retry classifier that retries the modeled errors of type `E` as their `@retryable` trait
+/// says, including whether they're throttling errors.
+///
+/// Unlike [`ModeledAsRetryableClassifier`], it classifies the type-erased error of an attempt, so
+/// it can be registered in the
+/// [`RetryClassifiers`](aws_smithy_runtime_api::client::retries::RetryClassifiers) as is. `E` is
+/// the operation's error type; errors of any other type aren't classified.
+pub struct ModeledErrorClassifier<E> {
+    _error: PhantomData<fn() -> E>,
+}
+
+impl<E> ModeledErrorClassifier<E> {
+    /// Create a new [`ModeledErrorClassifier`] for errors of type `E`.
+    pub fn new() -> Self {
+        Self {
+            _error: PhantomData,
+        }
+    }
+}
+
+impl<E> Default for ModeledErrorClassifier<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> fmt::Debug for ModeledErrorClassifier<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ModeledErrorClassifier")
+            .field(&std::any::type_name::<E>())
+            .finish()
+    }
+}
+
+impl<E> ClassifyRetry for ModeledErrorClassifier<E>
+where
+    E: ProvideErrorKind + fmt::Debug + Send + Sync + 'static,
+{
+    fn classify_retry(&self, error: &Error) -> Option<RetryReason> {
+        error
+            .downcast_ref::<E>()?
+            .retryable_error_kind()
+            .map(RetryReason::Error)
+    }
+}
+
 #[derive(Debug)]
 pub struct SmithyErrorClassifier;
 
@@ -80,11 +130,58 @@ impl Default for HttpStatusCodeClassifier {
     }
 }
 
+const RETRYABLE_IO_ERROR_KINDS: &[io::ErrorKind] =
+    &[io::ErrorKind::ConnectionReset, io::ErrorKind::BrokenPipe];
+
+/// A retry classifier that treats connection failures caused by the given IO error kinds as
+/// retryable. The `Default` version will retry connection resets and broken pipes.
+///
+/// The IO error is looked up anywhere in the connector error's source chain, so failures that
+/// the connector doesn't report as IO errors, such as a reset while the request body was being
+/// sent, are retried too. Since the server may have already processed the request when this
+/// happens, this classifier should only be registered for idempotent operations.
+#[derive(Debug)]
+pub struct TransportIoErrorClassifier {
+    retryable_kinds: Cow<'static, [io::ErrorKind]>,
+}
+
+impl TransportIoErrorClassifier {
+    /// Given a `Vec<io::ErrorKind>`, create a retry classifier that will treat connection failures
+    /// caused by IO errors of those kinds as retryable. The `Default` version will retry
+    /// connection resets and broken pipes.
+    pub fn new_from_kinds(retryable_kinds: impl Into<Cow<'static, [io::ErrorKind]>>) -> Self {
+        Self {
+            retryable_kinds: retryable_kinds.into(),
+        }
+    }
+
+    /// Classify a connector error based on the kind of the IO error that caused it.
+    pub fn classify_connector_error(&self, error: &ConnectorError) -> Option<RetryReason> {
+        error
+            .source_chain()
+            .filter_map(|err| err.downcast_ref::<io::Error>())
+            .any(|err| self.retryable_kinds.contains(&err.kind()))
+            .then_some(RetryReason::Error(ErrorKind::TransientError))
+    }
+}
+
+impl Default for TransportIoErrorClassifier {
+    fn default() -> Self {
+        Self::new_from_kinds(RETRYABLE_IO_ERROR_KINDS.to_owned())
+    }
+}
+
+impl ClassifyRetry for TransportIoErrorClassifier {
+    fn classify_retry(&self, error: &Error) -> Option<RetryReason> {
+        self.classify_connector_error(error.downcast_ref::<ConnectorError>()?)
+    }
+}
+
 // Generic smithy clients would have something like this:
 // pub fn default_retry_classifiers() -> RetryClassifiers {
 //     RetryClassifiers::new()
 //         .with_classifier(SmithyErrorClassifier::new())
-//         .with_classifier(ModeledAsRetryableClassifier::new())
+//         .with_classifier(ModeledErrorClassifier::<OperationError>::new())
 //         .with_classifier(HttpStatusCodeClassifier::new())
 // }
 // This ordering is different than the default AWS ordering because the old generic client classifer
@@ -95,12 +192,14 @@ mod test {
     use std::fmt;
 
     use crate::client::retries::classifier::{
-        HttpStatusCodeClassifier, ModeledAsRetryableClassifier,
+        HttpStatusCodeClassifier, ModeledAsRetryableClassifier, ModeledErrorClassifier,
+        TransportIoErrorClassifier,
     };
     use aws_smithy_http::body::SdkBody;
     use aws_smithy_http::operation;
-    use aws_smithy_http::result::SdkError;
-    use aws_smithy_runtime_api::client::retries::RetryReason;
+    use aws_smithy_http::result::{ConnectorError, SdkError};
+    use aws_smithy_runtime_api::client::retries::{ClassifyRetry, RetryReason};
+    use aws_smithy_runtime_api::type_erasure::TypedBox;
     use aws_smithy_types::retry::{ErrorKind, ProvideErrorKind};
 
     use super::SmithyErrorClassifier;
@@ -171,6 +270,33 @@ mod test {
         );
     }
 
+    #[test]
+    fn classify_erased_error_by_modeled_error_kind() {
+        #[derive(Debug)]
+        struct ThrottlingError;
+
+        impl ProvideErrorKind for ThrottlingError {
+            fn retryable_error_kind(&self) -> Option<ErrorKind> {
+                Some(ErrorKind::ThrottlingError)
+            }
+
+            fn code(&self) -> Option<&str> {
+                None
+            }
+        }
+
+        let policy = ModeledErrorClassifier::<ThrottlingError>::new();
+        assert_eq!(
+            policy.classify_retry(&TypedBox::new(ThrottlingError).erase()),
+            Some(RetryReason::Error(ErrorKind::ThrottlingError)),
+        );
+        // Errors of other types are left to other classifiers
+        assert_eq!(
+            policy.classify_retry(&TypedBox::new(UnmodeledError).erase()),
+            None
+        );
+    }
+
     #[test]
     fn classify_response_error() {
         let policy = SmithyErrorClassifier;
@@ -192,4 +318,20 @@ mod test {
             Some(RetryReason::Error(ErrorKind::TransientError)),
         );
     }
+
+    #[test]
+    fn classify_by_io_error_kind() {
+        let policy = TransportIoErrorClassifier::default();
+        let reset = std::io::Error::from(std::io::ErrorKind::ConnectionReset);
+        // Body transmission failures aren't necessarily reported as IO errors
+        let err = ConnectorError::other(reset.into(), None);
+        assert_eq!(
+            policy.classify_connector_error(&err),
+            Some(RetryReason::Error(ErrorKind::TransientError)),
+        );
+
+        let not_found = std::io::Error::from(std::io::ErrorKind::NotFound);
+        let err = ConnectorError::io(not_found.into());
+        assert_eq!(policy.classify_connector_error(&err), None);
+    }
 }
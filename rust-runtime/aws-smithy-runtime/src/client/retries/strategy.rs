@@ -4,5 +4,7 @@
  */
 
 mod never;
+mod retry_after;
 
 pub use never::NeverRetryStrategy;
+pub use retry_after::parse_retry_after;
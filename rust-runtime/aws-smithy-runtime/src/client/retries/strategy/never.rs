@@ -29,4 +29,8 @@ impl RetryStrategy for NeverRetryStrategy {
     ) -> Result<ShouldAttempt, BoxError> {
         Ok(ShouldAttempt::No)
     }
+
+    fn max_attempts(&self) -> Option<u32> {
+        Some(1)
+    }
 }
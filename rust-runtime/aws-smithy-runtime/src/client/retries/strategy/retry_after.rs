@@ -0,0 +1,81 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use aws_smithy_types::date_time::Format;
+use aws_smithy_types::DateTime;
+use http::HeaderMap;
+use std::time::{Duration, SystemTime};
+
+/// Parses the `Retry-After` header of a response into how long to wait before retrying.
+///
+/// The header may be either a number of seconds, or an HTTP-date as defined in
+/// [RFC 7231](https://www.rfc-editor.org/rfc/rfc7231#section-7.1.3). Dates are converted into a
+/// delay relative to `now`, which should come from the configured
+/// [`TimeSource`](aws_smithy_runtime_api::client::orchestrator::TimeSource). Dates in the past
+/// result in a delay of zero.
+///
+/// Returns `None` if the header is missing or malformed.
+pub fn parse_retry_after(headers: &HeaderMap, now: SystemTime) -> Option<Duration> {
+    let value = headers
+        .get(http::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) {
+        return value.parse().ok().map(Duration::from_secs);
+    }
+    let retry_at = DateTime::from_str(value, Format::HttpDate).ok()?;
+    let retry_at = SystemTime::try_from(retry_at).ok()?;
+    Some(retry_at.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    // Wed, 21 Oct 2015 07:28:00 GMT
+    fn now() -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1445412480)
+    }
+
+    fn retry_after(value: &'static str) -> Option<Duration> {
+        let mut headers = HeaderMap::new();
+        headers.insert(http::header::RETRY_AFTER, HeaderValue::from_static(value));
+        parse_retry_after(&headers, now())
+    }
+
+    #[test]
+    fn seconds() {
+        assert_eq!(Some(Duration::from_secs(120)), retry_after("120"));
+        assert_eq!(Some(Duration::ZERO), retry_after("0"));
+    }
+
+    #[test]
+    fn future_date() {
+        assert_eq!(
+            Some(Duration::from_secs(90)),
+            retry_after("Wed, 21 Oct 2015 07:29:30 GMT")
+        );
+    }
+
+    #[test]
+    fn past_date_is_clamped_to_zero() {
+        assert_eq!(
+            Some(Duration::ZERO),
+            retry_after("Wed, 21 Oct 2015 07:00:00 GMT")
+        );
+    }
+
+    #[test]
+    fn malformed_values() {
+        assert_eq!(None, retry_after(""));
+        assert_eq!(None, retry_after("-5"));
+        assert_eq!(None, retry_after("1.5"));
+        assert_eq!(None, retry_after("soon"));
+        assert_eq!(None, retry_after("21 Oct 2015"));
+        assert_eq!(None, parse_retry_after(&HeaderMap::new(), now()));
+    }
+}
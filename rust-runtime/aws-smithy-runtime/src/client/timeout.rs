@@ -3,16 +3,19 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
+use crate::client::latency::adaptive_attempt_timeout;
 use aws_smithy_async::future::timeout::Timeout;
 use aws_smithy_async::rt::sleep::{AsyncSleep, Sleep};
 use aws_smithy_client::SdkError;
-use aws_smithy_runtime_api::client::orchestrator::{ConfigBagAccessors, HttpResponse};
+use aws_smithy_runtime_api::client::orchestrator::{
+    ConfigBagAccessors, HttpResponse, SystemTimeSource, TimeSource,
+};
 use aws_smithy_runtime_api::config_bag::ConfigBag;
 use aws_smithy_types::timeout::TimeoutConfig;
 use pin_project_lite::pin_project;
 use std::future::Future;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::Once;
 use std::task::{Context, Poll};
 use std::time::Duration;
 
@@ -36,6 +39,7 @@ impl std::fmt::Display for MaybeTimeoutError {
             match self.kind {
                 TimeoutKind::Operation => "operation timeout (all attempts including retries)",
                 TimeoutKind::OperationAttempt => "operation attempt timeout (single attempt)",
+                TimeoutKind::Auth => "auth timeout (identity resolution and signing)",
             },
             self.duration
         )
@@ -100,43 +104,85 @@ where
 pub(super) enum TimeoutKind {
     Operation,
     OperationAttempt,
+    Auth,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub(super) struct MaybeTimeoutConfig {
-    sleep_impl: Option<Arc<dyn AsyncSleep>>,
+    // Started when the config is created, so that the timeout counts from then
+    sleep: Option<Sleep>,
     timeout: Option<Duration>,
     timeout_kind: TimeoutKind,
 }
 
+impl MaybeTimeoutConfig {
+    /// Returns the timeout, if one is set and can be enforced.
+    pub(super) fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+}
+
 pub(super) trait ProvideMaybeTimeoutConfig {
     fn maybe_timeout_config(&self, timeout_kind: TimeoutKind) -> MaybeTimeoutConfig;
 }
 
 impl ProvideMaybeTimeoutConfig for ConfigBag {
     fn maybe_timeout_config(&self, timeout_kind: TimeoutKind) -> MaybeTimeoutConfig {
-        if let Some(timeout_config) = self.get::<TimeoutConfig>() {
-            let sleep_impl = self.sleep_impl();
-            let timeout = match (sleep_impl.as_ref(), timeout_kind) {
-                (None, _) => None,
-                (Some(_), TimeoutKind::Operation) => timeout_config.operation_timeout(),
-                (Some(_), TimeoutKind::OperationAttempt) => {
-                    timeout_config.operation_attempt_timeout()
-                }
-            };
-            MaybeTimeoutConfig {
-                sleep_impl,
-                timeout,
-                timeout_kind,
-            }
-        } else {
-            MaybeTimeoutConfig {
-                sleep_impl: None,
-                timeout: None,
-                timeout_kind,
-            }
-        }
+        // Timeouts are looked up for every attempt of every operation, so only warn once per process
+        static MISSING_SLEEP_IMPL_WARNING: Once = Once::new();
+        maybe_timeout_config(self, timeout_kind, &MISSING_SLEEP_IMPL_WARNING)
+    }
+}
+
+/// Looks up the timeout of the given kind. If it can't be enforced, a warning is logged the first
+/// time `missing_sleep_impl_warning` is passed in.
+fn maybe_timeout_config(
+    cfg: &ConfigBag,
+    timeout_kind: TimeoutKind,
+    missing_sleep_impl_warning: &Once,
+) -> MaybeTimeoutConfig {
+    let adaptive_timeout = match timeout_kind {
+        TimeoutKind::OperationAttempt => adaptive_attempt_timeout(cfg),
+        _ => None,
+    };
+    let timeout = adaptive_timeout.or_else(|| {
+        cfg.get::<TimeoutConfig>()
+            .and_then(|timeout_config| match timeout_kind {
+                TimeoutKind::Operation => timeout_config.operation_timeout(),
+                TimeoutKind::OperationAttempt => timeout_config.operation_attempt_timeout(),
+                TimeoutKind::Auth => timeout_config.auth_timeout(),
+            })
+    });
+    let sleep = timeout.and_then(|timeout| sleep(cfg, timeout));
+    // A timeout can't be enforced without a way to sleep
+    if timeout.is_some() && sleep.is_none() {
+        missing_sleep_impl_warning.call_once(|| {
+            tracing::warn!(
+                "a timeout was configured but will not be enforced because no sleep implementation \
+                was set; use `ConfigBagAccessors::set_sleep_impl` to set one"
+            )
+        });
     }
+    MaybeTimeoutConfig {
+        timeout: sleep.as_ref().and(timeout),
+        sleep,
+        timeout_kind,
+    }
+}
+
+/// Returns the [`TimeSource`] in the `cfg`, or the [`SystemTimeSource`] if none is set.
+pub(super) fn time_source(cfg: &ConfigBag) -> &dyn TimeSource {
+    cfg.load::<Box<dyn TimeSource>>()
+        .map(|time_source| &**time_source)
+        .unwrap_or(&SystemTimeSource)
+}
+
+/// Returns a future that completes once `duration` has elapsed, according to the [`TimeSource`]
+/// if it can sleep, or the sleep implementation otherwise. Returns `None` if neither can.
+pub(super) fn sleep(cfg: &ConfigBag, duration: Duration) -> Option<Sleep> {
+    time_source(cfg)
+        .sleep(duration)
+        .or_else(|| Some(cfg.sleep_impl()?.sleep(duration)))
 }
 
 /// Trait to conveniently wrap a future with an optional timeout.
@@ -161,11 +207,11 @@ where
     ) -> MaybeTimeoutFuture<Self> {
         match timeout_config {
             MaybeTimeoutConfig {
-                sleep_impl: Some(sleep_impl),
+                sleep: Some(sleep),
                 timeout: Some(timeout),
                 timeout_kind,
             } => MaybeTimeoutFuture::Timeout {
-                future: Timeout::new(self, sleep_impl.sleep(timeout)),
+                future: Timeout::new(self, sleep),
                 timeout_kind,
                 duration: timeout,
             },
@@ -181,9 +227,16 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::client::latency::{AdaptiveAttemptTimeout, LatencyTracker};
     use aws_smithy_async::assert_elapsed;
     use aws_smithy_async::future::never::Never;
     use aws_smithy_async::rt::sleep::TokioSleep;
+    use aws_smithy_runtime_api::client::interceptors::Interceptors;
+    use aws_smithy_runtime_api::client::orchestrator::OperationDeadline;
+    use aws_smithy_runtime_api::client::runtime_plugin::RuntimePlugin;
+    use std::sync::{Arc, Mutex};
+    use std::time::SystemTime;
+    use tracing_test::traced_test;
 
     #[tokio::test]
     async fn test_no_timeout() {
@@ -234,7 +287,143 @@ mod tests {
             .await;
         let err = result.expect_err("should have timed out");
 
-        assert_eq!(format!("{:?}", err), "TimeoutError(TimeoutError { source: MaybeTimeoutError { kind: Operation, duration: 250ms } })");
+        assert_eq!(format!("{:?}", err), "TimeoutError(TimeoutError { source: MaybeTimeoutError { kind: Operation, duration: 250ms }, attempt_errors: [] })");
         assert_elapsed!(now, Duration::from_secs_f32(0.25));
     }
+
+    /// A time source whose sleeps complete immediately, fast-forwarding its clock instead.
+    #[derive(Clone, Debug)]
+    struct FastForwardTimeSource(Arc<Mutex<SystemTime>>);
+
+    impl TimeSource for FastForwardTimeSource {
+        fn now(&self) -> SystemTime {
+            *self.0.lock().unwrap()
+        }
+
+        fn sleep(&self, duration: Duration) -> Option<Sleep> {
+            let now = self.0.clone();
+            Some(Sleep::new(async move {
+                *now.lock().unwrap() += duration;
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timeouts_use_the_time_source() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let time_source = FastForwardTimeSource(Arc::new(Mutex::new(start)));
+        let never = Never::new();
+        let underlying_future = async {
+            never.await;
+            Result::<_, SdkError<(), HttpResponse>>::Ok(())
+        };
+
+        // No sleep impl is set, so the timeout can only be enforced by the time source
+        let mut cfg = ConfigBag::base();
+        cfg.put(
+            TimeoutConfig::builder()
+                .operation_timeout(Duration::from_secs(30))
+                .build(),
+        );
+        cfg.store_put::<Box<dyn TimeSource>>(Box::new(time_source.clone()));
+        let deadline = OperationDeadline::new(start + Duration::from_secs(30));
+        assert_eq!(Duration::from_secs(30), deadline.remaining(&time_source));
+
+        let result = underlying_future
+            .maybe_timeout(&cfg, TimeoutKind::Operation)
+            .await;
+        let err = result.expect_err("should have timed out");
+
+        assert_eq!(format!("{:?}", err), "TimeoutError(TimeoutError { source: MaybeTimeoutError { kind: Operation, duration: 30s }, attempt_errors: [] })");
+        assert_eq!(start + Duration::from_secs(30), time_source.now());
+        assert_eq!(Duration::ZERO, deadline.remaining(&time_source));
+    }
+
+    #[test]
+    fn test_adaptive_attempt_timeout_tracks_latencies() {
+        let time_source = FastForwardTimeSource(Arc::new(Mutex::new(SystemTime::UNIX_EPOCH)));
+        let mut cfg = ConfigBag::base();
+        cfg.put(
+            TimeoutConfig::builder()
+                .operation_timeout(Duration::from_secs(30))
+                .operation_attempt_timeout(Duration::from_secs(10))
+                .build(),
+        );
+        cfg.store_put::<Box<dyn TimeSource>>(Box::new(time_source));
+        let tracker = LatencyTracker::new().adaptive_attempt_timeout(
+            AdaptiveAttemptTimeout::new()
+                .factor(3.0)
+                .min_samples(50)
+                .max_timeout(Duration::from_secs(5)),
+        );
+        tracker
+            .configure(&mut cfg, &mut Interceptors::new())
+            .unwrap();
+
+        // There aren't enough latencies yet, so the static timeout is used
+        for millis in 1..=49 {
+            tracker.record(Duration::from_millis(millis));
+        }
+        let attempt_timeout = |cfg: &ConfigBag| {
+            cfg.maybe_timeout_config(TimeoutKind::OperationAttempt)
+                .timeout()
+        };
+        assert_eq!(Some(Duration::from_secs(10)), attempt_timeout(&cfg));
+
+        for millis in 50..=100 {
+            tracker.record(Duration::from_millis(millis));
+        }
+        let p99 = tracker.p99().unwrap();
+        assert!(p99 >= Duration::from_millis(99) && p99 < Duration::from_millis(101));
+        let timeout = attempt_timeout(&cfg).unwrap();
+        // The timeout is computed in floating point, so allow for rounding
+        assert!(
+            timeout.max(p99 * 3) - timeout.min(p99 * 3) < Duration::from_micros(1),
+            "expected {timeout:?} to be three times {p99:?}"
+        );
+
+        // Slower responses lengthen the timeout, up to its maximum
+        for _ in 0..100 {
+            tracker.record(Duration::from_secs(2));
+        }
+        assert_eq!(Some(Duration::from_secs(5)), attempt_timeout(&cfg));
+
+        // Other timeouts aren't affected
+        assert_eq!(
+            Some(Duration::from_secs(30)),
+            cfg.maybe_timeout_config(TimeoutKind::Operation).timeout()
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn test_timeout_without_sleep_impl_warns_once() {
+        let mut cfg = ConfigBag::base();
+        cfg.put(
+            TimeoutConfig::builder()
+                .operation_timeout(Duration::from_secs(1))
+                .operation_attempt_timeout(Duration::from_secs(1))
+                .build(),
+        );
+
+        // Other tests may have already used up the process-wide warning
+        let missing_sleep_impl_warning = Once::new();
+        for kind in [TimeoutKind::Operation, TimeoutKind::OperationAttempt] {
+            let config = maybe_timeout_config(&cfg, kind, &missing_sleep_impl_warning);
+            assert_eq!(None, config.timeout);
+        }
+
+        logs_assert(|lines: &[&str]| {
+            match lines
+                .iter()
+                .filter(|line| line.contains("no sleep implementation was set"))
+                .count()
+            {
+                1 => Ok(()),
+                n => Err(format!(
+                    "expected the warning exactly once, but it was logged {n} times"
+                )),
+            }
+        });
+    }
 }
@@ -16,6 +16,7 @@ pub struct TimeoutConfigBuilder {
     read_timeout: Option<Duration>,
     operation_timeout: Option<Duration>,
     operation_attempt_timeout: Option<Duration>,
+    auth_timeout: Option<Duration>,
 }
 
 impl TimeoutConfigBuilder {
@@ -113,6 +114,24 @@ impl TimeoutConfigBuilder {
         self
     }
 
+    /// Sets the auth timeout.
+    ///
+    /// The auth timeout is a limit on the amount of time it takes to resolve an identity (e.g. credentials)
+    /// and sign a request, for each attempt.
+    pub fn auth_timeout(mut self, auth_timeout: Duration) -> Self {
+        self.auth_timeout = Some(auth_timeout);
+        self
+    }
+
+    /// Sets the auth timeout.
+    ///
+    /// The auth timeout is a limit on the amount of time it takes to resolve an identity (e.g. credentials)
+    /// and sign a request, for each attempt.
+    pub fn set_auth_timeout(&mut self, auth_timeout: Option<Duration>) -> &mut Self {
+        self.auth_timeout = auth_timeout;
+        self
+    }
+
     /// Merges two timeout config builders together.
     ///
     /// Values from `other` will only be used as a fallback for values
@@ -144,6 +163,7 @@ impl TimeoutConfigBuilder {
             operation_attempt_timeout: self
                 .operation_attempt_timeout
                 .or(other.operation_attempt_timeout),
+            auth_timeout: self.auth_timeout.or(other.auth_timeout),
         }
     }
 
@@ -154,6 +174,7 @@ impl TimeoutConfigBuilder {
             read_timeout: self.read_timeout,
             operation_timeout: self.operation_timeout,
             operation_attempt_timeout: self.operation_attempt_timeout,
+            auth_timeout: self.auth_timeout,
         }
     }
 }
@@ -165,6 +186,7 @@ impl From<TimeoutConfig> for TimeoutConfigBuilder {
             read_timeout: timeout_config.read_timeout,
             operation_timeout: timeout_config.operation_timeout,
             operation_attempt_timeout: timeout_config.operation_attempt_timeout,
+            auth_timeout: timeout_config.auth_timeout,
         }
     }
 }
@@ -206,6 +228,7 @@ pub struct TimeoutConfig {
     read_timeout: Option<Duration>,
     operation_timeout: Option<Duration>,
     operation_attempt_timeout: Option<Duration>,
+    auth_timeout: Option<Duration>,
 }
 
 impl TimeoutConfig {
@@ -231,6 +254,7 @@ impl TimeoutConfig {
             read_timeout: None,
             operation_timeout: None,
             operation_attempt_timeout: None,
+            auth_timeout: None,
         }
     }
 
@@ -267,11 +291,20 @@ impl TimeoutConfig {
         self.operation_attempt_timeout
     }
 
+    /// Returns this config's auth timeout.
+    ///
+    /// The auth timeout is a limit on the amount of time it takes to resolve an identity (e.g. credentials)
+    /// and sign a request, for each attempt.
+    pub fn auth_timeout(&self) -> Option<Duration> {
+        self.auth_timeout
+    }
+
     /// Returns true if any of the possible timeouts are set.
     pub fn has_timeouts(&self) -> bool {
         self.connect_timeout.is_some()
             || self.operation_timeout.is_some()
             || self.operation_attempt_timeout.is_some()
+            || self.auth_timeout.is_some()
     }
 }
 